@@ -5,14 +5,18 @@ extern crate rand;
 extern crate rand_pcg;
 extern crate test;
 
-use evalexpr::build_operator_tree;
+use evalexpr::{
+    build_expression, build_operator_tree, build_operator_tree_arc,
+    build_operator_tree_with_options, ParseOptions,
+};
 use rand::{distributions::Uniform, seq::SliceRandom, Rng, SeedableRng};
 use rand_pcg::Pcg32;
-use std::{fmt::Write, hint::black_box};
+use std::{fmt::Write, hint::black_box, sync::Arc};
 use test::Bencher;
 
 const BENCHMARK_LEN: usize = 100_000;
 const EXPONENTIAL_TUPLE_ITERATIONS: usize = 12;
+const TUPLE_LITERAL_LEN: usize = 1000;
 
 fn generate_expression<Gen: Rng>(len: usize, gen: &mut Gen) -> String {
     let int_distribution = Uniform::new_inclusive(1, 100);
@@ -51,6 +55,13 @@ fn generate_small_expressions<Gen: Rng>(len: usize, gen: &mut Gen) -> Vec<String
     result
 }
 
+fn generate_tuple_literal(len: usize) -> String {
+    (0..len)
+        .map(|element| element.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 fn generate_large_tuple_expression<Gen: Rng>(iterations: usize, gen: &mut Gen) -> String {
     let mut result = String::from("a=(");
     result.push_str(&generate_expression(0, gen));
@@ -79,6 +90,24 @@ fn bench_parse_deep_expression_trees(bencher: &mut Bencher) {
     bencher.iter(|| build_operator_tree(&deep_expression_tree).unwrap());
 }
 
+#[bench]
+fn bench_parse_deep_expression_trees_with_source_text(bencher: &mut Bencher) {
+    let mut gen = Pcg32::seed_from_u64(15);
+    let deep_expression_tree = generate_expression(BENCHMARK_LEN, &mut gen);
+    let options = ParseOptions::new().preserve_source_text(true);
+
+    bencher.iter(|| build_operator_tree_with_options(&deep_expression_tree, &options).unwrap());
+}
+
+#[bench]
+fn bench_parse_deep_expression_trees_with_shared_source_text(bencher: &mut Bencher) {
+    let mut gen = Pcg32::seed_from_u64(15);
+    let deep_expression_tree: Arc<str> = generate_expression(BENCHMARK_LEN, &mut gen).into();
+    let options = ParseOptions::new().preserve_source_text(true);
+
+    bencher.iter(|| build_operator_tree_arc(deep_expression_tree.clone(), &options).unwrap());
+}
+
 #[bench]
 fn bench_parse_many_small_expressions(bencher: &mut Bencher) {
     let mut gen = Pcg32::seed_from_u64(33);
@@ -124,6 +153,22 @@ fn bench_evaluate_many_small_expressions(bencher: &mut Bencher) {
     });
 }
 
+#[bench]
+fn bench_evaluate_tuple_literal(bencher: &mut Bencher) {
+    let tuple_literal = build_operator_tree(&generate_tuple_literal(TUPLE_LITERAL_LEN)).unwrap();
+
+    bencher.iter(|| tuple_literal.eval().unwrap());
+}
+
+/// Evaluates a tuple literal ten times as long as in `bench_evaluate_tuple_literal`, which should take about ten times as long.
+#[bench]
+fn bench_evaluate_long_tuple_literal(bencher: &mut Bencher) {
+    let tuple_literal =
+        build_operator_tree(&generate_tuple_literal(10 * TUPLE_LITERAL_LEN)).unwrap();
+
+    bencher.iter(|| tuple_literal.eval().unwrap());
+}
+
 #[bench]
 fn bench_evaluate_large_tuple_expression(bencher: &mut Bencher) {
     let mut gen = Pcg32::seed_from_u64(44);
@@ -136,3 +181,53 @@ fn bench_evaluate_large_tuple_expression(bencher: &mut Bencher) {
 
     bencher.iter(|| large_tuple_expression.eval().unwrap());
 }
+
+#[bench]
+fn bench_build_deep_expression_arena(bencher: &mut Bencher) {
+    let mut gen = Pcg32::seed_from_u64(15);
+    let deep_expression_tree = generate_expression(BENCHMARK_LEN, &mut gen);
+
+    bencher.iter(|| build_expression(&deep_expression_tree).unwrap());
+}
+
+#[bench]
+fn bench_clone_deep_expression_trees(bencher: &mut Bencher) {
+    let mut gen = Pcg32::seed_from_u64(15);
+    let deep_expression_tree =
+        build_operator_tree(&generate_expression(BENCHMARK_LEN, &mut gen)).unwrap();
+
+    bencher.iter(|| deep_expression_tree.clone());
+}
+
+#[bench]
+fn bench_clone_deep_expression_arena(bencher: &mut Bencher) {
+    let mut gen = Pcg32::seed_from_u64(15);
+    let deep_expression_arena =
+        build_expression(&generate_expression(BENCHMARK_LEN, &mut gen)).unwrap();
+
+    bencher.iter(|| deep_expression_arena.clone());
+}
+
+#[bench]
+fn bench_evaluate_deep_expression_arena(bencher: &mut Bencher) {
+    let mut gen = Pcg32::seed_from_u64(15);
+    let deep_expression_arena =
+        build_expression(&generate_expression(BENCHMARK_LEN, &mut gen)).unwrap();
+
+    bencher.iter(|| deep_expression_arena.eval().unwrap());
+}
+
+#[bench]
+fn bench_evaluate_many_small_expression_arenas(bencher: &mut Bencher) {
+    let mut gen = Pcg32::seed_from_u64(33);
+    let small_expressions: Vec<_> = generate_small_expressions(BENCHMARK_LEN, &mut gen)
+        .iter()
+        .map(|expression| build_expression(expression).unwrap())
+        .collect();
+
+    bencher.iter(|| {
+        for expression in &small_expressions {
+            black_box(expression.eval().unwrap());
+        }
+    });
+}
@@ -0,0 +1,116 @@
+//! An interactive read-eval-print loop that evaluates each line of the standard input as expression.
+//!
+//! All lines are evaluated with the same mutable context, so variables assigned in one line can be read in the next ones.
+//! Besides expressions, the following commands are understood:
+//!
+//! * `:set <name> <literal>` binds the variable `<name>` to the value of `<literal>`, like the assignment `<name> = <literal>`,
+//! * `:vars` lists all variables with their values,
+//! * `:clear` removes all variables,
+//! * `:help` shows the commands,
+//! * `:quit` ends the loop, just like the end of the input.
+//!
+//! Run it with `cargo run --example repl`.
+
+use std::io::{self, BufRead, Write};
+
+use evalexpr::*;
+
+const HELP: &str = "\
+Enter an expression to evaluate it, e.g. `a = 2; a * 3`.
+Commands:
+  :set <name> <literal>  bind a variable to a literal value
+  :vars                  list all variables
+  :clear                 remove all variables
+  :help                  show this help
+  :quit                  exit";
+
+/// What the loop does after a line was handled.
+enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Handles a single line of input, writing its result to `output`.
+fn handle_line(
+    line: &str,
+    context: &mut HashMapContext,
+    output: &mut impl Write,
+) -> io::Result<Flow> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(Flow::Continue);
+    }
+
+    let Some(command) = line.strip_prefix(':') else {
+        match eval_with_context_mut(line, context) {
+            Ok(Value::Empty) => {},
+            Ok(value) => writeln!(output, "{}", value)?,
+            Err(error) => writeln!(output, "error: {}", error)?,
+        }
+        return Ok(Flow::Continue);
+    };
+
+    let (command, argument) = command
+        .split_once(char::is_whitespace)
+        .map_or((command, ""), |(command, argument)| {
+            (command, argument.trim())
+        });
+    match command {
+        "set" => {
+            let result = argument
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| EvalexprError::InvalidAssignment(argument.to_string()))
+                .and_then(|(name, literal)| {
+                    let value = literal.trim().parse::<Value>()?;
+                    context.set_value(name.to_string(), value)
+                });
+            if let Err(error) = result {
+                writeln!(output, "error: {}", error)?;
+            }
+        },
+        "vars" => {
+            let mut variables: Vec<_> = context.iter_variables().collect();
+            variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (name, value) in variables {
+                writeln!(output, "{} = {}", name, value)?;
+            }
+        },
+        "clear" => context.clear_variables(),
+        "help" => writeln!(output, "{}", HELP)?,
+        "quit" | "q" => return Ok(Flow::Quit),
+        _ => writeln!(
+            output,
+            "error: unknown command `:{}`, type `:help` for help",
+            command
+        )?,
+    }
+    Ok(Flow::Continue)
+}
+
+fn main() -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut input = stdin.lock();
+    let mut output = stdout.lock();
+    let mut context = HashMapContext::new();
+    let mut line = String::new();
+
+    writeln!(
+        output,
+        "evalexpr {}, type `:help` for help",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            break;
+        }
+        if let Flow::Quit = handle_line(&line, &mut context, &mut output)? {
+            break;
+        }
+    }
+    writeln!(output)
+}
@@ -0,0 +1,523 @@
+//! Translation of the expressions of the [`eval`](https://crates.io/crates/eval) crate.
+//!
+//! The syntax of the `eval` crate largely overlaps with the syntax of this crate.
+//! [`translate`] rewrites the constructs that are written differently, and [`parse_compat`] builds the operator tree of the result.
+//! Constructs without counterpart are reported as [`CompatIssue`]s, all at once.
+
+use std::{fmt, ops::Range};
+
+use crate::{build_operator_tree_with_options, Node, ParseOptions};
+
+/// A construct of an expression of the `eval` crate that cannot be translated.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompatIssue {
+    /// The text of the construct.
+    pub construct: String,
+    /// The byte range of the construct within the expression.
+    pub span: Range<usize>,
+    /// A hint on how to rewrite the construct such that it can be translated.
+    pub suggestion: String,
+}
+
+impl CompatIssue {
+    fn new(expression: &str, span: Range<usize>, suggestion: impl Into<String>) -> Self {
+        Self {
+            construct: expression[span.clone()].to_string(),
+            span,
+            suggestion: suggestion.into(),
+        }
+    }
+}
+
+impl fmt::Display for CompatIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` at {}..{}: {}",
+            self.construct, self.span.start, self.span.end, self.suggestion
+        )
+    }
+}
+
+/// A token of an expression of the `eval` crate.
+#[derive(Clone, Debug, PartialEq)]
+enum EvalToken {
+    /// An operator that is written the same in expressions.
+    Operator,
+    Number,
+    /// A name, which is a variable, a function or one of the booleans `true` and `false`.
+    Name,
+    /// A member access like `.b`, which follows an index like in `a[0].b`.
+    Member,
+    String(String),
+    Null,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Comma,
+    /// The range operator `..`.
+    Range,
+    /// A character without counterpart in the `eval` crate, with a suggestion on how to rewrite it.
+    Unsupported(&'static str),
+}
+
+impl EvalToken {
+    /// Returns true if this token can end an operand, such that a following `[` indexes the operand.
+    fn ends_operand(&self) -> bool {
+        matches!(
+            self,
+            EvalToken::Number
+                | EvalToken::Name
+                | EvalToken::Member
+                | EvalToken::String(_)
+                | EvalToken::Null
+                | EvalToken::RBrace
+                | EvalToken::RBracket
+        )
+    }
+}
+
+fn is_name_continuation(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Returns the length in bytes of the name at the start of the given string, including member accesses like `.b` in `a.b`.
+fn name_len(string: &str) -> usize {
+    let mut len = 0;
+    let mut chars = string.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let is_member_access =
+            c == '.' && matches!(chars.peek(), Some((_, next)) if is_name_continuation(*next));
+        if !(is_name_continuation(c) || is_member_access) {
+            break;
+        }
+        len = index + c.len_utf8();
+    }
+    len
+}
+
+/// Returns the length in bytes of the number at the start of the given string, including a fraction and an exponent.
+/// A dot followed by another dot is the range operator and not part of the number.
+fn number_len(string: &str) -> usize {
+    let digits = |string: &str| {
+        string
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(string.len())
+    };
+    let mut len = digits(string);
+    if let Some(fraction) = string[len..].strip_prefix('.') {
+        if digits(fraction) > 0 {
+            len += 1 + digits(fraction);
+        }
+    }
+    if let Some(exponent) = string[len..].strip_prefix(['e', 'E']) {
+        let sign = usize::from(exponent.starts_with(['+', '-']));
+        if digits(&exponent[sign..]) > 0 {
+            len += 1 + sign + digits(&exponent[sign..]);
+        }
+    }
+    len
+}
+
+/// Parses the string literal at the start of the given string, which is enclosed in single or double quotes.
+/// Returns the content of the string and the length in bytes of the literal, or `None` if the string is not terminated.
+fn string_literal(string: &str) -> Option<(String, usize)> {
+    let mut chars = string.char_indices();
+    let (_, quote) = chars.next()?;
+    let mut content = String::new();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            c if c == quote => return Some((content, index + 1)),
+            '\\' => content.push(match chars.next()?.1 {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                c => c,
+            }),
+            c => content.push(c),
+        }
+    }
+    None
+}
+
+/// Splits the given expression into tokens with their byte ranges.
+fn tokenize(expression: &str) -> Result<Vec<(EvalToken, Range<usize>)>, CompatIssue> {
+    const TWO_CHARACTER_OPERATORS: [&str; 6] = ["==", "!=", "<=", ">=", "&&", "||"];
+
+    let mut tokens = Vec::new();
+    let mut position = 0;
+
+    while let Some(c) = expression[position..].chars().next() {
+        let rest = &expression[position..];
+        let (token, len) = match c {
+            c if c.is_whitespace() => {
+                position += c.len_utf8();
+                continue;
+            },
+            '"' | '\'' => match string_literal(rest) {
+                Some((string, len)) => (EvalToken::String(string), len),
+                None => {
+                    return Err(CompatIssue::new(
+                        expression,
+                        position..expression.len(),
+                        format!("terminate the string with {}", c),
+                    ))
+                },
+            },
+            '0'..='9' => (EvalToken::Number, number_len(rest)),
+            c if c.is_alphabetic() || c == '_' => {
+                let len = name_len(rest);
+                let token = if &rest[..len] == "null" {
+                    EvalToken::Null
+                } else {
+                    EvalToken::Name
+                };
+                (token, len)
+            },
+            _ if rest.starts_with("..") => (EvalToken::Range, 2),
+            '.' if rest[1..].starts_with(|c: char| c.is_alphabetic() || c == '_') => {
+                (EvalToken::Member, 1 + name_len(&rest[1..]))
+            },
+            _ if TWO_CHARACTER_OPERATORS
+                .iter()
+                .any(|operator| rest.starts_with(operator)) =>
+            {
+                (EvalToken::Operator, 2)
+            },
+            // Expressions would skip these as comments
+            '/' if rest[1..].starts_with(['/', '*']) => (
+                EvalToken::Unsupported("remove the comment, which the eval crate does not support"),
+                2,
+            ),
+            '+' | '-' | '*' | '/' | '%' | '<' | '>' | '!' | '?' | ':' => (EvalToken::Operator, 1),
+            '(' => (EvalToken::LBrace, 1),
+            ')' => (EvalToken::RBrace, 1),
+            '[' => (EvalToken::LBracket, 1),
+            ']' => (EvalToken::RBracket, 1),
+            ',' => (EvalToken::Comma, 1),
+            '=' => (
+                EvalToken::Unsupported(
+                    "compare with `==`, as the eval crate does not support assignments",
+                ),
+                1,
+            ),
+            c => (
+                EvalToken::Unsupported(
+                    "remove the character, which the eval crate does not support",
+                ),
+                c.len_utf8(),
+            ),
+        };
+        tokens.push((token, position..position + len));
+        position += len;
+    }
+
+    Ok(tokens)
+}
+
+/// Writes the given string as string literal of an expression.
+fn quote(string: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in string.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Returns the key of a field path that reads the element indexed by the given token,
+/// which is the case for non-negative integer literals and strings that are valid keys.
+fn index_key(expression: &str, (token, span): &(EvalToken, Range<usize>)) -> Option<String> {
+    match token {
+        EvalToken::Number if expression[span.clone()].bytes().all(|b| b.is_ascii_digit()) => {
+            Some(expression[span.clone()].to_string())
+        },
+        EvalToken::String(key) if !key.is_empty() && key.chars().all(is_name_continuation) => {
+            Some(key.clone())
+        },
+        _ => None,
+    }
+}
+
+/// A pair of parentheses or brackets that is not closed yet.
+struct Group {
+    /// The span of the opening parenthesis or bracket.
+    opening: Range<usize>,
+    /// True if the group is opened by `[`.
+    square: bool,
+    /// True if the group is an array, which is translated into a tuple.
+    array: bool,
+    /// True if the group holds the argument of `is_empty`.
+    is_empty: bool,
+    /// The length of the translation before the opening parenthesis or bracket.
+    start: usize,
+    commas: usize,
+}
+
+/// Translates an expression of the `eval` crate into an expression of this crate.
+///
+/// The whitespace of the expression is kept, and the constructs are translated as follows:
+///  * Numbers, booleans, variables, member accesses like `a.b`, function calls and the operators
+///    `+`, `-`, `*`, `/`, `%`, `==`, `!=`, `<`, `<=`, `>`, `>=`, `&&`, `||`, `!` and `c ? a : b` are kept.
+///  * Strings in single or double quotes are written in double quotes.
+///  * `null` is translated into the empty value `()`.
+///  * Arrays `[a, b]` and `array(a, b)` are translated into tuples `(a, b)`, where empty arrays are translated into `()`.
+///  * Indexing a variable with an integer or string literal, like `items[0]` or `item["price"]`,
+///    is translated into a field path like `items.0` or `item.price`.
+///  * `is_empty(a)` is translated into `((a) == () ? true : len(a) == 0)`, as `len` does not accept the empty value of `null` and `[]`.
+///
+/// Arrays with a single element, ranges like `0..3`, indexing with other expressions and characters that are not part of
+/// the syntax of the `eval` crate are reported as `CompatIssue`s, as well as unmatched parentheses and brackets.
+/// All issues of the expression are returned, ordered by their position in the expression.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::compat::eval_crate::translate;
+///
+/// assert_eq!(
+///     translate("items[0].price > 10 && tags != null"),
+///     Ok("items.0.price > 10 && tags != ()".to_string())
+/// );
+/// assert_eq!(translate("0..3").unwrap_err()[0].construct, "..");
+/// ```
+pub fn translate(expression: &str) -> Result<String, Vec<CompatIssue>> {
+    let tokens = tokenize(expression).map_err(|issue| vec![issue])?;
+    let mut issues = Vec::new();
+    let mut translated = String::new();
+    let mut groups: Vec<Group> = Vec::new();
+    // The function call for the next opening parenthesis, as pair of whether it creates an array and whether it is `is_empty`
+    let mut call = (false, false);
+    // Whether the translation ends with a variable or a field path, which can be indexed
+    let mut path = false;
+    let mut translated_until = 0;
+    let mut index = 0;
+
+    while let Some((token, span)) = tokens.get(index) {
+        let previous = index.checked_sub(1).map(|previous| &tokens[previous].0);
+        let next = tokens.get(index + 1).map(|(token, _)| token);
+        let indexes =
+            *token == EvalToken::LBracket && previous.is_some_and(EvalToken::ends_operand);
+        let is_path = path;
+        path = match token {
+            EvalToken::Name => next != Some(&EvalToken::LBrace),
+            EvalToken::Member => is_path,
+            _ => false,
+        };
+        index += 1;
+        translated.push_str(&expression[translated_until..span.start]);
+        translated_until = span.end;
+
+        let replacement = match token {
+            EvalToken::Operator | EvalToken::Number => &expression[span.clone()],
+            EvalToken::Name if next == Some(&EvalToken::LBrace) => {
+                match &expression[span.clone()] {
+                    "array" => {
+                        call = (true, false);
+                        ""
+                    },
+                    "is_empty" => {
+                        call = (false, true);
+                        ""
+                    },
+                    name => name,
+                }
+            },
+            EvalToken::Name => &expression[span.clone()],
+            EvalToken::Member if is_path => &expression[span.clone()],
+            EvalToken::Member => {
+                issues.push(CompatIssue::new(
+                    expression,
+                    span.clone(),
+                    "access members of variables only, like `user.name` or `items[0].name`",
+                ));
+                ""
+            },
+            EvalToken::String(string) => {
+                translated.push_str(&quote(string));
+                continue;
+            },
+            EvalToken::Null => "()",
+            EvalToken::LBracket if indexes => {
+                let key = tokens.get(index).and_then(|key| index_key(expression, key));
+                let closing = tokens
+                    .get(index + 1)
+                    .filter(|(token, _)| *token == EvalToken::RBracket);
+                if let (Some(key), Some((_, closing)), true) = (key, closing, is_path) {
+                    translated.push('.');
+                    translated.push_str(&key);
+                    translated_until = closing.end;
+                    index += 2;
+                    path = true;
+                    continue;
+                }
+
+                // Skip the index up to the matching bracket
+                let mut depth = 0;
+                let skipped = tokens[index - 1..]
+                    .iter()
+                    .take_while(|(token, _)| {
+                        let open = depth > 0;
+                        match token {
+                            EvalToken::LBrace | EvalToken::LBracket => depth += 1,
+                            EvalToken::RBrace | EvalToken::RBracket => depth -= 1,
+                            _ => {},
+                        }
+                        open || depth > 0
+                    })
+                    .count();
+                index += skipped - 1;
+                let end = tokens[index - 1].1.end;
+                issues.push(CompatIssue::new(
+                    expression,
+                    span.start..end,
+                    "index a variable with an integer or string literal, like `items[0]` or `item[\"price\"]`",
+                ));
+                translated_until = end;
+                continue;
+            },
+            EvalToken::LBrace | EvalToken::LBracket => {
+                let square = *token == EvalToken::LBracket;
+                let (array, is_empty) = if square { (true, false) } else { call };
+                call = (false, false);
+                groups.push(Group {
+                    opening: span.clone(),
+                    square,
+                    array,
+                    is_empty,
+                    start: translated.len(),
+                    commas: 0,
+                });
+                "("
+            },
+            EvalToken::Comma => {
+                if let Some(group) = groups.last_mut() {
+                    group.commas += 1;
+                }
+                ","
+            },
+            EvalToken::RBrace | EvalToken::RBracket => {
+                let square = *token == EvalToken::RBracket;
+                match groups.pop() {
+                    None => {
+                        issues.push(CompatIssue::new(
+                            expression,
+                            span.clone(),
+                            "remove the unmatched closing parenthesis or bracket",
+                        ));
+                        ""
+                    },
+                    Some(group) if group.square != square => {
+                        issues.push(CompatIssue::new(
+                            expression,
+                            group.opening.start..span.end,
+                            if group.square {
+                                "close the `[` with `]`"
+                            } else {
+                                "close the `(` with `)`"
+                            },
+                        ));
+                        ""
+                    },
+                    Some(group) => {
+                        let empty =
+                            matches!(previous, Some(EvalToken::LBrace | EvalToken::LBracket));
+                        if group.array && group.commas == 0 && !empty {
+                            issues.push(CompatIssue::new(
+                                expression,
+                                group.opening.start..span.end,
+                                "use the element without an array, as tuples have at least two elements",
+                            ));
+                        }
+                        if group.is_empty {
+                            // The argument is written twice, so it is kept together by parentheses
+                            let argument = translated.split_off(group.start + 1);
+                            translated.truncate(group.start);
+                            translated.push_str(&format!(
+                                "(({}) == () ? true : len({}) == 0)",
+                                argument, argument
+                            ));
+                            ""
+                        } else {
+                            ")"
+                        }
+                    },
+                }
+            },
+            EvalToken::Range => {
+                issues.push(CompatIssue::new(
+                    expression,
+                    span.clone(),
+                    "write the elements of the range as an array, like `[0, 1, 2]`",
+                ));
+                ""
+            },
+            EvalToken::Unsupported(suggestion) => {
+                issues.push(CompatIssue::new(expression, span.clone(), *suggestion));
+                ""
+            },
+        };
+        translated.push_str(replacement);
+    }
+
+    for group in groups {
+        issues.push(CompatIssue::new(
+            expression,
+            group.opening,
+            "close the parenthesis or bracket",
+        ));
+    }
+    issues.sort_by_key(|issue| issue.span.start);
+
+    if issues.is_empty() {
+        translated.push_str(&expression[translated_until..]);
+        Ok(translated)
+    } else {
+        Err(issues)
+    }
+}
+
+/// Parses an expression of the `eval` crate into an operator tree.
+///
+/// The expression is translated with [`translate`] and built with float division enabled,
+/// as the `eval` crate divides numbers as floats.
+/// If the translated expression is invalid, for example because an operand is missing,
+/// a single issue spanning the whole expression is returned.
+///
+/// Some differences between the crates remain, which are not reported:
+///  * Arithmetic on integers other than division results in integers, while the `eval` crate computes with floats.
+///  * Like in the `eval` crate, `==` and `!=` consider values of different types unequal, such that `1 == 1.0` is false.
+///  * Empty arrays are the empty value, which has no length.
+///  * The context needs to provide arrays as tuples and objects as maps.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::{compat::eval_crate::parse_compat, *};
+///
+/// let tree = parse_compat("len(tags) > 1 && owner['name'] == 'ada'").unwrap(); // Do proper error handling here
+/// let mut context = HashMapContext::new();
+/// eval_with_context_mut("tags = (\"a\", \"b\"); owner = {name: \"ada\"}", &mut context).unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+///
+/// let issues = parse_compat("sum(0..n)").unwrap_err();
+/// assert_eq!(issues[0].span, 5..7);
+/// ```
+pub fn parse_compat(expression: &str) -> Result<Node, Vec<CompatIssue>> {
+    let translated = translate(expression)?;
+    build_operator_tree_with_options(&translated, &ParseOptions::new().float_division(true))
+        .map_err(|error| {
+            vec![CompatIssue::new(
+                expression,
+                0..expression.len(),
+                format!(
+                    "fix the expression, which is invalid after translation: {}",
+                    error
+                ),
+            )]
+        })
+}
@@ -0,0 +1,6 @@
+//! Parsers for the expressions of other expression evaluation crates, for migrating expressions to this crate.
+//!
+//! Each submodule translates the subset of another crate's syntax that has a counterpart in this crate,
+//! and reports the constructs it cannot translate with their position in the expression.
+
+pub mod eval_crate;
@@ -4,15 +4,23 @@
 //! This crate implements two basic variants, the `EmptyContext`, that returns `None` for each identifier and cannot be manipulated, and the `HashMapContext`, that stores its mappings in hash maps.
 //! The HashMapContext is type-safe and returns an error if the user tries to assign a value of a different type than before to an identifier.
 
-use std::{collections::HashMap, iter};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt, iter,
+};
 
 use crate::{
-    function::Function,
-    value::{value_type::ValueType, Value},
-    EvalexprError, EvalexprResult,
+    function::{Function, MacroFunction, MAX_EVALUATION_DEPTH},
+    token::{self, Token},
+    value::{value_type::ValueType, FloatType, Value},
+    EvalexprError, EvalexprResult, ParseOptions,
 };
 
 mod predefined;
+mod snapshot;
+
+pub use snapshot::{BindingChange, ContextSnapshot};
 
 /// An immutable context.
 pub trait Context {
@@ -23,12 +31,101 @@ pub trait Context {
     /// If no function with the given identifier is found, this method returns `EvalexprError::FunctionIdentifierNotFound`.
     fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value>;
 
+    /// Checks if a function is linked to the given identifier, such that `call_function` does not return `EvalexprError::FunctionIdentifierNotFound` for it.
+    /// The default implementation returns `false`, so contexts with functions should override it.
+    /// Otherwise, the builtin function `try_or` is evaluated lazily even if the context links its own function to `try_or`.
+    fn has_function(&self, _identifier: &str) -> bool {
+        false
+    }
+
     /// Checks if builtin functions are disabled.
     fn are_builtin_functions_disabled(&self) -> bool;
 
     /// Disables builtin functions if `disabled` is `true`, and enables them otherwise.
     /// If the context does not support enabling or disabling builtin functions, an error is returned.
     fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()>;
+
+    /// Returns how the `==` and `!=` operators compare floats.
+    /// The default implementation returns `FloatEquality::Exact`.
+    fn float_equality(&self) -> FloatEquality {
+        FloatEquality::Exact
+    }
+
+    /// Returns the maximum amount of nested evaluations on a thread when evaluating with this context, see `EvalContext`.
+    /// The default implementation returns `MAX_EVALUATION_DEPTH`.
+    fn max_evaluation_depth(&self) -> usize {
+        MAX_EVALUATION_DEPTH
+    }
+
+    /// Returns the macro function that is linked to the given identifier, which is called with the unevaluated arguments.
+    /// The default implementation returns `None`.
+    fn get_macro_function(&self, _identifier: &str) -> Option<&MacroFunction> {
+        None
+    }
+
+    /// Returns the units that quantities can be created with.
+    /// The default implementation returns `UnitRegistry::standard`.
+    #[cfg(feature = "units_support")]
+    fn unit_registry(&self) -> &crate::UnitRegistry {
+        crate::UnitRegistry::standard()
+    }
+
+    /// Returns the clock that the `now` builtin function reads the current time from.
+    /// The default implementation returns `Clock::system`.
+    #[cfg(feature = "time_support")]
+    fn clock(&self) -> crate::Clock {
+        crate::Clock::system()
+    }
+}
+
+/// Defines how the `==` and `!=` operators compare floats during evaluation.
+///
+/// The comparison applies if both operands are floats, or if one is a float and the other one an int.
+/// All other comparisons, including `<=` and `>=`, as well as floats within tuples, are unaffected.
+/// `NaN` is never equal to anything, and infinities are only equal to themselves.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum FloatEquality {
+    /// Floats are equal only if they are exactly equal.
+    /// An int is never equal to a float, like for all values of different types.
+    #[default]
+    Exact,
+    /// Numbers are equal if their difference is at most the given fraction of the larger absolute value.
+    Relative(FloatType),
+    /// Numbers are equal if their difference is at most the given tolerance.
+    Absolute(FloatType),
+}
+
+impl FloatEquality {
+    /// Returns true if the given floats are equal under this comparison.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// assert!(!FloatEquality::Exact.are_equal(0.1 + 0.2, 0.3));
+    /// assert!(FloatEquality::Absolute(1e-9).are_equal(0.1 + 0.2, 0.3));
+    /// assert!(FloatEquality::Relative(0.01).are_equal(100.0, 101.0));
+    /// assert!(!FloatEquality::Relative(0.01).are_equal(100.0, 102.0));
+    /// assert!(!FloatEquality::Relative(0.01).are_equal(FloatType::INFINITY, 1.0));
+    /// ```
+    pub fn are_equal(&self, a: FloatType, b: FloatType) -> bool {
+        if a == b {
+            return true;
+        }
+        // Infinities are only equal to themselves, and their difference to a finite number would be within a relative tolerance
+        if !a.is_finite() || !b.is_finite() {
+            return false;
+        }
+
+        let difference = (a - b).abs();
+        match *self {
+            FloatEquality::Exact => false,
+            FloatEquality::Relative(tolerance) => difference <= tolerance * a.abs().max(b.abs()),
+            FloatEquality::Absolute(tolerance) => difference <= tolerance,
+        }
+    }
 }
 
 /// A context that allows to assign to variables.
@@ -169,15 +266,49 @@ impl IterateVariablesContext for EmptyContextWithBuiltinFunctions {
 /// *Value and function mappings are stored independently, meaning that there can be a function and a value with the same identifier.*
 ///
 /// This context is type-safe, meaning that an identifier that is assigned a value of some type once cannot be assigned a value of another type.
-#[derive(Clone, Debug, Default)]
+///
+/// The `Debug` and `Display` implementations list the bindings sorted by identifier, so their output does not depend on the order of insertion.
+/// Functions are written with their amount of arguments like `<function/2>`, as their bodies cannot be shown.
+#[derive(Clone, Default)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct HashMapContext {
+    #[cfg_attr(
+        feature = "serde_support",
+        serde(serialize_with = "crate::feature_serde::serialize_sorted")
+    )]
     variables: HashMap<String, Value>,
     #[cfg_attr(feature = "serde_support", serde(skip))]
     functions: HashMap<String, Function>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    macro_functions: HashMap<String, MacroFunction>,
 
     /// True if builtin functions are disabled.
     without_builtin_functions: bool,
+
+    /// True if existing bindings must not be overwritten.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    strict: bool,
+
+    /// How `==` and `!=` compare floats.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    float_equality: FloatEquality,
+
+    /// The maximum amount of nested evaluations if it differs from `MAX_EVALUATION_DEPTH`.
+    #[cfg_attr(
+        feature = "serde_support",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    max_evaluation_depth: Option<usize>,
+
+    /// The units if they differ from the standard units.
+    #[cfg(feature = "units_support")]
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    unit_registry: Option<crate::UnitRegistry>,
+
+    /// The clock of the `now` builtin function.
+    #[cfg(feature = "time_support")]
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    clock: crate::Clock,
 }
 
 impl HashMapContext {
@@ -186,6 +317,173 @@ impl HashMapContext {
         Default::default()
     }
 
+    /// Constructs a strict `HashMapContext` with no mappings.
+    ///
+    /// A strict context never overwrites a binding.
+    /// Its `set_value` and `set_function` behave like `try_set_value` and `try_set_function`,
+    /// returning `EvalexprError::DuplicateBinding` instead of replacing an existing binding.
+    /// This includes assignments within expressions, so in a strict context every variable can be assigned only once.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::strict();
+    /// assert_eq!(eval_empty_with_context_mut("a = 1", &mut context), Ok(EMPTY_VALUE));
+    /// assert_eq!(
+    ///     eval_empty_with_context_mut("a = 2", &mut context),
+    ///     Err(EvalexprError::DuplicateBinding("a".to_string()))
+    /// );
+    /// assert_eq!(context.get_value("a"), Some(&1.into()));
+    /// ```
+    pub fn strict() -> Self {
+        Self {
+            strict: true,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if this context was constructed with `HashMapContext::strict`.
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Binds the given value to the given identifier, unless the identifier is already bound to a value.
+    ///
+    /// Returns `EvalexprError::DuplicateBinding` and leaves the context unchanged if the identifier is bound.
+    /// Functions are stored independently of values, so a function with the same identifier does not count as a binding.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// assert_eq!(context.try_set_value("a".into(), 1.into()), Ok(()));
+    /// assert_eq!(
+    ///     context.try_set_value("a".into(), 2.into()),
+    ///     Err(EvalexprError::DuplicateBinding("a".to_string()))
+    /// );
+    /// assert_eq!(context.get_value("a"), Some(&1.into()));
+    /// ```
+    pub fn try_set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+        match self.variables.entry(identifier) {
+            Entry::Occupied(entry) => Err(EvalexprError::DuplicateBinding(entry.key().clone())),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                Ok(())
+            },
+        }
+    }
+
+    /// Binds the given function to the given identifier, unless the identifier is already bound to a function.
+    ///
+    /// Returns `EvalexprError::DuplicateBinding` and leaves the context unchanged if the identifier is bound.
+    /// Values are stored independently of functions, so a value with the same identifier does not count as a binding.
+    pub fn try_set_function(
+        &mut self,
+        identifier: String,
+        function: Function,
+    ) -> EvalexprResult<()> {
+        match self.functions.entry(identifier) {
+            Entry::Occupied(entry) => Err(EvalexprError::DuplicateBinding(entry.key().clone())),
+            Entry::Vacant(entry) => {
+                entry.insert(function);
+                Ok(())
+            },
+        }
+    }
+
+    /// Binds the given macro function to the given identifier.
+    /// Calls of the identifier are dispatched to the macro before their arguments are evaluated,
+    /// so a macro takes precedence over a function with the same identifier.
+    ///
+    /// Returns `EvalexprError::DuplicateBinding` and leaves the context unchanged if the context is strict and the identifier is already bound to a macro function.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// context.insert_macro("is_defined".into(), MacroFunction::new(|arguments, context| {
+    ///     let mut variables = arguments.iter().flat_map(|argument| argument.iter_read_variable_identifiers());
+    ///     Ok(Value::from(variables.all(|identifier| context.get_value(identifier).is_some())))
+    /// })).unwrap(); // Do proper error handling here
+    /// context.set_value("a".into(), Value::from(1)).unwrap(); // Do proper error handling here
+    /// assert_eq!(eval_with_context("is_defined(a + 1)", &context), Ok(Value::from(true)));
+    /// assert_eq!(eval_with_context("is_defined(a, b)", &context), Ok(Value::from(false)));
+    /// ```
+    pub fn insert_macro(
+        &mut self,
+        identifier: String,
+        macro_function: MacroFunction,
+    ) -> EvalexprResult<()> {
+        match self.macro_functions.entry(identifier) {
+            Entry::Occupied(entry) if self.strict => {
+                Err(EvalexprError::DuplicateBinding(entry.key().clone()))
+            },
+            Entry::Occupied(mut entry) => {
+                entry.insert(macro_function);
+                Ok(())
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(macro_function);
+                Ok(())
+            },
+        }
+    }
+
+    /// Sets how the `==` and `!=` operators compare floats when evaluating with this context.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// assert_eq!(eval_with_context("0.1 + 0.2 == 0.3", &context), Ok(Value::from(false)));
+    /// context.set_float_equality(FloatEquality::Absolute(1e-9));
+    /// assert_eq!(eval_with_context("0.1 + 0.2 == 0.3", &context), Ok(Value::from(true)));
+    /// ```
+    pub fn set_float_equality(&mut self, float_equality: FloatEquality) {
+        self.float_equality = float_equality;
+    }
+
+    /// Sets the maximum amount of nested evaluations on a thread when evaluating with this context, replacing `MAX_EVALUATION_DEPTH`.
+    ///
+    /// Each nested evaluation needs stack space, so a higher limit may require threads with larger stacks.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// context.set_max_evaluation_depth(0);
+    /// assert_eq!(
+    ///     eval_with_context("1 + 2", &context),
+    ///     Err(EvalexprError::EvaluationTooDeep { limit: 0 })
+    /// );
+    /// ```
+    pub fn set_max_evaluation_depth(&mut self, max_evaluation_depth: usize) {
+        self.max_evaluation_depth = Some(max_evaluation_depth);
+    }
+
+    /// Sets the units that quantities can be created with when evaluating with this context, replacing the standard units.
+    #[cfg(feature = "units_support")]
+    pub fn set_unit_registry(&mut self, unit_registry: crate::UnitRegistry) {
+        self.unit_registry = Some(unit_registry);
+    }
+
+    /// Sets the clock that the `now` builtin function reads the current time from when evaluating with this context,
+    /// replacing the system clock.
+    #[cfg(feature = "time_support")]
+    pub fn set_clock(&mut self, clock: crate::Clock) {
+        self.clock = clock;
+    }
+
     /// Removes all variables from the context.
     /// This allows to reuse the context without allocating a new HashMap.
     ///
@@ -204,10 +502,11 @@ impl HashMapContext {
         self.variables.clear()
     }
 
-    /// Removes all functions from the context.
+    /// Removes all functions and macro functions from the context.
     /// This allows to reuse the context without allocating a new HashMap.
     pub fn clear_functions(&mut self) {
-        self.functions.clear()
+        self.functions.clear();
+        self.macro_functions.clear();
     }
 
     /// Removes all variables and functions from the context.
@@ -228,6 +527,64 @@ impl HashMapContext {
         self.clear_variables();
         self.clear_functions();
     }
+
+    /// Returns an iterator over the variables of this context and their values, sorted by identifier.
+    ///
+    /// Unlike `IterateVariablesContext::iter_variables`, the order is the same on every run, e.g. for golden tests.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use evalexpr::*;
+    ///
+    /// let context = context_map! { "b" => 2, "c" => 3, "a" => 1 }.unwrap(); // Do proper error handling here
+    /// let identifiers: Vec<_> = context.iter_variables_sorted().map(|(identifier, _)| identifier).collect();
+    /// assert_eq!(identifiers, ["a", "b", "c"]);
+    /// ```
+    pub fn iter_variables_sorted(&self) -> impl Iterator<Item = (&str, &Value)> {
+        sorted(&self.variables)
+            .into_iter()
+            .map(|(identifier, value)| (identifier.as_str(), value))
+    }
+
+    /// Returns a copy of the variables of this context, which can be compared with later snapshots using `ContextSnapshot::diff`.
+    /// Functions are not copied.
+    pub fn snapshot(&self) -> ContextSnapshot {
+        ContextSnapshot::new(self)
+    }
+
+    /// Constructs a `HashMapContext` from lines of the form `name=literal`, as given e.g. on the command line.
+    /// The literals are parsed like in an expression, see the `FromStr` implementation of `Value`.
+    ///
+    /// Returns `EvalexprError::InvalidAssignment` with the offending line if a line is not of this form.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use evalexpr::*;
+    ///
+    /// let context = HashMapContext::from_assignments(&["a=1", "b = \"two\"", "c=-3.5"]).unwrap();
+    /// assert_eq!(eval_with_context("(a, b, c)", &context), Ok(Value::from(vec![1.into(), "two".into(), (-3.5).into()])));
+    /// ```
+    pub fn from_assignments(lines: &[&str]) -> EvalexprResult<Self> {
+        let mut context = Self::new();
+        for line in lines {
+            let invalid_assignment = || EvalexprError::InvalidAssignment(line.to_string());
+            let (identifier, literal) = line.split_once('=').ok_or_else(invalid_assignment)?;
+            let identifier = identifier.trim();
+            let is_identifier = matches!(
+                token::tokenize(identifier, &ParseOptions::default()).as_deref(),
+                Ok([Token::Identifier(token)]) if token == identifier
+            );
+            if !is_identifier {
+                return Err(invalid_assignment());
+            }
+
+            let value = literal.parse().map_err(|_| invalid_assignment())?;
+            context.set_value(identifier.to_string(), value)?;
+        }
+        Ok(context)
+    }
 }
 
 impl Context for HashMapContext {
@@ -237,7 +594,7 @@ impl Context for HashMapContext {
 
     fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
         if let Some(function) = self.functions.get(identifier) {
-            function.call(argument)
+            function.call(identifier, argument)
         } else {
             Err(EvalexprError::FunctionIdentifierNotFound(
                 identifier.to_string(),
@@ -245,6 +602,10 @@ impl Context for HashMapContext {
         }
     }
 
+    fn has_function(&self, identifier: &str) -> bool {
+        self.functions.contains_key(identifier)
+    }
+
     fn are_builtin_functions_disabled(&self) -> bool {
         self.without_builtin_functions
     }
@@ -253,10 +614,38 @@ impl Context for HashMapContext {
         self.without_builtin_functions = disabled;
         Ok(())
     }
+
+    fn float_equality(&self) -> FloatEquality {
+        self.float_equality
+    }
+
+    fn max_evaluation_depth(&self) -> usize {
+        self.max_evaluation_depth.unwrap_or(MAX_EVALUATION_DEPTH)
+    }
+
+    fn get_macro_function(&self, identifier: &str) -> Option<&MacroFunction> {
+        self.macro_functions.get(identifier)
+    }
+
+    #[cfg(feature = "units_support")]
+    fn unit_registry(&self) -> &crate::UnitRegistry {
+        self.unit_registry
+            .as_ref()
+            .unwrap_or_else(|| crate::UnitRegistry::standard())
+    }
+
+    #[cfg(feature = "time_support")]
+    fn clock(&self) -> crate::Clock {
+        self.clock.clone()
+    }
 }
 
 impl ContextWithMutableVariables for HashMapContext {
     fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+        if self.strict {
+            return self.try_set_value(identifier, value);
+        }
+
         if let Some(existing_value) = self.variables.get_mut(&identifier) {
             if ValueType::from(&existing_value) == ValueType::from(&value) {
                 *existing_value = value;
@@ -274,6 +663,10 @@ impl ContextWithMutableVariables for HashMapContext {
 
 impl ContextWithMutableFunctions for HashMapContext {
     fn set_function(&mut self, identifier: String, function: Function) -> EvalexprResult<()> {
+        if self.strict {
+            return self.try_set_function(identifier, function);
+        }
+
         self.functions.insert(identifier, function);
         Ok(())
     }
@@ -298,6 +691,675 @@ impl IterateVariablesContext for HashMapContext {
     }
 }
 
+impl fmt::Debug for HashMapContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let functions = sorted(&self.functions)
+            .into_iter()
+            .map(|(identifier, function)| (identifier, Binding::Function(function)))
+            .collect();
+        let macro_functions = sorted(&self.macro_functions)
+            .into_iter()
+            .map(|(identifier, _)| (identifier, Binding::MacroFunction))
+            .collect();
+
+        let mut debug = f.debug_struct("HashMapContext");
+        debug
+            .field("variables", &SortedBindings(sorted(&self.variables)))
+            .field("functions", &SortedBindings(functions))
+            .field("macro_functions", &SortedBindings(macro_functions))
+            .field("without_builtin_functions", &self.without_builtin_functions)
+            .field("strict", &self.strict)
+            .field("float_equality", &self.float_equality)
+            .field("max_evaluation_depth", &self.max_evaluation_depth);
+        #[cfg(feature = "units_support")]
+        debug.field("unit_registry", &self.unit_registry);
+        #[cfg(feature = "time_support")]
+        debug.field("clock", &self.clock);
+        debug.finish()
+    }
+}
+
+/// Writes one binding per line like `a = 1`, sorted by identifier.
+/// Variables come first, followed by functions written like `f = <function/2>` and macro functions written like `m = <macro>`.
+impl fmt::Display for HashMapContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let variables = sorted(&self.variables)
+            .into_iter()
+            .map(|(identifier, value)| (identifier, Binding::Variable(value)));
+        let functions = sorted(&self.functions)
+            .into_iter()
+            .map(|(identifier, function)| (identifier, Binding::Function(function)));
+        let macro_functions = sorted(&self.macro_functions)
+            .into_iter()
+            .map(|(identifier, _)| (identifier, Binding::MacroFunction));
+
+        for (index, (identifier, binding)) in variables
+            .chain(functions)
+            .chain(macro_functions)
+            .enumerate()
+        {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{} = {}", identifier, binding)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the entries of the given map sorted by key.
+fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by_key(|(identifier, _)| *identifier);
+    entries
+}
+
+/// A binding of a `HashMapContext` as it is written by its `Debug` and `Display` implementations.
+enum Binding<'a> {
+    Variable(&'a Value),
+    Function(&'a Function),
+    MacroFunction,
+}
+
+impl fmt::Display for Binding<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Binding::Variable(value) => write!(f, "{}", value),
+            Binding::Function(function) => match function.argument_amount() {
+                Some(argument_amount) => write!(f, "<function/{}>", argument_amount),
+                None => write!(f, "<function>"),
+            },
+            Binding::MacroFunction => write!(f, "<macro>"),
+        }
+    }
+}
+
+impl fmt::Debug for Binding<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            Binding::Variable(value) => write!(f, "{:?}", value),
+            binding => write!(f, "{}", binding),
+        }
+    }
+}
+
+/// Writes the given bindings as a map in the given order.
+struct SortedBindings<'a, V>(Vec<(&'a String, V)>);
+
+impl<V: fmt::Debug> fmt::Debug for SortedBindings<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_map()
+            .entries(self.0.iter().map(|(identifier, value)| (identifier, value)))
+            .finish()
+    }
+}
+
+/// A layered context, where each lookup is resolved by the first layer that knows the identifier.
+///
+/// Builtin functions are disabled if any of the layers disables them.
+/// They cannot be enabled or disabled through the layers, as the layers are borrowed immutably.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let overrides = context_map! { "a" => 10 }.unwrap(); // Do proper error handling here
+/// let defaults = context_map! { "a" => 1, "b" => 2 }.unwrap(); // Do proper error handling here
+/// let layers: &[&dyn Context] = &[&overrides, &defaults];
+/// assert_eq!(eval_with_context("a + b", &layers), Ok(Value::from(12)));
+/// ```
+impl Context for &[&dyn Context] {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        self.iter().find_map(|layer| layer.get_value(identifier))
+    }
+
+    fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+        for layer in self.iter() {
+            match layer.call_function(identifier, argument) {
+                Err(EvalexprError::FunctionIdentifierNotFound(_)) => continue,
+                result => return result,
+            }
+        }
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            identifier.to_string(),
+        ))
+    }
+
+    fn has_function(&self, identifier: &str) -> bool {
+        self.iter().any(|layer| layer.has_function(identifier))
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        self.iter()
+            .any(|layer| layer.are_builtin_functions_disabled())
+    }
+
+    /// Builtin functions can't be enabled or disabled for a slice of layers.
+    fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+        builtin_functions_cannot_be_set(disabled)
+    }
+
+    /// Floats are compared like in the first layer, or exactly if there are no layers.
+    fn float_equality(&self) -> FloatEquality {
+        self.first()
+            .map_or(FloatEquality::Exact, |layer| layer.float_equality())
+    }
+
+    /// The maximum evaluation depth is the one of the first layer, or `MAX_EVALUATION_DEPTH` if there are no layers.
+    fn max_evaluation_depth(&self) -> usize {
+        self.first()
+            .map_or(MAX_EVALUATION_DEPTH, |layer| layer.max_evaluation_depth())
+    }
+
+    fn get_macro_function(&self, identifier: &str) -> Option<&MacroFunction> {
+        self.iter()
+            .find_map(|layer| layer.get_macro_function(identifier))
+    }
+
+    /// Units are defined like in the first layer, or by the standard units if there are no layers.
+    #[cfg(feature = "units_support")]
+    fn unit_registry(&self) -> &crate::UnitRegistry {
+        self.first().map_or_else(
+            || crate::UnitRegistry::standard(),
+            |layer| layer.unit_registry(),
+        )
+    }
+
+    /// The clock is the one of the first layer, or the system clock if there are no layers.
+    #[cfg(feature = "time_support")]
+    fn clock(&self) -> crate::Clock {
+        self.first()
+            .map_or_else(crate::Clock::system, |layer| layer.clock())
+    }
+}
+
+/// Forwards to the referenced context, such that a context of unknown type, like the one passed to a `MacroFunction`, can evaluate expressions.
+///
+/// Builtin functions can't be enabled or disabled through the reference, as the context is borrowed immutably.
+impl Context for &dyn Context {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        (**self).get_value(identifier)
+    }
+
+    fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+        (**self).call_function(identifier, argument)
+    }
+
+    fn has_function(&self, identifier: &str) -> bool {
+        (**self).has_function(identifier)
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        (**self).are_builtin_functions_disabled()
+    }
+
+    fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+        builtin_functions_cannot_be_set(disabled)
+    }
+
+    fn float_equality(&self) -> FloatEquality {
+        (**self).float_equality()
+    }
+
+    fn max_evaluation_depth(&self) -> usize {
+        (**self).max_evaluation_depth()
+    }
+
+    fn get_macro_function(&self, identifier: &str) -> Option<&MacroFunction> {
+        (**self).get_macro_function(identifier)
+    }
+
+    #[cfg(feature = "units_support")]
+    fn unit_registry(&self) -> &crate::UnitRegistry {
+        (**self).unit_registry()
+    }
+
+    #[cfg(feature = "time_support")]
+    fn clock(&self) -> crate::Clock {
+        (**self).clock()
+    }
+}
+
+/// A context that memoizes the variable lookups of the context it wraps.
+///
+/// Each identifier is looked up in the wrapped context at most once, which avoids resolving the same variable repeatedly,
+/// e.g. through the layers of a layered context when evaluating many expressions.
+/// Function calls are not cached.
+///
+/// The wrapped context is borrowed immutably, but may still change its values through interior mutability.
+/// It must not do so during the lifetime of the cache, as the cache would keep returning the old values.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let context = context_map! { "a" => 1 }.unwrap(); // Do proper error handling here
+/// let cached = CachingContext::new(&context);
+/// assert_eq!(eval_with_context("a + a", &cached), Ok(Value::from(2)));
+/// ```
+#[derive(Debug)]
+pub struct CachingContext<'a, C: Context + ?Sized> {
+    context: &'a C,
+    cache: RefCell<HashMap<String, Option<&'a Value>>>,
+}
+
+impl<'a, C: Context + ?Sized> CachingContext<'a, C> {
+    /// Constructs a `CachingContext` with an empty cache that wraps the given context.
+    pub fn new(context: &'a C) -> Self {
+        Self {
+            context,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the wrapped context.
+    pub fn inner(&self) -> &'a C {
+        self.context
+    }
+
+    /// Removes all cached lookups, such that changes of the wrapped context become visible.
+    pub fn clear_cache(&mut self) {
+        self.cache.get_mut().clear()
+    }
+}
+
+impl<'a, C: Context + ?Sized> Context for CachingContext<'a, C> {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        if let Some(value) = self.cache.borrow().get(identifier) {
+            return *value;
+        }
+
+        let value = self.context.get_value(identifier);
+        self.cache
+            .borrow_mut()
+            .insert(identifier.to_string(), value);
+        value
+    }
+
+    fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+        self.context.call_function(identifier, argument)
+    }
+
+    fn has_function(&self, identifier: &str) -> bool {
+        self.context.has_function(identifier)
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        self.context.are_builtin_functions_disabled()
+    }
+
+    /// Builtin functions can't be enabled or disabled for `CachingContext`, as the wrapped context is borrowed immutably.
+    fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+        builtin_functions_cannot_be_set(disabled)
+    }
+
+    fn float_equality(&self) -> FloatEquality {
+        self.context.float_equality()
+    }
+
+    fn max_evaluation_depth(&self) -> usize {
+        self.context.max_evaluation_depth()
+    }
+
+    fn get_macro_function(&self, identifier: &str) -> Option<&MacroFunction> {
+        self.context.get_macro_function(identifier)
+    }
+
+    #[cfg(feature = "units_support")]
+    fn unit_registry(&self) -> &crate::UnitRegistry {
+        self.context.unit_registry()
+    }
+
+    #[cfg(feature = "time_support")]
+    fn clock(&self) -> crate::Clock {
+        self.context.clock()
+    }
+}
+
+/// A context that records which variables are read from the context it wraps.
+///
+/// Unlike `Node::iter_variable_identifiers`, which lists the variables an expression references,
+/// this records only the variables that an evaluation actually read.
+/// For example, the fallback of `try_or` is not evaluated if the first argument succeeds, so its variables are not recorded.
+/// Variables are recorded whether or not the wrapped context binds them, as the absence of a variable may influence the result as well.
+/// Function calls are not recorded.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+/// use std::collections::HashSet;
+///
+/// let context = context_map! { "a" => 1, "b" => 2, "c" => 3 }.unwrap(); // Do proper error handling here
+/// let recording = RecordingContext::new(&context);
+/// assert_eq!(eval_with_context("try_or(a + b, c)", &recording), Ok(Value::from(3)));
+/// assert_eq!(
+///     recording.accessed_variables(),
+///     HashSet::from(["a".to_string(), "b".to_string()])
+/// );
+/// ```
+#[derive(Debug)]
+pub struct RecordingContext<'a, C: Context + ?Sized> {
+    context: &'a C,
+    accessed_variables: RefCell<HashSet<String>>,
+}
+
+impl<'a, C: Context + ?Sized> RecordingContext<'a, C> {
+    /// Constructs a `RecordingContext` without recorded variables that wraps the given context.
+    pub fn new(context: &'a C) -> Self {
+        Self {
+            context,
+            accessed_variables: RefCell::new(HashSet::new()),
+        }
+    }
+
+    /// Returns the wrapped context.
+    pub fn inner(&self) -> &'a C {
+        self.context
+    }
+
+    /// Returns the identifiers of all variables read since the construction or the last call of `clear_accessed_variables`.
+    pub fn accessed_variables(&self) -> HashSet<String> {
+        self.accessed_variables.borrow().clone()
+    }
+
+    /// Forgets the recorded variables, e.g. to record the variables of the next evaluation separately.
+    pub fn clear_accessed_variables(&mut self) {
+        self.accessed_variables.get_mut().clear()
+    }
+}
+
+impl<'a, C: Context + ?Sized> Context for RecordingContext<'a, C> {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        let mut accessed_variables = self.accessed_variables.borrow_mut();
+        if !accessed_variables.contains(identifier) {
+            accessed_variables.insert(identifier.to_string());
+        }
+        self.context.get_value(identifier)
+    }
+
+    fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+        self.context.call_function(identifier, argument)
+    }
+
+    fn has_function(&self, identifier: &str) -> bool {
+        self.context.has_function(identifier)
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        self.context.are_builtin_functions_disabled()
+    }
+
+    /// Builtin functions can't be enabled or disabled for `RecordingContext`, as the wrapped context is borrowed immutably.
+    fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+        builtin_functions_cannot_be_set(disabled)
+    }
+
+    fn float_equality(&self) -> FloatEquality {
+        self.context.float_equality()
+    }
+
+    fn max_evaluation_depth(&self) -> usize {
+        self.context.max_evaluation_depth()
+    }
+
+    fn get_macro_function(&self, identifier: &str) -> Option<&MacroFunction> {
+        self.context.get_macro_function(identifier)
+    }
+
+    #[cfg(feature = "units_support")]
+    fn unit_registry(&self) -> &crate::UnitRegistry {
+        self.context.unit_registry()
+    }
+
+    #[cfg(feature = "time_support")]
+    fn clock(&self) -> crate::Clock {
+        self.context.clock()
+    }
+}
+
+/// A context that adds a stack of variable scopes on top of the context it wraps.
+///
+/// Variables are resolved in the innermost scope that binds them, falling through to the wrapped context if no scope does.
+/// This allows to shadow variables temporarily, e.g. loop variables in a template, and to restore the previous bindings by popping the scope.
+/// Functions are always resolved by the wrapped context.
+///
+/// Assignments within expressions bind the variable in the innermost scope, so they never change the wrapped context.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let base = context_map! { "x" => 1, "y" => 2 }.unwrap(); // Do proper error handling here
+/// let mut scoped = ScopedContext::new(&base);
+/// scoped.push_scope();
+/// scoped.insert_in_current_scope("x".into(), 10.into()).unwrap();
+/// assert_eq!(eval_with_context("x + y", &scoped), Ok(Value::from(12)));
+/// scoped.pop_scope();
+/// assert_eq!(eval_with_context("x + y", &scoped), Ok(Value::from(3)));
+/// ```
+#[derive(Debug)]
+pub struct ScopedContext<'a, C: Context + ?Sized> {
+    context: &'a C,
+    scopes: Vec<HashMap<String, Value>>,
+}
+
+impl<'a, C: Context + ?Sized> ScopedContext<'a, C> {
+    /// Constructs a `ScopedContext` without scopes that wraps the given context.
+    pub fn new(context: &'a C) -> Self {
+        Self {
+            context,
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Returns the wrapped context.
+    pub fn inner(&self) -> &'a C {
+        self.context
+    }
+
+    /// Returns the amount of scopes on the stack.
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Pushes a new empty scope, which becomes the current scope.
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    /// Removes the current scope and returns its bindings, making the bindings of the previous scope visible again.
+    /// Returns `None` if there is no scope.
+    pub fn pop_scope(&mut self) -> Option<HashMap<String, Value>> {
+        self.scopes.pop()
+    }
+
+    /// Binds the given value to the given identifier in the current scope, shadowing the bindings of outer scopes and of the wrapped context.
+    /// An existing binding of the current scope is replaced, also with a value of a different type.
+    ///
+    /// Returns `EvalexprError::ContextNotMutable` if there is no scope.
+    pub fn insert_in_current_scope(
+        &mut self,
+        identifier: String,
+        value: Value,
+    ) -> EvalexprResult<()> {
+        let scope = self
+            .scopes
+            .last_mut()
+            .ok_or(EvalexprError::ContextNotMutable)?;
+        scope.insert(identifier, value);
+        Ok(())
+    }
+}
+
+impl<'a, C: Context + ?Sized> Context for ScopedContext<'a, C> {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(identifier))
+            .or_else(|| self.context.get_value(identifier))
+    }
+
+    fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+        self.context.call_function(identifier, argument)
+    }
+
+    fn has_function(&self, identifier: &str) -> bool {
+        self.context.has_function(identifier)
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        self.context.are_builtin_functions_disabled()
+    }
+
+    /// Builtin functions can't be enabled or disabled for `ScopedContext`, as the wrapped context is borrowed immutably.
+    fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+        builtin_functions_cannot_be_set(disabled)
+    }
+
+    fn float_equality(&self) -> FloatEquality {
+        self.context.float_equality()
+    }
+
+    fn max_evaluation_depth(&self) -> usize {
+        self.context.max_evaluation_depth()
+    }
+
+    fn get_macro_function(&self, identifier: &str) -> Option<&MacroFunction> {
+        self.context.get_macro_function(identifier)
+    }
+
+    #[cfg(feature = "units_support")]
+    fn unit_registry(&self) -> &crate::UnitRegistry {
+        self.context.unit_registry()
+    }
+
+    #[cfg(feature = "time_support")]
+    fn clock(&self) -> crate::Clock {
+        self.context.clock()
+    }
+}
+
+impl<'a, C: Context + ?Sized> ContextWithMutableVariables for ScopedContext<'a, C> {
+    /// Binds the variable in the current scope, see `ScopedContext::insert_in_current_scope`.
+    fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+        self.insert_in_current_scope(identifier, value)
+    }
+}
+
+/// A context that resolves variables lazily with a closure, memoizing the result for each identifier.
+///
+/// The closure is only called for variables that are actually read during evaluation, and at most once per identifier,
+/// also if the variable is read multiple times or by multiple expressions.
+/// This is useful if variables are expensive to compute, e.g. if they require a database query.
+/// If the closure returns `None`, the variable is unbound, which is memoized as well.
+/// Function calls are not supported, except for builtin functions.
+/// Calling any other identifier fails, and the closure is called for it to tell if a variable was called, see `EvalexprError::NotCallable`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let context = LazyContext::new(|identifier| match identifier {
+///     "price" => Some(Value::from(20)),
+///     _ => None,
+/// });
+/// assert_eq!(eval_with_context("price * 2 + price", &context), Ok(Value::from(60)));
+/// assert_eq!(
+///     eval_with_context("discount", &context),
+///     Err(EvalexprError::VariableIdentifierNotFound("discount".to_string()))
+/// );
+/// ```
+pub struct LazyContext<F> {
+    resolver: F,
+    /// The memoized variables, where unbound identifiers are memoized as `None`.
+    /// The values are boxed, such that references to them stay valid while the map grows.
+    values: RefCell<HashMap<String, Option<Box<Value>>>>,
+    without_builtin_functions: bool,
+}
+
+impl<F: Fn(&str) -> Option<Value>> LazyContext<F> {
+    /// Constructs a `LazyContext` that resolves variables with the given closure.
+    pub fn new(resolver: F) -> Self {
+        Self {
+            resolver,
+            values: RefCell::new(HashMap::new()),
+            without_builtin_functions: false,
+        }
+    }
+
+    /// Removes all memoized variables, such that the closure is called again on the next lookup of each identifier.
+    pub fn clear_cache(&mut self) {
+        self.values.get_mut().clear();
+    }
+}
+
+impl<F> fmt::Debug for LazyContext<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let values = self.values.borrow();
+        let mut entries: Vec<_> = values.iter().collect();
+        entries.sort_unstable_by_key(|(identifier, _)| *identifier);
+        f.debug_map()
+            .entries(
+                entries
+                    .into_iter()
+                    .map(|(identifier, value)| (identifier, value.as_deref())),
+            )
+            .finish()
+    }
+}
+
+impl<F: Fn(&str) -> Option<Value>> Context for LazyContext<F> {
+    #[allow(unsafe_code)]
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        let memoized = self
+            .values
+            .borrow()
+            .get(identifier)
+            .map(|value| value.as_deref().map(|value| value as *const Value));
+        let value = memoized.unwrap_or_else(|| {
+            // The closure is called without borrowing the map, and a value it memoized meanwhile is kept
+            let value = (self.resolver)(identifier).map(Box::new);
+            let mut values = self.values.borrow_mut();
+            let value = values.entry(identifier.to_string()).or_insert(value);
+            value.as_deref().map(|value| value as *const Value)
+        });
+
+        // SAFETY: The value is boxed, so it does not move when the map grows.
+        // Memoized values are neither replaced nor removed through a shared reference, and never mutated,
+        // so the value lives at least as long as the shared borrow of this context.
+        value.map(|value| unsafe { &*value })
+    }
+
+    fn call_function(&self, identifier: &str, _argument: &Value) -> EvalexprResult<Value> {
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            identifier.to_string(),
+        ))
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        self.without_builtin_functions
+    }
+
+    fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+        self.without_builtin_functions = disabled;
+        Ok(())
+    }
+}
+
+fn builtin_functions_cannot_be_set(disabled: bool) -> EvalexprResult<()> {
+    if disabled {
+        Err(EvalexprError::BuiltinFunctionsCannotBeDisabled)
+    } else {
+        Err(EvalexprError::BuiltinFunctionsCannotBeEnabled)
+    }
+}
+
 /// This macro provides a convenient syntax for creating a static context.
 ///
 /// # Examples
@@ -0,0 +1,106 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{Context, EvalexprError, EvalexprResult, FloatEquality, HashMapContext, Value};
+
+/// A copy of the variables of a `HashMapContext` at one point in time, as returned by `HashMapContext::snapshot`.
+///
+/// Two snapshots are compared with `ContextSnapshot::diff`, e.g. to find out why a rule decides differently than before.
+/// A snapshot is a context itself, which binds the variables of the snapshot.
+/// The functions of the `HashMapContext` are not copied, so only builtin functions can be called.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContextSnapshot {
+    variables: BTreeMap<String, Value>,
+    without_builtin_functions: bool,
+    float_equality: FloatEquality,
+}
+
+/// A binding that differs between two snapshots, as returned by `ContextSnapshot::diff`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BindingChange {
+    /// The identifier of the binding.
+    pub name: String,
+    /// The value in the old snapshot, or `None` if the binding was added.
+    pub old: Option<Value>,
+    /// The value in the new snapshot, or `None` if the binding was removed.
+    pub new: Option<Value>,
+}
+
+impl ContextSnapshot {
+    pub(crate) fn new(context: &HashMapContext) -> Self {
+        Self {
+            variables: context
+                .variables
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            without_builtin_functions: context.without_builtin_functions,
+            float_equality: context.float_equality,
+        }
+    }
+
+    /// Returns the bindings that differ between this snapshot and the given newer one, sorted by their identifiers.
+    ///
+    /// A binding differs if it was added, removed, or if its values are not equal according to `PartialEq`.
+    /// The comparison is strict, so e.g. `1` and `1.0` differ, and a float `NaN` always differs from itself.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let mut context = context_map! { "a" => 1, "b" => 2 }.unwrap(); // Do proper error handling here
+    /// let old = context.snapshot();
+    /// context.set_value("b".into(), 3.into()).unwrap(); // Do proper error handling here
+    /// assert_eq!(
+    ///     old.diff(&context.snapshot()),
+    ///     vec![BindingChange { name: "b".into(), old: Some(2.into()), new: Some(3.into()) }]
+    /// );
+    /// ```
+    pub fn diff(&self, new: &ContextSnapshot) -> Vec<BindingChange> {
+        let names: BTreeSet<&String> = self.variables.keys().chain(new.variables.keys()).collect();
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let old_value = self.variables.get(name);
+                let new_value = new.variables.get(name);
+                (old_value != new_value).then(|| BindingChange {
+                    name: name.clone(),
+                    old: old_value.cloned(),
+                    new: new_value.cloned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the variables of this snapshot, sorted by their identifiers.
+    pub fn iter_variables(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+    }
+}
+
+impl Context for ContextSnapshot {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        self.variables.get(identifier)
+    }
+
+    fn call_function(&self, identifier: &str, _argument: &Value) -> EvalexprResult<Value> {
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            identifier.to_string(),
+        ))
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        self.without_builtin_functions
+    }
+
+    fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+        self.without_builtin_functions = disabled;
+        Ok(())
+    }
+
+    fn float_equality(&self) -> FloatEquality {
+        self.float_equality
+    }
+}
@@ -60,6 +60,7 @@ impl fmt::Display for EvalexprError {
                 expected_length.end(),
                 actual
             ),
+            ExpectedMap { actual } => write!(f, "Expected a Value::Map, but got {:?}.", actual),
             ExpectedEmpty { actual } => write!(f, "Expected a Value::Empty, but got {:?}.", actual),
             AppendedToLeafNode => write!(f, "Tried to append a node to a leaf node."),
             PrecedenceViolation => write!(
@@ -76,9 +77,48 @@ impl fmt::Display for EvalexprError {
                 "Function identifier is not bound to anything by context: {:?}.",
                 identifier
             ),
+            NotCallable(actual) => write!(
+                f,
+                "Only functions can be called, but {:?} was called like a function.",
+                actual
+            ),
+            FunctionReturnedWrongType {
+                identifier,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Function {:?} is declared to return {:?}, but returned {:?}.",
+                identifier, expected, actual
+            ),
             TypeError { expected, actual } => {
                 write!(f, "Expected one of {:?}, but got {:?}.", expected, actual)
             },
+            ElementTypeError {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Expected the element at index {} to be one of {:?}, but got {:?}.",
+                index, expected, actual
+            ),
+            IncomparableValues {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "The value {:?} at index {} cannot be compared with the values before it, \
+                 expected one of {:?}.",
+                actual, index, expected
+            ),
+            InvalidMapPair { index, actual } => write!(
+                f,
+                "Expected the element at index {} to be a pair of a string key and a value, but \
+                 got {:?}.",
+                index, actual
+            ),
             WrongTypeCombination { operator, actual } => write!(
                 f,
                 "The operator {:?} was called with a wrong combination of types: {:?}",
@@ -86,8 +126,37 @@ impl fmt::Display for EvalexprError {
             ),
             UnmatchedLBrace => write!(f, "Found an unmatched opening parenthesis '('."),
             UnmatchedRBrace => write!(f, "Found an unmatched closing parenthesis ')'."),
+            UnmatchedQuestionMark { span } => write!(
+                f,
+                "Found a question mark '?' at {:?} without a matching colon ':' of the \
+                 conditional operator `c ? a : b`.",
+                span
+            ),
+            UnmatchedColon { span } => write!(
+                f,
+                "Found a colon ':' at {:?} outside of a map literal without a matching question \
+                 mark '?' of the conditional operator `c ? a : b`.",
+                span
+            ),
+            UnmatchedLCurlyBrace => write!(f, "Found an unmatched opening curly brace '{{'."),
+            UnmatchedRCurlyBrace => write!(f, "Found an unmatched closing curly brace '}}'."),
+            InvalidMapEntry => write!(
+                f,
+                "Found an invalid map entry. Map entries must be of the form `key: value` with a \
+                 unique identifier or string as key."
+            ),
+            DuplicateRecordField { name, span } => write!(
+                f,
+                "The name {:?} at {:?} appears more than once in the arguments of `record`.",
+                name, span
+            ),
+            InvalidTrailingComma => write!(
+                f,
+                "Found a trailing comma that does not follow a tuple element."
+            ),
             UnmatchedDoubleQuote => write!(f, "Found an unmatched double quote '\"'"),
-            MissingOperatorOutsideOfBrace { .. } => write!(
+            UnmatchedInlineComment => write!(f, "Found an unmatched inline comment '/*'"),
+            MissingOperatorOutsideOfBrace => write!(
                 f,
                 "Found an opening parenthesis that is preceded by something that does not take \
                  any arguments on the right, or found a closing parenthesis that is succeeded by \
@@ -130,7 +199,83 @@ impl fmt::Display for EvalexprError {
                 "Regular expression {:?} is invalid: {:?}",
                 regex, message
             ),
+            InvalidIdentifier {
+                identifier,
+                reason,
+                span,
+            } => write!(
+                f,
+                "Invalid identifier {:?} at {:?}: {}",
+                identifier, span, reason
+            ),
+            OperatorDisabled { operator, span } => {
+                write!(f, "The operator {:?} at {:?} is disabled", operator, span)
+            },
+            InvalidOperatorRemap { symbol, reason } => write!(
+                f,
+                "The operator symbol {:?} cannot be remapped: {}",
+                symbol, reason
+            ),
+            UnbuildableOperator { operator, reason } => write!(
+                f,
+                "A node with the operator {:?} cannot be built: {}",
+                operator, reason
+            ),
+            TupleTooLarge { limit, actual } => write!(
+                f,
+                "The tuple is too large: its {} elements exceed the limit {}",
+                actual, limit
+            ),
+            ExpressionTooLarge { limit, actual } => write!(
+                f,
+                "The expression is too large: its size {} exceeds the limit {}",
+                actual, limit
+            ),
+            InvalidRoundingMode { mode } => write!(
+                f,
+                "Rounding mode {:?} is invalid, expected one of \"half_up\", \"half_even\", \
+                 \"half_down\" or \"toward_zero\"",
+                mode
+            ),
+            InvalidNumber { string } => write!(f, "The string {:?} is not a number", string),
+            InvalidRadix { radix } => write!(
+                f,
+                "Radix {} is invalid, expected a radix from 2 to 36",
+                radix
+            ),
+            InvalidQuantity { actual } => write!(
+                f,
+                "Expected a finite non-negative number, but got {:?}.",
+                actual
+            ),
+            UnitMismatch {
+                left,
+                right,
+                operator,
+            } => write!(
+                f,
+                "Operator {:?} cannot be applied to the units {:?} and {:?}, which measure \
+                 different dimensions.",
+                operator, left, right
+            ),
+            UnknownUnit(unit) => write!(f, "Unknown unit: {:?}.", unit),
+            InvalidTimeFormat { format } => write!(f, "Time format {:?} is invalid", format),
+            TimestampOutOfRange { timestamp } => write!(
+                f,
+                "The timestamp {} is out of the range of supported dates",
+                timestamp
+            ),
+            EvaluationTooDeep { limit } => write!(
+                f,
+                "The evaluation is nested within {} other evaluations, which is the limit",
+                limit
+            ),
             ContextNotMutable => write!(f, "Cannot manipulate context"),
+            DuplicateBinding(identifier) => write!(
+                f,
+                "Identifier is already bound by context: {:?}.",
+                identifier
+            ),
             BuiltinFunctionsCannotBeEnabled => {
                 write!(f, "This context does not allow enabling builtin functions")
             },
@@ -138,6 +283,37 @@ impl fmt::Display for EvalexprError {
                 write!(f, "This context does not allow disabling builtin functions")
             },
             IllegalEscapeSequence(string) => write!(f, "Illegal escape sequence: {}", string),
+            InvalidLiteral(string) => {
+                write!(f, "Expected a single literal value, but got {:?}", string)
+            },
+            InvalidAssignment(line) => write!(
+                f,
+                "Expected an assignment of the form `name=literal`, but got {:?}",
+                line
+            ),
+            UnsupportedFormatVersion { found, supported } => write!(
+                f,
+                "Operator tree format version {} is not supported, expected version {}",
+                found, supported
+            ),
+            InvalidBinaryFormat(reason) => write!(f, "Invalid binary operator tree: {}", reason),
+            UnencodableValue(value) => write!(
+                f,
+                "The constant {} cannot be written in binary format, as it contains a custom \
+                 value or a quantity",
+                value
+            ),
+            UnsupportedExcelConstruct { construct, span } => write!(
+                f,
+                "The Excel construct {:?} at bytes {:?} is not supported.",
+                construct, span
+            ),
+            ExcelCellReference { reference, span } => write!(
+                f,
+                "The Excel cell reference {:?} at bytes {:?} is not supported. Replace it with a \
+                 named variable that is bound in the context.",
+                reference, span
+            ),
             OutOfBoundsAccess => write!(f, "Tried to access a tuple or string at an invalid index"),
             CustomMessage(message) => write!(f, "Error: {}", message),
         }
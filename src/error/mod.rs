@@ -5,11 +5,14 @@
 //! The module also contains some helper functions starting with `expect_` that check for a condition and return `Err(_)` if the condition is not fulfilled.
 //! They are meant as shortcuts to not write the same error checking code everywhere.
 
-use std::ops::RangeInclusive;
+use std::ops::{Range, RangeInclusive};
 
 use crate::{token::PartialToken, value::value_type::ValueType};
 
-use crate::{operator::Operator, value::Value};
+use crate::{
+    operator::Operator,
+    value::{IntType, Value},
+};
 
 // Exclude error display code from test coverage, as the code does not make sense to test.
 #[cfg(not(tarpaulin_include))]
@@ -20,6 +23,7 @@ mod display;
 #[non_exhaustive]
 pub enum EvalexprError {
     /// An operator was called with a wrong amount of arguments.
+    /// When parsing, this is returned for operators with missing operands, like in `1 +`.
     WrongOperatorArgumentAmount {
         /// The expected amount of arguments.
         expected: usize,
@@ -95,6 +99,12 @@ pub enum EvalexprError {
         actual: Value,
     },
 
+    /// A map value was expected.
+    ExpectedMap {
+        /// The actual value.
+        actual: Value,
+    },
+
     /// An empty value was expected.
     ExpectedEmpty {
         /// The actual value.
@@ -116,6 +126,21 @@ pub enum EvalexprError {
     /// A `FunctionIdentifier` operation did not find its value in the context.
     FunctionIdentifierNotFound(String),
 
+    /// A value that is not a function was called like a function.
+    /// This is returned while building the operator tree if a literal is followed by parentheses, as in `5(3)`,
+    /// and during evaluation if a called identifier is not bound to a function, but to a variable.
+    NotCallable(Value),
+
+    /// A function created with `Function::with_return_type` returned a value of another type than declared.
+    FunctionReturnedWrongType {
+        /// The identifier of the function.
+        identifier: String,
+        /// The declared return type.
+        expected: ValueType,
+        /// The returned value.
+        actual: Value,
+    },
+
     /// A value has the wrong type.
     /// Only use this if there is no other error that describes the expected and provided types in more detail.
     TypeError {
@@ -125,6 +150,37 @@ pub enum EvalexprError {
         actual: Value,
     },
 
+    /// An element of a tuple has the wrong type, as in the argument of the builtin functions `any` and `all`.
+    ElementTypeError {
+        /// The index of the element in the tuple.
+        index: usize,
+        /// The expected types of the element.
+        expected: Vec<ValueType>,
+        /// The element.
+        actual: Value,
+    },
+
+    /// A value cannot be compared with the values before it, as by the builtin functions `min` and `max`,
+    /// or a NaN is compared by the three-way comparator `<=>`.
+    /// Numbers can be compared with numbers, and strings with strings.
+    IncomparableValues {
+        /// The index of the value among the compared values.
+        /// As the values before it are comparable with each other, it is the first index of an incomparable pair of values.
+        index: usize,
+        /// The types that could have been compared with the values before it.
+        expected: Vec<ValueType>,
+        /// The value that cannot be compared.
+        actual: Value,
+    },
+
+    /// An element of the tuple given to the builtin function `to_map` is not a pair of a string key and a value.
+    InvalidMapPair {
+        /// The index of the element in the tuple.
+        index: usize,
+        /// The element.
+        actual: Value,
+    },
+
     /// An operator is used with a wrong combination of types.
     WrongTypeCombination {
         /// The operator that whose evaluation caused the error.
@@ -136,12 +192,46 @@ pub enum EvalexprError {
     /// An opening brace without a matching closing brace was found.
     UnmatchedLBrace,
 
+    /// An opening curly brace without a matching closing curly brace was found.
+    UnmatchedLCurlyBrace,
+
+    /// A closing curly brace without a matching opening curly brace was found.
+    UnmatchedRCurlyBrace,
+
+    /// A map literal contains an entry that is not of the form `key: value` with an identifier or string as key,
+    /// or a key appears twice.
+    /// Also returned if an argument of `record(name = value, ...)` is not of the form `name = value`.
+    InvalidMapEntry,
+
+    /// A name appears twice in the arguments of `record(name = value, ...)`.
+    DuplicateRecordField {
+        /// The name that appears twice.
+        name: String,
+        /// The byte range of the second occurrence of the name in the expression.
+        span: Range<usize>,
+    },
+
     /// A closing brace without a matching opening brace was found.
     UnmatchedRBrace,
 
+    /// A question mark of the conditional operator `c ? a : b` without a matching colon was found.
+    UnmatchedQuestionMark {
+        /// The byte range of the question mark in the expression.
+        span: Range<usize>,
+    },
+
+    /// A colon outside of a map literal without a matching question mark of the conditional operator `c ? a : b` was found.
+    UnmatchedColon {
+        /// The byte range of the colon in the expression.
+        span: Range<usize>,
+    },
+
     /// A double quote without a matching second double quote was found.
     UnmatchedDoubleQuote,
 
+    /// An inline comment `/*` without a matching `*/` was found.
+    UnmatchedInlineComment,
+
     /// Left of an opening brace or right of a closing brace is a token that does not expect the brace next to it.
     /// For example, writing `4(5)` would yield this error, as the `4` does not have any operands.
     MissingOperatorOutsideOfBrace,
@@ -210,12 +300,168 @@ pub enum EvalexprError {
         message: String,
     },
 
+    /// An unknown rounding mode was passed to the `round` builtin function.
+    InvalidRoundingMode {
+        /// The unknown rounding mode.
+        mode: String,
+    },
+
+    /// A string passed to one of the `parse_int` or `parse_float` builtin functions is not a number.
+    InvalidNumber {
+        /// The string that is not a number.
+        string: String,
+    },
+
+    /// A radix outside of the range from 2 to 36 was passed to the `parse_int` builtin function.
+    InvalidRadix {
+        /// The invalid radix.
+        radix: IntType,
+    },
+
+    /// A negative or non-finite number was passed to one of the `format_bytes` or `format_duration` builtin functions.
+    InvalidQuantity {
+        /// The invalid number.
+        actual: Value,
+    },
+
+    /// An operator or function was applied to quantities whose units measure different dimensions,
+    /// or to a quantity and a plain number where both need to measure the same dimension, like for `+`.
+    /// The unit of a plain number is given as empty string.
+    UnitMismatch {
+        /// The unit of the left operand.
+        left: String,
+        /// The unit of the right operand, or the target unit of a conversion.
+        right: String,
+        /// The operator, or the function identifier of a conversion.
+        operator: Operator,
+    },
+
+    /// A unit name is not defined by the `UnitRegistry` of the context.
+    UnknownUnit(String),
+
+    /// An invalid format string was passed to the `date::format` builtin function.
+    InvalidTimeFormat {
+        /// The invalid format string.
+        format: String,
+    },
+
+    /// A Unix timestamp is outside of the range of points in time that can be converted into a date and time.
+    TimestampOutOfRange {
+        /// The timestamp in seconds.
+        timestamp: IntType,
+    },
+
+    /// An evaluation was started within `limit` nested evaluations, e.g. by functions that evaluate rules which call these functions again.
+    /// See `EvalContext`.
+    EvaluationTooDeep {
+        /// The maximum amount of nested evaluations.
+        limit: usize,
+    },
+
     /// A modification was attempted on a `Context` that does not allow modifications.
     ContextNotMutable,
 
+    /// An identifier was bound in a `HashMapContext` that already binds it, and the binding was not allowed to overwrite the existing one.
+    /// This is returned by `HashMapContext::try_set_value` and `HashMapContext::try_set_function`, and by all assignments to a strict `HashMapContext`.
+    DuplicateBinding(String),
+
+    /// An identifier was rejected by the identifier validator set in the `ParseOptions`.
+    InvalidIdentifier {
+        /// The rejected identifier.
+        identifier: String,
+        /// The reason for the rejection given by the validator.
+        reason: String,
+        /// The byte range of the identifier in the expression.
+        span: Range<usize>,
+    },
+
+    /// A trailing comma before a closing parenthesis, a semicolon or the end of an expression does not follow a tuple element,
+    /// like in `(,)` or `(1, 2,,)`.
+    InvalidTrailingComma,
+
+    /// An expression string contains an operator that is not allowed by the operator filter set in the `ParseOptions`.
+    OperatorDisabled {
+        /// The disabled operator.
+        operator: Operator,
+        /// The byte range of the operator in the expression.
+        /// For function calls, this is the range of the function identifier, and for map literals the range of the whole literal.
+        span: Range<usize>,
+    },
+
+    /// An operator symbol cannot be remapped with `ParseOptions::remap_operator`.
+    InvalidOperatorRemap {
+        /// The symbol that was remapped.
+        symbol: String,
+        /// The reason why the symbol cannot be remapped.
+        reason: String,
+    },
+
+    /// A `NodeBuilder` cannot build a node with the given operator.
+    UnbuildableOperator {
+        /// The operator.
+        operator: Operator,
+        /// The reason why the node cannot be built.
+        reason: String,
+    },
+
+    /// An expression string exceeds the maximum length in bytes or the maximum amount of tokens set in the `ParseOptions`.
+    ExpressionTooLarge {
+        /// The exceeded limit.
+        limit: usize,
+        /// The length or amount of tokens of the expression.
+        /// If tokenization was stopped early because of the token limit, this is a lower bound of the amount of tokens.
+        actual: usize,
+    },
+
+    /// An expression string aggregates more elements into a tuple than the maximum tuple size set in the `ParseOptions`.
+    TupleTooLarge {
+        /// The exceeded limit.
+        limit: usize,
+        /// The amount of elements of the tuple.
+        actual: usize,
+    },
+
     /// An escape sequence within a string literal is illegal.
     IllegalEscapeSequence(String),
 
+    /// A string that was expected to be a single literal value is not.
+    InvalidLiteral(String),
+
+    /// A line that was expected to be an assignment of the form `name=literal` is not.
+    InvalidAssignment(String),
+
+    /// An operator tree in binary format was written in a format version that is not supported by this version of the crate.
+    UnsupportedFormatVersion {
+        /// The format version of the operator tree.
+        found: u8,
+        /// The format version supported by this version of the crate.
+        supported: u8,
+    },
+
+    /// Bytes that were expected to be an operator tree in binary format are not.
+    InvalidBinaryFormat(String),
+
+    /// A constant of an operator tree cannot be written in binary format, because it is or contains a custom value or a quantity.
+    UnencodableValue(Value),
+
+    /// An Excel formula given to `from_excel_formula` contains a construct that is not supported by the conversion,
+    /// like the percent operator `%`, a range `A1:B2` or a function without a counterpart among the builtin functions.
+    UnsupportedExcelConstruct {
+        /// The unsupported part of the formula.
+        construct: String,
+        /// The byte range of the construct in the formula.
+        span: Range<usize>,
+    },
+
+    /// An Excel formula given to `from_excel_formula` refers to a cell, like `A1` or `$B$2`.
+    /// Cell references need to be replaced by named variables, which are bound in the context.
+    ExcelCellReference {
+        /// The cell reference.
+        reference: String,
+        /// The byte range of the cell reference in the formula.
+        span: Range<usize>,
+    },
+
     /// This context does not allow enabling builtin functions.
     BuiltinFunctionsCannotBeEnabled,
 
@@ -256,6 +502,34 @@ impl EvalexprError {
         EvalexprError::TypeError { actual, expected }
     }
 
+    pub(crate) fn element_type_error(
+        index: usize,
+        expected: Vec<ValueType>,
+        actual: Value,
+    ) -> Self {
+        EvalexprError::ElementTypeError {
+            index,
+            expected,
+            actual,
+        }
+    }
+
+    pub(crate) fn invalid_map_pair(index: usize, actual: Value) -> Self {
+        EvalexprError::InvalidMapPair { index, actual }
+    }
+
+    pub(crate) fn incomparable_values(
+        index: usize,
+        expected: Vec<ValueType>,
+        actual: Value,
+    ) -> Self {
+        EvalexprError::IncomparableValues {
+            index,
+            expected,
+            actual,
+        }
+    }
+
     /// Constructs `EvalexprError::WrongTypeCombination{operator, actual}`.
     pub fn wrong_type_combination(operator: Operator, actual: Vec<ValueType>) -> Self {
         EvalexprError::WrongTypeCombination { operator, actual }
@@ -312,6 +586,11 @@ impl EvalexprError {
         }
     }
 
+    /// Constructs `EvalexprError::ExpectedMap{actual}`.
+    pub fn expected_map(actual: Value) -> Self {
+        EvalexprError::ExpectedMap { actual }
+    }
+
     /// Constructs `EvalexprError::ExpectedEmpty{actual}`.
     pub fn expected_empty(actual: Value) -> Self {
         EvalexprError::ExpectedEmpty { actual }
@@ -325,7 +604,28 @@ impl EvalexprError {
             ValueType::Float => Self::expected_float(actual),
             ValueType::Boolean => Self::expected_boolean(actual),
             ValueType::Tuple => Self::expected_tuple(actual),
+            ValueType::Map => Self::expected_map(actual),
             ValueType::Empty => Self::expected_empty(actual),
+            ValueType::Custom => Self::type_error(actual, vec![ValueType::Custom]),
+            #[cfg(feature = "units_support")]
+            ValueType::Quantity => Self::type_error(actual, vec![ValueType::Quantity]),
+        }
+    }
+
+    pub(crate) fn not_callable(actual: Value) -> Self {
+        EvalexprError::NotCallable(actual)
+    }
+
+    /// Constructs `EvalexprError::FunctionReturnedWrongType{identifier, expected, actual}`.
+    pub fn function_returned_wrong_type(
+        identifier: &str,
+        expected: ValueType,
+        actual: Value,
+    ) -> Self {
+        EvalexprError::FunctionReturnedWrongType {
+            identifier: identifier.to_string(),
+            expected,
+            actual,
         }
     }
 
@@ -366,10 +666,329 @@ impl EvalexprError {
         EvalexprError::ModulationError { dividend, divisor }
     }
 
+    #[cfg(feature = "units_support")]
+    pub(crate) fn unit_mismatch(left: String, right: String, operator: Operator) -> Self {
+        EvalexprError::UnitMismatch {
+            left,
+            right,
+            operator,
+        }
+    }
+
+    #[cfg(feature = "time_support")]
+    pub(crate) fn invalid_time_format(format: String) -> Self {
+        EvalexprError::InvalidTimeFormat { format }
+    }
+
+    #[cfg(feature = "time_support")]
+    pub(crate) fn timestamp_out_of_range(timestamp: IntType) -> Self {
+        EvalexprError::TimestampOutOfRange { timestamp }
+    }
+
+    pub(crate) fn invalid_identifier(
+        identifier: String,
+        reason: String,
+        span: Range<usize>,
+    ) -> Self {
+        EvalexprError::InvalidIdentifier {
+            identifier,
+            reason,
+            span,
+        }
+    }
+
+    pub(crate) fn operator_disabled(operator: Operator, span: Range<usize>) -> Self {
+        EvalexprError::OperatorDisabled { operator, span }
+    }
+
+    pub(crate) fn invalid_operator_remap(symbol: &str, reason: String) -> Self {
+        EvalexprError::InvalidOperatorRemap {
+            symbol: symbol.to_string(),
+            reason,
+        }
+    }
+
+    pub(crate) fn unbuildable_operator(operator: Operator, reason: &str) -> Self {
+        EvalexprError::UnbuildableOperator {
+            operator,
+            reason: reason.to_string(),
+        }
+    }
+
+    pub(crate) fn evaluation_too_deep(limit: usize) -> Self {
+        EvalexprError::EvaluationTooDeep { limit }
+    }
+
+    pub(crate) fn expression_too_large(limit: usize, actual: usize) -> Self {
+        EvalexprError::ExpressionTooLarge { limit, actual }
+    }
+
+    pub(crate) fn tuple_too_large(limit: usize, actual: usize) -> Self {
+        EvalexprError::TupleTooLarge { limit, actual }
+    }
+
+    pub(crate) fn invalid_rounding_mode(mode: String) -> Self {
+        EvalexprError::InvalidRoundingMode { mode }
+    }
+
+    pub(crate) fn invalid_number(string: String) -> Self {
+        EvalexprError::InvalidNumber { string }
+    }
+
+    pub(crate) fn invalid_radix(radix: IntType) -> Self {
+        EvalexprError::InvalidRadix { radix }
+    }
+
+    pub(crate) fn invalid_quantity(actual: Value) -> Self {
+        EvalexprError::InvalidQuantity { actual }
+    }
+
+    pub(crate) fn unmatched_question_mark(span: Range<usize>) -> Self {
+        EvalexprError::UnmatchedQuestionMark { span }
+    }
+
+    pub(crate) fn unmatched_colon(span: Range<usize>) -> Self {
+        EvalexprError::UnmatchedColon { span }
+    }
+
+    pub(crate) fn invalid_binary_format(reason: &str) -> Self {
+        EvalexprError::InvalidBinaryFormat(reason.to_string())
+    }
+
+    pub(crate) fn unencodable_value(value: Value) -> Self {
+        EvalexprError::UnencodableValue(value)
+    }
+
+    #[cfg(feature = "excel_support")]
+    pub(crate) fn unsupported_excel_construct(construct: &str, span: Range<usize>) -> Self {
+        EvalexprError::UnsupportedExcelConstruct {
+            construct: construct.to_string(),
+            span,
+        }
+    }
+
+    #[cfg(feature = "excel_support")]
+    pub(crate) fn excel_cell_reference(reference: &str, span: Range<usize>) -> Self {
+        EvalexprError::ExcelCellReference {
+            reference: reference.to_string(),
+            span,
+        }
+    }
+
     /// Constructs `EvalexprError::InvalidRegex(regex)`
     pub fn invalid_regex(regex: String, message: String) -> Self {
         EvalexprError::InvalidRegex { regex, message }
     }
+
+    /// Returns a stable snake_case code identifying the kind of this error.
+    ///
+    /// Unlike the variants of this enum, the codes are meant to stay the same across versions,
+    /// such that they can be used for machine handling of errors, e.g. as error codes of an API.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// assert_eq!(eval("1 / 0").unwrap_err().code(), "division_by_zero");
+    /// assert_eq!(eval("a + 1").unwrap_err().code(), "variable_not_found");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            EvalexprError::WrongOperatorArgumentAmount { .. } => "wrong_operator_argument_amount",
+            EvalexprError::WrongFunctionArgumentAmount { .. } => "wrong_function_argument_amount",
+            EvalexprError::ExpectedString { .. } => "expected_string",
+            EvalexprError::ExpectedInt { .. } => "expected_int",
+            EvalexprError::ExpectedFloat { .. } => "expected_float",
+            EvalexprError::ExpectedNumber { .. } => "expected_number",
+            EvalexprError::ExpectedNumberOrString { .. } => "expected_number_or_string",
+            EvalexprError::ExpectedBoolean { .. } => "expected_boolean",
+            EvalexprError::ExpectedTuple { .. } => "expected_tuple",
+            EvalexprError::ExpectedFixedLengthTuple { .. } => "expected_fixed_length_tuple",
+            EvalexprError::ExpectedRangedLengthTuple { .. } => "expected_ranged_length_tuple",
+            EvalexprError::ExpectedMap { .. } => "expected_map",
+            EvalexprError::ExpectedEmpty { .. } => "expected_empty",
+            EvalexprError::AppendedToLeafNode => "appended_to_leaf_node",
+            EvalexprError::PrecedenceViolation => "precedence_violation",
+            EvalexprError::VariableIdentifierNotFound(_) => "variable_not_found",
+            EvalexprError::FunctionIdentifierNotFound(_) => "function_not_found",
+            EvalexprError::NotCallable(_) => "not_callable",
+            EvalexprError::FunctionReturnedWrongType { .. } => "function_returned_wrong_type",
+            EvalexprError::TypeError { .. } => "type_mismatch",
+            EvalexprError::ElementTypeError { .. } => "element_type_mismatch",
+            EvalexprError::IncomparableValues { .. } => "incomparable_values",
+            EvalexprError::InvalidMapPair { .. } => "invalid_map_pair",
+            EvalexprError::WrongTypeCombination { .. } => "wrong_type_combination",
+            EvalexprError::UnmatchedLBrace => "unmatched_left_brace",
+            EvalexprError::UnmatchedLCurlyBrace => "unmatched_left_curly_brace",
+            EvalexprError::UnmatchedRCurlyBrace => "unmatched_right_curly_brace",
+            EvalexprError::InvalidMapEntry => "invalid_map_entry",
+            EvalexprError::DuplicateRecordField { .. } => "duplicate_record_field",
+            EvalexprError::UnmatchedRBrace => "unmatched_right_brace",
+            EvalexprError::UnmatchedQuestionMark { .. } => "unmatched_question_mark",
+            EvalexprError::UnmatchedColon { .. } => "unmatched_colon",
+            EvalexprError::UnmatchedDoubleQuote => "unmatched_double_quote",
+            EvalexprError::UnmatchedInlineComment => "unmatched_inline_comment",
+            EvalexprError::MissingOperatorOutsideOfBrace => "missing_operator_outside_of_brace",
+            EvalexprError::UnmatchedPartialToken { .. } => "unmatched_partial_token",
+            EvalexprError::AdditionError { .. } => "addition_overflow",
+            EvalexprError::SubtractionError { .. } => "subtraction_overflow",
+            EvalexprError::NegationError { .. } => "negation_overflow",
+            EvalexprError::MultiplicationError { .. } => "multiplication_overflow",
+            EvalexprError::DivisionError { divisor, .. } => {
+                if divisor == &Value::Int(0) {
+                    "division_by_zero"
+                } else {
+                    "division_overflow"
+                }
+            },
+            EvalexprError::ModulationError { divisor, .. } => {
+                if divisor == &Value::Int(0) {
+                    "modulo_by_zero"
+                } else {
+                    "modulo_overflow"
+                }
+            },
+            EvalexprError::InvalidRegex { .. } => "invalid_regex",
+            EvalexprError::InvalidRoundingMode { .. } => "invalid_rounding_mode",
+            EvalexprError::InvalidNumber { .. } => "invalid_number",
+            EvalexprError::InvalidRadix { .. } => "invalid_radix",
+            EvalexprError::InvalidQuantity { .. } => "invalid_quantity",
+            EvalexprError::UnitMismatch { .. } => "unit_mismatch",
+            EvalexprError::UnknownUnit(_) => "unknown_unit",
+            EvalexprError::InvalidTimeFormat { .. } => "invalid_time_format",
+            EvalexprError::TimestampOutOfRange { .. } => "timestamp_out_of_range",
+            EvalexprError::EvaluationTooDeep { .. } => "evaluation_too_deep",
+            EvalexprError::ContextNotMutable => "context_not_mutable",
+            EvalexprError::DuplicateBinding(_) => "duplicate_binding",
+            EvalexprError::InvalidIdentifier { .. } => "invalid_identifier",
+            EvalexprError::OperatorDisabled { .. } => "operator_disabled",
+            EvalexprError::InvalidOperatorRemap { .. } => "invalid_operator_remap",
+            EvalexprError::UnbuildableOperator { .. } => "unbuildable_operator",
+            EvalexprError::ExpressionTooLarge { .. } => "expression_too_large",
+            EvalexprError::TupleTooLarge { .. } => "tuple_too_large",
+            EvalexprError::InvalidTrailingComma => "invalid_trailing_comma",
+            EvalexprError::IllegalEscapeSequence(_) => "illegal_escape_sequence",
+            EvalexprError::InvalidLiteral(_) => "invalid_literal",
+            EvalexprError::InvalidAssignment(_) => "invalid_assignment",
+            EvalexprError::UnsupportedFormatVersion { .. } => "unsupported_format_version",
+            EvalexprError::InvalidBinaryFormat(_) => "invalid_binary_format",
+            EvalexprError::UnencodableValue(_) => "unencodable_value",
+            EvalexprError::UnsupportedExcelConstruct { .. } => "unsupported_excel_construct",
+            EvalexprError::ExcelCellReference { .. } => "excel_cell_reference",
+            EvalexprError::BuiltinFunctionsCannotBeEnabled => "builtin_functions_cannot_be_enabled",
+            EvalexprError::BuiltinFunctionsCannotBeDisabled => {
+                "builtin_functions_cannot_be_disabled"
+            },
+            EvalexprError::OutOfBoundsAccess => "out_of_bounds_access",
+            EvalexprError::CustomMessage(_) => "custom",
+        }
+    }
+
+    /// Returns true if this error occurs while parsing an expression string into an operator tree,
+    /// or while decoding an operator tree or a value.
+    pub fn is_parse_error(&self) -> bool {
+        matches!(
+            self,
+            EvalexprError::WrongOperatorArgumentAmount { .. }
+                | EvalexprError::AppendedToLeafNode
+                | EvalexprError::PrecedenceViolation
+                | EvalexprError::UnmatchedLBrace
+                | EvalexprError::UnmatchedLCurlyBrace
+                | EvalexprError::UnmatchedRCurlyBrace
+                | EvalexprError::InvalidMapEntry
+                | EvalexprError::DuplicateRecordField { .. }
+                | EvalexprError::InvalidTrailingComma
+                | EvalexprError::UnmatchedRBrace
+                | EvalexprError::UnmatchedQuestionMark { .. }
+                | EvalexprError::UnmatchedColon { .. }
+                | EvalexprError::UnmatchedDoubleQuote
+                | EvalexprError::UnmatchedInlineComment
+                | EvalexprError::MissingOperatorOutsideOfBrace
+                | EvalexprError::UnmatchedPartialToken { .. }
+                | EvalexprError::InvalidIdentifier { .. }
+                | EvalexprError::OperatorDisabled { .. }
+                | EvalexprError::ExpressionTooLarge { .. }
+                | EvalexprError::TupleTooLarge { .. }
+                | EvalexprError::IllegalEscapeSequence(_)
+                | EvalexprError::InvalidLiteral(_)
+                | EvalexprError::InvalidAssignment(_)
+                | EvalexprError::UnsupportedFormatVersion { .. }
+                | EvalexprError::InvalidBinaryFormat(_)
+                | EvalexprError::UnsupportedExcelConstruct { .. }
+                | EvalexprError::ExcelCellReference { .. }
+        )
+    }
+
+    /// Returns true if this error occurs while splitting an expression string into tokens,
+    /// i.e. if the expression string is lexically invalid.
+    /// Tokenize errors are parse errors.
+    pub fn is_tokenize_error(&self) -> bool {
+        matches!(
+            self,
+            EvalexprError::UnmatchedDoubleQuote
+                | EvalexprError::UnmatchedInlineComment
+                | EvalexprError::UnmatchedPartialToken { .. }
+                | EvalexprError::IllegalEscapeSequence(_)
+                | EvalexprError::ExpressionTooLarge { .. }
+        )
+    }
+
+    /// Returns true if this error occurs while building an operator tree from the tokens of an expression string,
+    /// i.e. if the expression string is lexically valid, but syntactically invalid.
+    /// Syntax errors are parse errors.
+    pub fn is_syntax_error(&self) -> bool {
+        matches!(
+            self,
+            EvalexprError::WrongOperatorArgumentAmount { .. }
+                | EvalexprError::AppendedToLeafNode
+                | EvalexprError::PrecedenceViolation
+                | EvalexprError::UnmatchedLBrace
+                | EvalexprError::UnmatchedRBrace
+                | EvalexprError::UnmatchedQuestionMark { .. }
+                | EvalexprError::UnmatchedColon { .. }
+                | EvalexprError::UnmatchedLCurlyBrace
+                | EvalexprError::UnmatchedRCurlyBrace
+                | EvalexprError::InvalidMapEntry
+                | EvalexprError::DuplicateRecordField { .. }
+                | EvalexprError::InvalidTrailingComma
+                | EvalexprError::MissingOperatorOutsideOfBrace
+                | EvalexprError::InvalidIdentifier { .. }
+        )
+    }
+
+    /// Returns true if this error occurs while evaluating an operator tree.
+    /// This is the case for all errors that are not parse errors, including type errors.
+    pub fn is_runtime_error(&self) -> bool {
+        !self.is_parse_error()
+    }
+
+    /// Returns true if this error is caused by a value of an unexpected type.
+    /// Type errors are runtime errors.
+    pub fn is_type_error(&self) -> bool {
+        matches!(
+            self,
+            EvalexprError::ExpectedString { .. }
+                | EvalexprError::ExpectedInt { .. }
+                | EvalexprError::ExpectedFloat { .. }
+                | EvalexprError::ExpectedNumber { .. }
+                | EvalexprError::ExpectedNumberOrString { .. }
+                | EvalexprError::ExpectedBoolean { .. }
+                | EvalexprError::ExpectedTuple { .. }
+                | EvalexprError::ExpectedFixedLengthTuple { .. }
+                | EvalexprError::ExpectedRangedLengthTuple { .. }
+                | EvalexprError::ExpectedMap { .. }
+                | EvalexprError::ExpectedEmpty { .. }
+                | EvalexprError::TypeError { .. }
+                | EvalexprError::ElementTypeError { .. }
+                | EvalexprError::IncomparableValues { .. }
+                | EvalexprError::InvalidMapPair { .. }
+                | EvalexprError::WrongTypeCombination { .. }
+                | EvalexprError::NotCallable(_)
+                | EvalexprError::FunctionReturnedWrongType { .. }
+                | EvalexprError::UnitMismatch { .. }
+        )
+    }
 }
 
 /// Returns `Ok(())` if the actual and expected parameters are equal, and `Err(Error::WrongOperatorArgumentAmount)` otherwise.
@@ -412,7 +1031,8 @@ pub type EvalexprResult<T> = Result<T, EvalexprError>;
 
 #[cfg(test)]
 mod tests {
-    use crate::{EvalexprError, Value, ValueType};
+    use crate::{EvalexprError, IntType, Operator, PartialToken, Value, ValueType};
+    use std::collections::HashSet;
 
     /// Tests whose only use is to bring test coverage of trivial lines up, like trivial constructors.
     #[test]
@@ -441,4 +1061,117 @@ mod tests {
             EvalexprError::expected_empty(Value::String("abc".to_string()))
         );
     }
+
+    #[test]
+    fn error_codes_are_unique() {
+        let errors = vec![
+            EvalexprError::wrong_operator_argument_amount(1, 2),
+            EvalexprError::wrong_function_argument_amount(1, 2),
+            EvalexprError::expected_string(Value::Empty),
+            EvalexprError::expected_int(Value::Empty),
+            EvalexprError::expected_float(Value::Empty),
+            EvalexprError::expected_number(Value::Empty),
+            EvalexprError::expected_number_or_string(Value::Empty),
+            EvalexprError::expected_boolean(Value::Empty),
+            EvalexprError::expected_tuple(Value::Empty),
+            EvalexprError::expected_fixed_len_tuple(2, Value::Empty),
+            EvalexprError::expected_ranged_len_tuple(2..=3, Value::Empty),
+            EvalexprError::expected_map(Value::Empty),
+            EvalexprError::expected_empty(Value::Int(1)),
+            EvalexprError::AppendedToLeafNode,
+            EvalexprError::PrecedenceViolation,
+            EvalexprError::VariableIdentifierNotFound("a".to_string()),
+            EvalexprError::FunctionIdentifierNotFound("f".to_string()),
+            EvalexprError::not_callable(Value::Int(5)),
+            EvalexprError::function_returned_wrong_type("f", ValueType::Int, Value::Empty),
+            EvalexprError::type_error(Value::Empty, vec![ValueType::Int]),
+            EvalexprError::element_type_error(1, vec![ValueType::Boolean], Value::Int(1)),
+            EvalexprError::incomparable_values(1, vec![ValueType::String], Value::Int(1)),
+            EvalexprError::invalid_map_pair(1, Value::Int(1)),
+            EvalexprError::wrong_type_combination(Operator::Add, vec![ValueType::Empty]),
+            EvalexprError::UnmatchedLBrace,
+            EvalexprError::UnmatchedLCurlyBrace,
+            EvalexprError::UnmatchedRCurlyBrace,
+            EvalexprError::InvalidMapEntry,
+            EvalexprError::DuplicateRecordField {
+                name: "a".to_string(),
+                span: 0..1,
+            },
+            EvalexprError::UnmatchedRBrace,
+            EvalexprError::unmatched_question_mark(0..1),
+            EvalexprError::unmatched_colon(0..1),
+            EvalexprError::UnmatchedDoubleQuote,
+            EvalexprError::UnmatchedInlineComment,
+            EvalexprError::MissingOperatorOutsideOfBrace,
+            EvalexprError::unmatched_partial_token(PartialToken::Ampersand, None),
+            EvalexprError::addition_error(Value::Int(1), Value::Int(1)),
+            EvalexprError::subtraction_error(Value::Int(1), Value::Int(1)),
+            EvalexprError::negation_error(Value::Int(1)),
+            EvalexprError::multiplication_error(Value::Int(1), Value::Int(1)),
+            EvalexprError::division_error(Value::Int(1), Value::Int(0)),
+            EvalexprError::division_error(Value::Int(1), Value::Int(-1)),
+            EvalexprError::modulation_error(Value::Int(1), Value::Int(0)),
+            EvalexprError::modulation_error(Value::Int(1), Value::Int(-1)),
+            EvalexprError::invalid_regex("(".to_string(), "unclosed group".to_string()),
+            EvalexprError::invalid_rounding_mode("up".to_string()),
+            EvalexprError::invalid_number("1x".to_string()),
+            EvalexprError::invalid_radix(37),
+            EvalexprError::invalid_quantity(Value::Int(-1)),
+            EvalexprError::UnitMismatch {
+                left: "m".to_string(),
+                right: "s".to_string(),
+                operator: Operator::Add,
+            },
+            EvalexprError::UnknownUnit("parsec".to_string()),
+            EvalexprError::InvalidTimeFormat {
+                format: "%Q".to_string(),
+            },
+            EvalexprError::TimestampOutOfRange {
+                timestamp: IntType::MAX,
+            },
+            EvalexprError::evaluation_too_deep(16),
+            EvalexprError::ContextNotMutable,
+            EvalexprError::DuplicateBinding("a".to_string()),
+            EvalexprError::invalid_identifier("A".to_string(), "upper-case".to_string(), 0..1),
+            EvalexprError::operator_disabled(Operator::Exp, 0..1),
+            EvalexprError::invalid_operator_remap("=", "conflict".to_string()),
+            EvalexprError::unbuildable_operator(Operator::Assign, "assignment"),
+            EvalexprError::expression_too_large(10, 11),
+            EvalexprError::tuple_too_large(3, 4),
+            EvalexprError::InvalidTrailingComma,
+            EvalexprError::IllegalEscapeSequence("\\a".to_string()),
+            EvalexprError::InvalidLiteral("1 + 2".to_string()),
+            EvalexprError::InvalidAssignment("a".to_string()),
+            EvalexprError::UnsupportedFormatVersion {
+                found: 2,
+                supported: 1,
+            },
+            EvalexprError::invalid_binary_format("unknown operator tag"),
+            EvalexprError::unencodable_value(Value::Empty),
+            EvalexprError::UnsupportedExcelConstruct {
+                construct: "%".to_string(),
+                span: 1..2,
+            },
+            EvalexprError::ExcelCellReference {
+                reference: "A1".to_string(),
+                span: 1..3,
+            },
+            EvalexprError::BuiltinFunctionsCannotBeEnabled,
+            EvalexprError::BuiltinFunctionsCannotBeDisabled,
+            EvalexprError::OutOfBoundsAccess,
+            EvalexprError::CustomMessage("custom".to_string()),
+        ];
+
+        let mut codes = HashSet::new();
+        for error in &errors {
+            let code = error.code();
+            assert!(codes.insert(code), "duplicate error code {}", code);
+            assert!(code.chars().all(|c| c.is_ascii_lowercase() || c == '_'));
+            assert!(!(error.is_parse_error() && error.is_type_error()));
+            assert_ne!(error.is_parse_error(), error.is_runtime_error());
+            assert!(!(error.is_tokenize_error() && error.is_syntax_error()));
+            assert!(!error.is_tokenize_error() || error.is_parse_error());
+            assert!(!error.is_syntax_error() || error.is_parse_error());
+        }
+    }
 }
@@ -0,0 +1,284 @@
+use std::{collections::HashMap, fmt, future::Future, pin::Pin, sync::Arc};
+
+use crate::{
+    context::{EmptyContext, EmptyContextWithBuiltinFunctions, FloatEquality},
+    error::expect_operator_argument_amount,
+    function,
+    tree::lazy::LazyNode,
+    Context, ContextWithMutableFunctions, ContextWithMutableVariables, EvalexprResult, Function,
+    HashMapContext, MacroFunction, Node, Operator, Value,
+};
+
+/// The future returned by calls of an `AsyncFunction`.
+pub type AsyncFunctionFuture = Pin<Box<dyn Future<Output = EvalexprResult<Value>> + Send>>;
+
+/// A user-defined asynchronous function, e.g. for functions that need to await I/O.
+/// Asynchronous functions can be used in expressions evaluated with `Node::eval_async` by storing them in an `AsyncContext`.
+///
+/// Like for `Function`, the argument is a tuple if the function is called with multiple arguments.
+/// The returned future must not borrow the argument, so the function has to clone what it needs from it.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let double = AsyncFunction::new(|argument| {
+///     let argument = argument.clone();
+///     async move { Ok(Value::from(argument.as_int()? * 2)) }
+/// });
+/// let mut context = AsyncFunctionContext::new(HashMapContext::new());
+/// context.set_async_function("double".into(), double);
+/// ```
+#[derive(Clone)]
+pub struct AsyncFunction {
+    function: Arc<dyn Fn(&Value) -> AsyncFunctionFuture + Send + Sync>,
+}
+
+impl AsyncFunction {
+    /// Creates a user-defined asynchronous function.
+    ///
+    /// The `function` is boxed for storage, and so are the futures it returns.
+    pub fn new<F, Fut>(function: F) -> Self
+    where
+        F: Fn(&Value) -> Fut,
+        F: Send + Sync + 'static,
+        Fut: Future<Output = EvalexprResult<Value>> + Send + 'static,
+    {
+        Self {
+            function: Arc::new(move |argument| Box::pin(function(argument)) as _),
+        }
+    }
+
+    /// Calls the function with the given argument, returning the future of its result.
+    pub fn call(&self, argument: &Value) -> AsyncFunctionFuture {
+        (self.function)(argument)
+    }
+}
+
+impl fmt::Debug for AsyncFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "AsyncFunction {{ [...] }}")
+    }
+}
+
+/// A context that may link identifiers to asynchronous functions, for evaluation with `Node::eval_async`.
+///
+/// Identifiers without an asynchronous function are called through `Context::call_function`,
+/// so the synchronous functions of a context, as well as the builtin functions, work unchanged in asynchronous evaluation.
+pub trait AsyncContext: Context {
+    /// Returns the asynchronous function that is linked to the given identifier, if any.
+    fn get_async_function(&self, identifier: &str) -> Option<&AsyncFunction>;
+}
+
+impl AsyncContext for HashMapContext {
+    fn get_async_function(&self, _identifier: &str) -> Option<&AsyncFunction> {
+        None
+    }
+}
+
+impl AsyncContext for EmptyContext {
+    fn get_async_function(&self, _identifier: &str) -> Option<&AsyncFunction> {
+        None
+    }
+}
+
+impl AsyncContext for EmptyContextWithBuiltinFunctions {
+    fn get_async_function(&self, _identifier: &str) -> Option<&AsyncFunction> {
+        None
+    }
+}
+
+/// A context that adds asynchronous functions to the context it wraps.
+///
+/// Variables and synchronous functions are resolved by the wrapped context.
+/// If an identifier is linked to both an asynchronous and a synchronous function, the asynchronous function is called.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// # async fn example() {
+/// let mut context = AsyncFunctionContext::new(context_map! {
+///     "a" => 2,
+///     "f" => Function::new(|argument| Ok(Value::from(argument.as_int()? + 1)))
+/// }.unwrap()); // Do proper error handling here
+/// context.set_async_function("g".into(), AsyncFunction::new(|argument| {
+///     let argument = argument.clone();
+///     async move { Ok(Value::from(argument.as_int()? * 10)) }
+/// }));
+///
+/// let tree = build_operator_tree("g(f(a))").unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval_async(&context).await, Ok(Value::from(30)));
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct AsyncFunctionContext<C> {
+    context: C,
+    functions: HashMap<String, AsyncFunction>,
+}
+
+impl<C: Context> AsyncFunctionContext<C> {
+    /// Constructs an `AsyncFunctionContext` without asynchronous functions that wraps the given context.
+    pub fn new(context: C) -> Self {
+        Self {
+            context,
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Links the given identifier to the given asynchronous function, replacing a previous one.
+    pub fn set_async_function(&mut self, identifier: String, function: AsyncFunction) {
+        self.functions.insert(identifier, function);
+    }
+
+    /// Returns the wrapped context.
+    pub fn inner(&self) -> &C {
+        &self.context
+    }
+
+    /// Returns the wrapped context mutably.
+    pub fn inner_mut(&mut self) -> &mut C {
+        &mut self.context
+    }
+
+    /// Returns the wrapped context, dropping the asynchronous functions.
+    pub fn into_inner(self) -> C {
+        self.context
+    }
+}
+
+impl<C: Context> Context for AsyncFunctionContext<C> {
+    fn get_value(&self, identifier: &str) -> Option<&Value> {
+        self.context.get_value(identifier)
+    }
+
+    fn call_function(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+        self.context.call_function(identifier, argument)
+    }
+
+    /// Asynchronous functions count as well, such that they take precedence over builtin functions like synchronous ones.
+    fn has_function(&self, identifier: &str) -> bool {
+        self.functions.contains_key(identifier) || self.context.has_function(identifier)
+    }
+
+    fn are_builtin_functions_disabled(&self) -> bool {
+        self.context.are_builtin_functions_disabled()
+    }
+
+    fn set_builtin_functions_disabled(&mut self, disabled: bool) -> EvalexprResult<()> {
+        self.context.set_builtin_functions_disabled(disabled)
+    }
+
+    fn float_equality(&self) -> FloatEquality {
+        self.context.float_equality()
+    }
+
+    fn max_evaluation_depth(&self) -> usize {
+        self.context.max_evaluation_depth()
+    }
+
+    fn get_macro_function(&self, identifier: &str) -> Option<&MacroFunction> {
+        self.context.get_macro_function(identifier)
+    }
+
+    #[cfg(feature = "units_support")]
+    fn unit_registry(&self) -> &crate::UnitRegistry {
+        self.context.unit_registry()
+    }
+
+    #[cfg(feature = "time_support")]
+    fn clock(&self) -> crate::Clock {
+        self.context.clock()
+    }
+}
+
+impl<C: ContextWithMutableVariables> ContextWithMutableVariables for AsyncFunctionContext<C> {
+    fn set_value(&mut self, identifier: String, value: Value) -> EvalexprResult<()> {
+        self.context.set_value(identifier, value)
+    }
+}
+
+impl<C: ContextWithMutableFunctions> ContextWithMutableFunctions for AsyncFunctionContext<C> {
+    fn set_function(&mut self, identifier: String, function: Function) -> EvalexprResult<()> {
+        self.context.set_function(identifier, function)
+    }
+}
+
+impl<C: Context> AsyncContext for AsyncFunctionContext<C> {
+    fn get_async_function(&self, identifier: &str) -> Option<&AsyncFunction> {
+        self.functions.get(identifier)
+    }
+}
+
+impl Node {
+    /// Evaluates the operator tree rooted at this node with the given context, awaiting calls of asynchronous functions.
+    ///
+    /// The evaluation order is the same as for `Node::eval_with_context`, and function calls are awaited one after another in that order.
+    /// The returned future does not depend on a specific async runtime.
+    /// It is `Send`, such that it can be spawned onto multi-threaded runtimes, which is why the context needs to be `Sync`.
+    /// Unlike in synchronous evaluation, functions marked with `Function::memoized` are not memoized.
+    /// Synchronous functions and macro functions are called within the evaluation depth limit of the context, see `EvalContext`,
+    /// such that expressions they evaluate are counted as nested evaluations.
+    ///
+    /// Fails, if one of the operators in the expression tree fails.
+    pub fn eval_async<'a, C: AsyncContext + Sync>(
+        &'a self,
+        context: &'a C,
+    ) -> impl Future<Output = EvalexprResult<Value>> + Send + 'a {
+        self.eval_node_async(context)
+    }
+
+    fn eval_node_async<'a, C: AsyncContext + Sync>(
+        &'a self,
+        context: &'a C,
+    ) -> Pin<Box<dyn Future<Output = EvalexprResult<Value>> + Send + 'a>> {
+        Box::pin(async move {
+            // Operands are awaited, so lazy nodes cannot be evaluated with the synchronous `LazyNode::eval`
+            match self.lazy_node(context) {
+                Some(LazyNode::MacroCall(macro_function, arguments)) => {
+                    return function::with_evaluation_scope(context.max_evaluation_depth(), || {
+                        macro_function.call(&arguments, context)
+                    });
+                },
+                Some(LazyNode::TryOr {
+                    expression,
+                    fallback,
+                }) => {
+                    return match expression.eval_node_async(context).await {
+                        Ok(value) => Ok(value),
+                        Err(_) => fallback.eval_node_async(context).await,
+                    };
+                },
+                Some(LazyNode::Conditional {
+                    condition,
+                    consequent,
+                    alternative,
+                }) => {
+                    return if condition.eval_node_async(context).await?.as_boolean()? {
+                        consequent.eval_node_async(context).await
+                    } else {
+                        alternative.eval_node_async(context).await
+                    };
+                },
+                None => {},
+            }
+
+            let mut arguments = Vec::with_capacity(self.children().len());
+            for child in self.children() {
+                arguments.push(child.eval_node_async(context).await?);
+            }
+            if let Operator::FunctionIdentifier { identifier } = self.operator() {
+                if let Some(function) = context.get_async_function(identifier) {
+                    expect_operator_argument_amount(arguments.len(), 1)?;
+                    return function.call(&arguments[0]).await;
+                }
+            }
+            // The future may move between threads while awaiting, so only the synchronous parts are counted as evaluation on the current thread
+            function::with_evaluation_scope(context.max_evaluation_depth(), || {
+                self.operator().eval_owned(arguments, context)
+            })
+        })
+    }
+}
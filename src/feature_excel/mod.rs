@@ -0,0 +1,546 @@
+use std::{
+    convert::TryFrom,
+    ops::{Range, RangeInclusive},
+    sync::Arc,
+};
+
+use crate::{
+    value::{FloatType, IntType},
+    EvalexprError, EvalexprResult, Node, Operator, Value,
+};
+
+/// A token of an Excel formula.
+#[derive(Clone, Debug, PartialEq)]
+enum ExcelToken {
+    Value(Value),
+    /// A name that is not followed by an opening parenthesis, which is converted into a variable.
+    Name(String),
+    /// A name that is followed by an opening parenthesis.
+    Function(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Hat,
+    Ampersand,
+    Eq,
+    Neq,
+    Lt,
+    Gt,
+    Leq,
+    Geq,
+    LBrace,
+    RBrace,
+    Comma,
+}
+
+/// Returns true if the given name looks like a reference to a cell in the A1 notation, like `B2` or `$AB$10`:
+/// one to three letters followed by digits, each optionally preceded by a dollar sign.
+fn is_cell_reference(name: &str) -> bool {
+    let name = name.strip_prefix('$').unwrap_or(name);
+    let letters = name.bytes().take_while(u8::is_ascii_alphabetic).count();
+    let row = &name[letters..];
+    let row = row.strip_prefix('$').unwrap_or(row);
+    (1..=3).contains(&letters) && !row.is_empty() && row.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || matches!(c, '_' | '\\' | '$')
+}
+
+fn is_name_continuation(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '\\' | '$' | '.')
+}
+
+/// Returns the length in bytes of the number at the start of the given string, including an exponent like `E-3`.
+fn number_len(string: &str) -> usize {
+    let mantissa = string
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(string.len());
+    let exponent = &string[mantissa..];
+    if let Some(exponent) = exponent.strip_prefix(['e', 'E']) {
+        let sign = usize::from(exponent.starts_with(['+', '-']));
+        let digits = exponent[sign..]
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(exponent.len() - sign);
+        if digits > 0 {
+            return mantissa + 1 + sign + digits;
+        }
+    }
+    mantissa
+}
+
+/// Splits the given formula into tokens with their byte ranges, starting at the given byte offset.
+fn tokenize(formula: &str, start: usize) -> EvalexprResult<Vec<(ExcelToken, Range<usize>)>> {
+    let mut tokens = Vec::new();
+    let mut position = start;
+
+    while let Some(c) = formula[position..].chars().next() {
+        let rest = &formula[position..];
+        let (token, len) = match c {
+            c if c.is_whitespace() => {
+                position += c.len_utf8();
+                continue;
+            },
+            '"' => {
+                // Double quotes within strings are escaped by doubling them
+                let mut string = String::new();
+                let mut chars = rest.char_indices().skip(1).peekable();
+                let end = loop {
+                    match chars.next() {
+                        Some((index, '"')) => {
+                            if chars.peek().map(|(_, c)| *c) == Some('"') {
+                                chars.next();
+                                string.push('"');
+                            } else {
+                                break index + 1;
+                            }
+                        },
+                        Some((_, c)) => string.push(c),
+                        None => return Err(EvalexprError::UnmatchedDoubleQuote),
+                    }
+                };
+                (ExcelToken::Value(Value::String(string)), end)
+            },
+            '0'..='9' | '.' => {
+                let len = number_len(rest);
+                let literal = &rest[..len];
+                let value = if let Ok(int) = literal.parse::<IntType>() {
+                    Value::Int(int)
+                } else if let Ok(float) = literal.parse::<FloatType>() {
+                    Value::Float(float)
+                } else {
+                    return Err(EvalexprError::unsupported_excel_construct(
+                        literal,
+                        position..position + len,
+                    ));
+                };
+                (ExcelToken::Value(value), len)
+            },
+            c if is_name_start(c) => {
+                let len = rest
+                    .find(|c: char| !is_name_continuation(c))
+                    .unwrap_or(rest.len());
+                let name = &rest[..len];
+                let span = position..position + len;
+                let token = if rest[len..].starts_with('(') {
+                    ExcelToken::Function(name.to_string())
+                } else if name.eq_ignore_ascii_case("true") {
+                    ExcelToken::Value(Value::Boolean(true))
+                } else if name.eq_ignore_ascii_case("false") {
+                    ExcelToken::Value(Value::Boolean(false))
+                } else if is_cell_reference(name) {
+                    return Err(EvalexprError::excel_cell_reference(name, span));
+                } else if name.contains('$') {
+                    return Err(EvalexprError::unsupported_excel_construct(name, span));
+                } else {
+                    ExcelToken::Name(name.to_string())
+                };
+                (token, len)
+            },
+            '<' if rest.starts_with("<>") => (ExcelToken::Neq, 2),
+            '<' if rest.starts_with("<=") => (ExcelToken::Leq, 2),
+            '>' if rest.starts_with(">=") => (ExcelToken::Geq, 2),
+            '+' => (ExcelToken::Plus, 1),
+            '-' => (ExcelToken::Minus, 1),
+            '*' => (ExcelToken::Star, 1),
+            '/' => (ExcelToken::Slash, 1),
+            '^' => (ExcelToken::Hat, 1),
+            '&' => (ExcelToken::Ampersand, 1),
+            '=' => (ExcelToken::Eq, 1),
+            '<' => (ExcelToken::Lt, 1),
+            '>' => (ExcelToken::Gt, 1),
+            '(' => (ExcelToken::LBrace, 1),
+            ')' => (ExcelToken::RBrace, 1),
+            ',' => (ExcelToken::Comma, 1),
+            // E.g. the percent operator `%`, the range operator `:`, sheet references with `!` or array constants in `{}`
+            c => {
+                return Err(EvalexprError::unsupported_excel_construct(
+                    &c.to_string(),
+                    position..position + c.len_utf8(),
+                ))
+            },
+        };
+        tokens.push((token, position..position + len));
+        position += len;
+    }
+
+    Ok(tokens)
+}
+
+/// Builds the node of a call of the given builtin function with the given arguments,
+/// with the same structure as built for the call in an expression string.
+fn call(identifier: &str, arguments: Vec<Node>) -> Node {
+    let argument = if arguments.len() > 1 {
+        let elements = arguments.into_iter().map(parenthesized).collect();
+        vec![Node::with_children(Operator::Tuple, elements)]
+    } else {
+        arguments
+    };
+    Node::with_children(
+        Operator::function_identifier(identifier.into()),
+        vec![Node::with_children(Operator::RootNode, argument)],
+    )
+}
+
+fn parenthesized(node: Node) -> Node {
+    Node::with_children(Operator::RootNode, vec![node])
+}
+
+/// Parenthesizes the given operand of the given operator if needed to keep it together in an expression string.
+fn operand(operator: &Operator, node: Node) -> Node {
+    if node.operator().precedence() < operator.precedence() {
+        parenthesized(node)
+    } else {
+        node
+    }
+}
+
+fn expect_arguments(arguments: &[Node], expected: RangeInclusive<usize>) -> EvalexprResult<()> {
+    if expected.contains(&arguments.len()) {
+        Ok(())
+    } else {
+        Err(EvalexprError::wrong_function_argument_amount_range(
+            arguments.len(),
+            expected,
+        ))
+    }
+}
+
+/// A recursive descent parser of the tokens of an Excel formula, ordered by the precedence of Excel's operators.
+struct Parser<'a> {
+    formula: &'a str,
+    tokens: Vec<(ExcelToken, Range<usize>)>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&ExcelToken> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<(ExcelToken, Range<usize>)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += usize::from(token.is_some());
+        token
+    }
+
+    /// Consumes the next token if it is a binary operator converted by the given function.
+    fn next_operator(
+        &mut self,
+        operator: impl Fn(&ExcelToken) -> Option<Operator>,
+    ) -> Option<Operator> {
+        let operator = operator(self.peek()?)?;
+        self.position += 1;
+        Some(operator)
+    }
+
+    fn unexpected(&self, span: Range<usize>) -> EvalexprError {
+        EvalexprError::unsupported_excel_construct(&self.formula[span.clone()], span)
+    }
+
+    /// Returns the error for a formula that ends where an operand is expected.
+    fn unexpected_end(&self) -> EvalexprError {
+        match self.tokens.last() {
+            Some((ExcelToken::LBrace | ExcelToken::Function(_), _)) => {
+                EvalexprError::UnmatchedLBrace
+            },
+            Some((_, span)) => self.unexpected(span.clone()),
+            None => EvalexprError::UnmatchedLBrace,
+        }
+    }
+
+    fn parse_comparison(&mut self) -> EvalexprResult<Node> {
+        let mut left = self.parse_concatenation()?;
+        while let Some(operator) = self.next_operator(|token| match token {
+            ExcelToken::Eq => Some(Operator::Eq),
+            ExcelToken::Neq => Some(Operator::Neq),
+            ExcelToken::Lt => Some(Operator::Lt),
+            ExcelToken::Gt => Some(Operator::Gt),
+            ExcelToken::Leq => Some(Operator::Leq),
+            ExcelToken::Geq => Some(Operator::Geq),
+            _ => None,
+        }) {
+            let right = self.parse_concatenation()?;
+            left = Node::with_children(operator, vec![left, right]);
+        }
+        Ok(left)
+    }
+
+    /// Parses a chain of concatenations `a & b & c` into a single call `str::concat(a, b, c)`.
+    fn parse_concatenation(&mut self) -> EvalexprResult<Node> {
+        let mut operands = vec![self.parse_additive()?];
+        while self.peek() == Some(&ExcelToken::Ampersand) {
+            self.position += 1;
+            operands.push(self.parse_additive()?);
+        }
+        if operands.len() == 1 {
+            Ok(operands.remove(0))
+        } else {
+            Ok(call("str::concat", operands))
+        }
+    }
+
+    fn parse_additive(&mut self) -> EvalexprResult<Node> {
+        let mut left = self.parse_multiplicative()?;
+        while let Some(operator) = self.next_operator(|token| match token {
+            ExcelToken::Plus => Some(Operator::Add),
+            ExcelToken::Minus => Some(Operator::Sub),
+            _ => None,
+        }) {
+            let right = self.parse_multiplicative()?;
+            left = Node::with_children(operator, vec![left, right]);
+        }
+        Ok(left)
+    }
+
+    fn parse_multiplicative(&mut self) -> EvalexprResult<Node> {
+        let mut left = self.parse_exponential()?;
+        while let Some(operator) = self.next_operator(|token| match token {
+            ExcelToken::Star => Some(Operator::Mul),
+            // Excel divides numbers as floats
+            ExcelToken::Slash => Some(Operator::FloatDiv),
+            _ => None,
+        }) {
+            let right = self.parse_exponential()?;
+            left = Node::with_children(operator, vec![left, right]);
+        }
+        Ok(left)
+    }
+
+    fn parse_exponential(&mut self) -> EvalexprResult<Node> {
+        let mut left = self.parse_unary()?;
+        while let Some(operator) = self.next_operator(|token| match token {
+            ExcelToken::Hat => Some(Operator::Exp),
+            _ => None,
+        }) {
+            let right = self.parse_unary()?;
+            // In Excel, negation binds stronger than exponentiation, such that `-2^2` is `(-2)^2`
+            left = Node::with_children(operator, vec![operand(&Operator::Exp, left), right]);
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> EvalexprResult<Node> {
+        match self.peek() {
+            Some(ExcelToken::Minus) => {
+                self.position += 1;
+                Ok(Node::with_children(
+                    Operator::Neg,
+                    vec![self.parse_unary()?],
+                ))
+            },
+            Some(ExcelToken::Plus) => {
+                self.position += 1;
+                self.parse_unary()
+            },
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> EvalexprResult<Node> {
+        let (token, span) = self.next().ok_or_else(|| self.unexpected_end())?;
+        match token {
+            ExcelToken::Value(value) => Ok(Node::with_children(Operator::value(value), Vec::new())),
+            ExcelToken::Name(name) => Ok(Node::with_children(
+                Operator::variable_identifier_read(Arc::from(name)),
+                Vec::new(),
+            )),
+            ExcelToken::LBrace => {
+                let node = self.parse_comparison()?;
+                self.expect_closing_brace()?;
+                Ok(parenthesized(node))
+            },
+            ExcelToken::Function(name) => {
+                let arguments = self.parse_arguments()?;
+                self.function_to_node(&name, span, arguments)
+            },
+            _ => Err(self.unexpected(span)),
+        }
+    }
+
+    fn expect_closing_brace(&mut self) -> EvalexprResult<()> {
+        match self.next() {
+            Some((ExcelToken::RBrace, _)) => Ok(()),
+            Some((_, span)) => Err(self.unexpected(span)),
+            None => Err(EvalexprError::UnmatchedLBrace),
+        }
+    }
+
+    /// Parses the comma-separated arguments of a function call, including the surrounding parentheses.
+    fn parse_arguments(&mut self) -> EvalexprResult<Vec<Node>> {
+        // The lexer only creates function tokens for names followed by an opening parenthesis
+        self.position += 1;
+        let mut arguments = Vec::new();
+        if self.peek() == Some(&ExcelToken::RBrace) {
+            self.position += 1;
+            return Ok(arguments);
+        }
+
+        loop {
+            arguments.push(self.parse_comparison()?);
+            match self.next() {
+                Some((ExcelToken::Comma, _)) => {},
+                Some((ExcelToken::RBrace, _)) => return Ok(arguments),
+                Some((_, span)) => return Err(self.unexpected(span)),
+                None => return Err(EvalexprError::UnmatchedLBrace),
+            }
+        }
+    }
+
+    fn function_to_node(
+        &self,
+        name: &str,
+        span: Range<usize>,
+        mut arguments: Vec<Node>,
+    ) -> EvalexprResult<Node> {
+        match name.to_ascii_uppercase().as_str() {
+            "IF" => {
+                expect_arguments(&arguments, 2..=3)?;
+                // Without a value for false, Excel returns false
+                if arguments.len() == 2 {
+                    arguments.push(Node::with_children(
+                        Operator::value(Value::Boolean(false)),
+                        Vec::new(),
+                    ));
+                }
+                // Excel evaluates only the chosen value, like the conditional operator
+                let alternative = arguments.pop().unwrap();
+                let consequent = arguments.pop().unwrap();
+                let condition = arguments.pop().unwrap();
+                Ok(parenthesized(Node::with_children(
+                    Operator::Conditional,
+                    vec![
+                        operand(&Operator::Conditional, condition),
+                        parenthesized(consequent),
+                        operand(&Operator::Conditional, alternative),
+                    ],
+                )))
+            },
+            "AND" | "OR" | "MIN" | "MAX" => {
+                expect_arguments(&arguments, 1..=usize::MAX)?;
+                let identifier = match name.to_ascii_uppercase().as_str() {
+                    "AND" => "all",
+                    "OR" => "any",
+                    "MIN" => "min",
+                    _ => "max",
+                };
+                Ok(call(identifier, arguments))
+            },
+            "NOT" => {
+                expect_arguments(&arguments, 1..=1)?;
+                Ok(Node::with_children(
+                    Operator::Not,
+                    vec![parenthesized(arguments.remove(0))],
+                ))
+            },
+            "ABS" => {
+                expect_arguments(&arguments, 1..=1)?;
+                Ok(call("math::abs", arguments))
+            },
+            "ROUND" => {
+                expect_arguments(&arguments, 2..=2)?;
+                let digits = arguments.remove(1);
+                let number = arguments.remove(0);
+                round_to_node(number, &digits)
+                    .ok_or_else(|| EvalexprError::unsupported_excel_construct(name, span))
+            },
+            _ => Err(EvalexprError::unsupported_excel_construct(name, span)),
+        }
+    }
+}
+
+/// Converts `ROUND(number, digits)` into a call of the builtin function `round`,
+/// scaling the number by a power of ten before rounding and back after rounding if the amount of digits is not zero.
+/// Returns `None` if the amount of digits is not an integer literal, or if the power of ten overflows.
+fn round_to_node(number: Node, digits: &Node) -> Option<Node> {
+    let digits = match (digits.operator(), digits.children()) {
+        (
+            Operator::Const {
+                value: Value::Int(digits),
+            },
+            _,
+        ) => *digits,
+        (Operator::Neg, [digits]) => match digits.operator() {
+            Operator::Const {
+                value: Value::Int(digits),
+            } => -digits,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if digits == 0 {
+        return Some(call("round", vec![number]));
+    }
+
+    let factor = IntType::checked_pow(10, u32::try_from(digits.unsigned_abs()).ok()?)?;
+    let factor = || Node::with_children(Operator::value(Value::Int(factor)), Vec::new());
+    let (scale, unscale) = if digits > 0 {
+        (Operator::Mul, Operator::FloatDiv)
+    } else {
+        (Operator::FloatDiv, Operator::Mul)
+    };
+    let scaled = Node::with_children(scale.clone(), vec![operand(&scale, number), factor()]);
+    Some(Node::with_children(
+        unscale,
+        vec![call("round", vec![scaled]), factor()],
+    ))
+}
+
+/// Converts an Excel formula into an operator tree.
+///
+/// The conversion supports the subset of Excel formulas that has a counterpart in expressions:
+///  * An optional leading `=`.
+///  * Numbers, strings with `""` as escaped double quote, and the booleans `TRUE` and `FALSE`.
+///  * The operators `+`, `-`, `*`, `/`, `^`, `=`, `<>`, `<`, `>`, `<=` and `>=` with Excel's precedence,
+///    where `/` always divides as floats, and negation binds stronger than `^`, such that `-2^2` is `4`.
+///  * The concatenation operator `&`, which is converted into a call of the builtin function `str::concat`.
+///  * The functions `IF`, `AND`, `OR`, `NOT`, `MIN`, `MAX`, `ABS` and `ROUND` with an integer literal as amount of digits,
+///    which are converted into the conditional operator `?:`, the builtin functions `all`, `any`, `min`, `max`, `math::abs` and `round`, and the operator `!`.
+///  * Names, which are converted into variables of the same name.
+///
+/// Unlike in Excel, function names are case-insensitive, but variable names are not,
+/// and strings are compared case-sensitively.
+///
+/// Names that look like cell references, such as `A1` or `$B$2`, are rejected with `EvalexprError::ExcelCellReference`,
+/// as the values of cells are not known to the expression.
+/// They need to be replaced by named variables, which are then bound in the context.
+/// All other constructs are rejected with `EvalexprError::UnsupportedExcelConstruct`, such as the percent operator `%`,
+/// ranges like `A1:B2`, references to other sheets and any other function.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let tree = from_excel_formula("=IF(price * quantity >= 100, \"bulk\", \"retail\") & \" order\"").unwrap(); // Do proper error handling here
+/// let context = context_map! { "price" => 12.5, "quantity" => 10 }.unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval_with_context(&context), Ok(Value::from("bulk order")));
+///
+/// assert_eq!(
+///     from_excel_formula("=A1 * 2"),
+///     Err(EvalexprError::ExcelCellReference { reference: "A1".to_string(), span: 1..3 })
+/// );
+/// ```
+pub fn from_excel_formula(formula: &str) -> EvalexprResult<Node> {
+    let start = match formula.trim_start().strip_prefix('=') {
+        Some(rest) => formula.len() - rest.len(),
+        None => 0,
+    };
+    let mut parser = Parser {
+        formula,
+        tokens: tokenize(formula, start)?,
+        position: 0,
+    };
+
+    if parser.tokens.is_empty() {
+        return Ok(Node::with_children(Operator::RootNode, Vec::new()));
+    }
+    let node = parser.parse_comparison()?;
+    match parser.next() {
+        None => Ok(parenthesized(node)),
+        Some((ExcelToken::RBrace, _)) => Err(EvalexprError::UnmatchedRBrace),
+        // E.g. two operands without operator, like the intersection of ranges
+        Some((_, span)) => Err(parser.unexpected(span)),
+    }
+}
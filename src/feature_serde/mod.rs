@@ -1,6 +1,10 @@
-use crate::{interface::build_operator_tree, Node};
-use serde::{de, Deserialize, Deserializer};
-use std::fmt;
+use crate::{interface::build_operator_tree, EvalexprError, Node, Value, ValueType};
+use serde::{de, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    ops::Range,
+};
 
 impl<'de> Deserialize<'de> for Node {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
@@ -33,3 +37,155 @@ impl<'de> de::Visitor<'de> for NodeVisitor {
         }
     }
 }
+
+/// Errors are serialized as a struct with the following fields, which are present for every error:
+///
+/// * `code`: the stable error code returned by `EvalexprError::code`,
+/// * `message`: the display message of the error,
+/// * `identifier`: the offending identifier, if the error is about an identifier, and none otherwise,
+/// * `value`: the value of an unexpected type, if the error is about one, and none otherwise,
+/// * `expected`: the names of the expected types, if the error is a type error that knows them, and none otherwise,
+/// * `actual`: the names of the actual types, if the error is a type error, and none otherwise,
+/// * `span`: the byte range `{ start, end }` of the offending part of the expression, if the error knows it, and none otherwise.
+///
+/// The type names are the ones returned by the builtin function `typeof`.
+/// Custom values cannot be serialized, so the `value` field is none if it would contain one.
+impl Serialize for EvalexprError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut state = serializer.serialize_struct("EvalexprError", 7)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("identifier", &offending_identifier(self))?;
+        let value = offending_value(self).filter(|value| is_serializable(value));
+        state.serialize_field("value", &value)?;
+        state.serialize_field("expected", &expected_types(self))?;
+        state.serialize_field("actual", &actual_types(self))?;
+        state.serialize_field("span", &span(self))?;
+        state.end()
+    }
+}
+
+/// Returns false for custom values and quantities, which cannot be serialized.
+fn is_serializable(value: &Value) -> bool {
+    match value {
+        Value::Custom(_) => false,
+        #[cfg(feature = "units_support")]
+        Value::Quantity { .. } => false,
+        _ => true,
+    }
+}
+
+fn offending_identifier(error: &EvalexprError) -> Option<&str> {
+    match error {
+        EvalexprError::VariableIdentifierNotFound(identifier)
+        | EvalexprError::FunctionIdentifierNotFound(identifier)
+        | EvalexprError::DuplicateBinding(identifier)
+        | EvalexprError::DuplicateRecordField {
+            name: identifier, ..
+        }
+        | EvalexprError::UnknownUnit(identifier)
+        | EvalexprError::InvalidIdentifier { identifier, .. }
+        | EvalexprError::ExcelCellReference {
+            reference: identifier,
+            ..
+        } => Some(identifier),
+        _ => None,
+    }
+}
+
+fn offending_value(error: &EvalexprError) -> Option<&Value> {
+    match error {
+        EvalexprError::ExpectedString { actual }
+        | EvalexprError::ExpectedInt { actual }
+        | EvalexprError::ExpectedFloat { actual }
+        | EvalexprError::ExpectedNumber { actual }
+        | EvalexprError::ExpectedNumberOrString { actual }
+        | EvalexprError::ExpectedBoolean { actual }
+        | EvalexprError::ExpectedTuple { actual }
+        | EvalexprError::ExpectedFixedLengthTuple { actual, .. }
+        | EvalexprError::ExpectedRangedLengthTuple { actual, .. }
+        | EvalexprError::ExpectedMap { actual }
+        | EvalexprError::ExpectedEmpty { actual }
+        | EvalexprError::TypeError { actual, .. }
+        | EvalexprError::ElementTypeError { actual, .. }
+        | EvalexprError::IncomparableValues { actual, .. }
+        | EvalexprError::InvalidMapPair { actual, .. }
+        | EvalexprError::NotCallable(actual)
+        | EvalexprError::UnencodableValue(actual) => Some(actual),
+        _ => None,
+    }
+}
+
+fn expected_types(error: &EvalexprError) -> Option<Vec<&'static str>> {
+    use ValueType::*;
+    let types = match error {
+        EvalexprError::ExpectedString { .. } => vec![String],
+        EvalexprError::ExpectedInt { .. } => vec![Int],
+        EvalexprError::ExpectedFloat { .. } => vec![Float],
+        EvalexprError::ExpectedNumber { .. } => vec![Int, Float],
+        EvalexprError::ExpectedNumberOrString { .. } => vec![String, Int, Float],
+        EvalexprError::ExpectedBoolean { .. } => vec![Boolean],
+        EvalexprError::ExpectedTuple { .. }
+        | EvalexprError::ExpectedFixedLengthTuple { .. }
+        | EvalexprError::ExpectedRangedLengthTuple { .. } => vec![Tuple],
+        EvalexprError::ExpectedMap { .. } => vec![Map],
+        EvalexprError::ExpectedEmpty { .. } => vec![Empty],
+        EvalexprError::TypeError { expected, .. }
+        | EvalexprError::ElementTypeError { expected, .. }
+        | EvalexprError::IncomparableValues { expected, .. } => expected.clone(),
+        _ => return None,
+    };
+    Some(types.into_iter().map(type_name).collect())
+}
+
+fn actual_types(error: &EvalexprError) -> Option<Vec<&'static str>> {
+    if let EvalexprError::WrongTypeCombination { actual, .. } = error {
+        Some(actual.iter().copied().map(type_name).collect())
+    } else {
+        offending_value(error).map(|value| vec![type_name(value.into())])
+    }
+}
+
+fn span(error: &EvalexprError) -> Option<&Range<usize>> {
+    match error {
+        EvalexprError::UnmatchedQuestionMark { span }
+        | EvalexprError::UnmatchedColon { span }
+        | EvalexprError::InvalidIdentifier { span, .. }
+        | EvalexprError::OperatorDisabled { span, .. }
+        | EvalexprError::DuplicateRecordField { span, .. }
+        | EvalexprError::UnsupportedExcelConstruct { span, .. }
+        | EvalexprError::ExcelCellReference { span, .. } => Some(span),
+        _ => None,
+    }
+}
+
+/// Returns the name of the given type as returned by the builtin function `typeof`, except for custom values, whose name depends on the value.
+fn type_name(value_type: ValueType) -> &'static str {
+    match value_type {
+        ValueType::String => "string",
+        ValueType::Float => "float",
+        ValueType::Int => "int",
+        ValueType::Boolean => "boolean",
+        ValueType::Tuple => "tuple",
+        ValueType::Map => "map",
+        ValueType::Empty => "empty",
+        ValueType::Custom => "custom",
+        #[cfg(feature = "units_support")]
+        ValueType::Quantity => "quantity",
+    }
+}
+
+/// Serializes the given map with its keys in sorted order, so the output does not depend on the order of insertion.
+pub(crate) fn serialize_sorted<V, S>(
+    map: &HashMap<String, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    V: Serialize,
+    S: Serializer,
+{
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
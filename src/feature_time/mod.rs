@@ -0,0 +1,173 @@
+use std::{
+    convert::TryFrom,
+    fmt::{self, Write},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Datelike, TimeZone, Timelike, Utc,
+};
+
+use crate::{
+    value::{FloatType, IntType},
+    Context, EvalexprError, EvalexprResult, Value,
+};
+
+/// The source of the current time for the `now` builtin function, as Unix timestamp in seconds.
+///
+/// The system clock is used by default.
+/// A fixed clock makes expressions that depend on the current time reproducible, e.g. in tests.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let mut context = HashMapContext::new();
+/// context.set_clock(Clock::fixed(1_700_000_000));
+/// assert_eq!(
+///     eval_with_context("now() + days(1)", &context),
+///     Ok(Value::from(1_700_086_400))
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct Clock {
+    /// The function returning the current time, or `None` for the system clock.
+    now: Option<Arc<dyn Fn() -> IntType + Send + Sync>>,
+}
+
+impl Clock {
+    /// Constructs a clock that reads the current time from the system.
+    pub fn system() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a clock that always returns the given Unix timestamp in seconds.
+    pub fn fixed(timestamp: IntType) -> Self {
+        Self::new(move || timestamp)
+    }
+
+    /// Constructs a clock that calls the given function for the current Unix timestamp in seconds.
+    pub fn new<F>(now: F) -> Self
+    where
+        F: Fn() -> IntType + Send + Sync + 'static,
+    {
+        Self {
+            now: Some(Arc::new(now)),
+        }
+    }
+
+    /// Returns the current time as Unix timestamp in seconds.
+    pub fn now(&self) -> IntType {
+        if let Some(now) = &self.now {
+            return now();
+        }
+
+        let seconds = |seconds: u64| IntType::try_from(seconds).unwrap_or(IntType::MAX);
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(duration) => seconds(duration.as_secs()),
+            Err(error) => -seconds(error.duration().as_secs()),
+        }
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.now {
+            Some(_) => write!(f, "Clock::new(..)"),
+            None => write!(f, "Clock::system()"),
+        }
+    }
+}
+
+/// Converts the point in time into its Unix timestamp in seconds, dropping fractions of a second.
+impl<Tz: TimeZone> From<DateTime<Tz>> for Value {
+    fn from(date_time: DateTime<Tz>) -> Self {
+        Value::Int(date_time.timestamp())
+    }
+}
+
+/// Converts a Unix timestamp in seconds into a point in time in UTC.
+impl TryFrom<Value> for DateTime<Utc> {
+    type Error = EvalexprError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        date_time(value.as_int()?)
+    }
+}
+
+fn date_time(timestamp: IntType) -> EvalexprResult<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp, 0)
+        .ok_or_else(|| EvalexprError::timestamp_out_of_range(timestamp))
+}
+
+/// Returns the given amount of seconds multiplied by the given factor, keeping integers as integers.
+fn seconds(amount: &Value, factor: IntType) -> EvalexprResult<Value> {
+    match amount {
+        Value::Int(int) => int
+            .checked_mul(factor)
+            .map(Value::Int)
+            .ok_or_else(|| EvalexprError::multiplication_error(amount.clone(), Value::Int(factor))),
+        Value::Float(float) => Ok(Value::Float(float * factor as FloatType)),
+        value => Err(EvalexprError::expected_number(value.clone())),
+    }
+}
+
+/// Returns the component of the given Unix timestamp in seconds in UTC that `component` selects.
+fn date_component(
+    argument: &Value,
+    component: impl FnOnce(DateTime<Utc>) -> IntType,
+) -> EvalexprResult<Value> {
+    Ok(Value::Int(component(date_time(argument.as_int()?)?)))
+}
+
+/// Formats the given Unix timestamp in seconds in UTC according to the given `strftime`-like format string.
+fn format_date(timestamp: IntType, format: &str) -> EvalexprResult<String> {
+    let invalid_format = || EvalexprError::invalid_time_format(format.to_string());
+    // Formatting panics on invalid items, so they are rejected beforehand
+    let items: Vec<_> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(invalid_format());
+    }
+
+    let mut result = String::new();
+    write!(
+        result,
+        "{}",
+        date_time(timestamp)?.format_with_items(items.iter())
+    )
+    .map_err(|_| invalid_format())?;
+    Ok(result)
+}
+
+/// Calls the builtin function with the given identifier if it is one of the time functions `now`, `days`, `hours` and `format_time`, or one of the `date::*` functions.
+///
+/// The `now` function needs the clock of the context, so these functions are not part of the other builtin functions.
+pub(crate) fn call_time_function<C: Context>(
+    identifier: &str,
+    argument: &Value,
+    context: &C,
+) -> Option<EvalexprResult<Value>> {
+    let result = match identifier {
+        "now" => argument
+            .as_empty()
+            .map(|()| Value::Int(context.clock().now())),
+        "days" => seconds(argument, 86_400),
+        "hours" => seconds(argument, 3600),
+        "date::year" => date_component(argument, |date_time| date_time.year().into()),
+        "date::month" => date_component(argument, |date_time| date_time.month().into()),
+        "date::day" => date_component(argument, |date_time| date_time.day().into()),
+        "date::weekday" => date_component(argument, |date_time| {
+            date_time.weekday().number_from_monday().into()
+        }),
+        "date::hour" => date_component(argument, |date_time| date_time.hour().into()),
+        "date::format" | "format_time" => argument.as_fixed_len_tuple(2).and_then(|arguments| {
+            let timestamp = arguments[0].as_int()?;
+            format_date(timestamp, &arguments[1].as_string()?).map(Value::String)
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
@@ -0,0 +1,456 @@
+use std::{
+    fmt,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    context::FloatEquality, error::expect_operator_argument_amount, value::FloatType, Context,
+    EvalexprError, EvalexprResult, Operator, Value,
+};
+
+/// The exponents of the base dimensions length, mass and time that a unit measures.
+///
+/// For example, speed has the dimension length per time, i.e. `Dimension::new(1, 0, -1)`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Dimension {
+    /// The exponent of length.
+    pub length: i8,
+    /// The exponent of mass.
+    pub mass: i8,
+    /// The exponent of time.
+    pub time: i8,
+}
+
+impl Dimension {
+    /// The dimension of plain numbers.
+    pub const NONE: Dimension = Dimension::new(0, 0, 0);
+    /// The dimension of lengths.
+    pub const LENGTH: Dimension = Dimension::new(1, 0, 0);
+    /// The dimension of masses.
+    pub const MASS: Dimension = Dimension::new(0, 1, 0);
+    /// The dimension of durations.
+    pub const TIME: Dimension = Dimension::new(0, 0, 1);
+
+    /// Constructs a dimension from the exponents of length, mass and time.
+    pub const fn new(length: i8, mass: i8, time: i8) -> Self {
+        Self { length, mass, time }
+    }
+
+    /// Returns the dimension of the product of quantities of this and the other dimension,
+    /// or of the quotient if `divide` is true.
+    /// Returns `None` if an exponent overflows.
+    fn combine(self, other: Dimension, divide: bool) -> Option<Dimension> {
+        let combine = |a: i8, b: i8| {
+            if divide {
+                a.checked_sub(b)
+            } else {
+                a.checked_add(b)
+            }
+        };
+        Some(Dimension::new(
+            combine(self.length, other.length)?,
+            combine(self.mass, other.mass)?,
+            combine(self.time, other.time)?,
+        ))
+    }
+}
+
+/// A unit of measurement.
+#[derive(Debug, PartialEq)]
+struct Unit {
+    name: String,
+    dimension: Dimension,
+    factor: FloatType,
+}
+
+/// A unit of measurement, as stored in `Value::Quantity`.
+///
+/// Units are defined in a `UnitRegistry`, or derived from registered units by multiplying and dividing quantities.
+/// Two units are equal if they have the same name, dimension and factor.
+#[derive(Clone, PartialEq)]
+pub struct UnitId(Arc<Unit>);
+
+impl UnitId {
+    fn new(name: String, dimension: Dimension, factor: FloatType) -> Self {
+        Self(Arc::new(Unit {
+            name,
+            dimension,
+            factor,
+        }))
+    }
+
+    /// Returns the name of this unit, e.g. `m` or `km/h`.
+    pub fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    /// Returns the dimension this unit measures.
+    pub fn dimension(&self) -> Dimension {
+        self.0.dimension
+    }
+
+    /// Returns the value of one of this unit in the base units `m`, `kg` and `s`, e.g. `1000.0` for `km`.
+    pub fn factor(&self) -> FloatType {
+        self.0.factor
+    }
+
+    /// Returns true if quantities of this and the other unit can be converted into each other, i.e. if they measure the same dimension.
+    pub fn is_compatible(&self, other: &UnitId) -> bool {
+        self.dimension() == other.dimension()
+    }
+
+    /// Returns the name of this unit such that it can be combined with other unit names.
+    fn operand_name(&self) -> String {
+        if self.name().contains(['*', '/']) {
+            format!("({})", self.name())
+        } else {
+            self.name().to_string()
+        }
+    }
+}
+
+impl fmt::Debug for UnitId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("UnitId").field(&self.name()).finish()
+    }
+}
+
+/// A set of named units that quantities can be created with by the builtin functions `with_unit` and `to_unit`.
+///
+/// The registry is also used to name the units of products and quotients of quantities:
+/// if a registered unit has the resulting dimension and factor, like `N` for `kg*m/s^2`, it is used,
+/// and otherwise a unit is derived with a name like `m*kg`.
+///
+/// A context provides its registry through `Context::unit_registry`, which defaults to `UnitRegistry::standard`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let mut registry = UnitRegistry::standard().clone();
+/// registry.add_unit("furlong", Dimension::LENGTH, 201.168).unwrap();
+/// let mut context = HashMapContext::new();
+/// context.set_unit_registry(registry);
+///
+/// assert_eq!(
+///     eval_with_context("str::from(to_unit(with_unit(1, \"furlong\"), \"m\"))", &context),
+///     Ok(Value::from("201.168 m"))
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct UnitRegistry {
+    units: Vec<UnitId>,
+}
+
+impl UnitRegistry {
+    /// Constructs a registry without units.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the registry of standard units.
+    ///
+    /// It contains units of length (`m`, `km`, `cm`, `mm`, `in`, `ft`, `yd`, `mi`),
+    /// mass (`kg`, `g`, `t`, `oz`, `lb`), time (`s`, `ms`, `min`, `h`, `d`),
+    /// and derived units (`m^2`, `m^3`, `L`, `m/s`, `km/h`, `mph`, `m/s^2`, `Hz`, `N`, `J`, `W`).
+    pub fn standard() -> &'static UnitRegistry {
+        static STANDARD: OnceLock<UnitRegistry> = OnceLock::new();
+        STANDARD.get_or_init(|| {
+            let units: &[(&str, Dimension, FloatType)] = &[
+                ("m", Dimension::LENGTH, 1.0),
+                ("km", Dimension::LENGTH, 1000.0),
+                ("cm", Dimension::LENGTH, 0.01),
+                ("mm", Dimension::LENGTH, 0.001),
+                ("in", Dimension::LENGTH, 0.0254),
+                ("ft", Dimension::LENGTH, 0.3048),
+                ("yd", Dimension::LENGTH, 0.9144),
+                ("mi", Dimension::LENGTH, 1609.344),
+                ("kg", Dimension::MASS, 1.0),
+                ("g", Dimension::MASS, 0.001),
+                ("t", Dimension::MASS, 1000.0),
+                ("oz", Dimension::MASS, 0.028_349_523_125),
+                ("lb", Dimension::MASS, 0.453_592_37),
+                ("s", Dimension::TIME, 1.0),
+                ("ms", Dimension::TIME, 0.001),
+                ("min", Dimension::TIME, 60.0),
+                ("h", Dimension::TIME, 3600.0),
+                ("d", Dimension::TIME, 86400.0),
+                ("m^2", Dimension::new(2, 0, 0), 1.0),
+                ("m^3", Dimension::new(3, 0, 0), 1.0),
+                ("L", Dimension::new(3, 0, 0), 0.001),
+                ("m/s", Dimension::new(1, 0, -1), 1.0),
+                ("km/h", Dimension::new(1, 0, -1), 1000.0 / 3600.0),
+                ("mph", Dimension::new(1, 0, -1), 0.44704),
+                ("m/s^2", Dimension::new(1, 0, -2), 1.0),
+                ("Hz", Dimension::new(0, 0, -1), 1.0),
+                ("N", Dimension::new(1, 1, -2), 1.0),
+                ("J", Dimension::new(2, 1, -2), 1.0),
+                ("W", Dimension::new(2, 1, -3), 1.0),
+            ];
+            let mut registry = UnitRegistry::new();
+            for (name, dimension, factor) in units {
+                registry.add_unit(name, *dimension, *factor).unwrap();
+            }
+            registry
+        })
+    }
+
+    /// Adds a unit with the given name that measures the given dimension,
+    /// where one of the unit equals `factor` of the base units `m`, `kg` and `s`.
+    ///
+    /// Returns `EvalexprError::DuplicateBinding` if a unit with the given name exists already.
+    pub fn add_unit(
+        &mut self,
+        name: &str,
+        dimension: Dimension,
+        factor: FloatType,
+    ) -> EvalexprResult<UnitId> {
+        if self.get(name).is_some() {
+            return Err(EvalexprError::DuplicateBinding(name.to_string()));
+        }
+
+        let unit = UnitId::new(name.to_string(), dimension, factor);
+        self.units.push(unit.clone());
+        Ok(unit)
+    }
+
+    /// Returns the unit with the given name.
+    pub fn get(&self, name: &str) -> Option<&UnitId> {
+        self.units.iter().find(|unit| unit.name() == name)
+    }
+
+    /// Returns a quantity of the given value in the unit with the given name.
+    ///
+    /// Returns `EvalexprError::UnknownUnit` if there is no unit with the given name.
+    pub fn quantity(&self, value: FloatType, unit: &str) -> EvalexprResult<Value> {
+        Ok(Value::Quantity {
+            value,
+            unit: self.unit(unit)?.clone(),
+        })
+    }
+
+    fn unit(&self, name: &str) -> EvalexprResult<&UnitId> {
+        self.get(name)
+            .ok_or_else(|| EvalexprError::UnknownUnit(name.to_string()))
+    }
+
+    /// Returns the first registered unit of the given dimension and factor,
+    /// or a new unit with the given name if there is none.
+    fn find_or_derive(&self, name: String, dimension: Dimension, factor: FloatType) -> UnitId {
+        self.units
+            .iter()
+            .find(|unit| {
+                unit.dimension() == dimension
+                    && FloatEquality::Relative(1e-12).are_equal(unit.factor(), factor)
+            })
+            .cloned()
+            .unwrap_or_else(|| UnitId::new(name, dimension, factor))
+    }
+}
+
+/// Returns the unit of the given number or quantity, or `None` for a plain number, or an error for any other value.
+fn unit_of(value: &Value) -> EvalexprResult<Option<&UnitId>> {
+    match value {
+        Value::Quantity { unit, .. } => Ok(Some(unit)),
+        value => value.as_number().map(|_| None),
+    }
+}
+
+/// Returns the amount of the given number or quantity, i.e. the value without unit.
+fn amount(value: &Value) -> EvalexprResult<FloatType> {
+    match value {
+        Value::Quantity { value, .. } => Ok(*value),
+        value => value.as_number(),
+    }
+}
+
+fn unit_mismatch(
+    operator: &Operator,
+    left: Option<&UnitId>,
+    right: Option<&UnitId>,
+) -> EvalexprError {
+    let name =
+        |unit: Option<&UnitId>| unit.map_or_else(String::new, |unit| unit.name().to_string());
+    EvalexprError::unit_mismatch(name(left), name(right), operator.clone())
+}
+
+/// Returns the amounts of the given operands of the given operator, with the right amount converted into the unit of the left one.
+fn compatible_amounts(
+    operator: &Operator,
+    arguments: &[Value],
+) -> EvalexprResult<(FloatType, FloatType)> {
+    let (left, right) = (unit_of(&arguments[0])?, unit_of(&arguments[1])?);
+    match (left, right) {
+        (Some(left), Some(right)) if left.is_compatible(right) => Ok((
+            amount(&arguments[0])?,
+            amount(&arguments[1])? * right.factor() / left.factor(),
+        )),
+        _ => Err(unit_mismatch(operator, left, right)),
+    }
+}
+
+/// Multiplies or divides the given operands, of which at least one is a quantity.
+fn multiply(
+    operator: &Operator,
+    arguments: &[Value],
+    registry: &UnitRegistry,
+    divide: bool,
+) -> EvalexprResult<Value> {
+    let (left, right) = (unit_of(&arguments[0])?, unit_of(&arguments[1])?);
+    let (a, b) = (amount(&arguments[0])?, amount(&arguments[1])?);
+    let value = if divide { a / b } else { a * b };
+    let (left, right) = match (left, right) {
+        (Some(unit), None) => {
+            return Ok(Value::Quantity {
+                value,
+                unit: unit.clone(),
+            })
+        },
+        (None, Some(unit)) if !divide => {
+            return Ok(Value::Quantity {
+                value,
+                unit: unit.clone(),
+            })
+        },
+        (left, right) => (left, right),
+    };
+
+    let (left_dimension, left_factor, left_name) = left
+        .map_or((Dimension::NONE, 1.0, "1".to_string()), |unit| {
+            (unit.dimension(), unit.factor(), unit.operand_name())
+        });
+    // At least one of the operands is a quantity, so the right one is if the left one is not
+    let right = right.expect("the right operand is a quantity");
+    let dimension = left_dimension
+        .combine(right.dimension(), divide)
+        .ok_or_else(|| unit_mismatch(operator, left, Some(right)))?;
+    let factor = if divide {
+        left_factor / right.factor()
+    } else {
+        left_factor * right.factor()
+    };
+    if dimension == Dimension::NONE {
+        return Ok(Value::Float(value * factor));
+    }
+
+    let separator = if divide { '/' } else { '*' };
+    let name = format!("{}{}{}", left_name, separator, right.operand_name());
+    Ok(Value::Quantity {
+        value,
+        unit: registry.find_or_derive(name, dimension, factor),
+    })
+}
+
+/// Evaluates the given operator if one of the given arguments is a quantity.
+///
+/// Returns `None` if none of the arguments is a quantity, or if the operator does not support quantities,
+/// in which case it fails with a type error as for other values that are not numbers.
+pub(crate) fn eval_quantity_operator<C: Context>(
+    operator: &Operator,
+    arguments: &[Value],
+    context: &C,
+) -> Option<EvalexprResult<Value>> {
+    if !arguments
+        .iter()
+        .any(|argument| matches!(argument, Value::Quantity { .. }))
+    {
+        return None;
+    }
+
+    let result = match operator {
+        Operator::Neg => {
+            expect_operator_argument_amount(arguments.len(), 1).map(|()| match &arguments[0] {
+                Value::Quantity { value, unit } => Value::Quantity {
+                    value: -value,
+                    unit: unit.clone(),
+                },
+                _ => unreachable!("the argument is a quantity"),
+            })
+        },
+        Operator::Add | Operator::Sub => binary(arguments, |arguments| {
+            let (a, b) = compatible_amounts(operator, arguments)?;
+            let value = if operator == &Operator::Add {
+                a + b
+            } else {
+                a - b
+            };
+            let unit = unit_of(&arguments[0])?
+                .expect("the left operand is a quantity")
+                .clone();
+            Ok(Value::Quantity { value, unit })
+        }),
+        Operator::Mul => binary(arguments, |arguments| {
+            multiply(operator, arguments, context.unit_registry(), false)
+        }),
+        Operator::Div | Operator::FloatDiv => binary(arguments, |arguments| {
+            multiply(operator, arguments, context.unit_registry(), true)
+        }),
+        Operator::Eq | Operator::Neq => binary(arguments, |arguments| {
+            let (a, b) = compatible_amounts(operator, arguments)?;
+            let equal = context.float_equality().are_equal(a, b);
+            Ok(Value::Boolean(equal == (operator == &Operator::Eq)))
+        }),
+        Operator::Gt | Operator::Lt | Operator::Geq | Operator::Leq => {
+            binary(arguments, |arguments| {
+                let (a, b) = compatible_amounts(operator, arguments)?;
+                Ok(Value::Boolean(match operator {
+                    Operator::Gt => a > b,
+                    Operator::Lt => a < b,
+                    Operator::Geq => a >= b,
+                    _ => a <= b,
+                }))
+            })
+        },
+        Operator::Cmp => binary(arguments, |arguments| {
+            let (a, b) = compatible_amounts(operator, arguments)?;
+            crate::operator::ordering_to_value(a.partial_cmp(&b), &arguments[0], &arguments[1])
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn binary(
+    arguments: &[Value],
+    eval: impl FnOnce(&[Value]) -> EvalexprResult<Value>,
+) -> EvalexprResult<Value> {
+    expect_operator_argument_amount(arguments.len(), 2)?;
+    eval(arguments)
+}
+
+/// Calls the builtin function with the given identifier if it is one of the unit functions `with_unit`, `unit_of` and `to_unit`.
+///
+/// These functions need the unit registry of the context, so they are not part of the other builtin functions.
+pub(crate) fn call_unit_function<C: Context>(
+    identifier: &str,
+    argument: &Value,
+    context: &C,
+) -> Option<EvalexprResult<Value>> {
+    let registry = context.unit_registry();
+    let result = match identifier {
+        "with_unit" => argument.as_fixed_len_tuple(2).and_then(|arguments| {
+            let value = arguments[0].as_number()?;
+            registry.quantity(value, &arguments[1].as_string()?)
+        }),
+        "unit_of" => unit_of(argument).map(|unit| Value::from(unit.map_or("", |unit| unit.name()))),
+        "to_unit" => argument.as_fixed_len_tuple(2).and_then(|arguments| {
+            let target = registry.unit(&arguments[1].as_string()?)?;
+            match unit_of(&arguments[0])? {
+                Some(unit) if unit.is_compatible(target) => Ok(Value::Quantity {
+                    value: amount(&arguments[0])? * unit.factor() / target.factor(),
+                    unit: target.clone(),
+                }),
+                unit => Err(unit_mismatch(
+                    &Operator::FunctionIdentifier {
+                        identifier: identifier.into(),
+                    },
+                    unit,
+                    Some(target),
+                )),
+            }
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
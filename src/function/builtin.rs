@@ -2,11 +2,14 @@
 use regex::Regex;
 
 use crate::{
-    value::{FloatType, IntType},
+    value::{FloatType, IntType, MapType},
     EvalexprError, Function, Value, ValueType,
 };
 use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
+    iter,
     ops::{BitAnd, BitOr, BitXor, Not, Shl, Shr},
 };
 
@@ -48,6 +51,406 @@ macro_rules! int_function {
     };
 }
 
+/// Rounds the given number to an integer according to the given rounding mode.
+/// `half_up` rounds half-way cases away from zero, `half_even` to the nearest even integer,
+/// `half_down` towards zero, and `toward_zero` truncates the number.
+fn round_with_mode(number: FloatType, mode: &str) -> Result<FloatType, EvalexprError> {
+    let is_tie = (number - number.trunc()).abs() == 0.5;
+    match mode {
+        "half_up" => Ok(number.round()),
+        "half_even" if is_tie => Ok(2.0 * (number / 2.0).round()),
+        "half_even" => Ok(number.round()),
+        "half_down" if is_tie => Ok(number.trunc()),
+        "half_down" => Ok(number.round()),
+        "toward_zero" => Ok(number.trunc()),
+        _ => Err(EvalexprError::invalid_rounding_mode(mode.to_string())),
+    }
+}
+
+/// Sums up the given numbers.
+/// If all numbers are integers, the sum is computed exactly as integer, failing on overflow.
+/// Otherwise, the sum is computed as float using Kahan-Babuška summation (Neumaier's variant),
+/// which compensates the rounding error of each addition and is therefore much more accurate than adding the numbers in a loop.
+fn sum_numbers(arguments: &[Value]) -> Result<Value, EvalexprError> {
+    if arguments.iter().all(Value::is_int) {
+        let mut sum: IntType = 0;
+        for argument in arguments {
+            let int = argument.as_int()?;
+            sum = sum
+                .checked_add(int)
+                .ok_or_else(|| EvalexprError::addition_error(Value::Int(sum), argument.clone()))?;
+        }
+        return Ok(Value::Int(sum));
+    }
+
+    let mut sum: FloatType = 0.0;
+    let mut compensation: FloatType = 0.0;
+    for argument in arguments {
+        let float = argument.as_number()?;
+        let new_sum = sum + float;
+        if sum.abs() >= float.abs() {
+            compensation += (sum - new_sum) + float;
+        } else {
+            compensation += (float - new_sum) + sum;
+        }
+        sum = new_sum;
+    }
+    Ok(Value::Float(sum + compensation))
+}
+
+/// Converts an index counting characters into the byte index of the character in the given string.
+/// The index of the end of the string, i.e. the amount of characters, is valid as well.
+///
+/// Returns `EvalexprError::OutOfBoundsAccess` if the index is negative or greater than the amount of characters.
+fn char_to_byte_index(string: &str, index: IntType) -> Result<usize, EvalexprError> {
+    let index = usize::try_from(index).map_err(|_| EvalexprError::OutOfBoundsAccess)?;
+    string
+        .char_indices()
+        .map(|(byte_index, _)| byte_index)
+        .chain(iter::once(string.len()))
+        .nth(index)
+        .ok_or(EvalexprError::OutOfBoundsAccess)
+}
+
+/// Divides the numerator and the non-zero denominator of a fraction by their greatest common divisor,
+/// and negates both if needed to make the denominator positive.
+///
+/// Returns `None` if the simplified fraction does not fit into integers.
+fn simplify_fraction(numerator: IntType, denominator: IntType) -> Option<(IntType, IntType)> {
+    let (mut a, mut b) = (
+        u128::from(numerator.unsigned_abs()),
+        u128::from(denominator.unsigned_abs()),
+    );
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    let gcd = i128::try_from(a).ok()?;
+    let sign = if denominator < 0 { -1 } else { 1 };
+    let numerator = IntType::try_from(sign * i128::from(numerator) / gcd).ok()?;
+    let denominator = IntType::try_from(sign * i128::from(denominator) / gcd).ok()?;
+    Some((numerator, denominator))
+}
+
+/// Removes the surrounding whitespace and all underscores from a string to be parsed as number.
+fn normalize_number_string(string: &str) -> String {
+    string.trim().replace('_', "")
+}
+
+/// Parses an integer in the given radix, after removing the surrounding whitespace and all underscores.
+/// The integer may start with a sign `+` or `-`, followed by at least one digit of the radix.
+fn parse_int(string: &str, radix: IntType) -> Result<IntType, EvalexprError> {
+    let radix = u32::try_from(radix)
+        .ok()
+        .filter(|radix| (2..=36).contains(radix))
+        .ok_or_else(|| EvalexprError::invalid_radix(radix))?;
+    IntType::from_str_radix(&normalize_number_string(string), radix)
+        .map_err(|_| EvalexprError::invalid_number(string.to_string()))
+}
+
+/// Parses a float after removing the surrounding whitespace and all underscores.
+/// The float may start with a sign `+` or `-`, followed by decimal digits with an optional decimal point and exponent,
+/// or by `inf`, `infinity` or `NaN` in any case.
+fn parse_float(string: &str) -> Result<FloatType, EvalexprError> {
+    normalize_number_string(string)
+        .parse()
+        .map_err(|_| EvalexprError::invalid_number(string.to_string()))
+}
+
+/// A hashable representation of a string, numeric or boolean value, with the same equality semantics as `==`.
+#[derive(PartialEq, Eq, Hash)]
+enum ScalarKey<'a> {
+    String(&'a str),
+    Int(IntType),
+    /// The bits of a float, where negative zero is represented as positive zero.
+    Float(u64),
+    Boolean(bool),
+}
+
+/// Returns the hashable representation of the given value, or `None` if the value is not equal to any string, numeric or boolean value.
+/// This is the case for tuples, maps, empty and custom values, as well as for NaN.
+fn scalar_key(value: &Value) -> Option<ScalarKey<'_>> {
+    match value {
+        Value::String(string) => Some(ScalarKey::String(string)),
+        Value::Int(int) => Some(ScalarKey::Int(*int)),
+        Value::Float(float) if float.is_nan() => None,
+        Value::Float(float) if *float == 0.0 => Some(ScalarKey::Float(0.0f64.to_bits())),
+        Value::Float(float) => Some(ScalarKey::Float(float.to_bits())),
+        Value::Boolean(boolean) => Some(ScalarKey::Boolean(*boolean)),
+        _ => None,
+    }
+}
+
+/// Returns the elements of a value used as a set, where a tuple is the set of its elements, the empty value is the empty set,
+/// and any other value is a set with one element.
+fn set_elements(value: &Value) -> &[Value] {
+    match value {
+        Value::Tuple(tuple) => tuple,
+        Value::Empty => &[],
+        value => std::slice::from_ref(value),
+    }
+}
+
+/// Returns the booleans of a value used as a set of booleans, see `set_elements`.
+/// Fails with `EvalexprError::ElementTypeError` naming the first element that is not a boolean.
+fn boolean_elements(value: &Value) -> Result<Vec<bool>, EvalexprError> {
+    set_elements(value)
+        .iter()
+        .enumerate()
+        .map(|(index, element)| match element {
+            Value::Boolean(boolean) => Ok(*boolean),
+            element => Err(EvalexprError::element_type_error(
+                index,
+                vec![ValueType::Boolean],
+                element.clone(),
+            )),
+        })
+        .collect()
+}
+
+/// Returns for each needle in the second element of the given pair whether it is contained in the haystack in the first element.
+/// Both the haystack and the needles are used as sets, see `set_elements`.
+///
+/// The needles must be string, numeric or boolean values.
+fn set_membership(argument: &Value) -> Result<Vec<bool>, EvalexprError> {
+    let arguments = argument.as_fixed_len_tuple(2)?;
+    // Hash the haystack once, such that each lookup is constant time instead of linear
+    let haystack: HashSet<_> = set_elements(&arguments[0])
+        .iter()
+        .filter_map(scalar_key)
+        .collect();
+
+    set_elements(&arguments[1])
+        .iter()
+        .map(|needle| match needle {
+            Value::String(_) | Value::Int(_) | Value::Float(_) | Value::Boolean(_) => {
+                Ok(scalar_key(needle).is_some_and(|key| haystack.contains(&key)))
+            },
+            _ => Err(EvalexprError::type_error(
+                needle.clone(),
+                vec![
+                    ValueType::String,
+                    ValueType::Int,
+                    ValueType::Float,
+                    ValueType::Boolean,
+                ],
+            )),
+        })
+        .collect()
+}
+
+/// Returns the elements of the given argument that are not `Value::Empty`, as reductions treat empty values as absent.
+/// A tuple argument is reduced over its elements, and any other argument is reduced over itself.
+/// If no element is left, there is nothing to reduce, and an error is returned.
+fn reduction_arguments(argument: &Value) -> Result<Vec<Value>, EvalexprError> {
+    let arguments: Vec<Value> = set_elements(argument)
+        .iter()
+        .filter(|argument| !argument.is_empty())
+        .cloned()
+        .collect();
+    if arguments.is_empty() {
+        Err(EvalexprError::wrong_function_argument_amount_range(
+            0,
+            1..=usize::MAX,
+        ))
+    } else {
+        Ok(arguments)
+    }
+}
+
+/// Compares two values if they are comparable: numbers numerically, where integers and floats can be mixed, and strings lexicographically.
+/// Returns `None` if the values are not both numbers or both strings.
+/// NaN is neither less than, equal to nor greater than any number, so it compares as `Some(None)`.
+fn compare_values(a: &Value, b: &Value) -> Option<Option<Ordering>> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(Some(a.cmp(b))),
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            Some(a.as_number().ok()?.partial_cmp(&b.as_number().ok()?))
+        },
+        (Value::String(a), Value::String(b)) => Some(Some(a.cmp(b))),
+        _ => None,
+    }
+}
+
+/// Returns the types that can be compared with the given value by `compare_values`.
+fn comparable_types(value: &Value) -> Vec<ValueType> {
+    match value {
+        Value::Int(_) | Value::Float(_) => vec![ValueType::Int, ValueType::Float],
+        Value::String(_) => vec![ValueType::String],
+        _ => vec![ValueType::String, ValueType::Int, ValueType::Float],
+    }
+}
+
+/// Returns the index of the minimal key if `wanted` is `Ordering::Less`, and of the maximal key if it is `Ordering::Greater`.
+/// Of equal keys, the first one is selected.
+/// Like `FloatType::min` and `FloatType::max`, NaN keys are ignored unless all keys are NaN.
+///
+/// The keys must not be empty, and must be pairwise comparable by `compare_values`.
+fn extreme_index(keys: &[&Value], wanted: Ordering) -> Result<usize, EvalexprError> {
+    let incomparable = |index: usize| {
+        EvalexprError::incomparable_values(index, comparable_types(keys[0]), keys[index].clone())
+    };
+    if compare_values(keys[0], keys[0]).is_none() {
+        return Err(incomparable(0));
+    }
+
+    let is_nan = |key: &Value| matches!(key, Value::Float(float) if float.is_nan());
+    let mut extreme = 0;
+    for (index, key) in keys.iter().enumerate().skip(1) {
+        let ordering = compare_values(key, keys[extreme]).ok_or_else(|| incomparable(index))?;
+        if ordering == Some(wanted) || (is_nan(keys[extreme]) && !is_nan(key)) {
+            extreme = index;
+        }
+    }
+    Ok(extreme)
+}
+
+/// Returns the element of a tuple with the minimal or maximal value at the given key, see `extreme_index`.
+/// The argument is a pair of the tuple of maps and the key, where the key may consist of multiple dot-separated keys to access the fields of nested maps.
+fn extreme_by_key(argument: &Value, wanted: Ordering) -> Result<Value, EvalexprError> {
+    let arguments = argument.as_fixed_len_tuple(2)?;
+    let elements = set_elements(&arguments[0]);
+    let key = arguments[1].as_string()?;
+    if elements.is_empty() {
+        return Err(EvalexprError::wrong_function_argument_amount_range(
+            0,
+            1..=usize::MAX,
+        ));
+    }
+
+    let keys = elements
+        .iter()
+        .map(|element| {
+            key.split('.').try_fold(element, |value, field| {
+                if !value.is_map() {
+                    return Err(EvalexprError::expected_map(value.clone()));
+                }
+                value
+                    .get_field(field)
+                    .ok_or_else(|| EvalexprError::VariableIdentifierNotFound(key.clone()))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(elements[extreme_index(&keys, wanted)?].clone())
+}
+
+/// Converts a tuple of pairs of a string key and a value into a map, where the empty value is converted into the empty map.
+/// If a key appears multiple times, the entry keeps the position of the first pair and the value of the last one.
+///
+/// Fails with `EvalexprError::InvalidMapPair` naming the first element that is not a pair with a string key.
+fn pairs_to_map(argument: &Value) -> Result<MapType, EvalexprError> {
+    let pairs = elements(argument)?;
+    let mut map = MapType::with_capacity(pairs.len());
+    let mut indices = HashMap::with_capacity(pairs.len());
+    for (index, pair) in pairs.iter().enumerate() {
+        let (key, value) = match pair {
+            Value::Tuple(pair) => match pair.as_slice() {
+                [Value::String(key), value] => (key, value),
+                _ => {
+                    return Err(EvalexprError::invalid_map_pair(
+                        index,
+                        Value::Tuple(pair.clone()),
+                    ))
+                },
+            },
+            pair => return Err(EvalexprError::invalid_map_pair(index, pair.clone())),
+        };
+        if let Some(&position) = indices.get(key) {
+            map[position] = (key.clone(), value.clone());
+        } else {
+            indices.insert(key, map.len());
+            map.push((key.clone(), value.clone()));
+        }
+    }
+    Ok(map)
+}
+
+/// Returns the elements of the given tuple, where the empty value is a tuple without elements.
+fn elements(argument: &Value) -> Result<&[Value], EvalexprError> {
+    match argument {
+        Value::Empty => Ok(&[]),
+        argument => argument.as_slice(),
+    }
+}
+
+/// Returns the entries of the given map sorted by their keys.
+fn sorted_entries(argument: &Value) -> Result<Vec<(String, Value)>, EvalexprError> {
+    let mut entries = argument.as_map()?;
+    entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(entries)
+}
+
+/// Formats an amount given in tenths, with one decimal digit that is omitted if it is zero.
+fn format_tenths(tenths: u128) -> String {
+    if tenths % 10 == 0 {
+        (tenths / 10).to_string()
+    } else {
+        format!("{}.{}", tenths / 10, tenths % 10)
+    }
+}
+
+/// The binary units of byte sizes, each 1024 times the previous one.
+const BYTE_UNITS: [&str; 7] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+/// Formats a byte size in the largest binary unit that it reaches, rounded half up to one decimal digit, e.g. `1.5 KiB`.
+fn format_bytes(bytes: IntType) -> Result<String, EvalexprError> {
+    let bytes =
+        u128::try_from(bytes).map_err(|_| EvalexprError::invalid_quantity(Value::Int(bytes)))?;
+    let rounded_tenths = |unit: usize| {
+        let unit_size = 1024u128.pow(unit as u32);
+        (bytes * 10 + unit_size / 2) / unit_size
+    };
+
+    let mut unit = 0;
+    while unit + 1 < BYTE_UNITS.len() && bytes >= 1024u128.pow(unit as u32 + 1) {
+        unit += 1;
+    }
+    // Rounding may reach the next unit, e.g. for 1023.99 KiB
+    if unit + 1 < BYTE_UNITS.len() && rounded_tenths(unit) >= 10_240 {
+        unit += 1;
+    }
+    Ok(format!(
+        "{} {}",
+        format_tenths(rounded_tenths(unit)),
+        BYTE_UNITS[unit]
+    ))
+}
+
+/// Formats a duration given in seconds as its non-zero amounts of days, hours, minutes and seconds, e.g. `1h 2m 5s`.
+/// Float durations are rounded half up to tenths of a second, which are shown as decimal digit of the seconds.
+fn format_duration(seconds: &Value) -> Result<String, EvalexprError> {
+    let tenths = match seconds {
+        Value::Int(int) => u128::try_from(*int).ok().map(|seconds| seconds * 10),
+        Value::Float(float) => {
+            let tenths = (float * 10.0).round();
+            // Also rejects NaN and infinity
+            if *float >= 0.0 && tenths <= u64::MAX as FloatType {
+                Some(tenths as u128)
+            } else {
+                None
+            }
+        },
+        value => return Err(EvalexprError::expected_number(value.clone())),
+    }
+    .ok_or_else(|| EvalexprError::invalid_quantity(seconds.clone()))?;
+
+    if tenths == 0 {
+        return Ok("0s".to_string());
+    }
+    let whole_seconds = tenths / 10;
+    let parts = [
+        (whole_seconds / 86_400 * 10, "d"),
+        (whole_seconds % 86_400 / 3600 * 10, "h"),
+        (whole_seconds % 3600 / 60 * 10, "m"),
+        (tenths % 600, "s"),
+    ];
+    Ok(parts
+        .iter()
+        .filter(|(tenths, _)| *tenths != 0)
+        .map(|(tenths, unit)| format!("{}{}", format_tenths(*tenths), unit))
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
 pub fn builtin_function(identifier: &str) -> Option<Function> {
     match identifier {
         // Log
@@ -83,7 +486,15 @@ pub fn builtin_function(identifier: &str) -> Option<Function> {
         "math::hypot" => simple_math!(hypot, 2),
         // Rounding
         "floor" => simple_math!(floor),
-        "round" => simple_math!(round),
+        "round" => Some(Function::new(|argument| {
+            if let Value::Tuple(_) = argument {
+                let arguments = argument.as_fixed_len_tuple(2)?;
+                let (number, mode) = (arguments[0].as_number()?, arguments[1].as_string()?);
+                Ok(Value::Float(round_with_mode(number, &mode)?))
+            } else {
+                Ok(Value::Float(argument.as_number()?.round()))
+            }
+        })),
         "ceil" => simple_math!(ceil),
         // Float special values
         "math::is_nan" => float_is(FloatType::is_nan),
@@ -104,54 +515,65 @@ pub fn builtin_function(identifier: &str) -> Option<Function> {
                 Value::Int(_) => "int",
                 Value::Boolean(_) => "boolean",
                 Value::Tuple(_) => "tuple",
+                Value::Map(_) => "map",
                 Value::Empty => "empty",
+                Value::Custom(custom) => custom.type_name(),
+                #[cfg(feature = "units_support")]
+                Value::Quantity { .. } => "quantity",
             }
             .into())
         })),
+        "any" => Some(Function::new(|argument| {
+            Ok(boolean_elements(argument)?
+                .into_iter()
+                .any(|boolean| boolean)
+                .into())
+        })),
+        "all" => Some(Function::new(|argument| {
+            Ok(boolean_elements(argument)?
+                .into_iter()
+                .all(|boolean| boolean)
+                .into())
+        })),
         "min" => Some(Function::new(|argument| {
-            let arguments = argument.as_tuple()?;
-            let mut min_int = IntType::max_value();
-            let mut min_float: FloatType = 1.0 / 0.0;
-            debug_assert!(min_float.is_infinite());
-
-            for argument in arguments {
-                if let Value::Float(float) = argument {
-                    min_float = min_float.min(float);
-                } else if let Value::Int(int) = argument {
-                    min_int = min_int.min(int);
-                } else {
-                    return Err(EvalexprError::expected_number(argument));
-                }
-            }
-
-            if (min_int as FloatType) < min_float {
-                Ok(Value::Int(min_int))
-            } else {
-                Ok(Value::Float(min_float))
-            }
+            let arguments = reduction_arguments(argument)?;
+            let keys: Vec<_> = arguments.iter().collect();
+            Ok(arguments[extreme_index(&keys, Ordering::Less)?].clone())
         })),
         "max" => Some(Function::new(|argument| {
-            let arguments = argument.as_tuple()?;
-            let mut max_int = IntType::min_value();
-            let mut max_float: FloatType = -1.0 / 0.0;
-            debug_assert!(max_float.is_infinite());
-
-            for argument in arguments {
-                if let Value::Float(float) = argument {
-                    max_float = max_float.max(float);
-                } else if let Value::Int(int) = argument {
-                    max_int = max_int.max(int);
-                } else {
-                    return Err(EvalexprError::expected_number(argument));
-                }
-            }
-
-            if (max_int as FloatType) > max_float {
-                Ok(Value::Int(max_int))
+            let arguments = reduction_arguments(argument)?;
+            let keys: Vec<_> = arguments.iter().collect();
+            Ok(arguments[extreme_index(&keys, Ordering::Greater)?].clone())
+        })),
+        "min_by" => Some(Function::new(|argument| {
+            extreme_by_key(argument, Ordering::Less)
+        })),
+        "max_by" => Some(Function::new(|argument| {
+            extreme_by_key(argument, Ordering::Greater)
+        })),
+        "between" => Some(Function::new(|argument| {
+            let arguments = argument.as_fixed_len_tuple(3)?;
+            if let (Value::Int(x), Value::Int(lo), Value::Int(hi)) =
+                (&arguments[0], &arguments[1], &arguments[2])
+            {
+                Ok(Value::Boolean(lo <= x && x <= hi))
             } else {
-                Ok(Value::Float(max_float))
+                let (x, lo, hi) = (
+                    arguments[0].as_number()?,
+                    arguments[1].as_number()?,
+                    arguments[2].as_number()?,
+                );
+                Ok(Value::Boolean(lo <= x && x <= hi))
             }
         })),
+        "sum" => Some(Function::new(|argument| {
+            sum_numbers(&reduction_arguments(argument)?)
+        })),
+        "avg" => Some(Function::new(|argument| {
+            let arguments = reduction_arguments(argument)?;
+            let sum = sum_numbers(&arguments)?.as_number()?;
+            Ok(Value::Float(sum / arguments.len() as FloatType))
+        })),
         "if" => Some(Function::new(|argument| {
             let mut arguments = argument.as_fixed_len_tuple(3)?;
             let result_index = if arguments[0].as_boolean()? { 1 } else { 2 };
@@ -177,43 +599,21 @@ pub fn builtin_function(identifier: &str) -> Option<Function> {
                 Err(EvalexprError::expected_tuple(arguments[0].clone()))
             }
         })),
-        "contains_any" => Some(Function::new(move |argument| {
-            let arguments = argument.as_fixed_len_tuple(2)?;
-            if let (Value::Tuple(a), b) = (&arguments[0].clone(), &arguments[1].clone()) {
-                if let Value::Tuple(b) = b {
-                    let mut contains = false;
-                    for value in b {
-                        if let Value::String(_)
-                        | Value::Int(_)
-                        | Value::Float(_)
-                        | Value::Boolean(_) = value
-                        {
-                            if a.contains(value) {
-                                contains = true;
-                            }
-                        } else {
-                            return Err(EvalexprError::type_error(
-                                value.clone(),
-                                vec![
-                                    ValueType::String,
-                                    ValueType::Int,
-                                    ValueType::Float,
-                                    ValueType::Boolean,
-                                ],
-                            ));
-                        }
-                    }
-                    Ok(contains.into())
-                } else {
-                    Err(EvalexprError::expected_tuple(b.clone()))
-                }
-            } else {
-                Err(EvalexprError::expected_tuple(arguments[0].clone()))
-            }
+        "contains_any" => Some(Function::new(|argument| {
+            Ok(set_membership(argument)?
+                .into_iter()
+                .any(|contained| contained)
+                .into())
+        })),
+        "contains_all" => Some(Function::new(|argument| {
+            Ok(set_membership(argument)?
+                .into_iter()
+                .all(|contained| contained)
+                .into())
         })),
         "len" => Some(Function::new(|argument| {
             if let Ok(subject) = argument.as_string() {
-                Ok(Value::from(subject.len() as IntType))
+                Ok(Value::from(subject.chars().count() as IntType))
             } else if let Ok(subject) = argument.as_tuple() {
                 Ok(Value::from(subject.len() as IntType))
             } else {
@@ -223,6 +623,66 @@ pub fn builtin_function(identifier: &str) -> Option<Function> {
                 ))
             }
         })),
+        "zip" => Some(Function::new(|argument| {
+            let arguments = argument.as_fixed_len_tuple(2)?;
+            let (firsts, seconds) = (elements(&arguments[0])?, elements(&arguments[1])?);
+            if firsts.len() != seconds.len() {
+                return Err(EvalexprError::expected_fixed_len_tuple(
+                    firsts.len(),
+                    arguments[1].clone(),
+                ));
+            }
+            Ok(Value::Tuple(
+                firsts
+                    .iter()
+                    .zip(seconds)
+                    .map(|(first, second)| Value::from((first.clone(), second.clone())))
+                    .collect(),
+            ))
+        })),
+        "unzip" => Some(Function::new(|argument| {
+            let pairs = elements(argument)?;
+            let mut firsts = Vec::with_capacity(pairs.len());
+            let mut seconds = Vec::with_capacity(pairs.len());
+            for pair in pairs {
+                match pair {
+                    Value::Tuple(pair) if pair.len() == 2 => {
+                        firsts.push(pair[0].clone());
+                        seconds.push(pair[1].clone());
+                    },
+                    pair => return Err(EvalexprError::expected_fixed_len_tuple(2, pair.clone())),
+                }
+            }
+            Ok(Value::from((Value::Tuple(firsts), Value::Tuple(seconds))))
+        })),
+        // Map functions
+        "to_map" => Some(Function::new(|argument| {
+            Ok(Value::Map(pairs_to_map(argument)?))
+        })),
+        "keys" => Some(Function::new(|argument| {
+            Ok(Value::Tuple(
+                sorted_entries(argument)?
+                    .into_iter()
+                    .map(|(key, _)| Value::String(key))
+                    .collect(),
+            ))
+        })),
+        "values" => Some(Function::new(|argument| {
+            Ok(Value::Tuple(
+                sorted_entries(argument)?
+                    .into_iter()
+                    .map(|(_, value)| value)
+                    .collect(),
+            ))
+        })),
+        "entries" => Some(Function::new(|argument| {
+            Ok(Value::Tuple(
+                sorted_entries(argument)?
+                    .into_iter()
+                    .map(Value::from)
+                    .collect(),
+            ))
+        })),
         // String functions
         #[cfg(feature = "regex_support")]
         "str::regex_matches" => Some(Function::new(|argument| {
@@ -270,26 +730,140 @@ pub fn builtin_function(identifier: &str) -> Option<Function> {
         "str::from" => Some(Function::new(|argument| {
             Ok(Value::String(argument.to_string()))
         })),
+        "str::concat" => Some(Function::new(|argument| {
+            let arguments = match argument {
+                Value::Tuple(tuple) => tuple.as_slice(),
+                Value::Empty => &[],
+                argument => std::slice::from_ref(argument),
+            };
+            let mut result = String::new();
+            for argument in arguments {
+                // Unlike `str::from`, strings are not quoted
+                match argument {
+                    Value::String(string) => result.push_str(string),
+                    argument => result.push_str(&argument.to_string()),
+                }
+            }
+            Ok(Value::String(result))
+        })),
         "str::substring" => Some(Function::new(|argument| {
             let args = argument.as_ranged_len_tuple(2..=3)?;
             let subject = args[0].as_string()?;
             let start = args[1].as_int()?;
-            let start = usize::try_from(start).map_err(|_| EvalexprError::OutOfBoundsAccess)?;
+            let start = char_to_byte_index(&subject, start)?;
             let end = if let Some(end) = args.get(2) {
-                usize::try_from(end.as_int()?).map_err(|_| EvalexprError::OutOfBoundsAccess)?
+                char_to_byte_index(&subject, end.as_int()?)?
             } else {
                 subject.len()
             };
-            if start > end || end > subject.len() {
+            if start > end {
                 return Err(EvalexprError::OutOfBoundsAccess);
             }
             Ok(Value::from(&subject[start..end]))
         })),
+        // Formatting
+        "format_bytes" => Some(Function::new(|argument| {
+            Ok(Value::String(format_bytes(argument.as_int()?)?))
+        })),
+        "format_duration" => Some(Function::new(|argument| {
+            Ok(Value::String(format_duration(argument)?))
+        })),
+        // Error recovery
+        // Calls of `try_or` with two arguments are evaluated lazily by the operator tree,
+        // so this function is only called if the arguments were evaluated eagerly without error
+        "try_or" => Some(Function::new(|argument| {
+            let arguments = argument.as_fixed_len_tuple(2)?;
+            Ok(arguments[0].clone())
+        })),
+        // Number parsing
+        "parse_int" => Some(Function::new(|argument| {
+            if let Value::Tuple(_) = argument {
+                let arguments = argument.as_ranged_len_tuple(2..=3)?;
+                let string = arguments[0].as_string()?;
+                let (radix, separator) = match &arguments[1..] {
+                    [Value::String(separator)] => (10, separator.clone()),
+                    [radix] => (radix.as_int()?, String::new()),
+                    [radix, separator] => (radix.as_int()?, separator.as_string()?),
+                    _ => unreachable!("the tuple has two or three elements"),
+                };
+                if separator.is_empty() {
+                    Ok(Value::Int(parse_int(&string, radix)?))
+                } else {
+                    match parse_int(&string.replace(&separator, ""), radix) {
+                        Err(EvalexprError::InvalidNumber { .. }) => {
+                            Err(EvalexprError::invalid_number(string))
+                        },
+                        result => Ok(Value::Int(result?)),
+                    }
+                }
+            } else {
+                Ok(Value::Int(parse_int(&argument.as_string()?, 10)?))
+            }
+        })),
+        "parse_int_or" => Some(Function::new(|argument| {
+            let arguments = argument.as_ranged_len_tuple(2..=3)?;
+            let string = arguments[0].as_string()?;
+            let (radix, default) = if let [_, radix, default] = arguments.as_slice() {
+                (radix.as_int()?, default)
+            } else {
+                (10, &arguments[1])
+            };
+            match parse_int(&string, radix) {
+                Err(EvalexprError::InvalidNumber { .. }) => Ok(default.clone()),
+                result => Ok(Value::Int(result?)),
+            }
+        })),
+        "parse_float" => Some(Function::new(|argument| {
+            Ok(Value::Float(parse_float(&argument.as_string()?)?))
+        })),
+        "parse_float_or" => Some(Function::new(|argument| {
+            let arguments = argument.as_fixed_len_tuple(2)?;
+            let string = arguments[0].as_string()?;
+            Ok(parse_float(&string).map_or_else(|_| arguments[1].clone(), Value::Float))
+        })),
         #[cfg(feature = "rand")]
         "random" => Some(Function::new(|argument| {
             argument.as_empty()?;
             Ok(Value::Float(rand::random()))
         })),
+        // Integer division
+        "int_div" => Some(Function::new(|argument| {
+            let arguments = argument.as_fixed_len_tuple(2)?;
+            let (a, b) = (arguments[0].as_int()?, arguments[1].as_int()?);
+            if let Some(result) = a.checked_div(b) {
+                Ok(Value::Int(result))
+            } else {
+                Err(EvalexprError::division_error(
+                    arguments[0].clone(),
+                    arguments[1].clone(),
+                ))
+            }
+        })),
+        "simplify_fraction" => Some(Function::new(|argument| {
+            let arguments = argument.as_fixed_len_tuple(2)?;
+            let (numerator, denominator) = (arguments[0].as_int()?, arguments[1].as_int()?);
+            if denominator == 0 {
+                return Err(EvalexprError::division_error(
+                    arguments[0].clone(),
+                    arguments[1].clone(),
+                ));
+            }
+            let (numerator, denominator) = simplify_fraction(numerator, denominator)
+                .ok_or_else(|| EvalexprError::negation_error(arguments[0].clone()))?;
+            Ok(Value::from((numerator, denominator)))
+        })),
+        // Truncating like the operators `/` and `%`, such that the remainder has the sign of the dividend
+        "divmod" => Some(Function::new(|argument| {
+            let arguments = argument.as_fixed_len_tuple(2)?;
+            let (dividend, divisor) = (arguments[0].as_int()?, arguments[1].as_int()?);
+            match (dividend.checked_div(divisor), dividend.checked_rem(divisor)) {
+                (Some(quotient), Some(remainder)) => Ok(Value::from((quotient, remainder))),
+                _ => Err(EvalexprError::division_error(
+                    arguments[0].clone(),
+                    arguments[1].clone(),
+                )),
+            }
+        })),
         // Bitwise operators
         "bitand" => int_function!(bitand, 2),
         "bitor" => int_function!(bitor, 2),
@@ -297,6 +871,15 @@ pub fn builtin_function(identifier: &str) -> Option<Function> {
         "bitnot" => int_function!(not),
         "shl" => int_function!(shl, 2),
         "shr" => int_function!(shr, 2),
+        // Bit reinterpretation, like `FloatType::to_bits` and `FloatType::from_bits`
+        "float_bits" => Some(Function::new(|argument| {
+            Ok(Value::Int(argument.as_float()?.to_bits() as IntType))
+        })),
+        "bits_float" => Some(Function::new(|argument| {
+            Ok(Value::Float(
+                FloatType::from_bits(argument.as_int()? as u64),
+            ))
+        })),
         _ => None,
     }
 }
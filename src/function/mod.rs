@@ -1,6 +1,19 @@
-use std::fmt;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
-use crate::{error::EvalexprResult, value::Value};
+use crate::{
+    error::{EvalexprError, EvalexprResult},
+    tree::binary::encode_value,
+    value::Value,
+    Context, Node, ValueType,
+};
 
 pub(crate) mod builtin;
 
@@ -40,12 +53,21 @@ where
 /// ```
 pub struct Function {
     function: Box<dyn ClonableFn>,
+    /// Identifies the function in the memoization cache, shared by its clones.
+    id: usize,
+    argument_amount: Option<usize>,
+    return_type: Option<ValueType>,
+    memoize: bool,
 }
 
 impl Clone for Function {
     fn clone(&self) -> Self {
         Self {
             function: self.function.dyn_clone(),
+            id: self.id,
+            argument_amount: self.argument_amount,
+            return_type: self.return_type,
+            memoize: self.memoize,
         }
     }
 }
@@ -62,14 +84,340 @@ impl Function {
     {
         Self {
             function: Box::new(function) as _,
+            id: NEXT_FUNCTION_ID.fetch_add(1, Ordering::Relaxed),
+            argument_amount: None,
+            return_type: None,
+            memoize: false,
         }
     }
 
-    pub(crate) fn call(&self, argument: &Value) -> EvalexprResult<Value> {
+    /// Creates a user-defined function that receives an `EvalContext` besides its argument,
+    /// which it can use to evaluate other expressions as part of the current evaluation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let rule = build_operator_tree("2 * 21").unwrap(); // Do proper error handling here
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("rule".into(), Function::new_with_eval_context(move |_, eval_context| {
+    ///     assert_eq!(eval_context.current_depth(), 1);
+    ///     eval_context.evaluate_sub_expression(&rule, &EmptyContextWithBuiltinFunctions)
+    /// })).unwrap(); // Do proper error handling here
+    /// assert_eq!(eval_with_context("rule()", &context), Ok(Value::from(42)));
+    /// ```
+    pub fn new_with_eval_context<F>(function: F) -> Self
+    where
+        F: Fn(&Value, &EvalContext) -> EvalexprResult<Value>,
+        F: Send + Sync + 'static,
+        F: Clone,
+    {
+        Self::new(move |argument| function(argument, &EvalContext { _private: () }))
+    }
+
+    /// Declares the amount of arguments this function expects.
+    ///
+    /// Calls with a different amount of arguments fail with `EvalexprError::WrongFunctionArgumentAmount` before the function is invoked.
+    /// A function expecting no arguments expects `Value::Empty`, a function expecting one argument accepts any value,
+    /// and a function expecting more arguments expects a tuple of that length.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("add".into(), Function::new(|argument| {
+    ///     let arguments = argument.as_tuple()?;
+    ///     Ok(Value::Int(arguments[0].as_int()? + arguments[1].as_int()?))
+    /// }).with_argument_amount(2)).unwrap(); // Do proper error handling here
+    ///
+    /// assert_eq!(eval_with_context("add(1, 2)", &context), Ok(Value::from(3)));
+    /// assert_eq!(
+    ///     eval_with_context("add(1, 2, 3)", &context),
+    ///     Err(EvalexprError::wrong_function_argument_amount(3, 2))
+    /// );
+    /// ```
+    pub fn with_argument_amount(mut self, amount: usize) -> Self {
+        self.argument_amount = Some(amount);
+        self
+    }
+
+    /// Declares the type of the values this function returns.
+    ///
+    /// Calls that return a value of another type fail with `EvalexprError::FunctionReturnedWrongType`,
+    /// which catches bugs in the function instead of passing the wrong value on to the rest of the expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("half".into(), Function::new(|argument| {
+    ///     Ok(Value::Float(argument.as_number()? / 2.0))
+    /// }).with_return_type(ValueType::Int)).unwrap(); // Do proper error handling here
+    ///
+    /// assert_eq!(
+    ///     eval_with_context("half(3)", &context),
+    ///     Err(EvalexprError::FunctionReturnedWrongType {
+    ///         identifier: "half".into(),
+    ///         expected: ValueType::Int,
+    ///         actual: Value::from(1.5),
+    ///     })
+    /// );
+    /// ```
+    pub fn with_return_type(mut self, value_type: ValueType) -> Self {
+        self.return_type = Some(value_type);
+        self
+    }
+
+    /// Marks this function as pure, such that its results are cached during an evaluation.
+    ///
+    /// While an operator tree is evaluated, the function is called at most once for each distinct argument,
+    /// and further calls with the same argument return the cached result.
+    /// Arguments are compared by value, with floats compared by their bit pattern, and arguments containing custom values are never cached.
+    /// The cache is dropped when the evaluation finishes, so results are never reused across evaluations.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::sync::{atomic::{AtomicUsize, Ordering}, Arc};
+    ///
+    /// let calls = Arc::new(AtomicUsize::new(0));
+    /// let counted_calls = calls.clone();
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("square".into(), Function::new(move |argument| {
+    ///     counted_calls.fetch_add(1, Ordering::SeqCst);
+    ///     let x = argument.as_int()?;
+    ///     Ok(Value::Int(x * x))
+    /// }).memoized()).unwrap(); // Do proper error handling here
+    ///
+    /// assert_eq!(eval_with_context("square(3) + square(3)", &context), Ok(Value::from(18)));
+    /// assert_eq!(calls.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn memoized(mut self) -> Self {
+        self.memoize = true;
+        self
+    }
+
+    /// Returns true if the results of this function are cached during an evaluation, see `Function::memoized`.
+    pub fn is_memoized(&self) -> bool {
+        self.memoize
+    }
+
+    /// Returns the amount of arguments this function expects, if it was declared with `Function::with_argument_amount`.
+    pub fn argument_amount(&self) -> Option<usize> {
+        self.argument_amount
+    }
+
+    /// Returns the type of the values this function returns, if it was declared with `Function::with_return_type`.
+    pub fn return_type(&self) -> Option<ValueType> {
+        self.return_type
+    }
+
+    /// Creates a new function that calls this function with the given fixed arguments prepended to the arguments it is called with.
+    ///
+    /// If this function declares its amount of arguments, the new function declares the reduced amount,
+    /// and fails with `EvalexprError::WrongFunctionArgumentAmount` referencing the reduced amount if it is called with a different amount.
+    /// The new function declares the same return type as this function.
+    /// Returns `EvalexprError::WrongFunctionArgumentAmount` if more arguments are fixed than this function expects.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let greater_than = Function::new(|argument| {
+    ///     let arguments = argument.as_tuple()?;
+    ///     Ok(Value::Boolean(arguments[1].as_number()? > arguments[0].as_number()?))
+    /// }).with_argument_amount(2);
+    ///
+    /// let threshold_check = greater_than.partial(vec![Value::Int(100)]).unwrap(); // Do proper error handling here
+    /// let mut context = HashMapContext::new();
+    /// context.set_function("threshold_check".into(), threshold_check).unwrap(); // Do proper error handling here
+    ///
+    /// assert_eq!(eval_with_context("threshold_check(150)", &context), Ok(Value::from(true)));
+    /// assert_eq!(eval_with_context("threshold_check(50)", &context), Ok(Value::from(false)));
+    /// ```
+    pub fn partial(self, fixed_arguments: Vec<Value>) -> EvalexprResult<Function> {
+        let remaining_amount = match self.argument_amount {
+            Some(amount) if fixed_arguments.len() > amount => {
+                return Err(EvalexprError::wrong_function_argument_amount(
+                    fixed_arguments.len(),
+                    amount,
+                ));
+            },
+            Some(amount) => Some(amount - fixed_arguments.len()),
+            None => None,
+        };
+
+        let return_type = self.return_type;
+        let function = Function::new(move |argument| {
+            let mut arguments = fixed_arguments.clone();
+            arguments.extend(split_arguments(argument, remaining_amount)?);
+            self.call_unchecked(&join_arguments(arguments, self.argument_amount))
+        });
+        Ok(Function {
+            argument_amount: remaining_amount,
+            return_type,
+            ..function
+        })
+    }
+
+    /// Calls this function, which is bound to the given identifier, with the given argument.
+    pub(crate) fn call(&self, identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+        let result = self.call_unchecked(argument)?;
+        match self.return_type {
+            Some(expected) if ValueType::from(&result) != expected => Err(
+                EvalexprError::function_returned_wrong_type(identifier, expected, result),
+            ),
+            _ => Ok(result),
+        }
+    }
+
+    /// Calls this function with the given argument without checking the type of the result.
+    fn call_unchecked(&self, argument: &Value) -> EvalexprResult<Value> {
+        if let Some(amount) = self.argument_amount {
+            split_arguments(argument, Some(amount))?;
+        }
+
+        if self.memoize && EVALUATION_DEPTH.with(Cell::get) > 0 {
+            if let Some(argument_bytes) = encode_value(argument) {
+                let key = (self.id, argument_bytes);
+                if let Some(result) =
+                    MEMOIZATION_CACHE.with(|cache| cache.borrow().get(&key).cloned())
+                {
+                    return Ok(result);
+                }
+
+                let result = (self.function)(argument)?;
+                MEMOIZATION_CACHE.with(|cache| cache.borrow_mut().insert(key, result.clone()));
+                return Ok(result);
+            }
+        }
+
         (self.function)(argument)
     }
 }
 
+/// The id of the next function created with `Function::new`.
+static NEXT_FUNCTION_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// The default maximum amount of nested evaluations on a thread, see `EvalContext` and `Context::max_evaluation_depth`.
+///
+/// Each nested evaluation needs stack space for the evaluation of its operator tree and for the function that started it,
+/// so the limit is low enough to stay within the default stack size of spawned threads, even without optimizations.
+pub const MAX_EVALUATION_DEPTH: usize = 16;
+
+/// A handle to the running evaluation, passed to functions created with `Function::new_with_eval_context`.
+///
+/// Functions may evaluate other expressions, e.g. to look up and evaluate a stored rule.
+/// Such nested evaluations are counted, and an evaluation that would be nested deeper than the limit of its context,
+/// which is `MAX_EVALUATION_DEPTH` by default, fails with `EvalexprError::EvaluationTooDeep` instead of being started.
+/// So rules that evaluate each other in a cycle fail deterministically instead of overflowing the stack.
+/// The limit applies to all evaluations of operator trees and expressions, not only to those started through this handle.
+#[derive(Debug)]
+pub struct EvalContext {
+    _private: (),
+}
+
+impl EvalContext {
+    /// Returns the amount of evaluations that are currently running on this thread,
+    /// which is 1 for a function called by an evaluation that is not nested in another one.
+    pub fn current_depth(&self) -> usize {
+        EVALUATION_DEPTH.with(Cell::get)
+    }
+
+    /// Evaluates the given operator tree with the given context, nested within the current evaluation.
+    ///
+    /// The nested evaluation shares the cache of memoized functions and the depth limit with the current evaluation.
+    /// If the given context has a lower limit than the current evaluation, the lower one applies, so a nested evaluation never raises the limit.
+    pub fn evaluate_sub_expression<C: Context>(
+        &self,
+        node: &Node,
+        context: &C,
+    ) -> EvalexprResult<Value> {
+        node.eval_with_context(context)
+    }
+}
+
+thread_local! {
+    /// The amount of evaluations that are currently running on this thread, including nested ones.
+    static EVALUATION_DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// The lowest depth limit of the evaluations that are currently running on this thread.
+    static EVALUATION_LIMIT: Cell<usize> = const { Cell::new(usize::MAX) };
+    /// The results of memoized functions, keyed by the id of the function and the encoded argument.
+    static MEMOIZATION_CACHE: RefCell<HashMap<(usize, Vec<u8>), Value>> = RefCell::new(HashMap::new());
+}
+
+/// Restores the depth limit of the enclosing evaluation and clears the memoization cache when the outermost evaluation finishes,
+/// even if it panics.
+struct EvaluationScope {
+    /// The depth limit of the enclosing evaluation.
+    enclosing_limit: usize,
+}
+
+impl Drop for EvaluationScope {
+    fn drop(&mut self) {
+        EVALUATION_LIMIT.with(|limit| limit.set(self.enclosing_limit));
+        let depth = EVALUATION_DEPTH.with(|depth| {
+            depth.set(depth.get() - 1);
+            depth.get()
+        });
+        if depth == 0 {
+            MEMOIZATION_CACHE.with(|cache| cache.borrow_mut().clear());
+        }
+    }
+}
+
+/// Runs the given evaluation with a memoization cache for functions marked with `Function::memoized`.
+/// Nested evaluations share the cache of the outermost one, which is dropped when it finishes.
+/// Fails without running the evaluation if it would be nested deeper than `limit`, or than the limit of an enclosing evaluation if that is lower.
+pub(crate) fn with_evaluation_scope(
+    limit: usize,
+    evaluation: impl FnOnce() -> EvalexprResult<Value>,
+) -> EvalexprResult<Value> {
+    let enclosing_limit = EVALUATION_LIMIT.with(Cell::get);
+    let limit = limit.min(enclosing_limit);
+    if EVALUATION_DEPTH.with(Cell::get) >= limit {
+        return Err(EvalexprError::evaluation_too_deep(limit));
+    }
+    EVALUATION_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    EVALUATION_LIMIT.with(|current| current.set(limit));
+    let _scope = EvaluationScope { enclosing_limit };
+    evaluation()
+}
+
+/// Splits the argument value a function is called with into the individual arguments.
+/// If the expected amount of arguments is given, it is checked and used to decide if a tuple is a single argument.
+fn split_arguments(argument: &Value, amount: Option<usize>) -> EvalexprResult<Vec<Value>> {
+    let arguments = match (amount, argument) {
+        (Some(1), argument) => vec![argument.clone()],
+        (_, Value::Empty) => Vec::new(),
+        (_, Value::Tuple(tuple)) => tuple.clone(),
+        (_, argument) => vec![argument.clone()],
+    };
+
+    match amount {
+        Some(amount) if arguments.len() != amount => Err(
+            EvalexprError::wrong_function_argument_amount(arguments.len(), amount),
+        ),
+        _ => Ok(arguments),
+    }
+}
+
+/// Joins individual arguments into the argument value a function is called with, the inverse of `split_arguments`.
+fn join_arguments(mut arguments: Vec<Value>, amount: Option<usize>) -> Value {
+    match (amount, arguments.len()) {
+        (Some(1), _) | (None, 1) => arguments.pop().unwrap_or(Value::Empty),
+        (_, 0) => Value::Empty,
+        _ => Value::Tuple(arguments),
+    }
+}
+
 impl fmt::Debug for Function {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         write!(f, "Function {{ [...] }}")
@@ -78,6 +426,132 @@ impl fmt::Debug for Function {
 
 /// A trait to ensure a type is `Send` and `Sync`.
 /// If implemented for a type, the crate will not compile if the type is not `Send` and `Sync`.
+#[allow(dead_code)]
 trait IsSendAndSync: Send + Sync {}
 
 impl IsSendAndSync for Function {}
+
+/// A user-defined function that receives the unevaluated operator trees of its arguments instead of their values.
+/// Macro functions are stored in a `HashMapContext` with `HashMapContext::insert_macro`.
+///
+/// A call of a macro function is dispatched to it before its arguments are evaluated,
+/// so the macro decides which arguments to evaluate and how, e.g. to count the identifiers of an argument,
+/// or to include the text of a failing assertion in its error.
+/// The arguments are split like the arguments of a function: there is no argument for `f()`,
+/// the argument itself for `f(a)` and one operator tree per element for `f(a, b)`.
+/// Each argument is enclosed in a root node, so it equals the operator tree parsed from its text.
+/// The macro also receives the context of the evaluation, with which it can evaluate its arguments.
+/// The context is immutable even if the expression is evaluated with a mutable context, so arguments evaluated by the macro cannot assign to variables.
+///
+/// As the result of a macro may depend on the form of its arguments and not only on their values,
+/// a call of a macro can never be constant-folded, even if all its arguments are constant:
+/// `count_identifiers(1 + 2)` and `count_identifiers(3)` may evaluate to different values.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let mut context = HashMapContext::new();
+/// context.insert_macro("count_identifiers".into(), MacroFunction::new(|arguments, _| {
+///     let count = arguments.iter().map(|argument| argument.iter_identifiers().count()).sum::<usize>();
+///     Ok(Value::Int(count as IntType))
+/// })).unwrap(); // Do proper error handling here
+/// context.insert_macro("first_ok".into(), MacroFunction::new(|arguments, context| {
+///     arguments
+///         .iter()
+///         .find_map(|argument| argument.eval_with_context(&context).ok())
+///         .ok_or_else(|| EvalexprError::CustomMessage("no argument evaluates".into()))
+/// })).unwrap(); // Do proper error handling here
+///
+/// assert_eq!(eval_with_context("count_identifiers(a + b, c)", &context), Ok(Value::from(3)));
+/// assert_eq!(eval_with_context("first_ok(undefined, 1 / 0, 2)", &context), Ok(Value::from(2)));
+/// ```
+#[derive(Clone)]
+pub struct MacroFunction {
+    function: Arc<MacroFn>,
+}
+
+/// The closure of a `MacroFunction`.
+type MacroFn = dyn Fn(&[Node], &dyn Context) -> EvalexprResult<Value> + Send + Sync;
+
+impl MacroFunction {
+    /// Creates a user-defined macro function from the given closure,
+    /// which receives the operator trees of the arguments and the context of the evaluation.
+    pub fn new<F>(function: F) -> Self
+    where
+        F: Fn(&[Node], &dyn Context) -> EvalexprResult<Value>,
+        F: Send + Sync + 'static,
+    {
+        Self {
+            function: Arc::new(function),
+        }
+    }
+
+    /// Calls the macro function with the given unevaluated arguments and context.
+    pub fn call(&self, arguments: &[Node], context: &dyn Context) -> EvalexprResult<Value> {
+        (self.function)(arguments, context)
+    }
+}
+
+impl fmt::Debug for MacroFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "MacroFunction {{ [...] }}")
+    }
+}
+
+impl IsSendAndSync for MacroFunction {}
+
+/// This macro validates the types of a slice of function arguments and binds their contents to variables in one step.
+///
+/// The arguments are matched against a tuple of patterns of the form `Variant(name)`,
+/// where `Variant` is one of `String`, `Int`, `Float`, `Number`, `Boolean` or `Tuple`.
+/// `Number` accepts both integers and floats and binds a float, like `Value::as_number`.
+/// If the amount of arguments does not match, `EvalexprError::WrongFunctionArgumentAmount` is returned,
+/// and if an argument has the wrong type, the respective `EvalexprError::Expected*` error is returned.
+/// Errors are returned from the surrounding function with `?`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let context = context_map! {
+///     "muladd" => Function::new(|argument| {
+///         value_args!(&argument.as_tuple()?, (Int(a), Int(b), Number(c)));
+///         Ok(Value::Float((a * b) as FloatType + c))
+///     })
+/// }.unwrap(); // Do proper error handling here
+///
+/// assert_eq!(eval_with_context("muladd(2, 3, 0.5)", &context), Ok(Value::from(6.5)));
+/// assert_eq!(
+///     eval_with_context("muladd(2, 3.0, 0.5)", &context),
+///     Err(EvalexprError::expected_int(Value::from(3.0)))
+/// );
+/// ```
+#[macro_export]
+macro_rules! value_args {
+    (@count) => { 0usize };
+    (@count $head:ident $($tail:ident)*) => { 1usize + $crate::value_args!(@count $($tail)*) };
+
+    (@extract String, $value:expr) => { $crate::Value::as_string($value) };
+    (@extract Int, $value:expr) => { $crate::Value::as_int($value) };
+    (@extract Float, $value:expr) => { $crate::Value::as_float($value) };
+    (@extract Number, $value:expr) => { $crate::Value::as_number($value) };
+    (@extract Boolean, $value:expr) => { $crate::Value::as_boolean($value) };
+    (@extract Tuple, $value:expr) => { $crate::Value::as_tuple($value) };
+
+    ($arguments:expr, ($($variant:ident($name:ident)),* $(,)?)) => {
+        let ($($name,)*) = {
+            let arguments: &[$crate::Value] = $arguments;
+            $crate::error::expect_function_argument_amount(
+                arguments.len(),
+                $crate::value_args!(@count $($name)*),
+            )?;
+            #[allow(unused_mut, unused_variables)]
+            let mut arguments = arguments.iter();
+            // Cannot fail, because the amount of arguments was checked above
+            ($($crate::value_args!(@extract $variant, arguments.next().unwrap())?,)*)
+        };
+    };
+}
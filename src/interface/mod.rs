@@ -1,7 +1,9 @@
 use crate::{
     token, tree, value::TupleType, Context, ContextWithMutableVariables, EmptyType, EvalexprError,
-    EvalexprResult, FloatType, HashMapContext, IntType, Node, Value, EMPTY_VALUE,
+    EvalexprResult, Expression, FloatType, HashMapContext, IdentifierInterner, IntType, Node,
+    ParseOptions, Value, EMPTY_VALUE,
 };
+use std::sync::Arc;
 
 /// Evaluate the given expression string.
 ///
@@ -34,7 +36,12 @@ pub fn eval(string: &str) -> EvalexprResult<Value> {
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
 pub fn eval_with_context<C: Context>(string: &str, context: &C) -> EvalexprResult<Value> {
-    tree::tokens_to_operator_tree(token::tokenize(string)?)?.eval_with_context(context)
+    let options = ParseOptions::default();
+    let (tokens, ranges) = token::tokenize_with_ranges(string, &options)?;
+    if let Some(value) = token::tokens_to_literal(&tokens) {
+        return Ok(value);
+    }
+    tree::tokens_to_operator_tree(&tokens, &ranges, &options)?.eval_with_context(context)
 }
 
 /// Evaluate the given expression string with the given mutable context.
@@ -56,7 +63,59 @@ pub fn eval_with_context_mut<C: ContextWithMutableVariables>(
     string: &str,
     context: &mut C,
 ) -> EvalexprResult<Value> {
-    tree::tokens_to_operator_tree(token::tokenize(string)?)?.eval_with_context_mut(context)
+    let options = ParseOptions::default();
+    let (tokens, ranges) = token::tokenize_with_ranges(string, &options)?;
+    if let Some(value) = token::tokens_to_literal(&tokens) {
+        return Ok(value);
+    }
+    tree::tokens_to_operator_tree(&tokens, &ranges, &options)?.eval_with_context_mut(context)
+}
+
+/// Evaluate the given expression string, returning `default` if parsing or evaluating it fails.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// assert_eq!(eval_or("1 + 2", Value::from(0)), Value::from(3));
+/// assert_eq!(eval_or("1 +", Value::from(0)), Value::from(0));
+/// assert_eq!(eval_or("1 / 0", Value::from(0)), Value::from(0));
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn eval_or(string: &str, default: Value) -> Value {
+    eval(string).unwrap_or(default)
+}
+
+/// Evaluate the given expression string with the given context, returning `default` if parsing or evaluating it fails.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let context = context_map! { "revenue" => 120, "visits" => 0 }.unwrap(); // Do proper error handling here
+/// assert_eq!(eval_or_with_context("revenue / 10", Value::Empty, &context), Value::from(12));
+/// assert_eq!(eval_or_with_context("revenue / visits", Value::Empty, &context), Value::Empty);
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn eval_or_with_context<C: Context>(string: &str, default: Value, context: &C) -> Value {
+    eval_with_context(string, context).unwrap_or(default)
+}
+
+/// Evaluate the given expression string with the given mutable context, returning `default` if parsing or evaluating it fails.
+///
+/// Assignments that were evaluated before the failure remain in the context.
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn eval_or_with_context_mut<C: ContextWithMutableVariables>(
+    string: &str,
+    default: Value,
+    context: &mut C,
+) -> Value {
+    eval_with_context_mut(string, context).unwrap_or(default)
 }
 
 /// Build the operator tree for the given expression string.
@@ -84,7 +143,162 @@ pub fn eval_with_context_mut<C: ContextWithMutableVariables>(
 ///
 /// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
 pub fn build_operator_tree(string: &str) -> EvalexprResult<Node> {
-    tree::tokens_to_operator_tree(token::tokenize(string)?)
+    build_operator_tree_with_options(string, &ParseOptions::default())
+}
+
+/// Build the operator tree for the given expression string, parsing it according to the given options.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let options = ParseOptions::new().keyword_logical_operators(true);
+/// let precomputed = build_operator_tree_with_options("a and not b", &options).unwrap(); // Do proper error handling here
+///
+/// let context = context_map! {
+///     "a" => true,
+///     "b" => false,
+/// }.unwrap(); // Do proper error handling here
+///
+/// assert_eq!(precomputed.eval_with_context(&context), Ok(Value::from(true)));
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn build_operator_tree_with_options(
+    string: &str,
+    options: &ParseOptions,
+) -> EvalexprResult<Node> {
+    build_tree(string, || Arc::from(string), options)
+}
+
+/// Build the operator tree for the given expression, parsing it according to the given options.
+///
+/// This works like `build_operator_tree_with_options`, but if `ParseOptions::preserve_source_text` is enabled,
+/// the operator tree keeps the given expression instead of a copy of it.
+/// This avoids copying expressions that are already stored in an `Arc<str>`, e.g. to be shared between threads.
+/// Other types like `String` are converted into an `Arc<str>`, which copies them,
+/// so `build_operator_tree_with_options` should be preferred for them, and whenever the source text is not preserved.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+/// use std::sync::Arc;
+///
+/// let expression: Arc<str> = Arc::from("max(a, b) * 2");
+/// let options = ParseOptions::new().preserve_source_text(true);
+/// let tree = build_operator_tree_arc(expression.clone(), &options).unwrap(); // Do proper error handling here
+///
+/// assert_eq!(tree.source_text(), Some("max(a, b) * 2"));
+/// assert!(Arc::ptr_eq(tree.source_expression().unwrap(), &expression));
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn build_operator_tree_arc(
+    expression: impl Into<Arc<str>>,
+    options: &ParseOptions,
+) -> EvalexprResult<Node> {
+    let expression = expression.into();
+    build_tree(&expression, || expression.clone(), options)
+}
+
+/// Builds the operator tree for the given expression string,
+/// calling `expression` for the expression to keep in the operator tree only if the source text is preserved.
+fn build_tree(
+    string: &str,
+    expression: impl FnOnce() -> Arc<str>,
+    options: &ParseOptions,
+) -> EvalexprResult<Node> {
+    let (tokens, ranges) = token::tokenize_with_ranges(string, options)?;
+    if options.has_preserved_source_text() {
+        tree::tokens_to_operator_tree_with_source(&tokens, &ranges, expression(), options)
+    } else {
+        tree::tokens_to_operator_tree(&tokens, &ranges, options)
+    }
+}
+
+/// Build the operator tree for the given expression string, storing its identifiers in the given interner.
+///
+/// This is a shorthand for `build_operator_tree_with_options` with `ParseOptions::identifier_interner`.
+/// Operator trees built with the same interner share one allocation per distinct identifier,
+/// which saves memory if many operator trees with the same identifiers are kept.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let interner = IdentifierInterner::new();
+/// let rules: Vec<_> = ["customer_id > 100", "customer_id < 10 || vip"]
+///     .iter()
+///     .map(|rule| build_operator_tree_with_interner(rule, &interner).unwrap()) // Do proper error handling here
+///     .collect();
+/// assert_eq!(interner.len(), 2);
+///
+/// let context = context_map! { "customer_id" => 7, "vip" => false }.unwrap(); // Do proper error handling here
+/// assert_eq!(rules[1].eval_with_context(&context), Ok(Value::from(true)));
+/// ```
+pub fn build_operator_tree_with_interner(
+    string: &str,
+    interner: &IdentifierInterner,
+) -> EvalexprResult<Node> {
+    build_operator_tree_with_options(
+        string,
+        &ParseOptions::new().identifier_interner(interner.clone()),
+    )
+}
+
+/// Build the operator tree for the given expression string, collecting all errors instead of stopping at the first one.
+///
+/// If building the operator tree fails, the expression is split into its independent parts,
+/// which are the elements of tuples and the expressions of chains outside of parentheses and map literals,
+/// and the error of each part that cannot be built on its own is returned.
+/// If the expression cannot be tokenized, only the tokenization error is returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// assert!(build_operator_tree_all_errors("a = 1; b = a * 2").is_ok());
+/// assert_eq!(
+///     build_operator_tree_all_errors("a = 1 +; b = (a * 2"),
+///     Err(vec![
+///         EvalexprError::WrongOperatorArgumentAmount { expected: 2, actual: 1 },
+///         EvalexprError::UnmatchedLBrace,
+///     ])
+/// );
+/// ```
+pub fn build_operator_tree_all_errors(string: &str) -> Result<Node, Vec<EvalexprError>> {
+    let options = ParseOptions::default();
+    let (tokens, ranges) =
+        token::tokenize_with_ranges(string, &options).map_err(|error| vec![error])?;
+    tree::tokens_to_operator_tree_all_errors(&tokens, &ranges, &options)
+}
+
+/// Build the operator tree for the given expression string and flatten it into an `Expression`.
+///
+/// An `Expression` evaluates like the `Node` returned by `build_operator_tree`, but stores its nodes in a single contiguous vector,
+/// which makes it cheaper to clone.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let precomputed = build_expression("one + two").unwrap(); // Do proper error handling here
+/// let context = context_map! {
+///     "one" => 1,
+///     "two" => 2,
+/// }.unwrap(); // Do proper error handling here
+///
+/// assert_eq!(precomputed.clone().eval_with_context(&context), Ok(Value::from(3)));
+/// ```
+///
+/// *See the [crate doc](index.html) for more examples and explanations of the expression format.*
+pub fn build_expression(string: &str) -> EvalexprResult<Expression> {
+    build_operator_tree(string).map(Expression::from)
 }
 
 /// Evaluate the given expression string into a string.
@@ -100,6 +100,31 @@
 //! assert_eq!(precompiled.eval_boolean_with_context(&context), Ok(false));
 //! ```
 //!
+//! Evaluating a precompiled expression does not modify it, and `Node` is `Send` and `Sync`,
+//! so a precompiled expression can be shared between threads in an `Arc` and evaluated concurrently without cloning it.
+//! If a precompiled expression is cloned often, `build_expression` can be used instead of `build_operator_tree`.
+//! It returns an `Expression` that stores the operator tree in a single contiguous vector, which makes cloning and evaluation cheaper.
+//! If many precompiled expressions with the same identifiers are kept, `build_operator_tree_with_interner` lets them share one allocation per distinct identifier.
+//! If the source text of precompiled expressions is preserved with `ParseOptions::preserve_source_text`, `build_operator_tree_arc` keeps expressions given as `Arc<str>` without copying them.
+//! If a precompiled expression only reads a variable or a literal, `Node::eval_ref` borrows the value instead of cloning it, and returns an owned value only if it has to be computed.
+//!
+//! To report several syntax errors at once, for example in an editor, `build_operator_tree_all_errors` returns the errors of all independent parts of an expression,
+//! which are the tuple elements and chained expressions outside of parentheses.
+//!
+//! To find out why a rule like `c1 && c2 && c3` evaluated to false, `Node::eval_boolean_explained` evaluates the clauses in order and returns the first failing one,
+//! together with the values of the variables it reads.
+//! For disjunctions like `c1 || c2 || c3`, it returns the first succeeding clause.
+//!
+//! To find slow functions or pathological expressions, `Node::eval_with_stats` returns `EvalStats` with the amount and duration of evaluations per kind of operator and per function,
+//! as well as the largest tuple and string produced during the evaluation.
+//! Statistics of many evaluations can be aggregated with `EvalStats::merge`.
+//!
+//! Where a failing expression should degrade gracefully instead, e.g. on a dashboard, `eval_or` and `eval_or_with_context` return a default value if parsing or evaluating fails.
+//!
+//! To compose rules from fragments without concatenating strings, `NodeBuilder` builds operator trees from values, variables, function calls and operators,
+//! checking that each operator gets as many operands as it requires.
+//! Parsed fragments are embedded as a whole, so a fragment like `a || b` cannot change the meaning of the surrounding rule.
+//!
 //! ## CLI
 //!
 //! While primarily meant to be used as a library, `evalexpr` is also available as a command line tool.
@@ -110,6 +135,9 @@
 //! evalexpr 2 + 3 # outputs `5` to stdout.
 //! ```
 //!
+//! To try out expressions interactively, the repository contains a read-eval-print loop that keeps variables between lines.
+//! It can be started with `cargo run --example repl`.
+//!
 //! ## Features
 //!
 //! ### Operators
@@ -135,8 +163,10 @@
 //! | \>= | 80 | Greater than or equal |
 //! | == | 80 | Equal |
 //! | != | 80 | Not equal |
+//! | <=> | 80 | Three-way comparison, `-1`, `0` or `1` if the left operand is lower than, equal to or greater than the right one |
 //! | && | 75 | Logical and |
 //! | &#124;&#124; | 70 | Logical or |
+//! | ? : | 60 | Conditional, e.g. `c ? a : b` (lazy and right-associative) |
 //! | = | 50 | Assignment |
 //! | += | 50 | Sum-Assignment or String-Concatenation-Assignment |
 //! | -= | 50 | Difference-Assignment |
@@ -156,6 +186,16 @@
 //! | - | 110 | Negation |
 //! | ! | 110 | Logical not |
 //!
+//! The logical operators `&&`, `||` and `!` can also be written as the keywords `and`, `or` and `not`.
+//! This needs to be enabled with `ParseOptions::keyword_logical_operators`, since otherwise the keywords are parsed as identifiers:
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let options = ParseOptions::new().keyword_logical_operators(true);
+//! assert_eq!(build_operator_tree_with_options("true and false or not false", &options).unwrap().eval(), Ok(Value::from(true)));
+//! ```
+//!
 //! Operators that take numbers as arguments can either take integers or floating point numbers.
 //! If one of the arguments is a floating point number, all others are converted to floating point numbers as well, and the resulting value is a floating point number as well.
 //! Otherwise, the result is an integer.
@@ -170,6 +210,27 @@
 //! assert_eq!(eval("2^2"), Ok(Value::from(4.0)));
 //! ```
 //!
+//! With `ParseOptions::float_division`, the division operator always returns a floating point number.
+//! Truncating integer division is then still available through the builtin function `int_div`.
+//!
+//! With `ParseOptions::three_valued_logic`, the logical operators `&&`, `||` and `!` treat the empty value `()` as an unknown boolean, like `NULL` in SQL.
+//! For example, `true && ()` is `()`, but `false && ()` is `false`, and `true || ()` is `true`.
+//!
+//! With `ParseOptions::percentage_of`, the keyword `of` multiplies like `*`, and a `%` followed by `of` takes a percentage, such that `20% of 150` is `30.0`.
+//!
+//! With `ParseOptions::remap_operator`, operator symbols can be given another meaning, or new symbols can be added.
+//! For example, remapping `=` to `Operator::Eq` and `:=` to `Operator::Assign` makes `a = b` a comparison and `a := 3` an assignment, like in Pascal.
+//!
+//! The comparison operators `<`, `>`, `<=` and `>=` also compare two tuples lexicographically, element by element.
+//! If one tuple is a prefix of the other, then the shorter tuple is the lesser one.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert_eq!(eval("(1, 2) < (1, 3)"), Ok(Value::from(true)));
+//! assert_eq!(eval("(1, 2) < (1, 2, 0)"), Ok(Value::from(true)));
+//! ```
+//!
 //! #### The Aggregation Operator
 //!
 //! The aggregation operator aggregates a set of values into a tuple.
@@ -199,6 +260,10 @@
 //! ])));
 //! ```
 //!
+//! A single trailing comma after the last element of a tuple or of the arguments of a function call is ignored,
+//! so `f(a, b, )` is the same as `f(a, b)`, and `(1, 2, )` is the same as `(1, 2)`.
+//! A trailing comma without an element before it, like in `(,)` or `(1, 2,,)`, results in `EvalexprError::InvalidTrailingComma`.
+//!
 //! #### The Assignment Operator
 //!
 //! This crate features the assignment operator, that allows expressions to store their result in a variable in the expression context.
@@ -255,6 +320,19 @@
 //! assert_eq!(healing_script.eval_int_with_context_mut(&mut context), Ok(5));
 //! ```
 //!
+//! #### Evaluation Order
+//!
+//! The operands of every operator and the arguments of every function call are evaluated from left to right and depth first, before the operator is applied or the function is called.
+//! For example, the functions in `f(a(), b()) + c()` are called in the order `a`, `b`, `f`, `c`.
+//! This order is guaranteed, such that assignments and functions with side effects behave predictably, and all evaluation methods keep it,
+//! including those of `Expression`, `Node::eval_profiled`, `Node::eval_with_stats` and `Node::eval_async`.
+//!
+//! If an operand fails, the evaluation stops, so the operands to the right of it are not evaluated.
+//! The logical operators `&&` and `||` do not short-circuit, so both of their operands are always evaluated.
+//! Only the fallback of the builtin function `try_or` is evaluated lazily, if the first argument fails,
+//! and only the branch of the conditional operator `c ? a : b` that is chosen by the condition is evaluated.
+//! A function marked with `Function::memoized` is called only once per argument during an evaluation.
+//!
 //! ### Contexts
 //!
 //! An expression evaluator that just evaluates expressions would be useful already, but this crate can do more.
@@ -325,6 +403,26 @@
 //!
 //! For more information about user-defined functions, refer to the respective [section](#user-defined-functions).
 //!
+//! Contexts can be layered by using a slice of context references `&[&dyn Context]` as context, where each identifier is resolved by the first layer that knows it.
+//! If the same variables are read repeatedly, e.g. when evaluating many expressions against a layered context, the `CachingContext` memoizes the lookups of the context it wraps.
+//! Variables that are expensive to compute can be resolved on demand with a `LazyContext`, which calls a closure once for each variable that is actually read.
+//! To shadow variables temporarily, e.g. for loop variables, a `ScopedContext` keeps a stack of variable scopes on top of the context it wraps.
+//! To find out which variables an evaluation actually read, e.g. to build minimal cache keys, a `RecordingContext` records the variable lookups of the context it wraps.
+//!
+//! The context also decides how `==` and `!=` compare floats.
+//! By default, floats are compared exactly, but a `HashMapContext` can be configured to compare them with a relative or absolute tolerance:
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! let mut context = HashMapContext::new();
+//! assert_eq!(eval_boolean_with_context("0.1 + 0.2 == 0.3", &context), Ok(false));
+//! context.set_float_equality(FloatEquality::Absolute(1e-9));
+//! assert_eq!(eval_boolean_with_context("0.1 + 0.2 == 0.3", &context), Ok(true));
+//! // With a tolerance, ints are compared numerically to floats
+//! assert_eq!(eval_boolean_with_context("3 == 3.0", &context), Ok(true));
+//! ```
+//!
 //! ### Builtin Functions
 //!
 //! This crate offers a set of builtin functions (see below for a full list).
@@ -344,16 +442,30 @@
 //!
 //! | Identifier           | Argument Amount | Argument Types                | Description |
 //! |----------------------|-----------------|-------------------------------|-------------|
-//! | `min`                | >= 1            | Numeric                       | Returns the minimum of the arguments |
-//! | `max`                | >= 1            | Numeric                       | Returns the maximum of the arguments |
+//! | `min`                | >= 1            | Numeric or String             | Returns the minimum of the arguments |
+//! | `max`                | >= 1            | Numeric or String             | Returns the maximum of the arguments |
+//! | `min_by`             | 2               | Tuple, String                 | Returns the element of the tuple whose value at the key given by the second argument is minimal. The elements must be maps, and the key may consist of dot-separated keys of nested maps. The values must be all numbers or all strings. Of equal values, the first element is returned |
+//! | `max_by`             | 2               | Tuple, String                 | Like `min_by`, but returns the element whose value at the key is maximal |
+//! | `sum`                | >= 1            | Numeric                       | Returns the sum of the arguments, computed exactly if all arguments are integers, and with compensated (Kahan-Babuška) summation otherwise |
+//! | `avg`                | >= 1            | Numeric                       | Returns the arithmetic mean of the arguments as float, summing with the same algorithm as `sum` |
 //! | `len`                | 1               | String/Tuple                  | Returns the character length of a string, or the amount of elements in a tuple (not recursively) |
+//! | `zip`                | 2               | Tuple, Tuple                  | Returns a tuple of pairs of the elements at the same index of two tuples of equal length, e.g. `zip((1, 2), (3, 4))` is `((1, 3), (2, 4))` |
+//! | `unzip`              | 1               | Tuple                         | Returns a pair of tuples of the first and the second elements of a tuple of pairs, e.g. `unzip(((1, 3), (2, 4)))` is `((1, 2), (3, 4))` |
+//! | `to_map`             | 1               | Tuple                         | Returns a map from a tuple of pairs of a string key and a value, e.g. `to_map(("a", 1), ("b", 2))` is `{a: 1, b: 2}`. Of pairs with the same key, the last one determines the value |
+//! | `keys`               | 1               | Map                           | Returns a tuple of the keys of a map, sorted in ascending order |
+//! | `values`             | 1               | Map                           | Returns a tuple of the values of a map, sorted by their keys in ascending order |
+//! | `entries`            | 1               | Map                           | Returns a tuple of the entries of a map as pairs of key and value, sorted by their keys in ascending order |
 //! | `floor`              | 1               | Numeric                       | Returns the largest integer less than or equal to a number |
-//! | `round`              | 1               | Numeric                       | Returns the nearest integer to a number. Rounds half-way cases away from 0.0 |
+//! | `round`              | 1/2             | Numeric, String               | Returns the nearest integer to a number. Rounds half-way cases away from 0.0, or according to the optional rounding mode `"half_up"` (away from 0.0), `"half_even"`, `"half_down"` (towards 0.0) or `"toward_zero"` (truncating) |
 //! | `ceil`               | 1               | Numeric                       | Returns the smallest integer greater than or equal to a number |
+//! | `between`            | 3               | Numeric, Numeric, Numeric     | Returns true if the first argument is greater than or equal to the second and less than or equal to the third argument |
 //! | `if`                 | 3               | Boolean, Any, Any             | If the first argument is true, returns the second argument, otherwise, returns the third  |
 //! | `contains`           | 2               | Tuple, any non-tuple          | Returns true if second argument exists in first tuple argument. |
-//! | `contains_any`       | 2               | Tuple, Tuple of any non-tuple | Returns true if one of the values in the second tuple argument exists in first tuple argument. |
-//! | `typeof`             | 1               | Any                           | returns "string", "float", "int", "boolean", "tuple", or "empty" depending on the type of the argument  |
+//! | `contains_any`       | 2               | Tuple, Tuple of any non-tuple | Returns true if one of the values in the second tuple argument exists in first tuple argument. Either argument may be a single value instead of a tuple. Returns false if the second argument is empty |
+//! | `contains_all`       | 2               | Tuple, Tuple of any non-tuple | Returns true if all of the values in the second tuple argument exist in first tuple argument. Either argument may be a single value instead of a tuple. Returns true if the second argument is empty |
+//! | `any`                | >= 0            | Boolean                       | Returns true if one of the arguments is true. Also accepts a single tuple of booleans, and returns false for an empty tuple |
+//! | `all`                | >= 0            | Boolean                       | Returns true if all arguments are true. Also accepts a single tuple of booleans, and returns true for an empty tuple |
+//! | `typeof`             | 1               | Any                           | returns "string", "float", "int", "boolean", "tuple", "map", or "empty" depending on the type of the argument  |
 //! | `math::is_nan`       | 1               | Numeric                       | Returns true if the argument is the floating-point value NaN, false if it is another floating-point value, and throws an error if it is not a number  |
 //! | `math::is_finite`    | 1               | Numeric                       | Returns true if the argument is a finite floating-point number, false otherwise  |
 //! | `math::is_infinite`  | 1               | Numeric                       | Returns true if the argument is an infinite floating-point number, false otherwise  |
@@ -388,25 +500,55 @@
 //! | `str::to_uppercase`  | 1               | String                        | Returns the upper-case version of the string |
 //! | `str::trim`          | 1               | String                        | Strips whitespace from the start and the end of the string |
 //! | `str::from`          | >= 0            | Any                           | Returns passed value as string |
-//! | `str::substring`     | 3               | String, Int, Int              | Returns a substring of the first argument, starting at the second argument and ending at the third argument. If the last argument is omitted, the substring extends to the end of the string |
+//! | `str::concat`        | >= 0            | Any                           | Returns the concatenation of the given values, where strings are not quoted, e.g. `str::concat("a", 1)` is `"a1"` |
+//! | `str::substring`     | 3               | String, Int, Int              | Returns a substring of the first argument, starting at the second argument and ending at the third argument, where both count characters. If the last argument is omitted, the substring extends to the end of the string |
+//! | `parse_int`          | 1, 2, 3         | String, Int, String           | Parses the string as integer in the radix given by the optional second argument, which defaults to 10 and must be between 2 and 36. Surrounding whitespace and all underscores are ignored, and the digits may be preceded by a `+` or `-` sign. Prefixes like `0x` are not accepted. An optional string as last argument is a group separator that is removed before parsing, like in `parse_int("1,234", ",")`. Note that the separator is removed everywhere, so with `"."` as separator, `"1.5"` is parsed as `15` instead of being rejected as decimal number |
+//! | `parse_int_or`       | 2, 3            | String, Int, Any              | Like `parse_int`, but returns the last argument if the string is not a valid integer |
+//! | `parse_float`        | 1               | String                        | Parses the string as float. Surrounding whitespace and all underscores are ignored, and the number may be preceded by a `+` or `-` sign |
+//! | `parse_float_or`     | 2               | String, Any                   | Like `parse_float`, but returns the last argument if the string is not a valid float |
+//! | `try_or`             | 2               | Any, Any                      | Returns the first argument, or the second argument if evaluating the first argument fails. The second argument is only evaluated if the first one fails. A context function named `try_or` takes precedence, like for other builtin functions |
+//! | `format_bytes`       | 1               | Int                           | Formats a non-negative byte size in the largest binary unit it reaches, from `B` and `KiB` up to `EiB`, rounded to one decimal digit, e.g. `"1.5 KiB"`. A zero decimal digit is omitted, e.g. `"1 KiB"` |
+//! | `format_duration`    | 1               | Number                        | Formats a non-negative duration in seconds as its non-zero amounts of days, hours, minutes and seconds, e.g. `"1d 2h 5s"`. Float durations are rounded to tenths of a second, e.g. `"59.9s"`. Zero is formatted as `"0s"` |
+//! | `int_div`            | 2               | Int                           | Divides the first integer by the second, truncating the result towards zero |
+//! | `simplify_fraction`  | 2               | Int                           | Divides the numerator and denominator of a fraction by their greatest common divisor, returning a tuple with a positive denominator, e.g. `simplify_fraction((6, -8))` is `(-3, 4)` |
+//! | `divmod`             | 2               | Int                           | Returns a tuple of the quotient and remainder of an integer division, truncating like `/` and `%` such that the remainder has the sign of the dividend, e.g. `divmod(-17, 5)` is `(-3, -2)` |
+//! | `with_unit`          | 2               | Number, String                | Returns the number as quantity of the unit with the given name (Requires `units_support` feature flag) |
+//! | `unit_of`            | 1               | Number                        | Returns the name of the unit of the given quantity, or an empty string for a plain number (Requires `units_support` feature flag) |
+//! | `to_unit`            | 2               | Quantity, String              | Converts the quantity into the unit with the given name, which needs to measure the same dimension (Requires `units_support` feature flag) |
 //! | `bitand`             | 2               | Int                           | Computes the bitwise and of the given integers |
 //! | `bitor`              | 2               | Int                           | Computes the bitwise or of the given integers |
 //! | `bitxor`             | 2               | Int                           | Computes the bitwise xor of the given integers |
 //! | `bitnot`             | 1               | Int                           | Computes the bitwise not of the given integer |
 //! | `shl`                | 2               | Int                           | Computes the given integer bitwise shifted left by the other given integer |
 //! | `shr`                | 2               | Int                           | Computes the given integer bitwise shifted right by the other given integer |
+//! | `float_bits`         | 1               | Float                         | Returns the integer with the same bit pattern as the given float, like `f64::to_bits` |
+//! | `bits_float`         | 1               | Int                           | Returns the float with the same bit pattern as the given integer, like `f64::from_bits` |
 //! | `random`             | 0               | Empty                         | Return a random float between 0 and 1. Requires the `rand` feature flag. |
-//!
-//! The `min` and `max` functions can deal with a mixture of integer and floating point arguments.
-//! If the maximum or minimum is an integer, then an integer is returned.
-//! Otherwise, a float is returned.
+//! | `date::year`         | 1               | Int                           | Returns the year of the given Unix timestamp in seconds, in UTC (Requires `time_support` feature flag) |
+//! | `date::month`        | 1               | Int                           | Returns the month (1 to 12) of the given Unix timestamp in seconds, in UTC (Requires `time_support` feature flag) |
+//! | `date::day`          | 1               | Int                           | Returns the day of the month of the given Unix timestamp in seconds, in UTC (Requires `time_support` feature flag) |
+//! | `date::weekday`      | 1               | Int                           | Returns the ISO weekday (1 for Monday to 7 for Sunday) of the given Unix timestamp in seconds, in UTC (Requires `time_support` feature flag) |
+//! | `date::hour`         | 1               | Int                           | Returns the hour (0 to 23) of the given Unix timestamp in seconds, in UTC (Requires `time_support` feature flag) |
+//! | `date::format`       | 2               | Int, String                   | Formats the given Unix timestamp in seconds in UTC according to the `strftime`-like format string of the `chrono` crate, e.g. `"%Y-%m-%d %H:%M:%S"` (Requires `time_support` feature flag) |
+//! | `now`                | 0               | Empty                         | Returns the current time as Unix timestamp in seconds, read from the clock of the context (Requires `time_support` feature flag) |
+//! | `days`               | 1               | Number                        | Returns the amount of seconds in the given amount of days, e.g. for `now() + days(1)` (Requires `time_support` feature flag) |
+//! | `hours`              | 1               | Number                        | Returns the amount of seconds in the given amount of hours (Requires `time_support` feature flag) |
+//! | `format_time`        | 2               | Int, String                   | Alias of `date::format` (Requires `time_support` feature flag) |
+//!
+//! The `min` and `max` functions compare either numbers or strings, where strings are compared lexicographically.
+//! Numbers can be a mixture of integer and floating point arguments, and the minimum or maximum is returned with its own type.
+//! If several arguments are equally minimal or maximal, the first of them is returned, and NaN is ignored unless all arguments are NaN.
+//! Arguments that cannot be compared with each other result in an `EvalexprError::IncomparableValues` error naming the index of the first one that does not fit.
+//!
+//! The reductions `min`, `max`, `sum` and `avg` skip empty arguments, so `avg` divides by the amount of non-empty arguments.
+//! If there are no non-empty arguments, there is nothing to reduce, and an error is returned.
 //!
 //! The regex functions require the feature flag `regex_support`.
 //!
 //! ### Values
 //!
 //! Operators take values as arguments and produce values as results.
-//! Values can be booleans, integer or floating point numbers, strings, tuples or the empty type.
+//! Values can be booleans, integer or floating point numbers, strings, tuples, maps or the empty type.
 //! Values are denoted as displayed in the following table.
 //!
 //! | Value type | Example |
@@ -414,15 +556,32 @@
 //! | `Value::String` | `"abc"`, `""`, `"a\"b\\c"` |
 //! | `Value::Boolean` | `true`, `false` |
 //! | `Value::Int` | `3`, `-9`, `0`, `135412`, `0xfe02`, `-0x1e` |
-//! | `Value::Float` | `3.`, `.35`, `1.00`, `0.5`, `123.554`, `23e4`, `-2e-3`, `3.54e+2` |
+//! | `Value::Float` | `3.`, `.35`, `1.00`, `0.5`, `123.554`, `23e4`, `-2e-3`, `3.54e+2`, `inf`, `-inf`, `NaN` |
 //! | `Value::Tuple` | `(3, 55.0, false, ())`, `(1, 2)` |
+//! | `Value::Map` | `{total: 3, avg: 1.5}`, `{"a b": (1, 2)}`, `{}` |
 //! | `Value::Empty` | `()` |
 //!
 //! Integers are internally represented as `i64`, and floating point numbers are represented as `f64`.
+//! The special floats infinity and not-a-number are displayed as the literals `inf`, `-inf` and `NaN`, such that they can be parsed again.
+//! Their literals are case-insensitive, and `infinity` is accepted as well.
 //! Tuples are represented as `Vec<Value>` and empty values are not stored, but represented by Rust's unit type `()` where necessary.
 //!
+//! Maps are written as comma-separated entries of the form `key: value` within curly braces, where each key is an identifier or a string and must be unique.
+//! As commas separate the entries, tuples need to be enclosed in parentheses to be used as values.
+//! Maps are represented as `Vec<(String, Value)>`, which keeps the entries in the order they were written in.
+//! A field of a map stored in a variable is read by appending its key to the identifier of the variable with a dot, like `result.total`, unless a variable with exactly that identifier exists.
+//! Keys can be chained to read nested fields, and a key that is a number reads the element of a tuple at that index, like `items.0.price`.
+//! If any key along such a path is missing, evaluation fails with `VariableIdentifierNotFound`.
+//! To compute several named results with one expression, a map can also be written as `record(total = a + b, avg = (a + b) / 2)` if `ParseOptions::record_syntax` is enabled, which is equivalent to `{total: a + b, avg: (a + b) / 2}`.
+//! Each name may appear only once, and `record` with arguments not of the form `name = value` calls a function named `record` as usual.
+//!
 //! There exist type aliases for some of the types.
-//! They include `IntType`, `FloatType`, `TupleType` and `EmptyType`.
+//! They include `IntType`, `FloatType`, `TupleType`, `MapType` and `EmptyType`.
+//!
+//! As floats cannot be totally ordered, `Value` does not implement `Ord`.
+//! To use values as keys of a `BTreeMap` or to sort them, the wrapper `OrderedValue` orders them totally, first by their type and then by their content.
+//! `Value` itself implements `PartialOrd` like the comparison operators, comparing integers and floats numerically and leaving values of different types incomparable.
+//! In both orders, `Value::Empty` is less than every other value, such that sorting puts empty values first.
 //!
 //! Values can be constructed either directly or using the `From` trait.
 //! They can be decomposed using the `Value::as_[type]` methods.
@@ -436,6 +595,7 @@
 //! | `Value::from(4.4)` | `Value::Float(4.4)` |
 //! | `Value::from(true)` | `Value::Boolean(true)` |
 //! | `Value::from(vec![Value::from(3)])` | `Value::Tuple(vec![Value::Int(3)])` |
+//! | `Value::from((3, "a"))` | `Value::Tuple(vec![Value::Int(3), Value::String("a".to_string())])` |
 //!
 //! **Examples for deconstructing a value:**
 //!
@@ -445,6 +605,10 @@
 //! | `Value::from(4.4).as_float()` | `Ok(4.4)` |
 //! | `Value::from(true).as_int()` | `Err(Error::ExpectedInt {actual: Value::Boolean(true)})` |
 //!
+//! Values of user-defined types can be embedded as `Value::Custom` by implementing the `CustomValue` trait.
+//! They cannot be written in expressions, but can be stored in a context or returned by a function.
+//! The operators `+` and `*` dispatch to the custom value if one of their operands is custom, and `==` and `!=` compare custom values with `CustomValue::eq`.
+//!
 //! Values have a precedence of 200.
 //!
 //! ### Variables
@@ -486,6 +650,7 @@
 //!
 //! Be aware that functions need to verify the types of values that are passed to them.
 //! The `error` module contains some shortcuts for verification, and error types for passing a wrong value type.
+//! The `value_args!` macro validates the types of multiple arguments and binds their contents to variables in one step.
 //! Also, most numeric functions need to distinguish between being called with integers or floating point numbers, and act accordingly.
 //!
 //! Here are some examples and counter-examples on expressions that are interpreted as function calls:
@@ -500,6 +665,13 @@
 //! | `12 3` | no | Error, value cannot be followed by a value |
 //! | `a 5 6` | no | Error, function call cannot be followed by a value |
 //!
+//! With `ParseOptions::require_parentheses_for_calls`, only identifiers followed by an opening brace `(` are function calls, such that `a v` and `x 5.5` are errors.
+//!
+//! A `MacroFunction` receives the unevaluated operator trees of its arguments instead of their values, together with the context.
+//! Macro functions are stored with `HashMapContext::insert_macro`, and their calls are dispatched before the arguments are evaluated,
+//! e.g. to count the identifiers of an argument or to include the text of a failing assertion in the error.
+//! As the result of a macro may depend on the form of its arguments, a call of a macro can never be constant-folded.
+//!
 //! Functions have a precedence of 190.
 //!
 //! ### [Serde](https://serde.rs)
@@ -516,6 +688,20 @@
 //! The implementation expects a [serde `string`](https://serde.rs/data-model.html) as input.
 //! Example parsing with [ron format](docs.rs/ron):
 //!
+//! ### Whitespace and Line Breaks
+//!
+//! Whitespace only separates tokens, and line breaks are whitespace like any other.
+//! So an expression can span multiple lines, with operators at the end or the start of a line, and the line break continues the expression.
+//! Only string literals keep their line breaks.
+//! Chained expressions are still separated by `;`, not by line breaks.
+//!
+//! ```rust
+//! use evalexpr::*;
+//!
+//! assert_eq!(eval("1 +\n2"), Ok(Value::from(3)));
+//! assert_eq!(eval("max(\n    1,\n    2 * 3,\n)"), Ok(Value::from(6)));
+//! ```
+//!
 //! ### Comments
 //!
 //! Evalexpr supports C-style inline comments and end-of-line comments.
@@ -560,8 +746,137 @@
 //!
 //! With `serde`, expressions can be integrated into arbitrarily complex data.
 //!
+//! To store compiled operator trees across versions of this crate, `Node::to_bytes` and `Node::from_bytes` provide a versioned binary format that does not require the `serde_support` feature.
+//!
 //! The crate also implements `Serialize` and `Deserialize` for the `HashMapContext`,
 //! but note that only the variables get (de)serialized, not the functions.
+//! The variables are serialized sorted by identifier, so the output does not depend on the order of insertion.
+//! Likewise, the `Debug` and `Display` output of a `HashMapContext` lists its bindings sorted, writing functions with their amount of arguments like `<function/2>`,
+//! and `HashMapContext::iter_variables_sorted` iterates the variables in this order.
+//!
+//! `EvalexprError` implements `Serialize` as a struct with the fields `code`, `message`, `identifier`, `value`, `expected` and `actual`,
+//! where `code` is the stable error code returned by `EvalexprError::code`.
+//! The other fields contain the offending identifier, the value of an unexpected type, and the names of the expected and actual types, if the error has them.
+//!
+//! ### Async Functions
+//!
+//! With the `async_support` feature flag, functions can be asynchronous, e.g. to await I/O.
+//! The feature does not depend on an async runtime.
+//! An `AsyncFunction` returns a future of its result, and is stored in an `AsyncFunctionContext` that wraps a synchronous context.
+//! `Node::eval_async` evaluates an operator tree like `Node::eval_with_context`, but awaits each call of an asynchronous function in evaluation order.
+//! Variables and synchronous functions of the wrapped context work unchanged.
+//! The returned future is `Send` if the context is `Sync`, so it can be spawned onto a multi-threaded runtime.
+//!
+//! ```rust
+//! # #[cfg(feature = "async_support")]
+//! # async fn example() {
+//! use evalexpr::*;
+//!
+//! let mut context = AsyncFunctionContext::new(HashMapContext::new());
+//! context.set_async_function("double".into(), AsyncFunction::new(|argument| {
+//!     let argument = argument.clone();
+//!     async move { Ok(Value::from(argument.as_int()? * 2)) }
+//! }));
+//! let tree = build_operator_tree("double(2) + 1").unwrap(); // Do proper error handling here
+//! assert_eq!(tree.eval_async(&context).await, Ok(Value::from(5)));
+//! # }
+//! ```
+//!
+//! ### Units
+//!
+//! With the `units_support` feature flag, numbers can carry a unit of measurement as `Value::Quantity`, such that formulas are checked for dimensional consistency.
+//! The builtin function `with_unit` attaches a unit to a number, `unit_of` returns the unit of a number, and `to_unit` converts a quantity into another unit of the same dimension.
+//! Quantities can be added, subtracted and compared if their units measure the same dimension, where the right operand is converted into the unit of the left one.
+//! Multiplying and dividing quantities combines their dimensions, and multiplying or dividing a quantity by a plain number scales it.
+//! Operations on incompatible units fail with `EvalexprError::UnitMismatch`.
+//!
+//! ```rust
+//! # #[cfg(feature = "units_support")] {
+//! use evalexpr::*;
+//!
+//! assert_eq!(
+//!     eval("str::from(with_unit(1, \"km\") + with_unit(500, \"m\"))"),
+//!     Ok(Value::from("1.5 km"))
+//! );
+//! assert_eq!(
+//!     eval("unit_of(with_unit(100, \"km\") / with_unit(2, \"h\"))"),
+//!     Ok(Value::from("km/h"))
+//! );
+//! assert_eq!(eval("with_unit(1, \"ft\") < with_unit(1, \"m\")"), Ok(Value::from(true)));
+//! assert!(matches!(
+//!     eval("with_unit(1, \"m\") + with_unit(1, \"s\")"),
+//!     Err(EvalexprError::UnitMismatch { .. })
+//! ));
+//! # }
+//! ```
+//!
+//! The units are defined by the `UnitRegistry` of the context, which defaults to standard units of length, mass and time, and derived units like `m/s` and `N`.
+//! A `HashMapContext` can use another registry with `HashMapContext::set_unit_registry`.
+//!
+//! ### Time
+//!
+//! With the `time_support` feature flag, the builtin functions `now`, `days`, `hours`, `format_time` and `date::*` compute with points in time stored as Unix timestamps in seconds, which are plain integers.
+//! `now` reads the current time from the `Clock` of the context, which is the system clock by default.
+//! A `HashMapContext` can use another clock with `HashMapContext::set_clock`, e.g. a fixed clock to make expressions reproducible in tests.
+//! Dates are computed and formatted with the optional `chrono` dependency, and `chrono::DateTime` converts into a `Value` and back.
+//!
+//! ```rust
+//! # #[cfg(feature = "time_support")] {
+//! use evalexpr::*;
+//!
+//! let mut context = HashMapContext::new();
+//! context.set_clock(Clock::fixed(1_700_000_000));
+//! assert_eq!(eval_with_context("now() + days(1)", &context), Ok(Value::from(1_700_086_400)));
+//! assert_eq!(
+//!     eval_with_context("date::format(now() + hours(2), \"%Y-%m-%d %H:%M\")", &context),
+//!     Ok(Value::from("2023-11-15 00:13"))
+//! );
+//! # }
+//! ```
+//!
+//! ### Excel Formulas
+//!
+//! With the `excel_support` feature flag, `from_excel_formula` converts a formula written in a restricted dialect of Excel formulas into an operator tree.
+//! The dialect covers literals, named variables, the arithmetic operators with Excel's precedence, the comparisons `=` and `<>`, concatenation with `&`, and the functions `IF`, `AND`, `OR`, `NOT`, `MIN`, `MAX`, `ABS` and `ROUND`.
+//! Cell references like `A1` are rejected with `EvalexprError::ExcelCellReference`, and other constructs of Excel, like ranges or unknown functions, with `EvalexprError::UnsupportedExcelConstruct`.
+//! Both errors carry the byte range of the offending part of the formula.
+//!
+//! ```rust
+//! # #[cfg(feature = "excel_support")] {
+//! use evalexpr::*;
+//!
+//! let tree = from_excel_formula("=IF(price * 2 > budget, \"over\", \"within \" & budget)").unwrap(); // Do proper error handling here
+//! let context = context_map! { "price" => 40, "budget" => 100 }.unwrap(); // Do proper error handling here
+//! assert_eq!(tree.eval_with_context(&context), Ok(Value::from("within 100")));
+//! assert_eq!(
+//!     from_excel_formula("=B2 * 2"),
+//!     Err(EvalexprError::ExcelCellReference { reference: "B2".into(), span: 1..3 })
+//! );
+//! # }
+//! ```
+//!
+//! Unlike in Excel, strings and variable names are case-sensitive.
+//!
+//! ### Expressions of the `eval` Crate
+//!
+//! With the `eval_compat_support` feature flag, `compat::eval_crate::parse_compat` parses expressions written for the `eval` crate, to migrate them to this crate.
+//! It translates the constructs that are written differently, like arrays `[a, b]` into tuples `(a, b)`, `null` into `()`, strings in single quotes and indexing like `items[0]["price"]` into field paths like `items.0.price`, and builds the result with float division.
+//! Constructs without counterpart, like ranges `0..3` or indexing with computed indices, are reported all at once as `CompatIssue`s with their byte range and a suggestion, and `compat::eval_crate::translate` returns the translated expression string.
+//!
+//! ```rust
+//! # #[cfg(feature = "eval_compat_support")] {
+//! use evalexpr::{compat::eval_crate::parse_compat, *};
+//!
+//! let tree = parse_compat("len(tags) > 1 && owner['name'] == 'ada'").unwrap(); // Do proper error handling here
+//! let mut context = HashMapContext::new();
+//! eval_with_context_mut("tags = (\"a\", \"b\"); owner = {name: \"ada\"}", &mut context).unwrap(); // Do proper error handling here
+//! assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+//!
+//! let issues = parse_compat("sum(0..n) + [x]").unwrap_err();
+//! assert_eq!(issues.len(), 2);
+//! assert_eq!(issues[0].construct, "..");
+//! # }
+//! ```
 //!
 //! ## License
 //!
@@ -570,7 +885,7 @@
 //!
 
 #![deny(missing_docs)]
-#![forbid(unsafe_code)]
+#![deny(unsafe_code)]
 #![allow(clippy::get_first)]
 
 #[cfg(feature = "regex_support")]
@@ -585,25 +900,58 @@ extern crate serde_derive;
 
 pub use crate::{
     context::{
-        Context, ContextWithMutableFunctions, ContextWithMutableVariables, EmptyContext,
-        EmptyContextWithBuiltinFunctions, HashMapContext, IterateVariablesContext,
+        BindingChange, CachingContext, Context, ContextSnapshot, ContextWithMutableFunctions,
+        ContextWithMutableVariables, EmptyContext, EmptyContextWithBuiltinFunctions, FloatEquality,
+        HashMapContext, IterateVariablesContext, LazyContext, RecordingContext, ScopedContext,
     },
     error::{EvalexprError, EvalexprResult},
-    function::Function,
+    function::{EvalContext, Function, MacroFunction, MAX_EVALUATION_DEPTH},
     interface::*,
-    operator::Operator,
+    operator::{Operator, OperatorCategory},
+    parse_options::{
+        known_identifier_validator, snake_case_identifier_validator, IdentifierInterner,
+        IdentifierValidator, OperatorFilter, ParseOptions,
+    },
     token::PartialToken,
-    tree::Node,
-    value::{value_type::ValueType, EmptyType, FloatType, IntType, TupleType, Value, EMPTY_VALUE},
+    tree::{
+        BooleanOutcome, ChangeExplanation, DecidingClause, EvalStats, Expression, FunctionCall,
+        Node, NodeBuilder, OperationStats, Pattern, ProcessedNode, Rule, TypeHints,
+    },
+    value::{
+        custom_value::CustomValue, value_type::ValueType, EmptyType, FloatType, IntType, MapType,
+        OrderedValue, TupleType, Value, EMPTY_VALUE,
+    },
+};
+
+#[cfg(feature = "async_support")]
+pub use crate::feature_async::{
+    AsyncContext, AsyncFunction, AsyncFunctionContext, AsyncFunctionFuture,
 };
+#[cfg(feature = "excel_support")]
+pub use crate::feature_excel::from_excel_formula;
+#[cfg(feature = "time_support")]
+pub use crate::feature_time::Clock;
+#[cfg(feature = "units_support")]
+pub use crate::feature_units::{Dimension, UnitId, UnitRegistry};
 
+#[cfg(feature = "eval_compat_support")]
+pub mod compat;
 mod context;
 pub mod error;
+#[cfg(feature = "async_support")]
+mod feature_async;
+#[cfg(feature = "excel_support")]
+mod feature_excel;
 #[cfg(feature = "serde_support")]
 mod feature_serde;
+#[cfg(feature = "time_support")]
+mod feature_time;
+#[cfg(feature = "units_support")]
+mod feature_units;
 mod function;
 mod interface;
 mod operator;
+mod parse_options;
 mod token;
 mod tree;
 mod value;
@@ -13,8 +13,9 @@ impl Display for Operator {
             Sub => write!(f, "-"),
             Neg => write!(f, "-"),
             Mul => write!(f, "*"),
-            Div => write!(f, "/"),
+            Div | FloatDiv => write!(f, "/"),
             Mod => write!(f, "%"),
+            PercentOf => write!(f, "% of"),
             Exp => write!(f, "^"),
 
             Eq => write!(f, "=="),
@@ -23,15 +24,17 @@ impl Display for Operator {
             Lt => write!(f, "<"),
             Geq => write!(f, ">="),
             Leq => write!(f, "<="),
-            And => write!(f, "&&"),
-            Or => write!(f, "||"),
-            Not => write!(f, "!"),
+            Cmp => write!(f, "<=>"),
+            And | KleeneAnd => write!(f, "&&"),
+            Or | KleeneOr => write!(f, "||"),
+            Not | KleeneNot => write!(f, "!"),
+            Conditional => write!(f, "?:"),
 
             Assign => write!(f, " = "),
             AddAssign => write!(f, " += "),
             SubAssign => write!(f, " -= "),
             MulAssign => write!(f, " *= "),
-            DivAssign => write!(f, " /= "),
+            DivAssign | FloatDivAssign => write!(f, " /= "),
             ModAssign => write!(f, " %= "),
             ExpAssign => write!(f, " ^= "),
             AndAssign => write!(f, " &&= "),
@@ -39,6 +42,7 @@ impl Display for Operator {
 
             Tuple => write!(f, ", "),
             Chain => write!(f, "; "),
+            Map { keys } => write!(f, "{{{}}}", keys.join(", ")),
 
             Const { value } => write!(f, "{}", value),
             VariableIdentifierWrite { identifier } | VariableIdentifierRead { identifier } => {
@@ -1,6 +1,13 @@
+use std::{cmp::Ordering, sync::Arc};
+
 use crate::function::builtin::builtin_function;
 
-use crate::{context::Context, error::*, value::Value, ContextWithMutableVariables};
+use crate::{
+    context::{Context, FloatEquality},
+    error::*,
+    value::{value_type::ValueType, FloatType, IntType, Value},
+    ContextWithMutableVariables, CustomValue,
+};
 
 mod display;
 
@@ -21,8 +28,13 @@ pub enum Operator {
     Mul,
     /// A binary division operator.
     Div,
+    /// A binary division operator that always returns a float, even if both arguments are integers.
+    FloatDiv,
     /// A binary modulo operator.
     Mod,
+    /// A binary percentage-of operator, where `a % of b` is `a` percent of `b`.
+    /// It always returns a float.
+    PercentOf,
     /// A binary exponentiation operator.
     Exp,
 
@@ -38,12 +50,23 @@ pub enum Operator {
     Geq,
     /// A binary lower-than-or-equal comparator.
     Leq,
+    /// A binary three-way comparator `a <=> b`, which returns `-1` if `a` is lower than `b`, `0` if they are equal, and `1` if `a` is greater.
+    Cmp,
     /// A binary logical and operator.
     And,
     /// A binary logical or operator.
     Or,
     /// A binary logical not operator.
     Not,
+    /// A binary logical and operator in three-valued logic, where `Value::Empty` stands for an unknown boolean.
+    KleeneAnd,
+    /// A binary logical or operator in three-valued logic, where `Value::Empty` stands for an unknown boolean.
+    KleeneOr,
+    /// A unary logical not operator in three-valued logic, where `Value::Empty` stands for an unknown boolean.
+    KleeneNot,
+    /// A ternary conditional operator `c ? a : b`.
+    /// It is evaluated lazily, such that only the branch chosen by the condition is evaluated.
+    Conditional,
 
     /// A binary assignment operator.
     Assign,
@@ -55,6 +78,8 @@ pub enum Operator {
     MulAssign,
     /// A binary divide-assign operator.
     DivAssign,
+    /// A binary divide-assign operator that always divides as `FloatDiv`.
+    FloatDivAssign,
     /// A binary modulo-assign operator.
     ModAssign,
     /// A binary exponentiate-assign operator.
@@ -68,6 +93,12 @@ pub enum Operator {
     Tuple,
     /// An n-ary subexpression chain.
     Chain,
+    /// An n-ary map constructor.
+    /// Its arguments are the values of the map, which are labeled with the keys in the same order.
+    Map {
+        /// The keys of the map.
+        keys: Vec<String>,
+    },
 
     /// A constant value.
     Const {
@@ -77,34 +108,94 @@ pub enum Operator {
     /// A write to a variable identifier.
     VariableIdentifierWrite {
         /// The identifier of the variable.
-        identifier: String,
+        identifier: Arc<str>,
     },
     /// A read from a variable identifier.
     VariableIdentifierRead {
         /// The identifier of the variable.
-        identifier: String,
+        identifier: Arc<str>,
     },
     /// A function identifier.
     FunctionIdentifier {
         /// The identifier of the function.
-        identifier: String,
+        identifier: Arc<str>,
     },
 }
 
+/// A category of operators with a related purpose, used to disable whole language features with an `OperatorFilter`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum OperatorCategory {
+    /// The arithmetic operators `+`, `-`, `*`, `/`, `%`, `% of` and `^`, including the unary negation `-`.
+    Arithmetic,
+    /// The comparison operators `==`, `!=`, `>`, `<`, `>=` and `<=`.
+    Comparison,
+    /// The logical operators `&&`, `||` and `!`, and the conditional operator `? :`.
+    Logic,
+    /// The assignment operator `=` and all compound assignment operators like `+=`.
+    Assignment,
+    /// The tuple constructor `,`.
+    Tuple,
+    /// The expression chaining operator `;`.
+    Chain,
+    /// Map literals like `{a: 1}`.
+    Map,
+    /// Function calls, including calls of builtin functions.
+    FunctionCall,
+}
+
+impl OperatorCategory {
+    /// All operator categories.
+    pub const ALL: [OperatorCategory; 8] = [
+        OperatorCategory::Arithmetic,
+        OperatorCategory::Comparison,
+        OperatorCategory::Logic,
+        OperatorCategory::Assignment,
+        OperatorCategory::Tuple,
+        OperatorCategory::Chain,
+        OperatorCategory::Map,
+        OperatorCategory::FunctionCall,
+    ];
+}
+
 impl Operator {
+    /// Returns the category of this operator.
+    /// Root nodes, constants and variable identifiers do not belong to any category, as every expression needs them.
+    pub fn category(&self) -> Option<OperatorCategory> {
+        use crate::operator::Operator::*;
+        match self {
+            Add | Sub | Neg | Mul | Div | FloatDiv | Mod | PercentOf | Exp => {
+                Some(OperatorCategory::Arithmetic)
+            },
+            Eq | Neq | Gt | Lt | Geq | Leq | Cmp => Some(OperatorCategory::Comparison),
+            And | Or | Not | KleeneAnd | KleeneOr | KleeneNot | Conditional => {
+                Some(OperatorCategory::Logic)
+            },
+            Assign | AddAssign | SubAssign | MulAssign | DivAssign | FloatDivAssign | ModAssign
+            | ExpAssign | AndAssign | OrAssign => Some(OperatorCategory::Assignment),
+            Tuple => Some(OperatorCategory::Tuple),
+            Chain => Some(OperatorCategory::Chain),
+            Map { .. } => Some(OperatorCategory::Map),
+            FunctionIdentifier { .. } => Some(OperatorCategory::FunctionCall),
+            RootNode
+            | Const { .. }
+            | VariableIdentifierWrite { .. }
+            | VariableIdentifierRead { .. } => None,
+        }
+    }
+
     pub(crate) fn value(value: Value) -> Self {
         Operator::Const { value }
     }
 
-    pub(crate) fn variable_identifier_write(identifier: String) -> Self {
+    pub(crate) fn variable_identifier_write(identifier: Arc<str>) -> Self {
         Operator::VariableIdentifierWrite { identifier }
     }
 
-    pub(crate) fn variable_identifier_read(identifier: String) -> Self {
+    pub(crate) fn variable_identifier_read(identifier: Arc<str>) -> Self {
         Operator::VariableIdentifierRead { identifier }
     }
 
-    pub(crate) fn function_identifier(identifier: String) -> Self {
+    pub(crate) fn function_identifier(identifier: Arc<str>) -> Self {
         Operator::FunctionIdentifier { identifier }
     }
 
@@ -117,19 +208,21 @@ impl Operator {
 
             Add | Sub => 95,
             Neg => 110,
-            Mul | Div | Mod => 100,
+            Mul | Div | FloatDiv | Mod | PercentOf => 100,
             Exp => 120,
 
-            Eq | Neq | Gt | Lt | Geq | Leq => 80,
-            And => 75,
-            Or => 70,
-            Not => 110,
+            Eq | Neq | Gt | Lt | Geq | Leq | Cmp => 80,
+            And | KleeneAnd => 75,
+            Or | KleeneOr => 70,
+            Not | KleeneNot => 110,
+            Conditional => 60,
 
-            Assign | AddAssign | SubAssign | MulAssign | DivAssign | ModAssign | ExpAssign
-            | AndAssign | OrAssign => 50,
+            Assign | AddAssign | SubAssign | MulAssign | DivAssign | FloatDivAssign | ModAssign
+            | ExpAssign | AndAssign | OrAssign => 50,
 
             Tuple => 40,
             Chain => 0,
+            Map { .. } => 200,
 
             Const { .. } => 200,
             VariableIdentifierWrite { .. } | VariableIdentifierRead { .. } => 200,
@@ -142,7 +235,16 @@ impl Operator {
     /// Left-to-right chaining has priority if operators with different order but same precedence are chained.
     pub(crate) const fn is_left_to_right(&self) -> bool {
         use crate::operator::Operator::*;
-        !matches!(self, Assign | FunctionIdentifier { .. })
+        !matches!(self, Assign | Conditional | FunctionIdentifier { .. })
+    }
+
+    /// Returns true if this operator is a call of the builtin function `try_or` that evaluates its fallback lazily.
+    /// Like other builtin functions, `try_or` is only available if builtin functions are enabled in the context,
+    /// and a function of the context that is named `try_or` takes precedence.
+    pub(crate) fn is_lazy_try_or<C: Context>(&self, context: &C) -> bool {
+        matches!(self, Operator::FunctionIdentifier { identifier } if &**identifier == "try_or")
+            && !context.are_builtin_functions_disabled()
+            && !context.has_function("try_or")
     }
 
     /// Returns true if chains of this operator should be flattened into one operator with many arguments.
@@ -152,20 +254,23 @@ impl Operator {
     }
 
     /// True if this operator is a leaf, meaning it accepts no arguments.
+    /// Maps are leaves as well, as their arguments are complete once the map literal is parsed.
     // Make this a const fn as soon as whatever is missing gets stable (issue #57563)
     pub(crate) fn is_leaf(&self) -> bool {
-        self.max_argument_amount() == Some(0)
+        self.max_argument_amount() == Some(0) || matches!(self, Operator::Map { .. })
     }
 
     /// Returns the maximum amount of arguments required by this operator.
     pub(crate) const fn max_argument_amount(&self) -> Option<usize> {
         use crate::operator::Operator::*;
         match self {
-            Add | Sub | Mul | Div | Mod | Exp | Eq | Neq | Gt | Lt | Geq | Leq | And | Or
-            | Assign | AddAssign | SubAssign | MulAssign | DivAssign | ModAssign | ExpAssign
-            | AndAssign | OrAssign => Some(2),
-            Tuple | Chain => None,
-            Not | Neg | RootNode => Some(1),
+            Add | Sub | Mul | Div | FloatDiv | Mod | PercentOf | Exp | Eq | Neq | Gt | Lt | Geq
+            | Leq | Cmp | And | Or | KleeneAnd | KleeneOr | Assign | AddAssign | SubAssign
+            | MulAssign | DivAssign | FloatDivAssign | ModAssign | ExpAssign | AndAssign
+            | OrAssign => Some(2),
+            Conditional => Some(3),
+            Tuple | Chain | Map { .. } => None,
+            Not | KleeneNot | Neg | RootNode => Some(1),
             Const { .. } => Some(0),
             VariableIdentifierWrite { .. } | VariableIdentifierRead { .. } => Some(0),
             FunctionIdentifier { .. } => Some(1),
@@ -184,6 +289,12 @@ impl Operator {
         context: &C,
     ) -> EvalexprResult<Value> {
         use crate::operator::Operator::*;
+        #[cfg(feature = "units_support")]
+        if let Some(result) = crate::feature_units::eval_quantity_operator(self, arguments, context)
+        {
+            return result;
+        }
+
         match self {
             RootNode => {
                 if let Some(first) = arguments.first() {
@@ -194,6 +305,9 @@ impl Operator {
             },
             Add => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
+                if let Some(result) = eval_custom(self, arguments, CustomValue::add) {
+                    return result;
+                }
                 expect_number_or_string(&arguments[0])?;
                 expect_number_or_string(&arguments[1])?;
 
@@ -263,6 +377,9 @@ impl Operator {
             },
             Mul => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
+                if let Some(result) = eval_custom(self, arguments, CustomValue::mul) {
+                    return result;
+                }
                 arguments[0].as_number()?;
                 arguments[1].as_number()?;
 
@@ -303,6 +420,20 @@ impl Operator {
                     ))
                 }
             },
+            FloatDiv => {
+                expect_operator_argument_amount(arguments.len(), 2)?;
+                let (a, b) = (arguments[0].as_number()?, arguments[1].as_number()?);
+
+                // Like the integer division, dividing integers by zero is an error instead of an infinite float
+                if arguments[0].is_int() && arguments[1] == Value::Int(0) {
+                    Err(EvalexprError::division_error(
+                        arguments[0].clone(),
+                        arguments[1].clone(),
+                    ))
+                } else {
+                    Ok(Value::Float(a / b))
+                }
+            },
             Mod => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
                 arguments[0].as_number()?;
@@ -324,6 +455,13 @@ impl Operator {
                     ))
                 }
             },
+            PercentOf => {
+                expect_operator_argument_amount(arguments.len(), 2)?;
+
+                Ok(Value::Float(
+                    arguments[0].as_number()? / 100.0 * arguments[1].as_number()?,
+                ))
+            },
             Exp => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
                 arguments[0].as_number()?;
@@ -336,15 +474,29 @@ impl Operator {
             Eq => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
 
-                Ok(Value::Boolean(arguments[0] == arguments[1]))
+                Ok(Value::Boolean(values_equal(
+                    &arguments[0],
+                    &arguments[1],
+                    context.float_equality(),
+                )))
             },
             Neq => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
 
-                Ok(Value::Boolean(arguments[0] != arguments[1]))
+                Ok(Value::Boolean(!values_equal(
+                    &arguments[0],
+                    &arguments[1],
+                    context.float_equality(),
+                )))
             },
             Gt => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
+                if arguments[0].is_tuple() && arguments[1].is_tuple() {
+                    return Ok(Value::Boolean(matches!(
+                        partial_cmp_values(self, &arguments[0], &arguments[1])?,
+                        Some(Ordering::Greater)
+                    )));
+                }
                 expect_number_or_string(&arguments[0])?;
                 expect_number_or_string(&arguments[1])?;
 
@@ -360,6 +512,12 @@ impl Operator {
             },
             Lt => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
+                if arguments[0].is_tuple() && arguments[1].is_tuple() {
+                    return Ok(Value::Boolean(matches!(
+                        partial_cmp_values(self, &arguments[0], &arguments[1])?,
+                        Some(Ordering::Less)
+                    )));
+                }
                 expect_number_or_string(&arguments[0])?;
                 expect_number_or_string(&arguments[1])?;
 
@@ -375,6 +533,12 @@ impl Operator {
             },
             Geq => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
+                if arguments[0].is_tuple() && arguments[1].is_tuple() {
+                    return Ok(Value::Boolean(matches!(
+                        partial_cmp_values(self, &arguments[0], &arguments[1])?,
+                        Some(Ordering::Greater | Ordering::Equal)
+                    )));
+                }
                 expect_number_or_string(&arguments[0])?;
                 expect_number_or_string(&arguments[1])?;
 
@@ -390,6 +554,12 @@ impl Operator {
             },
             Leq => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
+                if arguments[0].is_tuple() && arguments[1].is_tuple() {
+                    return Ok(Value::Boolean(matches!(
+                        partial_cmp_values(self, &arguments[0], &arguments[1])?,
+                        Some(Ordering::Less | Ordering::Equal)
+                    )));
+                }
                 expect_number_or_string(&arguments[0])?;
                 expect_number_or_string(&arguments[1])?;
 
@@ -403,6 +573,11 @@ impl Operator {
                     ))
                 }
             },
+            Cmp => {
+                expect_operator_argument_amount(arguments.len(), 2)?;
+                let ordering = partial_cmp_values(self, &arguments[0], &arguments[1])?;
+                ordering_to_value(ordering, &arguments[0], &arguments[1])
+            },
             And => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
                 let a = arguments[0].as_boolean()?;
@@ -423,9 +598,55 @@ impl Operator {
 
                 Ok(Value::Boolean(!a))
             },
-            Assign | AddAssign | SubAssign | MulAssign | DivAssign | ModAssign | ExpAssign
-            | AndAssign | OrAssign => Err(EvalexprError::ContextNotMutable),
+            KleeneAnd => {
+                expect_operator_argument_amount(arguments.len(), 2)?;
+                let a = as_unknown_boolean(&arguments[0])?;
+                let b = as_unknown_boolean(&arguments[1])?;
+
+                Ok(match (a, b) {
+                    (Some(false), _) | (_, Some(false)) => Value::Boolean(false),
+                    (Some(true), Some(true)) => Value::Boolean(true),
+                    _ => Value::Empty,
+                })
+            },
+            KleeneOr => {
+                expect_operator_argument_amount(arguments.len(), 2)?;
+                let a = as_unknown_boolean(&arguments[0])?;
+                let b = as_unknown_boolean(&arguments[1])?;
+
+                Ok(match (a, b) {
+                    (Some(true), _) | (_, Some(true)) => Value::Boolean(true),
+                    (Some(false), Some(false)) => Value::Boolean(false),
+                    _ => Value::Empty,
+                })
+            },
+            KleeneNot => {
+                expect_operator_argument_amount(arguments.len(), 1)?;
+                let a = as_unknown_boolean(&arguments[0])?;
+
+                Ok(a.map_or(Value::Empty, |a| Value::Boolean(!a)))
+            },
+            Conditional => {
+                expect_operator_argument_amount(arguments.len(), 3)?;
+                if arguments[0].as_boolean()? {
+                    Ok(arguments[1].clone())
+                } else {
+                    Ok(arguments[2].clone())
+                }
+            },
+            Assign | AddAssign | SubAssign | MulAssign | DivAssign | FloatDivAssign | ModAssign
+            | ExpAssign | AndAssign | OrAssign => Err(EvalexprError::ContextNotMutable),
             Tuple => Ok(Value::Tuple(arguments.into())),
+            Map { keys } => {
+                expect_operator_argument_amount(arguments.len(), keys.len())?;
+
+                Ok(Value::Map(
+                    keys.iter()
+                        .cloned()
+                        .zip(arguments.iter().cloned())
+                        .collect(),
+                ))
+            },
             Chain => {
                 if arguments.is_empty() {
                     return Err(EvalexprError::wrong_operator_argument_amount(0, 1));
@@ -441,32 +662,41 @@ impl Operator {
             VariableIdentifierWrite { identifier } => {
                 expect_operator_argument_amount(arguments.len(), 0)?;
 
-                Ok(identifier.clone().into())
+                Ok(identifier.to_string().into())
             },
             VariableIdentifierRead { identifier } => {
                 expect_operator_argument_amount(arguments.len(), 0)?;
-
-                if let Some(value) = context.get_value(identifier).cloned() {
-                    Ok(value)
-                } else {
-                    Err(EvalexprError::VariableIdentifierNotFound(
-                        identifier.clone(),
-                    ))
-                }
+                read_variable(identifier, context).cloned()
             },
             FunctionIdentifier { identifier } => {
                 expect_operator_argument_amount(arguments.len(), 1)?;
                 let arguments = &arguments[0];
 
                 match context.call_function(identifier, arguments) {
-                    Err(EvalexprError::FunctionIdentifierNotFound(_))
-                        if !context.are_builtin_functions_disabled() =>
-                    {
-                        if let Some(builtin_function) = builtin_function(identifier) {
-                            builtin_function.call(arguments)
+                    Err(EvalexprError::FunctionIdentifierNotFound(_)) => {
+                        if !context.are_builtin_functions_disabled() {
+                            #[cfg(feature = "units_support")]
+                            if let Some(result) = crate::feature_units::call_unit_function(
+                                identifier, arguments, context,
+                            ) {
+                                return result;
+                            }
+                            #[cfg(feature = "time_support")]
+                            if let Some(result) = crate::feature_time::call_time_function(
+                                identifier, arguments, context,
+                            ) {
+                                return result;
+                            }
+                            if let Some(builtin_function) = builtin_function(identifier) {
+                                return builtin_function.call(identifier, arguments);
+                            }
+                        }
+
+                        if let Some(value) = context.get_value(identifier) {
+                            Err(EvalexprError::not_callable(value.clone()))
                         } else {
                             Err(EvalexprError::FunctionIdentifierNotFound(
-                                identifier.clone(),
+                                identifier.to_string(),
                             ))
                         }
                     },
@@ -476,6 +706,32 @@ impl Operator {
         }
     }
 
+    /// Evaluates the operator with the given owned arguments and context.
+    /// Tuples take over their arguments instead of cloning them, so large tuples are built in a single pass.
+    pub(crate) fn eval_owned<C: Context>(
+        &self,
+        arguments: Vec<Value>,
+        context: &C,
+    ) -> EvalexprResult<Value> {
+        match self {
+            Operator::Tuple => Ok(Value::Tuple(arguments)),
+            _ => self.eval(&arguments, context),
+        }
+    }
+
+    /// Evaluates the operator with the given owned arguments and mutable context.
+    /// Tuples take over their arguments instead of cloning them, so large tuples are built in a single pass.
+    pub(crate) fn eval_mut_owned<C: ContextWithMutableVariables>(
+        &self,
+        arguments: Vec<Value>,
+        context: &mut C,
+    ) -> EvalexprResult<Value> {
+        match self {
+            Operator::Tuple => Ok(Value::Tuple(arguments)),
+            _ => self.eval_mut(&arguments, context),
+        }
+    }
+
     /// Evaluates the operator with the given arguments and mutable context.
     pub(crate) fn eval_mut<C: ContextWithMutableVariables>(
         &self,
@@ -491,13 +747,13 @@ impl Operator {
 
                 Ok(Value::Empty)
             },
-            AddAssign | SubAssign | MulAssign | DivAssign | ModAssign | ExpAssign | AndAssign
-            | OrAssign => {
+            AddAssign | SubAssign | MulAssign | DivAssign | FloatDivAssign | ModAssign
+            | ExpAssign | AndAssign | OrAssign => {
                 expect_operator_argument_amount(arguments.len(), 2)?;
 
                 let target = arguments[0].as_string()?;
                 let left_value = Operator::VariableIdentifierRead {
-                    identifier: target.clone(),
+                    identifier: target.as_str().into(),
                 }
                 .eval(&Vec::new(), context)?;
                 let arguments = vec![left_value, arguments[1].clone()];
@@ -507,6 +763,7 @@ impl Operator {
                     SubAssign => Operator::Sub.eval(&arguments, context),
                     MulAssign => Operator::Mul.eval(&arguments, context),
                     DivAssign => Operator::Div.eval(&arguments, context),
+                    FloatDivAssign => Operator::FloatDiv.eval(&arguments, context),
                     ModAssign => Operator::Mod.eval(&arguments, context),
                     ExpAssign => Operator::Exp.eval(&arguments, context),
                     AndAssign => Operator::And.eval(&arguments, context),
@@ -524,3 +781,150 @@ impl Operator {
         }
     }
 }
+
+/// Reads the variable with the given identifier from the context, or the field of a map variable if there is no such variable.
+pub(crate) fn read_variable<'a, C: Context>(
+    identifier: &str,
+    context: &'a C,
+) -> EvalexprResult<&'a Value> {
+    match context.get_value(identifier) {
+        Some(value) => Ok(value),
+        None => read_field(identifier, context),
+    }
+}
+
+/// Reads a field of a map variable, where the identifier is the name of the variable followed by the keys of the field, separated by dots.
+/// Fields of nested maps are accessed with multiple keys, like `a.b.c`.
+/// Elements of tuples are accessed with their zero-based index as key, like `items.0.price`.
+///
+/// Variables whose identifier contains dots take precedence over fields, so this is only called if there is no variable with the given identifier.
+fn read_field<'a, C: Context>(identifier: &str, context: &'a C) -> EvalexprResult<&'a Value> {
+    let not_found = || EvalexprError::VariableIdentifierNotFound(identifier.to_string());
+    let (variable, keys) = identifier.split_once('.').ok_or_else(not_found)?;
+
+    let mut value = context.get_value(variable).ok_or_else(not_found)?;
+    for key in keys.split('.') {
+        value = match value {
+            Value::Map(_) => value.get_field(key),
+            Value::Tuple(tuple) => key.parse::<usize>().ok().and_then(|index| tuple.get(index)),
+            _ => return Err(EvalexprError::expected_map(value.clone())),
+        }
+        .ok_or_else(not_found)?;
+    }
+    Ok(value)
+}
+
+/// Evaluates a binary operator with the given method of the custom value among its arguments.
+/// If only the right argument is a custom value, the method is called on it with the left argument.
+///
+/// Returns `None` if none of the arguments is a custom value.
+fn eval_custom(
+    operator: &Operator,
+    arguments: &[Value],
+    method: fn(&(dyn CustomValue + 'static), &Value) -> Option<EvalexprResult<Value>>,
+) -> Option<EvalexprResult<Value>> {
+    let result = match (&arguments[0], &arguments[1]) {
+        (Value::Custom(custom), other) | (other, Value::Custom(custom)) => {
+            method(custom.as_ref(), other)
+        },
+        _ => return None,
+    };
+    Some(result.unwrap_or_else(|| {
+        Err(EvalexprError::wrong_type_combination(
+            operator.clone(),
+            vec![(&arguments[0]).into(), (&arguments[1]).into()],
+        ))
+    }))
+}
+
+/// Compares two values for the ordering comparators.
+/// Strings are compared lexicographically, numbers numerically with integers converted to floats if needed,
+/// and tuples lexicographically element by element, where a tuple that is a prefix of another tuple is the lesser one.
+///
+/// Returns `None` if the values have no ordering, which happens if a NaN is compared.
+fn partial_cmp_values(
+    operator: &Operator,
+    a: &Value,
+    b: &Value,
+) -> EvalexprResult<Option<Ordering>> {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => Ok(a.partial_cmp(b)),
+        (Value::Int(a), Value::Int(b)) => Ok(a.partial_cmp(b)),
+        (Value::Tuple(a), Value::Tuple(b)) => {
+            for (a, b) in a.iter().zip(b) {
+                match partial_cmp_values(operator, a, b)? {
+                    Some(Ordering::Equal) => {},
+                    ordering => return Ok(ordering),
+                }
+            }
+            Ok(a.len().partial_cmp(&b.len()))
+        },
+        (a, b) if a.is_number() && b.is_number() => Ok(a.as_number()?.partial_cmp(&b.as_number()?)),
+        (a, b) => Err(EvalexprError::wrong_type_combination(
+            operator.clone(),
+            vec![a.into(), b.into()],
+        )),
+    }
+}
+
+/// Returns the result of the three-way comparator `<=>` for the ordering of the given values.
+/// The ordering is `None` if a NaN is compared, which is neither lower than, equal to nor greater than any number.
+pub(crate) fn ordering_to_value(
+    ordering: Option<Ordering>,
+    a: &Value,
+    b: &Value,
+) -> EvalexprResult<Value> {
+    match ordering {
+        Some(ordering) => Ok(Value::Int(ordering as IntType)),
+        None => {
+            let (index, nan) = if contains_nan(a) { (0, a) } else { (1, b) };
+            Err(EvalexprError::incomparable_values(
+                index,
+                vec![ValueType::Int, ValueType::Float],
+                nan.clone(),
+            ))
+        },
+    }
+}
+
+/// Returns true if the given value is NaN or a tuple containing NaN.
+fn contains_nan(value: &Value) -> bool {
+    match value {
+        Value::Float(float) => float.is_nan(),
+        Value::Tuple(tuple) => tuple.iter().any(contains_nan),
+        #[cfg(feature = "units_support")]
+        Value::Quantity { value, .. } => value.is_nan(),
+        _ => false,
+    }
+}
+
+/// Compares two values for the equality operators.
+/// Floats, and ints compared with floats, are compared as configured by the context, all other values exactly.
+fn values_equal(a: &Value, b: &Value, float_equality: FloatEquality) -> bool {
+    match (a, b, float_equality) {
+        (Value::Float(a), Value::Float(b), float_equality) => float_equality.are_equal(*a, *b),
+        (
+            Value::Int(a),
+            Value::Float(b),
+            FloatEquality::Relative(_) | FloatEquality::Absolute(_),
+        ) => float_equality.are_equal(*a as FloatType, *b),
+        (
+            Value::Float(a),
+            Value::Int(b),
+            FloatEquality::Relative(_) | FloatEquality::Absolute(_),
+        ) => float_equality.are_equal(*a, *b as FloatType),
+        (a, b, _) => a == b,
+    }
+}
+
+/// Returns the boolean of a three-valued logic operand, or `None` if the operand is empty and hence unknown.
+fn as_unknown_boolean(value: &Value) -> EvalexprResult<Option<bool>> {
+    match value {
+        Value::Boolean(boolean) => Ok(Some(*boolean)),
+        Value::Empty => Ok(None),
+        value => Err(EvalexprError::type_error(
+            value.clone(),
+            vec![ValueType::Boolean, ValueType::Empty],
+        )),
+    }
+}
@@ -0,0 +1,694 @@
+use std::{
+    collections::HashSet,
+    fmt, mem,
+    ops::Range,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    operator::OperatorCategory, token, token::Token, EvalexprError, EvalexprResult, Operator,
+};
+
+/// Options that change how an expression string is parsed into an operator tree.
+///
+/// The default options parse the expression format as described in the [crate doc](index.html).
+/// Options are set with builder-style methods and passed to `build_operator_tree_with_options`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let options = ParseOptions::new().keyword_logical_operators(true);
+/// let tree = build_operator_tree_with_options("true and not false", &options).unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval(), Ok(Value::from(true)));
+/// ```
+#[derive(Clone, Default)]
+pub struct ParseOptions {
+    keyword_logical_operators: bool,
+    float_division: bool,
+    three_valued_logic: bool,
+    percentage_of: bool,
+    record_syntax: bool,
+    identifier_validator: Option<Arc<IdentifierValidator>>,
+    max_length: Option<usize>,
+    max_tokens: Option<usize>,
+    allowed_operators: Option<OperatorFilter>,
+    max_tuple_size: Option<usize>,
+    identifier_interner: Option<IdentifierInterner>,
+    preserve_source_text: bool,
+    require_parentheses_for_calls: bool,
+    operator_remaps: Vec<OperatorRemap>,
+}
+
+/// An operator symbol remapped with `ParseOptions::remap_operator`.
+#[derive(Clone)]
+pub(crate) struct OperatorRemap {
+    symbol: String,
+    operator: Operator,
+    /// The tokens the symbol is split into by default.
+    pub(crate) tokens: Vec<Token>,
+    /// The token of the operator, which replaces the tokens of the symbol.
+    pub(crate) token: Token,
+}
+
+/// A function that checks if an identifier is valid, returning the reason for the rejection otherwise.
+pub type IdentifierValidator = dyn Fn(&str) -> Result<(), String> + Send + Sync;
+
+impl ParseOptions {
+    /// Constructs `ParseOptions` with all options set to their defaults.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// If `enabled` is true, the keywords `and`, `or` and `not` are parsed as the operators `&&`, `||` and `!`.
+    /// They have the same precedence as the operators they stand for.
+    ///
+    /// This is disabled by default, such that `and`, `or` and `not` can be used as identifiers.
+    pub fn keyword_logical_operators(mut self, enabled: bool) -> Self {
+        self.keyword_logical_operators = enabled;
+        self
+    }
+
+    /// If `enabled` is true, the division operators `/` and `/=` always return a float, even if both arguments are integers.
+    /// For truncating integer division, the builtin function `int_div` can be used instead.
+    /// Dividing an integer by the integer zero fails with `EvalexprError::DivisionError` like the integer division,
+    /// while divisions with a float argument return infinity or NaN.
+    ///
+    /// This is disabled by default, such that `5 / 4` evaluates to `1`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().float_division(true);
+    /// assert_eq!(build_operator_tree_with_options("5 / 4", &options).unwrap().eval(), Ok(Value::from(1.25)));
+    /// ```
+    pub fn float_division(mut self, enabled: bool) -> Self {
+        self.float_division = enabled;
+        self
+    }
+
+    /// If `enabled` is true, the logical operators `&&`, `||` and `!` follow Kleene's three-valued logic,
+    /// where `Value::Empty` stands for an unknown boolean, like `NULL` in SQL.
+    /// The result is unknown only if it depends on an unknown operand, so e.g. `false && ()` is `false`,
+    /// while `true && ()` is `()`.
+    /// Operands that are neither booleans nor empty are still rejected.
+    ///
+    /// This is disabled by default, such that the logical operators only accept booleans.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().three_valued_logic(true);
+    /// let tree = build_operator_tree_with_options("a || b", &options).unwrap(); // Do proper error handling here
+    /// let context = context_map! { "a" => true, "b" => Value::Empty }.unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(true)));
+    /// ```
+    pub fn three_valued_logic(mut self, enabled: bool) -> Self {
+        self.three_valued_logic = enabled;
+        self
+    }
+
+    /// If `enabled` is true, the keyword `of` is parsed as the multiplication operator `*`,
+    /// and a `%` directly followed by `of` is parsed as the percentage-of operator, such that `20% of 150` is `30.0`.
+    /// The percentage-of operator has the same precedence as `*` and always returns a float.
+    ///
+    /// This is disabled by default, such that `of` can be used as an identifier.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().percentage_of(true);
+    /// assert_eq!(build_operator_tree_with_options("20% of 150", &options).unwrap().eval(), Ok(Value::from(30.0)));
+    /// assert_eq!(build_operator_tree_with_options("0.5 of 10", &options).unwrap().eval(), Ok(Value::from(5.0)));
+    /// ```
+    pub fn percentage_of(mut self, enabled: bool) -> Self {
+        self.percentage_of = enabled;
+        self
+    }
+
+    /// If `enabled` is true, `record(name = value, ...)` is parsed as a map of named results, equivalent to the map literal `{name: value, ...}`.
+    /// Only arguments starting like `(name = ` are parsed this way, so `record` with other arguments still calls a function named `record`.
+    ///
+    /// This is disabled by default, such that a function named `record` can be called with assignments as arguments.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().record_syntax(true);
+    /// let tree = build_operator_tree_with_options("record(total = 1 + 2, double = 2 * 3)", &options).unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.eval(), eval("{total: 3, double: 6}"));
+    /// ```
+    pub fn record_syntax(mut self, enabled: bool) -> Self {
+        self.record_syntax = enabled;
+        self
+    }
+
+    /// Sets a validator that is called for each variable and function identifier while building the operator tree.
+    /// If the validator rejects an identifier, building the operator tree fails with `EvalexprError::InvalidIdentifier`.
+    ///
+    /// Note that builtin functions are validated as well, so e.g. `math::sin` must be accepted to be able to use it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().identifier_validator(snake_case_identifier_validator);
+    /// assert!(build_operator_tree_with_options("user_name + 1", &options).is_ok());
+    /// assert!(matches!(
+    ///     build_operator_tree_with_options("userName + 1", &options),
+    ///     Err(EvalexprError::InvalidIdentifier { .. })
+    /// ));
+    /// ```
+    pub fn identifier_validator<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.identifier_validator = Some(Arc::new(validator));
+        self
+    }
+
+    /// Limits the length of expression strings to the given amount of bytes.
+    /// Longer strings are rejected with `EvalexprError::ExpressionTooLarge` before they are tokenized.
+    ///
+    /// By default, the length is unlimited.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Limits the amount of tokens of expression strings, not counting whitespace and comments.
+    /// Expressions with more tokens are rejected with `EvalexprError::ExpressionTooLarge`.
+    ///
+    /// The limit is checked while tokenizing, so tokenization of a huge expression stops early,
+    /// and the memory it uses is proportional to the limit and not to the length of the expression.
+    /// By default, the amount of tokens is unlimited.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().max_tokens(3);
+    /// assert!(build_operator_tree_with_options("a + b", &options).is_ok());
+    /// assert_eq!(
+    ///     build_operator_tree_with_options("a + b + c", &options),
+    ///     Err(EvalexprError::ExpressionTooLarge { limit: 3, actual: 5 })
+    /// );
+    /// ```
+    pub fn max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Limits the amount of elements that the aggregation operator `,` may combine into a tuple.
+    /// Expressions with larger tuples are rejected with `EvalexprError::TupleTooLarge` while building the operator tree,
+    /// so the tuples are never allocated.
+    ///
+    /// The elements of the aggregation operator are known when the operator tree is built,
+    /// so the limit holds for all tuples created by the operator in any evaluation.
+    /// By default, the size of tuples is unlimited.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().max_tuple_size(3);
+    /// assert!(build_operator_tree_with_options("(1, 2, 3), (4, 5)", &options).is_ok());
+    /// assert_eq!(
+    ///     build_operator_tree_with_options("max(1, 2, 3, 4)", &options),
+    ///     Err(EvalexprError::TupleTooLarge { limit: 3, actual: 4 })
+    /// );
+    /// ```
+    pub fn max_tuple_size(mut self, max_tuple_size: usize) -> Self {
+        self.max_tuple_size = Some(max_tuple_size);
+        self
+    }
+
+    /// Restricts the operators that may appear in expressions to the ones allowed by the given filter.
+    /// If an expression contains another operator, building the operator tree fails with `EvalexprError::OperatorDisabled`.
+    ///
+    /// By default, all operators are allowed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().allowed_operators(OperatorFilter::all().exclude_operator(Operator::Exp));
+    /// assert!(build_operator_tree_with_options("2 * 3", &options).is_ok());
+    /// assert_eq!(
+    ///     build_operator_tree_with_options("2 ^ 3", &options),
+    ///     Err(EvalexprError::OperatorDisabled { operator: Operator::Exp, span: 2..3 })
+    /// );
+    /// ```
+    pub fn allowed_operators(mut self, filter: OperatorFilter) -> Self {
+        self.allowed_operators = Some(filter);
+        self
+    }
+
+    /// Sets an interner that stores the identifiers of the built operator trees.
+    /// Operator trees built with the same interner share one allocation per distinct identifier,
+    /// which saves memory if many operator trees with the same identifiers are kept.
+    ///
+    /// By default, each occurrence of an identifier is allocated separately.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let interner = IdentifierInterner::new();
+    /// let options = ParseOptions::new().identifier_interner(interner.clone());
+    /// build_operator_tree_with_options("customer_id > 100", &options).unwrap(); // Do proper error handling here
+    /// build_operator_tree_with_options("customer_id == 7", &options).unwrap(); // Do proper error handling here
+    /// assert_eq!(interner.len(), 1);
+    /// ```
+    pub fn identifier_interner(mut self, interner: IdentifierInterner) -> Self {
+        self.identifier_interner = Some(interner);
+        self
+    }
+
+    /// If `enabled` is true, each node of the built operator tree keeps the part of the expression it was built from,
+    /// which is returned by `Node::source_text`.
+    /// The text is kept even if the tree is transformed afterwards, e.g. by `Node::rewrite`,
+    /// such that error messages can quote exactly what the user wrote.
+    /// Nodes synthesized by a transformation have no source text.
+    ///
+    /// This is disabled by default, as it costs an allocation per node and keeps the whole expression in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().preserve_source_text(true);
+    /// let tree = build_operator_tree_with_options("2 * ( a+b )", &options).unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.source_text(), Some("2 * ( a+b )"));
+    /// assert_eq!(tree.children()[0].children()[1].source_text(), Some("( a+b )"));
+    /// assert_eq!(build_operator_tree("2 * ( a+b )").unwrap().source_text(), None);
+    /// ```
+    pub fn preserve_source_text(mut self, enabled: bool) -> Self {
+        self.preserve_source_text = enabled;
+        self
+    }
+
+    /// If `enabled` is true, an identifier is only parsed as a function call if it is followed by an opening parenthesis, like in `f(5)`.
+    /// An identifier followed by another value, like in `f 5` or `a b`, is then rejected with `EvalexprError::AppendedToLeafNode`,
+    /// just like two juxtaposed literals such as `1 2`.
+    ///
+    /// This is disabled by default, such that `f 5` calls `f` with the argument `5`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().require_parentheses_for_calls(true);
+    /// let tree = build_operator_tree_with_options("max(1, 5)", &options).unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.eval_with_context(&HashMapContext::new()), Ok(Value::from(5)));
+    /// assert_eq!(
+    ///     build_operator_tree_with_options("max (1, 5) + len \"a\"", &options),
+    ///     Err(EvalexprError::AppendedToLeafNode)
+    /// );
+    /// assert!(build_operator_tree("max (1, 5) + len \"a\"").is_ok());
+    /// ```
+    pub fn require_parentheses_for_calls(mut self, enabled: bool) -> Self {
+        self.require_parentheses_for_calls = enabled;
+        self
+    }
+
+    /// Remaps the given operator symbol to the given operator, such that e.g. `=` can mean equality and `:=` assignment.
+    /// The symbol may be a default symbol, whose default meaning is replaced, or a new combination of operator characters like `:=`.
+    /// Expressions can still use the default symbols of the operator, unless these are remapped as well.
+    ///
+    /// The symbol is remapped in the tokenizer, wherever the expression contains the default tokens of the symbol without whitespace between them.
+    /// If multiple remapped symbols match at the same position, the longest one is used.
+    ///
+    /// Like their default symbols, a symbol remapped to `Operator::Sub` also stands for the negation,
+    /// and a symbol remapped to `Operator::Div` stands for the float division if `ParseOptions::float_division` is enabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EvalexprError::InvalidOperatorRemap` if the symbol contains whitespace or characters other than operator characters and `:` or `?`,
+    /// if the operator has no symbol of its own, like `Operator::FunctionIdentifier`,
+    /// or if the symbol is already remapped to another operator.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new()
+    ///     .remap_operator("=", Operator::Eq)
+    ///     .and_then(|options| options.remap_operator(":=", Operator::Assign))
+    ///     .unwrap(); // Do proper error handling here
+    /// let mut context = HashMapContext::new();
+    /// let tree = build_operator_tree_with_options("a := 3; a = 3", &options).unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.eval_with_context_mut(&mut context), Ok(Value::from(true)));
+    /// ```
+    pub fn remap_operator(mut self, symbol: &str, operator: Operator) -> EvalexprResult<Self> {
+        let invalid = |reason: String| EvalexprError::invalid_operator_remap(symbol, reason);
+        let (tokens, ranges) = token::tokenize_with_ranges(symbol, &ParseOptions::default())
+            .map_err(|_| invalid("it is not made of operator characters".to_string()))?;
+        if tokens.is_empty() || tokens.iter().any(Token::is_value_or_brace) {
+            return Err(invalid("it is not made of operator characters".to_string()));
+        }
+        let is_contiguous = ranges.first().map(|range| range.start) == Some(0)
+            && ranges.last().map(|range| range.end) == Some(symbol.len())
+            && ranges.windows(2).all(|pair| pair[0].end == pair[1].start);
+        if !is_contiguous {
+            return Err(invalid("it contains whitespace or comments".to_string()));
+        }
+        let token = Token::from_operator(&operator).ok_or_else(|| {
+            invalid(format!(
+                "the operator {:?} has no symbol of its own",
+                operator
+            ))
+        })?;
+
+        match self
+            .operator_remaps
+            .iter()
+            .find(|remap| remap.tokens == tokens)
+        {
+            Some(remap) if remap.operator == operator => {},
+            Some(remap) => {
+                return Err(invalid(format!(
+                    "it is already remapped to the operator {:?}",
+                    remap.operator
+                )))
+            },
+            None => self.operator_remaps.push(OperatorRemap {
+                symbol: symbol.to_string(),
+                operator,
+                tokens,
+                token,
+            }),
+        }
+        Ok(self)
+    }
+
+    pub(crate) fn has_keyword_logical_operators(&self) -> bool {
+        self.keyword_logical_operators
+    }
+
+    pub(crate) fn has_float_division(&self) -> bool {
+        self.float_division
+    }
+
+    pub(crate) fn has_three_valued_logic(&self) -> bool {
+        self.three_valued_logic
+    }
+
+    pub(crate) fn has_percentage_of(&self) -> bool {
+        self.percentage_of
+    }
+
+    pub(crate) fn has_record_syntax(&self) -> bool {
+        self.record_syntax
+    }
+
+    pub(crate) fn has_preserved_source_text(&self) -> bool {
+        self.preserve_source_text
+    }
+
+    pub(crate) fn has_required_parentheses_for_calls(&self) -> bool {
+        self.require_parentheses_for_calls
+    }
+
+    pub(crate) fn get_operator_remaps(&self) -> &[OperatorRemap] {
+        &self.operator_remaps
+    }
+
+    pub(crate) fn get_max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    pub(crate) fn get_max_tokens(&self) -> Option<usize> {
+        self.max_tokens
+    }
+
+    /// Checks the given amount of tuple elements against the maximum tuple size, if there is one.
+    pub(crate) fn check_tuple_size(&self, size: usize) -> EvalexprResult<()> {
+        match self.max_tuple_size {
+            Some(max_tuple_size) if size > max_tuple_size => {
+                Err(EvalexprError::tuple_too_large(max_tuple_size, size))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the given operator, which spans the given byte range of the expression, with the operator filter, if there is one.
+    pub(crate) fn check_operator(
+        &self,
+        operator: &Operator,
+        span: Range<usize>,
+    ) -> EvalexprResult<()> {
+        match &self.allowed_operators {
+            Some(filter) if !filter.allows(operator) => {
+                Err(EvalexprError::operator_disabled(operator.clone(), span))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the given identifier from the identifier interner, if there is one, or allocates it otherwise.
+    pub(crate) fn intern_identifier(&self, identifier: &str) -> Arc<str> {
+        match &self.identifier_interner {
+            Some(interner) => interner.intern(identifier),
+            None => identifier.into(),
+        }
+    }
+
+    /// Checks the given identifier, which spans the given byte range of the expression, with the identifier validator, if there is one.
+    pub(crate) fn validate_identifier(
+        &self,
+        identifier: &str,
+        span: Range<usize>,
+    ) -> EvalexprResult<()> {
+        if let Some(validator) = &self.identifier_validator {
+            validator(identifier).map_err(|reason| {
+                EvalexprError::invalid_identifier(identifier.to_string(), reason, span)
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Debug for ParseOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("ParseOptions")
+            .field("keyword_logical_operators", &self.keyword_logical_operators)
+            .field("float_division", &self.float_division)
+            .field("three_valued_logic", &self.three_valued_logic)
+            .field("percentage_of", &self.percentage_of)
+            .field("record_syntax", &self.record_syntax)
+            .field(
+                "identifier_validator",
+                &self.identifier_validator.as_ref().map(|_| "[...]"),
+            )
+            .field("max_length", &self.max_length)
+            .field("max_tokens", &self.max_tokens)
+            .field("allowed_operators", &self.allowed_operators)
+            .field("max_tuple_size", &self.max_tuple_size)
+            .field("identifier_interner", &self.identifier_interner)
+            .field("preserve_source_text", &self.preserve_source_text)
+            .field(
+                "require_parentheses_for_calls",
+                &self.require_parentheses_for_calls,
+            )
+            .field(
+                "operator_remaps",
+                &self
+                    .operator_remaps
+                    .iter()
+                    .map(|remap| (&remap.symbol, &remap.operator))
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// A filter that decides which operators may appear in expressions, see `ParseOptions::allowed_operators`.
+///
+/// Operators can be excluded by their category, or one by one.
+/// Root nodes, constants and variable reads are always allowed.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let options = ParseOptions::new().allowed_operators(
+///     OperatorFilter::all()
+///         .exclude_category(OperatorCategory::FunctionCall)
+///         .exclude_category(OperatorCategory::Tuple),
+/// );
+/// assert!(build_operator_tree_with_options("a + b * 2", &options).is_ok());
+/// assert!(build_operator_tree_with_options("max(a, b)", &options).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorFilter {
+    excluded_categories: Vec<OperatorCategory>,
+    excluded_operators: Vec<Operator>,
+}
+
+impl OperatorFilter {
+    /// Constructs a filter that allows all operators.
+    pub fn all() -> Self {
+        Self {
+            excluded_categories: Vec::new(),
+            excluded_operators: Vec::new(),
+        }
+    }
+
+    /// Constructs a filter that only allows the operators of the given categories.
+    pub fn only(categories: &[OperatorCategory]) -> Self {
+        Self {
+            excluded_categories: OperatorCategory::ALL
+                .iter()
+                .copied()
+                .filter(|category| !categories.contains(category))
+                .collect(),
+            excluded_operators: Vec::new(),
+        }
+    }
+
+    /// Constructs a filter that only allows arithmetic operators.
+    pub fn arithmetic_only() -> Self {
+        Self::only(&[OperatorCategory::Arithmetic])
+    }
+
+    /// Constructs a filter that only allows comparison and logical operators.
+    pub fn comparison_and_logic_only() -> Self {
+        Self::only(&[OperatorCategory::Comparison, OperatorCategory::Logic])
+    }
+
+    /// Excludes all operators of the given category.
+    pub fn exclude_category(mut self, category: OperatorCategory) -> Self {
+        self.excluded_categories.push(category);
+        self
+    }
+
+    /// Excludes the given operator.
+    /// Operators with fields, like `Operator::FunctionIdentifier`, are excluded independent of the values of their fields.
+    ///
+    /// Compound assignments are separate operators, so excluding `Operator::Exp` does not exclude `Operator::ExpAssign`.
+    pub fn exclude_operator(mut self, operator: Operator) -> Self {
+        self.excluded_operators.push(operator);
+        self
+    }
+
+    /// Returns true if this filter allows the given operator.
+    pub fn allows(&self, operator: &Operator) -> bool {
+        let is_excluded_category = operator
+            .category()
+            .is_some_and(|category| self.excluded_categories.contains(&category));
+        let is_excluded_operator = self
+            .excluded_operators
+            .iter()
+            .any(|excluded| mem::discriminant(excluded) == mem::discriminant(operator));
+        !is_excluded_category && !is_excluded_operator
+    }
+}
+
+/// A pool of identifiers that operator trees built with the same interner share, see `ParseOptions::identifier_interner`.
+///
+/// Clones of an interner share the same pool, and it can be used from multiple threads.
+/// Identifiers are never removed from the pool, so it grows with the amount of distinct identifiers.
+#[derive(Debug, Clone, Default)]
+pub struct IdentifierInterner {
+    identifiers: Arc<Mutex<HashSet<Arc<str>>>>,
+}
+
+impl IdentifierInterner {
+    /// Constructs an empty interner.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the pooled copy of the given identifier, adding it to the pool if it is not in there yet.
+    pub fn intern(&self, identifier: &str) -> Arc<str> {
+        let mut identifiers = self.lock();
+        if let Some(interned) = identifiers.get(identifier) {
+            interned.clone()
+        } else {
+            let interned: Arc<str> = identifier.into();
+            identifiers.insert(interned.clone());
+            interned
+        }
+    }
+
+    /// Returns the amount of distinct identifiers in the pool.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns true if the pool contains no identifiers.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashSet<Arc<str>>> {
+        // The pool stays consistent even if a thread panicked while holding the lock, as insertions are atomic.
+        self.identifiers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// An identifier validator that accepts lower-case ASCII snake_case identifiers, i.e. identifiers matching `[a-z][a-z0-9_]*`.
+/// Namespaced identifiers like `math::sin` are accepted if each of their segments is snake_case.
+///
+/// Use it with `ParseOptions::identifier_validator`.
+pub fn snake_case_identifier_validator(identifier: &str) -> Result<(), String> {
+    let is_snake_case = |segment: &str| {
+        let mut chars = segment.chars();
+        matches!(chars.next(), Some('a'..='z'))
+            && chars.all(|c| matches!(c, 'a'..='z' | '0'..='9' | '_'))
+    };
+
+    if identifier.split("::").all(is_snake_case) {
+        Ok(())
+    } else {
+        Err("identifiers must be lower-case snake_case".to_string())
+    }
+}
+
+/// Constructs an identifier validator that only accepts the given identifiers.
+///
+/// Use it with `ParseOptions::identifier_validator`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let validator = known_identifier_validator(["a", "b"].iter().map(|s| s.to_string()).collect());
+/// let options = ParseOptions::new().identifier_validator(validator);
+/// assert!(build_operator_tree_with_options("a + b", &options).is_ok());
+/// assert!(build_operator_tree_with_options("a + c", &options).is_err());
+/// ```
+pub fn known_identifier_validator(
+    identifiers: HashSet<String>,
+) -> impl Fn(&str) -> Result<(), String> + Send + Sync + 'static {
+    move |identifier| {
+        if identifiers.contains(identifier) {
+            Ok(())
+        } else {
+            Err("unknown identifier".to_string())
+        }
+    }
+}
@@ -12,6 +12,7 @@ impl fmt::Display for Token {
             Slash => write!(f, "/"),
             Percent => write!(f, "%"),
             Hat => write!(f, "^"),
+            PercentOf => write!(f, "% of"),
 
             // Logic
             Eq => write!(f, "=="),
@@ -20,14 +21,23 @@ impl fmt::Display for Token {
             Lt => write!(f, "<"),
             Geq => write!(f, ">="),
             Leq => write!(f, "<="),
+            Cmp => write!(f, "<=>"),
             And => write!(f, "&&"),
             Or => write!(f, "||"),
             Not => write!(f, "!"),
 
+            // Conditional
+            Question => write!(f, "?"),
+
             // Precedence
             LBrace => write!(f, "("),
             RBrace => write!(f, ")"),
 
+            // Maps
+            LCurlyBrace => write!(f, "{{"),
+            RCurlyBrace => write!(f, "}}"),
+            Colon => write!(f, ":"),
+
             // Assignment
             Assign => write!(f, "="),
             PlusAssign => write!(f, "+="),
@@ -1,6 +1,9 @@
+use std::ops::Range;
+
 use crate::{
     error::{EvalexprError, EvalexprResult},
-    value::{FloatType, IntType},
+    value::{FloatType, IntType, Value},
+    Operator, ParseOptions,
 };
 
 mod display;
@@ -14,6 +17,7 @@ pub enum Token {
     Slash,
     Percent,
     Hat,
+    PercentOf,
 
     // Logic
     Eq,
@@ -22,14 +26,23 @@ pub enum Token {
     Lt,
     Geq,
     Leq,
+    Cmp,
     And,
     Or,
     Not,
 
+    // Conditional
+    Question,
+
     // Precedence
     LBrace,
     RBrace,
 
+    // Maps
+    LCurlyBrace,
+    RCurlyBrace,
+    Colon,
+
     // Assignment
     Assign,
     PlusAssign,
@@ -101,6 +114,12 @@ fn char_to_partial_token(c: char) -> PartialToken {
         '(' => PartialToken::Token(Token::LBrace),
         ')' => PartialToken::Token(Token::RBrace),
 
+        '?' => PartialToken::Token(Token::Question),
+
+        '{' => PartialToken::Token(Token::LCurlyBrace),
+        '}' => PartialToken::Token(Token::RCurlyBrace),
+        ':' => PartialToken::Token(Token::Colon),
+
         ',' => PartialToken::Token(Token::Comma),
         ';' => PartialToken::Token(Token::Semicolon),
 
@@ -131,6 +150,7 @@ impl Token {
             Token::Slash => false,
             Token::Percent => false,
             Token::Hat => false,
+            Token::PercentOf => false,
 
             Token::Eq => false,
             Token::Neq => false,
@@ -138,13 +158,20 @@ impl Token {
             Token::Lt => false,
             Token::Geq => false,
             Token::Leq => false,
+            Token::Cmp => false,
             Token::And => false,
             Token::Or => false,
             Token::Not => false,
 
+            Token::Question => false,
+
             Token::LBrace => true,
             Token::RBrace => false,
 
+            Token::LCurlyBrace => true,
+            Token::RCurlyBrace => false,
+            Token::Colon => false,
+
             Token::Comma => false,
             Token::Semicolon => false,
 
@@ -175,6 +202,7 @@ impl Token {
             Token::Slash => false,
             Token::Percent => false,
             Token::Hat => false,
+            Token::PercentOf => false,
 
             Token::Eq => false,
             Token::Neq => false,
@@ -182,13 +210,20 @@ impl Token {
             Token::Lt => false,
             Token::Geq => false,
             Token::Leq => false,
+            Token::Cmp => false,
             Token::And => false,
             Token::Or => false,
             Token::Not => false,
 
+            Token::Question => false,
+
             Token::LBrace => false,
             Token::RBrace => true,
 
+            Token::LCurlyBrace => false,
+            Token::RCurlyBrace => true,
+            Token::Colon => false,
+
             Token::Comma => false,
             Token::Semicolon => false,
 
@@ -210,6 +245,60 @@ impl Token {
         }
     }
 
+    /// Returns the token that stands for the given operator by default, if the operator has a symbol of its own.
+    /// The negation shares its symbol with the subtraction, and the float division and the operators of three-valued logic
+    /// are chosen by the `ParseOptions`, so they have no symbol of their own.
+    pub(crate) fn from_operator(operator: &Operator) -> Option<Token> {
+        Some(match operator {
+            Operator::Add => Token::Plus,
+            Operator::Sub => Token::Minus,
+            Operator::Mul => Token::Star,
+            Operator::Div => Token::Slash,
+            Operator::Mod => Token::Percent,
+            Operator::Exp => Token::Hat,
+            Operator::PercentOf => Token::PercentOf,
+            Operator::Eq => Token::Eq,
+            Operator::Neq => Token::Neq,
+            Operator::Gt => Token::Gt,
+            Operator::Lt => Token::Lt,
+            Operator::Geq => Token::Geq,
+            Operator::Leq => Token::Leq,
+            Operator::Cmp => Token::Cmp,
+            Operator::And => Token::And,
+            Operator::Or => Token::Or,
+            Operator::Not => Token::Not,
+            Operator::Assign => Token::Assign,
+            Operator::AddAssign => Token::PlusAssign,
+            Operator::SubAssign => Token::MinusAssign,
+            Operator::MulAssign => Token::StarAssign,
+            Operator::DivAssign => Token::SlashAssign,
+            Operator::ModAssign => Token::PercentAssign,
+            Operator::ExpAssign => Token::HatAssign,
+            Operator::AndAssign => Token::AndAssign,
+            Operator::OrAssign => Token::OrAssign,
+            Operator::Tuple => Token::Comma,
+            Operator::Chain => Token::Semicolon,
+            _ => return None,
+        })
+    }
+
+    /// Returns true if this token is a value or a brace, which cannot be part of a remapped operator symbol.
+    pub(crate) fn is_value_or_brace(&self) -> bool {
+        use Token::*;
+        matches!(
+            self,
+            Identifier(_)
+                | Float(_)
+                | Int(_)
+                | Boolean(_)
+                | String(_)
+                | LBrace
+                | RBrace
+                | LCurlyBrace
+                | RCurlyBrace
+        )
+    }
+
     #[cfg(not(tarpaulin_include))]
     pub(crate) fn is_assignment(&self) -> bool {
         use Token::*;
@@ -260,10 +349,10 @@ fn parse_string_literal<Iter: Iterator<Item = char>>(
     Err(EvalexprError::UnmatchedDoubleQuote)
 }
 
-fn try_skip_comment(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) -> EvalexprResult<bool> {
+fn try_skip_comment(iter: &mut CharIter<'_>) -> EvalexprResult<bool> {
     let mut matched = false;
     if let Some(lookahead) = iter.peek() {
-        if *lookahead == '/' {
+        if lookahead == '/' {
             matched = true;
             iter.next();
             // line comment
@@ -272,12 +361,12 @@ fn try_skip_comment(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Eval
                     break;
                 }
             }
-        } else if *lookahead == '*' {
+        } else if lookahead == '*' {
             // inline commment
             iter.next();
             while let Some(c) = iter.next() {
                 if let Some(next) = iter.peek() {
-                    if c == '*' && *next == '/' {
+                    if c == '*' && next == '/' {
                         matched = true;
                         iter.next();
                         break;
@@ -285,9 +374,7 @@ fn try_skip_comment(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Eval
                 }
             }
             if !matched {
-                return Err(EvalexprError::CustomMessage(
-                    "unmatched inline comment".into(),
-                ));
+                return Err(EvalexprError::UnmatchedInlineComment);
             }
         }
     }
@@ -295,16 +382,77 @@ fn try_skip_comment(iter: &mut std::iter::Peekable<std::str::Chars<'_>>) -> Eval
     Ok(matched)
 }
 
-/// Converts a string to a vector of partial tokens.
-fn str_to_partial_tokens(string: &str) -> EvalexprResult<Vec<PartialToken>> {
+/// An iterator over the characters of a string that knows the byte offset of the next character.
+struct CharIter<'a> {
+    string: &'a str,
+    offset: usize,
+}
+
+impl<'a> CharIter<'a> {
+    fn new(string: &'a str) -> Self {
+        Self { string, offset: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.string[self.offset..].chars().next()
+    }
+}
+
+impl Iterator for CharIter<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+}
+
+/// The maximum amount of partial tokens that are resolved into a single token, as e.g. for `&&=`.
+const MAX_PARTIAL_TOKENS_PER_TOKEN: usize = 3;
+
+/// Converts a string to a vector of partial tokens, together with the byte range in the string of each partial token.
+///
+/// If `max_tokens` is given, conversion stops with an error as soon as there are too many partial tokens to be resolved into at most `max_tokens` tokens.
+/// Consecutive whitespace is collapsed into a single partial token, such that the length of the result is bounded by the limit, and not by the length of the string.
+fn str_to_partial_tokens(
+    string: &str,
+    max_tokens: Option<usize>,
+) -> EvalexprResult<(Vec<PartialToken>, Vec<Range<usize>>)> {
     let mut result = Vec::new();
-    let mut iter = string.chars().peekable();
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut iter = CharIter::new(string);
+    let mut non_whitespace_count = 0;
+
+    loop {
+        let start = iter.offset;
+        let c = if let Some(c) = iter.next() {
+            c
+        } else {
+            break;
+        };
+        if let Some(max_tokens) = max_tokens {
+            if non_whitespace_count > max_tokens.saturating_mul(MAX_PARTIAL_TOKENS_PER_TOKEN) {
+                return Err(EvalexprError::expression_too_large(
+                    max_tokens,
+                    (non_whitespace_count + MAX_PARTIAL_TOKENS_PER_TOKEN - 1)
+                        / MAX_PARTIAL_TOKENS_PER_TOKEN,
+                ));
+            }
+        }
 
-    while let Some(c) = iter.next() {
         if c == '"' {
             result.push(parse_string_literal(&mut iter)?);
+            ranges.push(start..iter.offset);
+            non_whitespace_count += 1;
         } else {
-            let partial_token = char_to_partial_token(c);
+            // A double colon is part of an identifier like `math::sqrt`, while a single colon separates a key from its value in a map
+            let partial_token = if c == ':' && iter.peek() == Some(':') {
+                iter.next();
+                PartialToken::Literal("::".to_string())
+            } else {
+                char_to_partial_token(c)
+            };
 
             if let PartialToken::Slash = partial_token {
                 if try_skip_comment(&mut iter)? {
@@ -322,24 +470,56 @@ fn str_to_partial_tokens(string: &str) -> EvalexprResult<Vec<PartialToken>> {
                     false
                 };
 
-            if !if_let_successful {
+            let is_repeated_whitespace = partial_token == PartialToken::Whitespace
+                && result.last() == Some(&PartialToken::Whitespace);
+            if !if_let_successful && !is_repeated_whitespace {
+                if partial_token != PartialToken::Whitespace {
+                    non_whitespace_count += 1;
+                }
                 result.push(partial_token);
+                ranges.push(start..iter.offset);
+            } else if let Some(range) = ranges.last_mut() {
+                range.end = iter.offset;
             }
         }
     }
-    Ok(result)
+    Ok((result, ranges))
+}
+
+/// Returns the operator token a keyword stands for, if keyword operators are enabled by the given options.
+fn keyword_to_token(literal: &str, options: &ParseOptions) -> Option<Token> {
+    let keyword_logical_operators = options.has_keyword_logical_operators();
+    match literal {
+        "and" if keyword_logical_operators => Some(Token::And),
+        "or" if keyword_logical_operators => Some(Token::Or),
+        "not" if keyword_logical_operators => Some(Token::Not),
+        "of" if options.has_percentage_of() => Some(Token::Star),
+        _ => None,
+    }
+}
+
+/// Returns true if the given partial token is the keyword `of` and the percentage-of operator is enabled by the given options.
+fn is_of_keyword(partial_token: Option<&PartialToken>, options: &ParseOptions) -> bool {
+    matches!(partial_token, Some(PartialToken::Literal(literal)) if literal == "of")
+        && options.has_percentage_of()
 }
 
 /// Resolves all partial tokens by converting them to complex tokens.
-fn partial_tokens_to_tokens(mut tokens: &[PartialToken]) -> EvalexprResult<Vec<Token>> {
+/// The byte ranges of the partial tokens are merged into the byte ranges of the resulting tokens.
+fn partial_tokens_to_tokens(
+    mut tokens: &[PartialToken],
+    mut ranges: &[Range<usize>],
+    options: &ParseOptions,
+) -> EvalexprResult<(Vec<Token>, Vec<Range<usize>>)> {
     let mut result = Vec::new();
+    let mut result_ranges = Vec::new();
     while !tokens.is_empty() {
         let first = tokens[0].clone();
         let second = tokens.get(1).cloned();
         let third = tokens.get(2).cloned();
         let mut cutoff = 2;
 
-        result.extend(match first {
+        let token = match first {
             PartialToken::Token(token) => {
                 cutoff = 1;
                 Some(token)
@@ -374,6 +554,11 @@ fn partial_tokens_to_tokens(mut tokens: &[PartialToken]) -> EvalexprResult<Vec<T
             },
             PartialToken::Percent => match second {
                 Some(PartialToken::Eq) => Some(Token::PercentAssign),
+                _ if is_of_keyword(second.as_ref(), options) => Some(Token::PercentOf),
+                Some(PartialToken::Whitespace) if is_of_keyword(third.as_ref(), options) => {
+                    cutoff = 3;
+                    Some(Token::PercentOf)
+                },
                 _ => {
                     cutoff = 1;
                     Some(Token::Percent)
@@ -388,7 +573,9 @@ fn partial_tokens_to_tokens(mut tokens: &[PartialToken]) -> EvalexprResult<Vec<T
             },
             PartialToken::Literal(literal) => {
                 cutoff = 1;
-                if let Ok(number) = parse_dec_or_hex(&literal) {
+                if let Some(token) = keyword_to_token(&literal, options) {
+                    Some(token)
+                } else if let Ok(number) = parse_dec_or_hex(&literal) {
                     Some(Token::Int(number))
                 } else if let Ok(number) = literal.parse::<FloatType>() {
                     Some(Token::Float(number))
@@ -442,7 +629,13 @@ fn partial_tokens_to_tokens(mut tokens: &[PartialToken]) -> EvalexprResult<Vec<T
                 },
             },
             PartialToken::Lt => match second {
-                Some(PartialToken::Eq) => Some(Token::Leq),
+                Some(PartialToken::Eq) => match third {
+                    Some(PartialToken::Gt) => {
+                        cutoff = 3;
+                        Some(Token::Cmp)
+                    },
+                    _ => Some(Token::Leq),
+                },
                 _ => {
                     cutoff = 1;
                     Some(Token::Lt)
@@ -468,15 +661,104 @@ fn partial_tokens_to_tokens(mut tokens: &[PartialToken]) -> EvalexprResult<Vec<T
                 },
                 _ => return Err(EvalexprError::unmatched_partial_token(first, second)),
             },
-        });
+        };
 
+        if let Some(token) = token {
+            result.push(token);
+            result_ranges.push(ranges[0].start..ranges[cutoff - 1].end);
+        }
         tokens = &tokens[cutoff..];
+        ranges = &ranges[cutoff..];
     }
-    Ok(result)
+    Ok((result, result_ranges))
 }
 
-pub(crate) fn tokenize(string: &str) -> EvalexprResult<Vec<Token>> {
-    partial_tokens_to_tokens(&str_to_partial_tokens(string)?)
+/// Replaces the tokens of the operator symbols remapped in the given options with the tokens of their operators.
+/// A symbol matches where its tokens follow each other without whitespace between them, preferring longer symbols.
+fn remap_operators(
+    tokens: Vec<Token>,
+    ranges: Vec<Range<usize>>,
+    options: &ParseOptions,
+) -> (Vec<Token>, Vec<Range<usize>>) {
+    let remaps = options.get_operator_remaps();
+    if remaps.is_empty() {
+        return (tokens, ranges);
+    }
+
+    let mut result = Vec::with_capacity(tokens.len());
+    let mut result_ranges = Vec::with_capacity(ranges.len());
+    let mut index = 0;
+    while index < tokens.len() {
+        let remap = remaps
+            .iter()
+            .filter(|remap| {
+                let end = index + remap.tokens.len();
+                tokens[index..].starts_with(&remap.tokens)
+                    && ranges[index..end]
+                        .windows(2)
+                        .all(|pair| pair[0].end == pair[1].start)
+            })
+            .max_by_key(|remap| remap.tokens.len());
+        let cutoff = match remap {
+            Some(remap) => {
+                result.push(remap.token.clone());
+                remap.tokens.len()
+            },
+            None => {
+                result.push(tokens[index].clone());
+                1
+            },
+        };
+        result_ranges.push(ranges[index].start..ranges[index + cutoff - 1].end);
+        index += cutoff;
+    }
+    (result, result_ranges)
+}
+
+pub(crate) fn tokenize(string: &str, options: &ParseOptions) -> EvalexprResult<Vec<Token>> {
+    tokenize_with_ranges(string, options).map(|(tokens, _)| tokens)
+}
+
+/// Like `tokenize`, but also returns the byte range in the string of each token.
+pub(crate) fn tokenize_with_ranges(
+    string: &str,
+    options: &ParseOptions,
+) -> EvalexprResult<(Vec<Token>, Vec<Range<usize>>)> {
+    if let Some(max_length) = options.get_max_length() {
+        if string.len() > max_length {
+            return Err(EvalexprError::expression_too_large(
+                max_length,
+                string.len(),
+            ));
+        }
+    }
+
+    let max_tokens = options.get_max_tokens();
+    let (partial_tokens, ranges) = str_to_partial_tokens(string, max_tokens)?;
+    let (tokens, ranges) = partial_tokens_to_tokens(&partial_tokens, &ranges, options)?;
+    let (tokens, ranges) = remap_operators(tokens, ranges, options);
+    match max_tokens {
+        Some(max_tokens) if tokens.len() > max_tokens => Err(EvalexprError::expression_too_large(
+            max_tokens,
+            tokens.len(),
+        )),
+        _ => Ok((tokens, ranges)),
+    }
+}
+
+/// Returns the value of the given tokens if they consist of a single literal, which may be negated if it is numeric.
+/// Such expressions can be evaluated without building an operator tree.
+pub(crate) fn tokens_to_literal(tokens: &[Token]) -> Option<Value> {
+    match tokens {
+        [Token::Int(int)] => Some(Value::Int(*int)),
+        [Token::Float(float)] => Some(Value::Float(*float)),
+        [Token::Boolean(boolean)] => Some(Value::Boolean(*boolean)),
+        [Token::String(string)] => Some(Value::String(string.clone())),
+        // Negating a literal int cannot overflow, because it is not negative
+        [Token::Minus, Token::Int(int)] => Some(Value::Int(-int)),
+        [Token::Minus, Token::Float(float)] => Some(Value::Float(-float)),
+        _ => None,
+    }
 }
 
 fn parse_dec_or_hex(literal: &str) -> Result<IntType, std::num::ParseIntError> {
@@ -489,13 +771,17 @@ fn parse_dec_or_hex(literal: &str) -> Result<IntType, std::num::ParseIntError> {
 
 #[cfg(test)]
 mod tests {
-    use crate::token::{char_to_partial_token, tokenize, Token};
+    use crate::{
+        token::{char_to_partial_token, tokenize, tokenize_with_ranges, tokens_to_literal, Token},
+        ParseOptions, Value,
+    };
     use std::fmt::Write;
 
     #[test]
     fn test_partial_token_display() {
         let chars = vec![
-            '+', '-', '*', '/', '%', '^', '(', ')', ',', ';', '=', '!', '>', '<', '&', '|', ' ',
+            '+', '-', '*', '/', '%', '^', '(', ')', '{', '}', ':', '?', ',', ';', '=', '!', '>',
+            '<', '&', '|', ' ',
         ];
 
         for char in chars {
@@ -510,7 +796,7 @@ mod tests {
     fn test_token_display() {
         let token_string =
             "+ - * / % ^ == != > < >= <= && || ! ( ) = += -= *= /= %= ^= &&= ||= , ; ";
-        let tokens = tokenize(token_string).unwrap();
+        let tokens = tokenize(token_string, &ParseOptions::default()).unwrap();
         let mut result_string = String::new();
 
         for token in tokens {
@@ -532,7 +818,7 @@ mod tests {
             &&= ||= , ; 
             ";
 
-        let tokens = tokenize(token_string_with_comments).unwrap();
+        let tokens = tokenize(token_string_with_comments, &ParseOptions::default()).unwrap();
         let mut result_string = String::new();
 
         for token in tokens {
@@ -544,7 +830,7 @@ mod tests {
 
     #[test]
     fn assignment_lhs_is_identifier() {
-        let tokens = tokenize("a = 1").unwrap();
+        let tokens = tokenize("a = 1", &ParseOptions::default()).unwrap();
         assert_eq!(
             tokens.as_slice(),
             [
@@ -554,4 +840,91 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn colons() {
+        assert_eq!(
+            tokenize("{a: math::abs}", &ParseOptions::default()).unwrap(),
+            [
+                Token::LCurlyBrace,
+                Token::Identifier("a".to_string()),
+                Token::Colon,
+                Token::Identifier("math::abs".to_string()),
+                Token::RCurlyBrace,
+            ]
+        );
+    }
+
+    #[test]
+    fn keyword_logical_operators() {
+        let options = ParseOptions::new().keyword_logical_operators(true);
+        assert_eq!(
+            tokenize("a and b or not c", &options).unwrap(),
+            [
+                Token::Identifier("a".to_string()),
+                Token::And,
+                Token::Identifier("b".to_string()),
+                Token::Or,
+                Token::Not,
+                Token::Identifier("c".to_string()),
+            ]
+        );
+        assert_eq!(
+            tokenize("and", &ParseOptions::default()).unwrap(),
+            [Token::Identifier("and".to_string())]
+        );
+    }
+
+    #[test]
+    fn literal_fast_path() {
+        let literal =
+            |string| tokens_to_literal(&tokenize(string, &ParseOptions::default()).unwrap());
+        assert_eq!(literal("42"), Some(Value::Int(42)));
+        assert_eq!(literal(" -42 "), Some(Value::Int(-42)));
+        assert_eq!(literal("3.5"), Some(Value::Float(3.5)));
+        assert_eq!(literal("-2e-3"), Some(Value::Float(-2e-3)));
+        assert_eq!(literal("0x1f"), Some(Value::Int(31)));
+        assert_eq!(literal("true"), Some(Value::Boolean(true)));
+        assert_eq!(literal("\"abc\""), Some(Value::from("abc")));
+        assert_eq!(literal("-true"), None);
+        assert_eq!(literal("--1"), None);
+        assert_eq!(literal("1 + 2"), None);
+        assert_eq!(literal("a"), None);
+        assert_eq!(literal("(1)"), None);
+    }
+
+    #[test]
+    fn line_breaks_are_whitespace() {
+        let options = ParseOptions::default();
+        let single_line = tokenize("a + f(1, 2) * b", &options).unwrap();
+        for string in [
+            "a +\nf(1, 2) * b",
+            "a\n+ f(1, 2) * b",
+            "a +\r\n  f(\n\t1,\n\t2\n) *\n\n b",
+            "a + // comment\nf(1, 2) /* more\ncomment */ * b",
+        ] {
+            assert_eq!(
+                tokenize(string, &options).unwrap(),
+                single_line,
+                "{:?}",
+                string
+            );
+        }
+        assert_eq!(
+            tokenize("\"a\nb\"", &options),
+            Ok(vec![Token::String("a\nb".to_string())])
+        );
+    }
+
+    #[test]
+    fn token_ranges() {
+        let string = "ab  += \"c\\\"d\" /* comment */ 1e-3 && !x";
+        let (tokens, ranges) = tokenize_with_ranges(string, &ParseOptions::default()).unwrap();
+        let texts: Vec<_> = ranges.iter().map(|range| &string[range.clone()]).collect();
+        assert_eq!(tokens.len(), texts.len());
+        assert_eq!(texts, ["ab", "+=", "\"c\\\"d\"", "1e-3", "&&", "!", "x"]);
+
+        let (_, ranges) = tokenize_with_ranges("ä + ö", &ParseOptions::default()).unwrap();
+        assert_eq!(ranges, [0..2, 3..4, 5..7]);
+    }
 }
@@ -0,0 +1,421 @@
+//! A versioned binary format for operator trees.
+//!
+//! The format starts with the magic bytes `EVXP` and a format version byte.
+//! Then follow the nodes in pre-order, each encoded as its operator tag, the payload of the operator, and the amount of children.
+//! Operators and values are encoded with the stable tags defined in this module, and not with enum discriminants,
+//! such that changes to the `Operator` and `Value` enums do not invalidate stored trees.
+//! Integers and floats are stored in little endian, lengths and child counts as `u32`.
+//! Operator trees and values may be nested at most `MAX_NESTING_DEPTH` levels deep, so decoding untrusted bytes cannot overflow the stack.
+
+use std::convert::TryFrom;
+
+use crate::{
+    error::{EvalexprError, EvalexprResult},
+    operator::Operator,
+    value::Value,
+    Node,
+};
+
+const MAGIC: &[u8; 4] = b"EVXP";
+const FORMAT_VERSION: u8 = 1;
+
+/// The maximum depth of nested nodes and values that is decoded, counting both nodes and the values nested in their constants.
+const MAX_NESTING_DEPTH: usize = 256;
+
+const TAG_ROOT_NODE: u8 = 0;
+const TAG_ADD: u8 = 1;
+const TAG_SUB: u8 = 2;
+const TAG_NEG: u8 = 3;
+const TAG_MUL: u8 = 4;
+const TAG_DIV: u8 = 5;
+const TAG_MOD: u8 = 6;
+const TAG_EXP: u8 = 7;
+const TAG_EQ: u8 = 8;
+const TAG_NEQ: u8 = 9;
+const TAG_GT: u8 = 10;
+const TAG_LT: u8 = 11;
+const TAG_GEQ: u8 = 12;
+const TAG_LEQ: u8 = 13;
+const TAG_AND: u8 = 14;
+const TAG_OR: u8 = 15;
+const TAG_NOT: u8 = 16;
+const TAG_ASSIGN: u8 = 17;
+const TAG_ADD_ASSIGN: u8 = 18;
+const TAG_SUB_ASSIGN: u8 = 19;
+const TAG_MUL_ASSIGN: u8 = 20;
+const TAG_DIV_ASSIGN: u8 = 21;
+const TAG_MOD_ASSIGN: u8 = 22;
+const TAG_EXP_ASSIGN: u8 = 23;
+const TAG_AND_ASSIGN: u8 = 24;
+const TAG_OR_ASSIGN: u8 = 25;
+const TAG_TUPLE: u8 = 26;
+const TAG_CHAIN: u8 = 27;
+const TAG_CONST: u8 = 28;
+const TAG_VARIABLE_IDENTIFIER_WRITE: u8 = 29;
+const TAG_VARIABLE_IDENTIFIER_READ: u8 = 30;
+const TAG_FUNCTION_IDENTIFIER: u8 = 31;
+const TAG_FLOAT_DIV: u8 = 32;
+const TAG_FLOAT_DIV_ASSIGN: u8 = 33;
+const TAG_MAP: u8 = 34;
+const TAG_KLEENE_AND: u8 = 35;
+const TAG_KLEENE_OR: u8 = 36;
+const TAG_KLEENE_NOT: u8 = 37;
+const TAG_PERCENT_OF: u8 = 38;
+const TAG_CONDITIONAL: u8 = 39;
+const TAG_CMP: u8 = 40;
+
+const TAG_VALUE_EMPTY: u8 = 0;
+const TAG_VALUE_INT: u8 = 1;
+const TAG_VALUE_FLOAT: u8 = 2;
+const TAG_VALUE_BOOLEAN: u8 = 3;
+const TAG_VALUE_STRING: u8 = 4;
+const TAG_VALUE_TUPLE: u8 = 5;
+const TAG_VALUE_MAP: u8 = 6;
+
+impl Node {
+    /// Encodes this operator tree in a versioned binary format that stays readable across versions of this crate.
+    /// The tree can be decoded with `Node::from_bytes`.
+    ///
+    /// Returns `EvalexprError::UnencodableValue` if the tree contains a constant that is or contains a custom value or a quantity,
+    /// which cannot be encoded.
+    /// Such constants cannot be written in an expression, but can be part of trees built with e.g. `NodeBuilder::value`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("a = 2 * 3; a + 1").unwrap(); // Do proper error handling here
+    /// let bytes = tree.to_bytes().unwrap(); // Do proper error handling here
+    /// assert_eq!(Node::from_bytes(&bytes), Ok(tree));
+    /// ```
+    pub fn to_bytes(&self) -> EvalexprResult<Vec<u8>> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(FORMAT_VERSION);
+        write_node(&mut bytes, self)?;
+        Ok(bytes)
+    }
+
+    /// Decodes an operator tree encoded with `Node::to_bytes`.
+    ///
+    /// Returns `EvalexprError::UnsupportedFormatVersion` if the bytes were written in a format version this version of the crate cannot read,
+    /// and `EvalexprError::InvalidBinaryFormat` if the bytes are not a valid encoding of an operator tree,
+    /// or if nodes and values are nested more than 256 levels deep.
+    pub fn from_bytes(bytes: &[u8]) -> EvalexprResult<Node> {
+        let mut reader = Reader { bytes };
+        if reader.read_slice(MAGIC.len())? != MAGIC {
+            return Err(EvalexprError::invalid_binary_format("missing magic bytes"));
+        }
+        let version = reader.read_u8()?;
+        if version != FORMAT_VERSION {
+            return Err(EvalexprError::UnsupportedFormatVersion {
+                found: version,
+                supported: FORMAT_VERSION,
+            });
+        }
+
+        let node = reader.read_node(0)?;
+        if reader.bytes.is_empty() {
+            Ok(node)
+        } else {
+            Err(EvalexprError::invalid_binary_format(
+                "trailing bytes after the operator tree",
+            ))
+        }
+    }
+}
+
+fn write_u32(bytes: &mut Vec<u8>, int: usize) {
+    let int = u32::try_from(int).expect("lengths in an operator tree fit into 32 bits");
+    bytes.extend_from_slice(&int.to_le_bytes());
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    write_u32(bytes, string.len());
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+/// Encodes the given value in the binary format, or returns `None` if it contains a custom value or a quantity.
+pub(crate) fn encode_value(value: &Value) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    write_value(&mut bytes, value)?;
+    Some(bytes)
+}
+
+/// Writes the given value, or returns `None` if it contains a custom value or a quantity, which cannot be encoded.
+fn write_value(bytes: &mut Vec<u8>, value: &Value) -> Option<()> {
+    match value {
+        Value::Empty => bytes.push(TAG_VALUE_EMPTY),
+        Value::Int(int) => {
+            bytes.push(TAG_VALUE_INT);
+            bytes.extend_from_slice(&int.to_le_bytes());
+        },
+        Value::Float(float) => {
+            bytes.push(TAG_VALUE_FLOAT);
+            bytes.extend_from_slice(&float.to_bits().to_le_bytes());
+        },
+        Value::Boolean(boolean) => {
+            bytes.push(TAG_VALUE_BOOLEAN);
+            bytes.push(u8::from(*boolean));
+        },
+        Value::String(string) => {
+            bytes.push(TAG_VALUE_STRING);
+            write_string(bytes, string);
+        },
+        Value::Tuple(tuple) => {
+            bytes.push(TAG_VALUE_TUPLE);
+            write_u32(bytes, tuple.len());
+            for value in tuple {
+                write_value(bytes, value)?;
+            }
+        },
+        Value::Map(map) => {
+            bytes.push(TAG_VALUE_MAP);
+            write_u32(bytes, map.len());
+            for (key, value) in map {
+                write_string(bytes, key);
+                write_value(bytes, value)?;
+            }
+        },
+        Value::Custom(_) => return None,
+        #[cfg(feature = "units_support")]
+        Value::Quantity { .. } => return None,
+    }
+    Some(())
+}
+
+fn write_node(bytes: &mut Vec<u8>, node: &Node) -> EvalexprResult<()> {
+    use Operator::*;
+    let tag = match node.operator() {
+        RootNode => TAG_ROOT_NODE,
+        Add => TAG_ADD,
+        Sub => TAG_SUB,
+        Neg => TAG_NEG,
+        Mul => TAG_MUL,
+        Div => TAG_DIV,
+        FloatDiv => TAG_FLOAT_DIV,
+        Mod => TAG_MOD,
+        Exp => TAG_EXP,
+        Eq => TAG_EQ,
+        Neq => TAG_NEQ,
+        Gt => TAG_GT,
+        Lt => TAG_LT,
+        Geq => TAG_GEQ,
+        Leq => TAG_LEQ,
+        Cmp => TAG_CMP,
+        And => TAG_AND,
+        Or => TAG_OR,
+        Not => TAG_NOT,
+        KleeneAnd => TAG_KLEENE_AND,
+        KleeneOr => TAG_KLEENE_OR,
+        KleeneNot => TAG_KLEENE_NOT,
+        Conditional => TAG_CONDITIONAL,
+        PercentOf => TAG_PERCENT_OF,
+        Assign => TAG_ASSIGN,
+        AddAssign => TAG_ADD_ASSIGN,
+        SubAssign => TAG_SUB_ASSIGN,
+        MulAssign => TAG_MUL_ASSIGN,
+        DivAssign => TAG_DIV_ASSIGN,
+        FloatDivAssign => TAG_FLOAT_DIV_ASSIGN,
+        ModAssign => TAG_MOD_ASSIGN,
+        ExpAssign => TAG_EXP_ASSIGN,
+        AndAssign => TAG_AND_ASSIGN,
+        OrAssign => TAG_OR_ASSIGN,
+        Tuple => TAG_TUPLE,
+        Chain => TAG_CHAIN,
+        Map { .. } => TAG_MAP,
+        Const { .. } => TAG_CONST,
+        VariableIdentifierWrite { .. } => TAG_VARIABLE_IDENTIFIER_WRITE,
+        VariableIdentifierRead { .. } => TAG_VARIABLE_IDENTIFIER_READ,
+        FunctionIdentifier { .. } => TAG_FUNCTION_IDENTIFIER,
+    };
+    bytes.push(tag);
+
+    match node.operator() {
+        Const { value } => {
+            write_value(bytes, value)
+                .ok_or_else(|| EvalexprError::unencodable_value(value.clone()))?;
+        },
+        VariableIdentifierWrite { identifier }
+        | VariableIdentifierRead { identifier }
+        | FunctionIdentifier { identifier } => write_string(bytes, identifier),
+        Map { keys } => {
+            write_u32(bytes, keys.len());
+            for key in keys {
+                write_string(bytes, key);
+            }
+        },
+        _ => {},
+    }
+
+    write_u32(bytes, node.children().len());
+    for child in node.children() {
+        write_node(bytes, child)?;
+    }
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn read_slice(&mut self, len: usize) -> EvalexprResult<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(EvalexprError::invalid_binary_format(
+                "unexpected end of input",
+            ));
+        }
+        let (slice, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(slice)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> EvalexprResult<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_slice(N)?);
+        Ok(array)
+    }
+
+    fn read_u8(&mut self) -> EvalexprResult<u8> {
+        Ok(self.read_slice(1)?[0])
+    }
+
+    fn read_len(&mut self) -> EvalexprResult<usize> {
+        let len = u32::from_le_bytes(self.read_array()?);
+        usize::try_from(len).map_err(|_| EvalexprError::invalid_binary_format("length too large"))
+    }
+
+    fn read_string(&mut self) -> EvalexprResult<String> {
+        let len = self.read_len()?;
+        let bytes = self.read_slice(len)?;
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| EvalexprError::invalid_binary_format("string is not valid UTF-8"))
+    }
+
+    /// Fails if a node or value at the given depth would be nested too deeply.
+    fn check_depth(depth: usize) -> EvalexprResult<()> {
+        if depth > MAX_NESTING_DEPTH {
+            Err(EvalexprError::invalid_binary_format("nested too deeply"))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn read_value(&mut self, depth: usize) -> EvalexprResult<Value> {
+        Self::check_depth(depth)?;
+        match self.read_u8()? {
+            TAG_VALUE_EMPTY => Ok(Value::Empty),
+            TAG_VALUE_INT => Ok(Value::Int(i64::from_le_bytes(self.read_array()?))),
+            TAG_VALUE_FLOAT => Ok(Value::Float(f64::from_bits(u64::from_le_bytes(
+                self.read_array()?,
+            )))),
+            TAG_VALUE_BOOLEAN => match self.read_u8()? {
+                0 => Ok(Value::Boolean(false)),
+                1 => Ok(Value::Boolean(true)),
+                _ => Err(EvalexprError::invalid_binary_format("invalid boolean")),
+            },
+            TAG_VALUE_STRING => Ok(Value::String(self.read_string()?)),
+            TAG_VALUE_TUPLE => {
+                let len = self.read_len()?;
+                // Do not trust the length for preallocation, as every value takes at least one byte.
+                let mut tuple = Vec::with_capacity(len.min(self.bytes.len()));
+                for _ in 0..len {
+                    tuple.push(self.read_value(depth + 1)?);
+                }
+                Ok(Value::Tuple(tuple))
+            },
+            TAG_VALUE_MAP => {
+                let len = self.read_len()?;
+                // Do not trust the length for preallocation, as every entry takes at least five bytes.
+                let mut map = Vec::with_capacity(len.min(self.bytes.len()));
+                for _ in 0..len {
+                    map.push((self.read_string()?, self.read_value(depth + 1)?));
+                }
+                Ok(Value::Map(map))
+            },
+            _ => Err(EvalexprError::invalid_binary_format("unknown value tag")),
+        }
+    }
+
+    fn read_node(&mut self, depth: usize) -> EvalexprResult<Node> {
+        Self::check_depth(depth)?;
+        let operator = self.read_operator(depth)?;
+        let child_count = self.read_len()?;
+        // Do not trust the child count for preallocation, as every node takes at least one byte.
+        let mut children = Vec::with_capacity(child_count.min(self.bytes.len()));
+        for _ in 0..child_count {
+            children.push(self.read_node(depth + 1)?);
+        }
+        Ok(Node {
+            operator,
+            children,
+            source: None,
+        })
+    }
+
+    /// Reads the operator of a node at the given depth.
+    /// This is separate from `read_node` to keep the stack frames of the recursion small.
+    fn read_operator(&mut self, depth: usize) -> EvalexprResult<Operator> {
+        use Operator::*;
+        Ok(match self.read_u8()? {
+            TAG_ROOT_NODE => RootNode,
+            TAG_ADD => Add,
+            TAG_SUB => Sub,
+            TAG_NEG => Neg,
+            TAG_MUL => Mul,
+            TAG_DIV => Div,
+            TAG_FLOAT_DIV => FloatDiv,
+            TAG_MOD => Mod,
+            TAG_EXP => Exp,
+            TAG_EQ => Eq,
+            TAG_NEQ => Neq,
+            TAG_GT => Gt,
+            TAG_LT => Lt,
+            TAG_GEQ => Geq,
+            TAG_LEQ => Leq,
+            TAG_CMP => Cmp,
+            TAG_AND => And,
+            TAG_OR => Or,
+            TAG_NOT => Not,
+            TAG_KLEENE_AND => KleeneAnd,
+            TAG_KLEENE_OR => KleeneOr,
+            TAG_KLEENE_NOT => KleeneNot,
+            TAG_CONDITIONAL => Conditional,
+            TAG_PERCENT_OF => PercentOf,
+            TAG_ASSIGN => Assign,
+            TAG_ADD_ASSIGN => AddAssign,
+            TAG_SUB_ASSIGN => SubAssign,
+            TAG_MUL_ASSIGN => MulAssign,
+            TAG_DIV_ASSIGN => DivAssign,
+            TAG_FLOAT_DIV_ASSIGN => FloatDivAssign,
+            TAG_MOD_ASSIGN => ModAssign,
+            TAG_EXP_ASSIGN => ExpAssign,
+            TAG_AND_ASSIGN => AndAssign,
+            TAG_OR_ASSIGN => OrAssign,
+            TAG_TUPLE => Tuple,
+            TAG_CHAIN => Chain,
+            TAG_MAP => {
+                let len = self.read_len()?;
+                let mut keys = Vec::with_capacity(len.min(self.bytes.len()));
+                for _ in 0..len {
+                    keys.push(self.read_string()?);
+                }
+                Map { keys }
+            },
+            TAG_CONST => Const {
+                value: self.read_value(depth + 1)?,
+            },
+            TAG_VARIABLE_IDENTIFIER_WRITE => VariableIdentifierWrite {
+                identifier: self.read_string()?.into(),
+            },
+            TAG_VARIABLE_IDENTIFIER_READ => VariableIdentifierRead {
+                identifier: self.read_string()?.into(),
+            },
+            TAG_FUNCTION_IDENTIFIER => FunctionIdentifier {
+                identifier: self.read_string()?.into(),
+            },
+            _ => return Err(EvalexprError::invalid_binary_format("unknown operator tag")),
+        })
+    }
+}
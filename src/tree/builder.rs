@@ -0,0 +1,232 @@
+use std::ops;
+
+use crate::{
+    error::expect_operator_argument_amount, EvalexprError, EvalexprResult, Node, Operator, Value,
+};
+
+/// Builds operator trees from operators, values and identifiers, without formatting and parsing an expression string.
+///
+/// Each method checks that the operators get as many operands as they require, so the built tree is always complete.
+/// Operands that consist of multiple nodes are enclosed in a root node, like a parenthesized part of an expression.
+/// So the built tree equals the tree parsed from the expression where each such operand is put in parentheses,
+/// and an operand can never be split up by the operators around it.
+/// This makes it safe to compose fragments of untrusted expressions like `a || b`, which would change the meaning of a concatenated string.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// // A fragment entered by a user, which must not change the meaning of the rest of the rule
+/// let fragment = build_operator_tree("country == \"NL\" || true").unwrap(); // Do proper error handling here
+/// let rule = NodeBuilder::all([
+///     NodeBuilder::variable("age").compare(Operator::Geq, Value::Int(18)).unwrap(), // Do proper error handling here
+///     NodeBuilder::from(fragment),
+///     !NodeBuilder::function("len", vec![NodeBuilder::variable("name")])
+///         .compare(Operator::Gt, Value::Int(0))
+///         .unwrap(), // Do proper error handling here
+/// ])
+/// .build();
+///
+/// assert_eq!(
+///     rule,
+///     build_operator_tree("(age >= 18) && (country == \"NL\" || true) && (!((len(name)) > 0))").unwrap()
+/// );
+/// let context = context_map! { "age" => 17, "country" => "DE", "name" => "" }.unwrap(); // Do proper error handling here
+/// assert_eq!(rule.eval_with_context(&context), Ok(Value::from(false)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeBuilder {
+    node: Node,
+}
+
+impl NodeBuilder {
+    /// Builds a constant with the given value.
+    pub fn value<V: Into<Value>>(value: V) -> Self {
+        Self::leaf(Operator::value(value.into()))
+    }
+
+    /// Builds a read of the variable with the given identifier.
+    pub fn variable(identifier: &str) -> Self {
+        Self::leaf(Operator::variable_identifier_read(identifier.into()))
+    }
+
+    /// Builds a call of the function with the given identifier.
+    /// Like in an expression, the function is called with the empty value if there are no arguments,
+    /// with the argument itself if there is one, and with a tuple of the arguments otherwise.
+    pub fn function(identifier: &str, arguments: Vec<NodeBuilder>) -> Self {
+        let argument = match arguments.len() {
+            0 => Node::root_node(),
+            1 => enclosed(arguments.into_iter().next().unwrap().node),
+            _ => enclosed(Self::compound(Operator::Tuple, arguments).node),
+        };
+        Self {
+            node: Node::with_children(
+                Operator::function_identifier(identifier.into()),
+                vec![argument],
+            ),
+        }
+    }
+
+    /// Builds the given operator applied to the given operands.
+    ///
+    /// Tuples and chains require at least two operands, maps as many operands as they have keys,
+    /// and all other operators exactly as many as they take in an expression, e.g. two for `Operator::Add` and one for `Operator::Not`.
+    /// Constants and variables take no operands, but are more easily built with `NodeBuilder::value` and `NodeBuilder::variable`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EvalexprError::WrongOperatorArgumentAmount` if the amount of operands does not fit the operator,
+    /// and `EvalexprError::UnbuildableOperator` for assignments, as these require a variable identifier to write to.
+    pub fn operation(operator: Operator, operands: Vec<NodeBuilder>) -> EvalexprResult<Self> {
+        let expected = match &operator {
+            Operator::Assign
+            | Operator::AddAssign
+            | Operator::SubAssign
+            | Operator::MulAssign
+            | Operator::DivAssign
+            | Operator::FloatDivAssign
+            | Operator::ModAssign
+            | Operator::ExpAssign
+            | Operator::AndAssign
+            | Operator::OrAssign
+            | Operator::VariableIdentifierWrite { .. } => {
+                return Err(EvalexprError::unbuildable_operator(
+                    operator,
+                    "assignments cannot be built",
+                ))
+            },
+            Operator::Tuple | Operator::Chain => operands.len().max(2),
+            Operator::Map { keys } => keys.len(),
+            // Unwrap cannot fail because only tuples, chains and maps have no maximum
+            operator => operator.max_argument_amount().unwrap(),
+        };
+        expect_operator_argument_amount(operands.len(), expected)?;
+        Ok(Self::compound(operator, operands))
+    }
+
+    /// Builds the comparison of this with the other operand, using the given comparison operator like `Operator::Gt`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EvalexprError::UnbuildableOperator` if the operator is not a comparison.
+    pub fn compare<B: Into<NodeBuilder>>(
+        self,
+        operator: Operator,
+        other: B,
+    ) -> EvalexprResult<Self> {
+        if !matches!(
+            operator,
+            Operator::Eq
+                | Operator::Neq
+                | Operator::Gt
+                | Operator::Lt
+                | Operator::Geq
+                | Operator::Leq
+                | Operator::Cmp
+        ) {
+            return Err(EvalexprError::unbuildable_operator(
+                operator,
+                "expected a comparison",
+            ));
+        }
+        Ok(Self::compound(operator, vec![self, other.into()]))
+    }
+
+    /// Builds the conjunction of the given operands with `&&`, which is `true` if there are no operands.
+    pub fn all<I: IntoIterator<Item = NodeBuilder>>(operands: I) -> Self {
+        Self::fold(Operator::And, operands).unwrap_or_else(|| Self::value(true))
+    }
+
+    /// Builds the disjunction of the given operands with `||`, which is `false` if there are no operands.
+    pub fn any<I: IntoIterator<Item = NodeBuilder>>(operands: I) -> Self {
+        Self::fold(Operator::Or, operands).unwrap_or_else(|| Self::value(false))
+    }
+
+    /// Returns the built operator tree, which is enclosed in a root node like a parsed operator tree.
+    pub fn build(self) -> Node {
+        enclosed(self.node)
+    }
+
+    fn leaf(operator: Operator) -> Self {
+        Self {
+            node: Node::new(operator),
+        }
+    }
+
+    /// Builds the given operator with the given operands, without checking their amount.
+    fn compound(operator: Operator, operands: Vec<NodeBuilder>) -> Self {
+        let children = operands
+            .into_iter()
+            .enumerate()
+            .map(|(index, operand)| {
+                // Like the parser, enclose each element of a sequence, each value of a map and the operand between `?` and `:`
+                // in a root node, even if it is a single node
+                let always_enclosed = match operator {
+                    Operator::Tuple | Operator::Chain | Operator::Map { .. } => true,
+                    Operator::Conditional => index == 1,
+                    _ => false,
+                };
+                if always_enclosed {
+                    enclosed(operand.node)
+                } else {
+                    operand.operand()
+                }
+            })
+            .collect();
+        Self {
+            node: Node::with_children(operator, children),
+        }
+    }
+
+    /// Chains the given operands with the given left-to-right binary operator, returning `None` if there are no operands.
+    fn fold<I: IntoIterator<Item = NodeBuilder>>(operator: Operator, operands: I) -> Option<Self> {
+        let mut operands = operands.into_iter();
+        let first = operands.next()?;
+        let mut result = first.operand();
+        for operand in operands {
+            result = Node::with_children(operator.clone(), vec![result, operand.operand()]);
+        }
+        Some(Self { node: result })
+    }
+
+    /// Returns the node of this as an operand, enclosed in a root node if it consists of multiple nodes.
+    fn operand(self) -> Node {
+        if self.node.children().is_empty() {
+            self.node
+        } else {
+            enclosed(self.node)
+        }
+    }
+}
+
+/// Returns the given node enclosed in a root node, unless it is a root node itself.
+fn enclosed(node: Node) -> Node {
+    if node.operator() == &Operator::RootNode {
+        node
+    } else {
+        Node::with_children(Operator::RootNode, vec![node])
+    }
+}
+
+impl ops::Not for NodeBuilder {
+    type Output = NodeBuilder;
+
+    /// Builds the negation of this with `!`.
+    fn not(self) -> Self::Output {
+        Self::compound(Operator::Not, vec![self])
+    }
+}
+
+impl From<Node> for NodeBuilder {
+    /// Uses the given operator tree as operand, e.g. a parsed fragment of an expression.
+    fn from(node: Node) -> Self {
+        Self { node }
+    }
+}
+
+impl From<Value> for NodeBuilder {
+    fn from(value: Value) -> Self {
+        Self::value(value)
+    }
+}
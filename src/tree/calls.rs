@@ -0,0 +1,63 @@
+use std::ops::Range;
+
+use crate::{Node, Operator, Value};
+
+/// A call of a function in an operator tree, as returned by `Node::function_calls`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionCall<'a> {
+    /// The identifier of the called function.
+    pub name: &'a str,
+    /// The amount of arguments the function is called with, or `None` if it depends on the values the argument expression evaluates to.
+    ///
+    /// A function receives a tuple argument as multiple arguments, so the amount is only known if the argument expression
+    /// is empty, a tuple expression, a literal or an operator that never results in a tuple, like a comparison.
+    /// A variable or a function call as single argument, like in `f(t)`, may evaluate to a tuple, and hence has no known amount.
+    pub argument_count: Option<usize>,
+    /// The byte range of the call within the expression, if the tree was built with `ParseOptions::preserve_source_text`.
+    pub span: Option<Range<usize>>,
+}
+
+impl<'a> FunctionCall<'a> {
+    /// Returns the call represented by the given node, if it is a function identifier.
+    pub(crate) fn from_node(node: &'a Node) -> Option<Self> {
+        match node.operator() {
+            Operator::FunctionIdentifier { identifier } => Some(FunctionCall {
+                name: identifier,
+                argument_count: node.children().first().and_then(argument_count),
+                span: node.source.as_ref().map(|source| source.range.clone()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the amount of values the given argument expression is flattened into when passed to a function, if it is statically known.
+fn argument_count(argument: &Node) -> Option<usize> {
+    match argument.operator() {
+        Operator::RootNode => match argument.children() {
+            [] => Some(0),
+            [child] => argument_count(child),
+            _ => None,
+        },
+        Operator::Tuple => Some(argument.children().len()),
+        Operator::Const {
+            value: Value::Tuple(tuple),
+        } => Some(tuple.len()),
+        Operator::Const {
+            value: Value::Empty,
+        } => Some(0),
+        Operator::Const { .. }
+        | Operator::Map { .. }
+        | Operator::Eq
+        | Operator::Neq
+        | Operator::Gt
+        | Operator::Lt
+        | Operator::Geq
+        | Operator::Leq
+        | Operator::Cmp
+        | Operator::And
+        | Operator::Or
+        | Operator::Not => Some(1),
+        _ => None,
+    }
+}
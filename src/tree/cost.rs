@@ -0,0 +1,88 @@
+use crate::{Node, Operator};
+
+/// The cost of reading or writing a variable, which is a lookup in the context.
+const VARIABLE_COST: u64 = 1;
+/// The cost of a comparison or a logical operator.
+const COMPARISON_COST: u64 = 1;
+/// The cost of an arithmetic operator other than exponentiation, or of an assignment.
+const ARITHMETIC_COST: u64 = 2;
+/// The cost of building a map.
+const MAP_COST: u64 = 4;
+/// The cost of an exponentiation, which is computed with floats.
+const EXPONENTIATION_COST: u64 = 8;
+/// The cost of a function call, which needs to look up the function and may do arbitrary work.
+const FUNCTION_CALL_COST: u64 = 16;
+
+/// Returns the estimated cost of evaluating the given operator, excluding the evaluation of its arguments.
+fn operator_cost(operator: &Operator) -> u64 {
+    match operator {
+        Operator::RootNode | Operator::Tuple | Operator::Chain | Operator::Const { .. } => 0,
+        Operator::VariableIdentifierRead { .. } | Operator::VariableIdentifierWrite { .. } => {
+            VARIABLE_COST
+        },
+        Operator::Eq
+        | Operator::Neq
+        | Operator::Gt
+        | Operator::Lt
+        | Operator::Geq
+        | Operator::Leq
+        | Operator::Cmp
+        | Operator::And
+        | Operator::Or
+        | Operator::Not
+        | Operator::KleeneAnd
+        | Operator::KleeneOr
+        | Operator::KleeneNot
+        | Operator::Conditional => COMPARISON_COST,
+        Operator::Add
+        | Operator::Sub
+        | Operator::Neg
+        | Operator::Mul
+        | Operator::Div
+        | Operator::FloatDiv
+        | Operator::Mod
+        | Operator::PercentOf
+        | Operator::Assign
+        | Operator::AddAssign
+        | Operator::SubAssign
+        | Operator::MulAssign
+        | Operator::DivAssign
+        | Operator::FloatDivAssign
+        | Operator::ModAssign
+        | Operator::AndAssign
+        | Operator::OrAssign => ARITHMETIC_COST,
+        Operator::Map { .. } => MAP_COST,
+        Operator::Exp | Operator::ExpAssign => EXPONENTIATION_COST,
+        Operator::FunctionIdentifier { .. } => FUNCTION_CALL_COST,
+    }
+}
+
+impl Node {
+    /// Returns an estimate of the cost of evaluating the operator tree rooted at this node, e.g. to decide whether to evaluate it eagerly.
+    ///
+    /// The estimate is the sum of the weights of all operators in the tree, where
+    ///  * literals and the structural operators `()`, `,` and `;` weigh 0,
+    ///  * reading or writing a variable, comparisons, logical operators and the conditional operator `? :` weigh 1,
+    ///  * other arithmetic operators and assignments weigh 2,
+    ///  * map literals weigh 4,
+    ///  * exponentiation weighs 8,
+    ///  * and function calls weigh 16, regardless of the function.
+    ///
+    /// The estimate is static, so it counts all operators, even those that are skipped during evaluation, like the fallback of `try_or` or the branch of `? :` that is not chosen.
+    /// It saturates at `u64::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// assert_eq!(build_operator_tree("a + 1").unwrap().estimated_cost(), 3);
+    /// assert_eq!(build_operator_tree("max(a, 1)").unwrap().estimated_cost(), 17);
+    /// ```
+    pub fn estimated_cost(&self) -> u64 {
+        std::iter::once(self)
+            .chain(self.iter())
+            .map(|node| operator_cost(node.operator()))
+            .fold(0, u64::saturating_add)
+    }
+}
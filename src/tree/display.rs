@@ -1,12 +1,358 @@
-use crate::Node;
-use std::fmt::{Display, Error, Formatter};
+use crate::{operator::Operator, Node, Value};
+use std::{
+    fmt::{Debug, Display, Error, Formatter, Write},
+    iter,
+};
 
+/// The amount of nodes written by the alternate `Display` mode if no precision is given.
+const DEFAULT_NODE_BUDGET: usize = 100;
+
+/// Writes the operators of the tree in pre-order, separated by spaces.
+///
+/// With the alternate flag `{:#}`, at most as many nodes as the precision are written, or 100 nodes if no precision is given.
+/// The omitted nodes are replaced by an ellipsis with their amount, e.g. `{:#.3}` writes `+ + 1 ... (2 more nodes)` for `1 + 2 + 3`.
+///
+/// The tree is traversed iteratively, so deep trees do not overflow the stack.
 impl Display for Node {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        self.operator.fmt(f)?;
-        for child in self.children() {
-            write!(f, " {}", child)?;
+        let budget = f
+            .alternate()
+            .then(|| f.precision().unwrap_or(DEFAULT_NODE_BUDGET));
+        write_prefix(self, f, budget, |operator, f| write!(f, "{}", operator))
+    }
+}
+
+/// Writes the same as the derived implementation would, but traverses the tree iteratively,
+/// so deep trees do not overflow the stack.
+impl Debug for Node {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        let pretty = f.alternate();
+        let mut stack = vec![DebugItem::Node(self, 0)];
+        while let Some(item) = stack.pop() {
+            match item {
+                DebugItem::Text(text) => f.write_str(text)?,
+                DebugItem::Indent(depth) => write!(f, "{:indent$}", "", indent = 4 * depth)?,
+                DebugItem::Node(node, depth) if pretty => {
+                    let operator = format!("{:#?}", node.operator())
+                        .replace('\n', &format!("\n{:indent$}", "", indent = 4 * (depth + 1)));
+                    write!(
+                        f,
+                        "Node {{\n{:indent$}operator: {},\n{:indent$}children: [",
+                        "",
+                        operator,
+                        "",
+                        indent = 4 * (depth + 1)
+                    )?;
+                    if node.children().is_empty() {
+                        write!(f, "],\n{:indent$}}}", "", indent = 4 * depth)?;
+                        continue;
+                    }
+
+                    f.write_char('\n')?;
+                    stack.extend([
+                        DebugItem::Text("}"),
+                        DebugItem::Indent(depth),
+                        DebugItem::Text("],\n"),
+                        DebugItem::Indent(depth + 1),
+                    ]);
+                    for child in node.children().iter().rev() {
+                        stack.extend([
+                            DebugItem::Text(",\n"),
+                            DebugItem::Node(child, depth + 2),
+                            DebugItem::Indent(depth + 2),
+                        ]);
+                    }
+                },
+                DebugItem::Node(node, _) => {
+                    write!(f, "Node {{ operator: {:?}, children: [", node.operator())?;
+                    stack.push(DebugItem::Text("] }"));
+                    for (index, child) in node.children().iter().enumerate().rev() {
+                        stack.push(DebugItem::Node(child, 0));
+                        if index > 0 {
+                            stack.push(DebugItem::Text(", "));
+                        }
+                    }
+                },
+            }
         }
         Ok(())
     }
 }
+
+/// A pending step of the `Debug` implementation of `Node`.
+enum DebugItem<'a> {
+    Text(&'static str),
+    /// Indentation by four spaces per level.
+    Indent(usize),
+    /// A node with its indentation level, which is only used when pretty-printing.
+    Node(&'a Node, usize),
+}
+
+/// Writes the operators of the tree rooted at the given node in pre-order, separated by spaces.
+/// If a node budget is given, at most that many nodes are written, followed by an ellipsis with the amount of omitted nodes.
+fn write_prefix(
+    node: &Node,
+    f: &mut Formatter,
+    budget: Option<usize>,
+    mut write_operator: impl FnMut(&Operator, &mut Formatter) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut stack = vec![node];
+    let mut written = 0;
+    while let Some(node) = stack.pop() {
+        if written > 0 {
+            f.write_char(' ')?;
+        }
+        if budget == Some(written) {
+            let omitted: usize = iter::once(node)
+                .chain(stack)
+                .map(|node| node.iter().count() + 1)
+                .sum();
+            return write!(f, "... ({} more nodes)", omitted);
+        }
+
+        write_operator(node.operator(), f)?;
+        written += 1;
+        stack.extend(node.children().iter().rev());
+    }
+    Ok(())
+}
+
+impl Node {
+    /// Returns an indented, multi-line representation of the operator tree rooted at this node.
+    ///
+    /// Each node is written on its own line, with its children indented by two spaces beneath it.
+    /// Root nodes with children, as created for the whole expression and for each pair of braces, are omitted,
+    /// and the indentation of their children is not increased.
+    /// A root node without children, i.e. the empty tuple `()`, is written as `()`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("1 + 2 * 3").unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.to_tree_string(), "+\n  1\n  *\n    2\n    3\n");
+    /// ```
+    pub fn to_tree_string(&self) -> String {
+        let mut result = String::new();
+        self.write_tree_string(&mut result, 0);
+        result
+    }
+
+    fn write_tree_string(&self, result: &mut String, depth: usize) {
+        if self.operator == Operator::RootNode {
+            match self.children() {
+                [] => writeln!(result, "{:indent$}()", "", indent = 2 * depth).unwrap(),
+                children => {
+                    for child in children {
+                        child.write_tree_string(result, depth);
+                    }
+                },
+            }
+            return;
+        }
+
+        writeln!(
+            result,
+            "{:indent$}{}",
+            "",
+            self.operator.to_string().trim(),
+            indent = 2 * depth
+        )
+        .unwrap();
+        for child in self.children() {
+            child.write_tree_string(result, depth + 1);
+        }
+    }
+
+    /// Returns the same representation as the `Display` implementation, but with every string literal replaced by `"***"`.
+    /// This allows to log expressions that may contain secrets, keeping their identifiers and structure intact.
+    /// Constants introduced after parsing, e.g. by rewrite rules, are redacted in the same way,
+    /// including string literals within constant tuples and maps.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("api_key == \"sk_live_123\" && retries < 3").unwrap(); // Do proper error handling here
+    /// let redacted = build_operator_tree("api_key == \"***\" && retries < 3").unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.redacted_string(), redacted.to_string());
+    /// ```
+    pub fn redacted_string(&self) -> String {
+        Redacted {
+            node: self,
+            numbers: false,
+        }
+        .to_string()
+    }
+
+    /// Returns the same representation as `Node::redacted_string`, but with every numeric literal replaced by `0` as well.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("pin == 1234 && name == \"admin\"").unwrap(); // Do proper error handling here
+    /// let redacted = build_operator_tree("pin == 0 && name == \"***\"").unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.redacted_string_with_numbers(), redacted.to_string());
+    /// ```
+    pub fn redacted_string_with_numbers(&self) -> String {
+        Redacted {
+            node: self,
+            numbers: true,
+        }
+        .to_string()
+    }
+
+    /// Returns true if this expression contains a string literal, including string literals within constant tuples and maps.
+    /// If it does not, then `Node::redacted_string` returns the same as the `Display` implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// assert!(build_operator_tree("f(1, \"a\")").unwrap().contains_string_literals());
+    /// assert!(!build_operator_tree("f(1, a)").unwrap().contains_string_literals());
+    /// ```
+    pub fn contains_string_literals(&self) -> bool {
+        self.iter().any(|node| match node.operator() {
+            Operator::Const { value } => contains_strings(value),
+            _ => false,
+        })
+    }
+}
+
+/// A node that is displayed with its literals redacted.
+struct Redacted<'a> {
+    node: &'a Node,
+    numbers: bool,
+}
+
+impl Display for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write_prefix(self.node, f, None, |operator, f| match operator {
+            Operator::Const { value } => write!(f, "{}", redact(value, self.numbers)),
+            operator => write!(f, "{}", operator),
+        })
+    }
+}
+
+/// Returns the given value with all strings replaced by `"***"`, and all numbers replaced by `0` if `numbers` is true.
+fn redact(value: &Value, numbers: bool) -> Value {
+    match value {
+        Value::String(_) => Value::String("***".to_string()),
+        Value::Int(_) | Value::Float(_) if numbers => Value::Int(0),
+        Value::Tuple(tuple) => {
+            Value::Tuple(tuple.iter().map(|value| redact(value, numbers)).collect())
+        },
+        Value::Map(map) => Value::Map(
+            map.iter()
+                .map(|(key, value)| (key.clone(), redact(value, numbers)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+fn contains_strings(value: &Value) -> bool {
+    match value {
+        Value::String(_) => true,
+        Value::Tuple(tuple) => tuple.iter().any(contains_strings),
+        Value::Map(map) => map.iter().any(|(_, value)| contains_strings(value)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{build_operator_tree, operator::Operator, Node, Value};
+
+    /// Mirrors `Node` with a derived `Debug` implementation.
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct DerivedNode {
+        operator: Operator,
+        children: Vec<DerivedNode>,
+    }
+
+    impl From<&Node> for DerivedNode {
+        fn from(node: &Node) -> Self {
+            Self {
+                operator: node.operator().clone(),
+                children: node.children().iter().map(DerivedNode::from).collect(),
+            }
+        }
+    }
+
+    /// Builds `1 + 1 + ... + 1` with the given amount of additions, which is as deep as it is long.
+    fn addition_chain(length: usize) -> Node {
+        let one = || {
+            Node::new(Operator::Const {
+                value: Value::Int(1),
+            })
+        };
+        let mut node = one();
+        for _ in 0..length {
+            let mut addition = Node::new(Operator::Add);
+            addition.children = vec![node, one()];
+            node = addition;
+        }
+        node
+    }
+
+    #[test]
+    fn debug_matches_derived_implementation() {
+        for expression in [
+            "",
+            "()",
+            "1 + 2 * 3",
+            "a = (1, \"b\", 2.5); f(a) && !true",
+            "{x: 1, y: (2, 3)}",
+        ] {
+            let node = build_operator_tree(expression).unwrap();
+            let derived = DerivedNode::from(&node);
+            assert_eq!(
+                format!("{:?}", node),
+                format!("{:?}", derived).replace("DerivedNode", "Node")
+            );
+            assert_eq!(
+                format!("{:#?}", node),
+                format!("{:#?}", derived).replace("DerivedNode", "Node")
+            );
+        }
+    }
+
+    #[test]
+    fn alternate_display_truncates() {
+        let node = build_operator_tree("1 + 2 + 3").unwrap();
+        assert_eq!(format!("{}", node), format!("{:#}", node));
+        assert_eq!(format!("{:#.3}", node), " + + ... (3 more nodes)");
+        assert_eq!(format!("{:#.5}", node), " + + 1 2 ... (1 more nodes)");
+        assert_eq!(format!("{:#.6}", node), node.to_string());
+        assert_eq!(format!("{:#.0}", node), "... (6 more nodes)");
+
+        let chain = addition_chain(1000);
+        let truncated = format!("{:#}", chain);
+        assert!(truncated.starts_with(&"+ ".repeat(100)));
+        assert!(truncated.ends_with(" ... (1901 more nodes)"));
+    }
+
+    #[test]
+    fn formatting_deep_trees_does_not_overflow() {
+        let chain = addition_chain(100_000);
+        let display = chain.to_string();
+        assert_eq!(display, "+ ".repeat(100_000) + "1" + &" 1".repeat(100_000));
+        assert_eq!(format!("{:#.2}", chain), "+ + ... (199999 more nodes)");
+
+        let debug = format!("{:?}", chain);
+        assert!(debug.starts_with("Node { operator: Add, children: [Node { operator: Add"));
+        assert!(debug.ends_with("children: [] }] }"));
+        assert!(!format!("{:#?}", addition_chain(1000)).is_empty());
+
+        // Dropping does not recurse either
+        drop(chain);
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{BindingChange, Context, ContextSnapshot, EvalexprResult, Node, Operator, Value};
+
+/// The outcome of `Node::eval_boolean_explained`, telling which clause decided the result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BooleanOutcome {
+    /// The expression evaluated to true.
+    True {
+        /// The first clause that evaluated to true if the expression is a disjunction `c1 || c2 || ...`, and `None` otherwise.
+        succeeding_clause: Option<DecidingClause>,
+    },
+    /// The expression evaluated to false.
+    False {
+        /// The first clause that evaluated to false if the expression is a conjunction `c1 && c2 && ...`,
+        /// or the whole expression if it is neither a conjunction nor a disjunction, and `None` otherwise.
+        failing_clause: Option<DecidingClause>,
+    },
+}
+
+impl BooleanOutcome {
+    /// Returns the boolean value of the expression.
+    pub fn as_bool(&self) -> bool {
+        matches!(self, BooleanOutcome::True { .. })
+    }
+
+    /// Returns the clause that decided the result, if there is one.
+    pub fn deciding_clause(&self) -> Option<&DecidingClause> {
+        match self {
+            BooleanOutcome::True { succeeding_clause } => succeeding_clause.as_ref(),
+            BooleanOutcome::False { failing_clause } => failing_clause.as_ref(),
+        }
+    }
+}
+
+/// A clause of a top-level conjunction or disjunction that decided the result of `Node::eval_boolean_explained`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecidingClause {
+    /// The position of the clause in the conjunction or disjunction, starting from zero.
+    pub index: usize,
+    /// The operator tree of the clause.
+    pub node: Node,
+    /// The variables read by the clause with their values in the context, in order of their first occurrence.
+    /// Variables that are not bound by the context are omitted.
+    pub variables: Vec<(String, Value)>,
+}
+
+impl DecidingClause {
+    fn new<C: Context>(index: usize, node: &Node, context: &C) -> Self {
+        let mut variables: Vec<(String, Value)> = Vec::new();
+        for identifier in node.iter_read_variable_identifiers() {
+            if variables.iter().all(|(known, _)| known != identifier) {
+                if let Some(value) = context.get_value(identifier) {
+                    variables.push((identifier.to_string(), value.clone()));
+                }
+            }
+        }
+
+        Self {
+            index,
+            node: node.clone(),
+            variables,
+        }
+    }
+}
+
+/// The outcome of `Node::eval_explaining_change`, telling how the result changed between two snapshots and which bindings caused it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeExplanation {
+    /// The result of the evaluation with the old snapshot.
+    pub old: EvalexprResult<Value>,
+    /// The result of the evaluation with the new snapshot.
+    pub new: EvalexprResult<Value>,
+    /// The bindings that differ between the snapshots and are read by the expression, sorted by their identifiers.
+    pub changes: Vec<BindingChange>,
+}
+
+impl ChangeExplanation {
+    /// Returns true if the results of the evaluations differ.
+    pub fn is_result_changed(&self) -> bool {
+        self.old != self.new
+    }
+}
+
+/// Returns true if reading the given variable identifier reads the binding with the given name,
+/// either directly or as a field of a map like in `name.key`.
+fn reads_binding(identifier: &str, name: &str) -> bool {
+    identifier
+        .strip_prefix(name)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+impl Node {
+    /// Evaluates the operator tree rooted at this node with the old and the new snapshot of a context,
+    /// explaining which of the bindings that differ between the snapshots the expression reads.
+    ///
+    /// A binding is read if the expression contains a variable read of its identifier or of a field of it, like `name.key`,
+    /// regardless of whether the read is reached during evaluation.
+    /// The snapshots only contain variables, so expressions that call functions other than builtin functions fail.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let mut context = context_map! { "age" => 17, "country" => "DE" }.unwrap(); // Do proper error handling here
+    /// let old = context.snapshot();
+    /// context.set_value("age".into(), 18.into()).unwrap(); // Do proper error handling here
+    /// context.set_value("name".into(), "Ada".into()).unwrap(); // Do proper error handling here
+    ///
+    /// let rule = build_operator_tree("age >= 18").unwrap(); // Do proper error handling here
+    /// let explanation = rule.eval_explaining_change(&old, &context.snapshot());
+    /// assert!(explanation.is_result_changed());
+    /// assert_eq!(
+    ///     explanation.changes,
+    ///     vec![BindingChange { name: "age".into(), old: Some(17.into()), new: Some(18.into()) }]
+    /// );
+    /// ```
+    pub fn eval_explaining_change(
+        &self,
+        old: &ContextSnapshot,
+        new: &ContextSnapshot,
+    ) -> ChangeExplanation {
+        let changes = old
+            .diff(new)
+            .into_iter()
+            .filter(|change| {
+                self.iter_read_variable_identifiers()
+                    .any(|identifier| reads_binding(identifier, &change.name))
+            })
+            .collect();
+
+        ChangeExplanation {
+            old: self.eval_with_context(old),
+            new: self.eval_with_context(new),
+            changes,
+        }
+    }
+
+    /// Evaluates the operator tree rooted at this node into a boolean with the given context, explaining which clause decided the result.
+    ///
+    /// If the expression is a conjunction `c1 && c2 && ...`, the clauses are evaluated in order until one is false,
+    /// which is then returned as failing clause.
+    /// If the expression is a disjunction `c1 || c2 || ...`, the clauses are evaluated in order until one is true,
+    /// which is then returned as succeeding clause.
+    /// Parentheses around the expression or around parts of the chain are ignored, such that `(c1 && c2) && c3` has three clauses.
+    /// An expression that is neither a conjunction nor a disjunction is treated as a conjunction with a single clause.
+    ///
+    /// Unlike `Node::eval_boolean_with_context`, the clauses after the deciding one are not evaluated,
+    /// so errors in them are not reported.
+    /// Fails, if a clause fails or does not evaluate to a boolean.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let rule = build_operator_tree("age >= 18 && country == \"DE\" && score > 600").unwrap(); // Do proper error handling here
+    /// let context = context_map! { "age" => 30, "country" => "FR", "score" => 700 }.unwrap(); // Do proper error handling here
+    ///
+    /// let outcome = rule.eval_boolean_explained(&context).unwrap(); // Do proper error handling here
+    /// assert!(!outcome.as_bool());
+    /// let failing_clause = outcome.deciding_clause().unwrap();
+    /// assert_eq!(failing_clause.index, 1);
+    /// assert_eq!(failing_clause.variables, vec![("country".to_string(), Value::from("FR"))]);
+    /// ```
+    pub fn eval_boolean_explained<C: Context>(
+        &self,
+        context: &C,
+    ) -> EvalexprResult<BooleanOutcome> {
+        let node = self.without_root_nodes();
+        let is_disjunction = *node.operator() == Operator::Or;
+        let operator = if is_disjunction {
+            Operator::Or
+        } else {
+            Operator::And
+        };
+        let mut clauses = Vec::new();
+        node.collect_clauses(&operator, &mut clauses);
+
+        for (index, clause) in clauses.into_iter().enumerate() {
+            if clause.eval_boolean_with_context(context)? == is_disjunction {
+                let clause = Some(DecidingClause::new(index, clause, context));
+                return Ok(if is_disjunction {
+                    BooleanOutcome::True {
+                        succeeding_clause: clause,
+                    }
+                } else {
+                    BooleanOutcome::False {
+                        failing_clause: clause,
+                    }
+                });
+            }
+        }
+
+        Ok(if is_disjunction {
+            BooleanOutcome::False {
+                failing_clause: None,
+            }
+        } else {
+            BooleanOutcome::True {
+                succeeding_clause: None,
+            }
+        })
+    }
+
+    /// Returns the innermost node below the root nodes that wrap a single child, i.e. below redundant parentheses.
+    fn without_root_nodes(&self) -> &Node {
+        match (self.operator(), self.children()) {
+            (Operator::RootNode, [child]) => child.without_root_nodes(),
+            _ => self,
+        }
+    }
+
+    /// Collects the operands of the chain of the given operator rooted at this node.
+    fn collect_clauses<'a>(&'a self, operator: &Operator, clauses: &mut Vec<&'a Node>) {
+        let node = self.without_root_nodes();
+        if node.operator() == operator {
+            for child in node.children() {
+                child.collect_clauses(operator, clauses);
+            }
+        } else {
+            clauses.push(node);
+        }
+    }
+}
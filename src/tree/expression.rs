@@ -0,0 +1,331 @@
+use std::{
+    borrow::Cow,
+    fmt::{Display, Error, Formatter},
+    iter, mem,
+};
+
+use crate::{
+    error::EvalexprResult,
+    function::{self, MacroFunction},
+    operator::Operator,
+    tree::lazy::LazyNode,
+    Context, ContextWithMutableVariables, HashMapContext, Node, Value,
+};
+
+/// An operator tree flattened into a contiguous arena.
+///
+/// The nodes are stored in pre-order, each together with the size of its subtree, such that the children of a node can be found without following pointers.
+/// Cloning an `Expression` clones a single vector instead of a tree of vectors.
+///
+/// An `Expression` is built with `build_expression` or converted from a `Node`.
+/// The recursive `Node` view is available via `Expression::to_node`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let expression = build_expression("1 + alpha").unwrap(); // Do proper error handling here
+/// let context = context_map! { "alpha" => 2 }.unwrap(); // Do proper error handling here
+/// assert_eq!(expression.eval_with_context(&context), Ok(Value::from(3)));
+/// assert_eq!(expression.to_node(), build_operator_tree("1 + alpha").unwrap());
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Expression {
+    nodes: Vec<ExpressionNode>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct ExpressionNode {
+    operator: Operator,
+    /// The amount of nodes in the subtree rooted at this node, including this node.
+    subtree_len: usize,
+}
+
+impl Expression {
+    /// Returns the indices of the children of the node at the given index.
+    fn children(&self, index: usize) -> impl Iterator<Item = usize> + '_ {
+        let end = index + self.nodes[index].subtree_len;
+        iter::successors(
+            Some(index + 1).filter(|&child| child < end),
+            move |&child| Some(child + self.nodes[child].subtree_len).filter(|&next| next < end),
+        )
+    }
+
+    /// Returns the node at the given index as lazy node if it is one, see `Node::lazy_node`.
+    fn lazy_node<C: Context>(&self, index: usize, context: &C) -> Option<LazyNode<'static, usize>> {
+        if let Some((macro_function, arguments)) = self.macro_call(index, context) {
+            return Some(LazyNode::MacroCall(
+                macro_function.clone(),
+                Cow::Owned(arguments),
+            ));
+        }
+        if let Some((expression, fallback)) = self.try_or_arguments(index, context) {
+            return Some(LazyNode::TryOr {
+                expression,
+                fallback,
+            });
+        }
+        self.conditional_operands(index)
+            .map(
+                |(condition, consequent, alternative)| LazyNode::Conditional {
+                    condition,
+                    consequent,
+                    alternative,
+                },
+            )
+    }
+
+    /// Returns the indices of the expression and the fallback if the node at the given index is a call of `try_or` with two arguments that is evaluated lazily.
+    fn try_or_arguments<C: Context>(&self, index: usize, context: &C) -> Option<(usize, usize)> {
+        if !self.nodes[index].operator.is_lazy_try_or(context) {
+            return None;
+        }
+
+        let mut argument = self.children(index).next()?;
+        while self.nodes[argument].operator == Operator::RootNode
+            && self.children(argument).count() == 1
+        {
+            argument += 1;
+        }
+        if self.nodes[argument].operator != Operator::Tuple {
+            return None;
+        }
+        let mut arguments = self.children(argument);
+        match (arguments.next(), arguments.next(), arguments.next()) {
+            (Some(expression), Some(fallback), None) => Some((expression, fallback)),
+            _ => None,
+        }
+    }
+
+    /// Returns the macro function and the operator trees of the unevaluated arguments if the node at the given index is a call of a macro function of the given context,
+    /// see `Node::macro_call`.
+    fn macro_call<'a, C: Context>(
+        &self,
+        index: usize,
+        context: &'a C,
+    ) -> Option<(&'a MacroFunction, Vec<Node>)> {
+        let macro_function = match &self.nodes[index].operator {
+            Operator::FunctionIdentifier { identifier } => {
+                context.get_macro_function(identifier)?
+            },
+            _ => return None,
+        };
+
+        let mut argument = self.children(index).next()?;
+        let is_root = |index: usize| self.nodes[index].operator == Operator::RootNode;
+        while is_root(argument) && self.children(argument).count() == 1 && is_root(argument + 1) {
+            argument += 1;
+        }
+        let arguments = if !is_root(argument) {
+            vec![self.node_at(argument)]
+        } else if self.nodes[argument].subtree_len == 1 {
+            Vec::new()
+        } else if self.children(argument).count() == 1
+            && self.nodes[argument + 1].operator == Operator::Tuple
+        {
+            self.children(argument + 1)
+                .map(|child| self.node_at(child))
+                .collect()
+        } else {
+            vec![self.node_at(argument)]
+        };
+        Some((macro_function, arguments))
+    }
+
+    /// Returns the indices of the condition and the two branches if the node at the given index is a conditional operator `c ? a : b`, which is evaluated lazily.
+    fn conditional_operands(&self, index: usize) -> Option<(usize, usize, usize)> {
+        if self.nodes[index].operator != Operator::Conditional {
+            return None;
+        }
+
+        let mut operands = self.children(index);
+        match (
+            operands.next(),
+            operands.next(),
+            operands.next(),
+            operands.next(),
+        ) {
+            (Some(condition), Some(consequent), Some(alternative), None) => {
+                Some((condition, consequent, alternative))
+            },
+            _ => None,
+        }
+    }
+
+    fn eval_node_with_context<C: Context>(
+        &self,
+        index: usize,
+        context: &C,
+    ) -> EvalexprResult<Value> {
+        if let Some(lazy_node) = self.lazy_node(index, context) {
+            return lazy_node.eval(
+                &mut &*context,
+                |context| *context,
+                |context, operand| self.eval_node_with_context(operand, *context),
+            );
+        }
+
+        let mut arguments = Vec::new();
+        for child in self.children(index) {
+            arguments.push(self.eval_node_with_context(child, context)?);
+        }
+        self.nodes[index].operator.eval_owned(arguments, context)
+    }
+
+    fn eval_node_with_context_mut<C: ContextWithMutableVariables>(
+        &self,
+        index: usize,
+        context: &mut C,
+    ) -> EvalexprResult<Value> {
+        if let Some(lazy_node) = self.lazy_node(index, context) {
+            return lazy_node.eval(
+                context,
+                |context| context,
+                |context, operand| self.eval_node_with_context_mut(operand, context),
+            );
+        }
+
+        let mut arguments = Vec::new();
+        for child in self.children(index) {
+            arguments.push(self.eval_node_with_context_mut(child, context)?);
+        }
+        self.nodes[index]
+            .operator
+            .eval_mut_owned(arguments, context)
+    }
+
+    /// Evaluates the expression with the given context.
+    ///
+    /// Fails, if one of the operators in the expression fails.
+    pub fn eval_with_context<C: Context>(&self, context: &C) -> EvalexprResult<Value> {
+        function::with_evaluation_scope(context.max_evaluation_depth(), || {
+            self.eval_node_with_context(0, context)
+        })
+    }
+
+    /// Evaluates the expression with the given mutable context.
+    ///
+    /// Fails, if one of the operators in the expression fails.
+    pub fn eval_with_context_mut<C: ContextWithMutableVariables>(
+        &self,
+        context: &mut C,
+    ) -> EvalexprResult<Value> {
+        function::with_evaluation_scope(context.max_evaluation_depth(), || {
+            self.eval_node_with_context_mut(0, context)
+        })
+    }
+
+    /// Evaluates the expression.
+    ///
+    /// Fails, if one of the operators in the expression fails.
+    pub fn eval(&self) -> EvalexprResult<Value> {
+        self.eval_with_context_mut(&mut HashMapContext::new())
+    }
+
+    /// Returns an iterator over the operators of this expression in pre-order.
+    /// Unlike `Node::iter`, the iterator includes the root node.
+    pub fn iter_operators(&self) -> impl Iterator<Item = &Operator> {
+        self.nodes.iter().map(|node| &node.operator)
+    }
+
+    /// Returns an iterator over all identifiers in this expression.
+    /// Each occurrence of an identifier is returned separately.
+    ///
+    /// The identifiers are returned in the same order as by `Node::iter_identifiers`.
+    pub fn iter_identifiers(&self) -> impl Iterator<Item = &str> {
+        self.iter_operators().filter_map(|operator| match operator {
+            Operator::VariableIdentifierWrite { identifier }
+            | Operator::VariableIdentifierRead { identifier }
+            | Operator::FunctionIdentifier { identifier } => Some(&**identifier),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over all variable identifiers in this expression.
+    /// Each occurrence of a variable identifier is returned separately.
+    pub fn iter_variable_identifiers(&self) -> impl Iterator<Item = &str> {
+        self.iter_operators().filter_map(|operator| match operator {
+            Operator::VariableIdentifierWrite { identifier }
+            | Operator::VariableIdentifierRead { identifier } => Some(&**identifier),
+            _ => None,
+        })
+    }
+
+    /// Returns an iterator over all function identifiers in this expression.
+    /// Each occurrence of a function identifier is returned separately.
+    pub fn iter_function_identifiers(&self) -> impl Iterator<Item = &str> {
+        self.iter_operators().filter_map(|operator| match operator {
+            Operator::FunctionIdentifier { identifier } => Some(&**identifier),
+            _ => None,
+        })
+    }
+
+    /// Returns the amount of nodes in this expression.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns false, as an expression always contains at least its root node.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn node_at(&self, index: usize) -> Node {
+        Node {
+            operator: self.nodes[index].operator.clone(),
+            children: self
+                .children(index)
+                .map(|child| self.node_at(child))
+                .collect(),
+            source: None,
+        }
+    }
+
+    /// Builds the recursive operator tree that corresponds to this expression.
+    pub fn to_node(&self) -> Node {
+        self.node_at(0)
+    }
+
+    fn push_node(&mut self, mut node: Node) {
+        let index = self.nodes.len();
+        self.nodes.push(ExpressionNode {
+            operator: mem::replace(&mut node.operator, Operator::RootNode),
+            subtree_len: 0,
+        });
+        for child in mem::take(&mut node.children) {
+            self.push_node(child);
+        }
+        self.nodes[index].subtree_len = self.nodes.len() - index;
+    }
+
+    fn fmt_node(&self, index: usize, f: &mut Formatter) -> Result<(), Error> {
+        self.nodes[index].operator.fmt(f)?;
+        for child in self.children(index) {
+            write!(f, " ")?;
+            self.fmt_node(child, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Node> for Expression {
+    fn from(node: Node) -> Self {
+        let mut expression = Expression { nodes: Vec::new() };
+        expression.push_node(node);
+        expression
+    }
+}
+
+impl From<&Expression> for Node {
+    fn from(expression: &Expression) -> Self {
+        expression.to_node()
+    }
+}
+
+/// Displays the expression in the same prefix notation as `Node`.
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        self.fmt_node(0, f)
+    }
+}
@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+
+use crate::{function::MacroFunction, Context, EvalexprResult, Node, Operator, Value};
+
+/// A node that is not evaluated by evaluating all its children and applying its operator to their values,
+/// but that evaluates only some of its operands, or none at all.
+///
+/// Every evaluator of operator trees asks `Node::lazy_node` or `Expression::lazy_node` first if a node is lazy,
+/// and the synchronous evaluators evaluate lazy nodes with `LazyNode::eval`.
+/// So a new kind of lazy node is added here and in the two detection methods, and not in each evaluator.
+///
+/// `N` identifies the operands, which are nodes of an operator tree or indices into an `Expression`.
+pub(crate) enum LazyNode<'a, N> {
+    /// A call of a macro function with the operator trees of its unevaluated arguments, see `MacroFunction`.
+    /// The macro function is cloned from the context, such that the context can be borrowed mutably while the node is evaluated.
+    MacroCall(MacroFunction, Cow<'a, [Node]>),
+    /// A call of the builtin function `try_or`, which evaluates its fallback only if the expression fails.
+    TryOr { expression: N, fallback: N },
+    /// A conditional operator `c ? a : b`, which evaluates only the chosen branch.
+    Conditional {
+        condition: N,
+        consequent: N,
+        alternative: N,
+    },
+}
+
+impl<N> LazyNode<'_, N> {
+    /// Evaluates this node, evaluating its operands with `eval_operand`.
+    ///
+    /// The state is passed on to `eval_operand`, such that it can be a mutable context,
+    /// and macro functions are called with the context that `context` returns for the state.
+    pub(crate) fn eval<S>(
+        self,
+        state: &mut S,
+        context: fn(&S) -> &dyn Context,
+        mut eval_operand: impl FnMut(&mut S, N) -> EvalexprResult<Value>,
+    ) -> EvalexprResult<Value> {
+        match self {
+            LazyNode::MacroCall(macro_function, arguments) => {
+                macro_function.call(&arguments, context(state))
+            },
+            LazyNode::TryOr {
+                expression,
+                fallback,
+            } => eval_operand(state, expression).or_else(|_| eval_operand(state, fallback)),
+            LazyNode::Conditional {
+                condition,
+                consequent,
+                alternative,
+            } => {
+                if eval_operand(state, condition)?.as_boolean()? {
+                    eval_operand(state, consequent)
+                } else {
+                    eval_operand(state, alternative)
+                }
+            },
+        }
+    }
+}
+
+impl Node {
+    /// Returns this node as lazy node if it is one.
+    /// Macro calls take precedence over `try_or`, such that a macro function can be named `try_or`.
+    pub(crate) fn lazy_node<C: Context>(&self, context: &C) -> Option<LazyNode<'_, &Node>> {
+        if let Some((macro_function, arguments)) = self.macro_call(context) {
+            return Some(LazyNode::MacroCall(
+                macro_function.clone(),
+                Cow::Borrowed(arguments),
+            ));
+        }
+        if let Some((expression, fallback)) = self.try_or_arguments(context) {
+            return Some(LazyNode::TryOr {
+                expression,
+                fallback,
+            });
+        }
+        match (self.operator(), self.children()) {
+            (Operator::Conditional, [condition, consequent, alternative]) => {
+                Some(LazyNode::Conditional {
+                    condition,
+                    consequent,
+                    alternative,
+                })
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the expression and the fallback if this node is a call of `try_or` with two arguments that is evaluated lazily.
+    fn try_or_arguments<C: Context>(&self, context: &C) -> Option<(&Node, &Node)> {
+        if !self.operator().is_lazy_try_or(context) {
+            return None;
+        }
+
+        let mut argument = self.children().first()?;
+        while argument.operator() == &Operator::RootNode && argument.children().len() == 1 {
+            argument = &argument.children()[0];
+        }
+        match (argument.operator(), argument.children()) {
+            (Operator::Tuple, [expression, fallback]) => Some((expression, fallback)),
+            _ => None,
+        }
+    }
+
+    /// Returns the macro function and the unevaluated arguments if this node is a call of a macro function of the given context.
+    /// The arguments are split like the argument of a function: none for `f()`, the argument itself for `f(a)` and the elements for `f(a, b)`.
+    /// Each argument within parentheses is enclosed in a root node, like a parsed operator tree.
+    fn macro_call<'a, 'c, C: Context>(
+        &'a self,
+        context: &'c C,
+    ) -> Option<(&'c MacroFunction, &'a [Node])> {
+        let macro_function = match self.operator() {
+            Operator::FunctionIdentifier { identifier } => {
+                context.get_macro_function(identifier)?
+            },
+            _ => return None,
+        };
+
+        let mut argument = self.children().first()?;
+        while let (Operator::RootNode, [child]) = (argument.operator(), argument.children()) {
+            if child.operator() != &Operator::RootNode {
+                break;
+            }
+            argument = child;
+        }
+        let arguments = match (argument.operator(), argument.children()) {
+            (Operator::RootNode, []) => &[],
+            (Operator::RootNode, [tuple]) if tuple.operator() == &Operator::Tuple => {
+                tuple.children()
+            },
+            _ => std::slice::from_ref(argument),
+        };
+        Some((macro_function, arguments))
+    }
+}
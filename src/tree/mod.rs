@@ -1,20 +1,42 @@
 use crate::{
-    token::Token,
+    token::{self, Token},
     value::{TupleType, EMPTY_VALUE},
     Context, ContextWithMutableVariables, EmptyType, FloatType, HashMapContext, IntType,
+    ParseOptions,
 };
 
 use crate::{
-    error::{EvalexprError, EvalexprResult},
+    error::{expect_operator_argument_amount, EvalexprError, EvalexprResult},
+    function,
     operator::*,
     value::Value,
 };
-use std::mem;
+use std::{borrow::Cow, collections::HashMap, mem, ops::Range, sync::Arc};
 
 // Exclude display module from coverage, as it prints not well-defined prefix notation.
 #[cfg(not(tarpaulin_include))]
+pub(crate) mod binary;
+mod builder;
+mod calls;
+mod cost;
 mod display;
+mod explain;
+mod expression;
 mod iter;
+pub(crate) mod lazy;
+mod processed;
+mod rewrite;
+mod search;
+mod stats;
+
+pub use builder::NodeBuilder;
+pub use calls::FunctionCall;
+pub use explain::{BooleanOutcome, ChangeExplanation, DecidingClause};
+pub use expression::Expression;
+pub use processed::ProcessedNode;
+pub use rewrite::{Pattern, Rule};
+pub use search::TypeHints;
+pub use stats::{EvalStats, OperationStats};
 
 /// A node in the operator tree.
 /// The operator tree is created by the crate-level `build_operator_tree` method.
@@ -33,10 +55,38 @@ mod iter;
 /// assert_eq!(node.eval_with_context(&context), Ok(Value::from(3)));
 /// ```
 ///
-#[derive(Debug, PartialEq, Clone)]
+/// Two nodes are equal if their operators and children are equal, regardless of their source text.
+#[derive(Clone)]
 pub struct Node {
     operator: Operator,
     children: Vec<Node>,
+    source: Option<Box<SourceText>>,
+}
+
+/// The part of an expression a node was built from, see `ParseOptions::preserve_source_text`.
+#[derive(Clone)]
+struct SourceText {
+    /// The whole expression, shared by all nodes built from it.
+    expression: Arc<str>,
+    /// The byte range of the part within the expression.
+    range: Range<usize>,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.operator == other.operator && self.children == other.children
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        // The derived drop glue recurses into the children, which would overflow the stack for deep trees,
+        // so the descendants are moved onto a stack and dropped one by one when they have no children anymore
+        let mut descendants = mem::take(&mut self.children);
+        while let Some(mut descendant) = descendants.pop() {
+            descendants.append(&mut descendant.children);
+        }
+    }
 }
 
 impl Node {
@@ -44,6 +94,7 @@ impl Node {
         Self {
             children: Vec::new(),
             operator,
+            source: None,
         }
     }
 
@@ -51,6 +102,41 @@ impl Node {
         Self::new(Operator::RootNode)
     }
 
+    pub(crate) fn with_children(operator: Operator, children: Vec<Node>) -> Self {
+        Self {
+            operator,
+            children,
+            source: None,
+        }
+    }
+
+    /// Checks that each operator in this tree has as many arguments as it requires,
+    /// such that operators with missing operands like in `1 +` are rejected while parsing instead of while evaluating.
+    fn check_argument_amounts(&self) -> EvalexprResult<()> {
+        let expected = match self.operator() {
+            // Root nodes are empty for `()`
+            Operator::RootNode if self.children.is_empty() => None,
+            Operator::Map { keys } => Some(keys.len()),
+            operator => operator.max_argument_amount(),
+        };
+        if let Some(expected) = expected {
+            expect_operator_argument_amount(self.children.len(), expected)?;
+        }
+        self.children
+            .iter()
+            .try_for_each(Node::check_argument_amounts)
+    }
+
+    /// Checks that no tuple in this tree has more elements than the maximum tuple size of the given options.
+    fn check_tuple_sizes(&self, options: &ParseOptions) -> EvalexprResult<()> {
+        if self.operator() == &Operator::Tuple {
+            options.check_tuple_size(self.children.len())?;
+        }
+        self.children
+            .iter()
+            .try_for_each(|child| child.check_tuple_sizes(options))
+    }
+
     /// Returns an iterator over all identifiers in this expression.
     /// Each occurrence of an identifier is returned separately.
     ///
@@ -71,7 +157,7 @@ impl Node {
         self.iter().filter_map(|node| match node.operator() {
             Operator::VariableIdentifierWrite { identifier }
             | Operator::VariableIdentifierRead { identifier }
-            | Operator::FunctionIdentifier { identifier } => Some(identifier.as_str()),
+            | Operator::FunctionIdentifier { identifier } => Some(&**identifier),
             _ => None,
         })
     }
@@ -87,7 +173,7 @@ impl Node {
     /// let mut tree = build_operator_tree("a + b + c * f()").unwrap(); // Do proper error handling here
     ///
     /// for identifier in tree.iter_identifiers_mut() {
-    ///     *identifier = String::from("x");
+    ///     *identifier = "x".into();
     /// }
     ///
     /// let mut iter = tree.iter_identifiers();
@@ -98,7 +184,7 @@ impl Node {
     /// assert_eq!(iter.next(), Some("x"));
     /// assert_eq!(iter.next(), None);
     /// ```
-    pub fn iter_identifiers_mut(&mut self) -> impl Iterator<Item = &mut String> {
+    pub fn iter_identifiers_mut(&mut self) -> impl Iterator<Item = &mut Arc<str>> {
         self.iter_operators_mut()
             .filter_map(|operator| match operator {
                 Operator::VariableIdentifierWrite { identifier }
@@ -126,7 +212,7 @@ impl Node {
     pub fn iter_variable_identifiers(&self) -> impl Iterator<Item = &str> {
         self.iter().filter_map(|node| match node.operator() {
             Operator::VariableIdentifierWrite { identifier }
-            | Operator::VariableIdentifierRead { identifier } => Some(identifier.as_str()),
+            | Operator::VariableIdentifierRead { identifier } => Some(&**identifier),
             _ => None,
         })
     }
@@ -142,7 +228,7 @@ impl Node {
     /// let mut tree = build_operator_tree("a + b + c * f()").unwrap(); // Do proper error handling here
     ///
     /// for identifier in tree.iter_variable_identifiers_mut() {
-    ///     *identifier = String::from("x");
+    ///     *identifier = "x".into();
     /// }
     ///
     /// let mut iter = tree.iter_identifiers();
@@ -153,7 +239,7 @@ impl Node {
     /// assert_eq!(iter.next(), Some("f"));
     /// assert_eq!(iter.next(), None);
     /// ```
-    pub fn iter_variable_identifiers_mut(&mut self) -> impl Iterator<Item = &mut String> {
+    pub fn iter_variable_identifiers_mut(&mut self) -> impl Iterator<Item = &mut Arc<str>> {
         self.iter_operators_mut()
             .filter_map(|operator| match operator {
                 Operator::VariableIdentifierWrite { identifier }
@@ -179,7 +265,7 @@ impl Node {
     /// ```
     pub fn iter_read_variable_identifiers(&self) -> impl Iterator<Item = &str> {
         self.iter().filter_map(|node| match node.operator() {
-            Operator::VariableIdentifierRead { identifier } => Some(identifier.as_str()),
+            Operator::VariableIdentifierRead { identifier } => Some(&**identifier),
             _ => None,
         })
     }
@@ -195,7 +281,7 @@ impl Node {
     /// let mut tree = build_operator_tree("d = a + f(b + c)").unwrap(); // Do proper error handling here
     ///
     /// for identifier in tree.iter_read_variable_identifiers_mut() {
-    ///     *identifier = String::from("x");
+    ///     *identifier = "x".into();
     /// }
     ///
     /// let mut iter = tree.iter_identifiers();
@@ -207,7 +293,7 @@ impl Node {
     /// assert_eq!(iter.next(), Some("x"));
     /// assert_eq!(iter.next(), None);
     /// ```
-    pub fn iter_read_variable_identifiers_mut(&mut self) -> impl Iterator<Item = &mut String> {
+    pub fn iter_read_variable_identifiers_mut(&mut self) -> impl Iterator<Item = &mut Arc<str>> {
         self.iter_operators_mut()
             .filter_map(|operator| match operator {
                 Operator::VariableIdentifierRead { identifier } => Some(identifier),
@@ -230,7 +316,7 @@ impl Node {
     /// ```
     pub fn iter_write_variable_identifiers(&self) -> impl Iterator<Item = &str> {
         self.iter().filter_map(|node| match node.operator() {
-            Operator::VariableIdentifierWrite { identifier } => Some(identifier.as_str()),
+            Operator::VariableIdentifierWrite { identifier } => Some(&**identifier),
             _ => None,
         })
     }
@@ -246,7 +332,7 @@ impl Node {
     /// let mut tree = build_operator_tree("d = a + f(b + c)").unwrap(); // Do proper error handling here
     ///
     /// for identifier in tree.iter_write_variable_identifiers_mut() {
-    ///     *identifier = String::from("x");
+    ///     *identifier = "x".into();
     /// }
     ///
     /// let mut iter = tree.iter_identifiers();
@@ -258,7 +344,7 @@ impl Node {
     /// assert_eq!(iter.next(), Some("c"));
     /// assert_eq!(iter.next(), None);
     /// ```
-    pub fn iter_write_variable_identifiers_mut(&mut self) -> impl Iterator<Item = &mut String> {
+    pub fn iter_write_variable_identifiers_mut(&mut self) -> impl Iterator<Item = &mut Arc<str>> {
         self.iter_operators_mut()
             .filter_map(|operator| match operator {
                 Operator::VariableIdentifierWrite { identifier } => Some(identifier),
@@ -281,7 +367,7 @@ impl Node {
     /// ```
     pub fn iter_function_identifiers(&self) -> impl Iterator<Item = &str> {
         self.iter().filter_map(|node| match node.operator() {
-            Operator::FunctionIdentifier { identifier } => Some(identifier.as_str()),
+            Operator::FunctionIdentifier { identifier } => Some(&**identifier),
             _ => None,
         })
     }
@@ -297,7 +383,7 @@ impl Node {
     /// let mut tree = build_operator_tree("d = a + f(b + c)").unwrap(); // Do proper error handling here
     ///
     /// for identifier in tree.iter_function_identifiers_mut() {
-    ///     *identifier = String::from("x");
+    ///     *identifier = "x".into();
     /// }
     ///
     /// let mut iter = tree.iter_identifiers();
@@ -309,7 +395,7 @@ impl Node {
     /// assert_eq!(iter.next(), Some("c"));
     /// assert_eq!(iter.next(), None);
     /// ```
-    pub fn iter_function_identifiers_mut(&mut self) -> impl Iterator<Item = &mut String> {
+    pub fn iter_function_identifiers_mut(&mut self) -> impl Iterator<Item = &mut Arc<str>> {
         self.iter_operators_mut()
             .filter_map(|operator| match operator {
                 Operator::FunctionIdentifier { identifier } => Some(identifier),
@@ -317,15 +403,183 @@ impl Node {
             })
     }
 
+    /// Returns an iterator over all function calls in this expression, including this node if it is a call, in pre-order.
+    /// Each call is returned separately, also if it is nested in the arguments of another call or in a branch that might not be evaluated.
+    ///
+    /// See `FunctionCall` for when the amount of arguments of a call is known.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().preserve_source_text(true);
+    /// let tree = build_operator_tree_with_options("f(a, g(b)) + h()", &options).unwrap(); // Do proper error handling here
+    /// let calls: Vec<_> = tree
+    ///     .function_calls()
+    ///     .map(|call| (call.name, call.argument_count, call.span))
+    ///     .collect();
+    /// assert_eq!(
+    ///     calls,
+    ///     vec![("f", Some(2), Some(0..10)), ("g", None, Some(5..9)), ("h", Some(0), Some(13..16))]
+    /// );
+    /// ```
+    pub fn function_calls(&self) -> impl Iterator<Item = FunctionCall<'_>> {
+        std::iter::once(self)
+            .chain(self.iter())
+            .filter_map(FunctionCall::from_node)
+    }
+
+    /// Returns an iterator over all literal values in this expression, in the order in which they appear.
+    /// Each occurrence of a literal is returned separately.
+    ///
+    /// Negative numbers are not literals, but negations of literals, so only their absolute value is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("f(2, \"a\") + -3.5").unwrap(); // Do proper error handling here
+    /// let mut iter = tree.iter_literals();
+    /// assert_eq!(iter.next(), Some(&Value::from(2)));
+    /// assert_eq!(iter.next(), Some(&Value::from("a")));
+    /// assert_eq!(iter.next(), Some(&Value::from(3.5)));
+    /// assert_eq!(iter.next(), None);
+    /// ```
+    pub fn iter_literals(&self) -> impl Iterator<Item = &Value> {
+        self.iter().filter_map(|node| match node.operator() {
+            Operator::Const { value } => Some(value),
+            _ => None,
+        })
+    }
+
+    /// Returns all literal values in this expression, in the order in which they appear.
+    /// See `Node::iter_literals`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("2 * x + 3.5").unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.literals(), vec![Value::from(2), Value::from(3.5)]);
+    /// ```
+    pub fn literals(&self) -> Vec<Value> {
+        self.iter_literals().cloned().collect()
+    }
+
     /// Evaluates the operator tree rooted at this node with the given context.
     ///
     /// Fails, if one of the operators in the expression tree fails.
     pub fn eval_with_context<C: Context>(&self, context: &C) -> EvalexprResult<Value> {
-        let mut arguments = Vec::new();
+        function::with_evaluation_scope(context.max_evaluation_depth(), || {
+            self.eval_node_with_context(context)
+        })
+    }
+
+    /// Evaluates the operator tree rooted at this node with the given context, borrowing the result where possible.
+    ///
+    /// If the expression consists of a single variable, including a field of a map variable like `a.b`, the value is borrowed from the context,
+    /// and if it consists of a single literal, the value is borrowed from this operator tree.
+    /// Otherwise, the expression is evaluated like with `Node::eval_with_context` and the owned result is returned.
+    /// This avoids cloning large values like tuples or strings if they are only read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::borrow::Cow;
+    ///
+    /// let context = context_map! { "name" => "Ada", "a" => 1 }.unwrap(); // Do proper error handling here
+    /// let tree = build_operator_tree("name").unwrap(); // Do proper error handling here
+    /// assert!(matches!(tree.eval_ref(&context), Ok(Cow::Borrowed(Value::String(name))) if name == "Ada"));
+    /// let tree = build_operator_tree("a + 1").unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.eval_ref(&context), Ok(Cow::Owned(Value::from(2))));
+    /// ```
+    pub fn eval_ref<'a, C: Context>(&'a self, context: &'a C) -> EvalexprResult<Cow<'a, Value>> {
+        let mut node = self;
+        while let (Operator::RootNode, [child]) = (node.operator(), node.children()) {
+            node = child;
+        }
+        match node.operator() {
+            Operator::VariableIdentifierRead { identifier } => {
+                read_variable(identifier, context).map(Cow::Borrowed)
+            },
+            Operator::Const { value } => Ok(Cow::Borrowed(value)),
+            _ => self.eval_with_context(context).map(Cow::Owned),
+        }
+    }
+
+    fn eval_node_with_context<C: Context>(&self, context: &C) -> EvalexprResult<Value> {
+        if let Some(lazy_node) = self.lazy_node(context) {
+            return lazy_node.eval(
+                &mut &*context,
+                |context| *context,
+                |context, operand| operand.eval_node_with_context(*context),
+            );
+        }
+
+        // The children are evaluated from left to right and depth first, as guaranteed by the crate documentation.
+        // All other evaluation methods must keep this order, as it is observable through assignments and functions with side effects.
+        let mut arguments = Vec::with_capacity(self.children().len());
         for child in self.children() {
-            arguments.push(child.eval_with_context(context)?);
+            arguments.push(child.eval_node_with_context(context)?);
         }
-        self.operator().eval(&arguments, context)
+        self.operator().eval_owned(arguments, context)
+    }
+
+    /// Evaluates the operator tree rooted at this node with the given context, counting how often each function is called.
+    ///
+    /// Returns the result of the evaluation together with the amount of calls per function identifier.
+    /// Calls are counted as they are made, so if the evaluation fails, the counts include all calls up to and including the failing one.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("max(math::abs(-1), math::abs(2))").unwrap(); // Do proper error handling here
+    /// let (result, call_counts) = tree.eval_profiled(&HashMapContext::new());
+    /// assert_eq!(result, Ok(Value::from(2)));
+    /// assert_eq!(call_counts["math::abs"], 2);
+    /// assert_eq!(call_counts["max"], 1);
+    /// ```
+    pub fn eval_profiled<C: Context>(
+        &self,
+        context: &C,
+    ) -> (EvalexprResult<Value>, HashMap<String, u64>) {
+        let mut call_counts = HashMap::new();
+        let result = function::with_evaluation_scope(context.max_evaluation_depth(), || {
+            self.eval_node_profiled(context, &mut call_counts)
+        });
+        (result, call_counts)
+    }
+
+    fn eval_node_profiled<C: Context>(
+        &self,
+        context: &C,
+        call_counts: &mut HashMap<String, u64>,
+    ) -> EvalexprResult<Value> {
+        if let Some(lazy_node) = self.lazy_node(context) {
+            if let Operator::FunctionIdentifier { identifier } = self.operator() {
+                *call_counts.entry(identifier.to_string()).or_insert(0) += 1;
+            }
+            return lazy_node.eval(
+                &mut &*context,
+                |context| *context,
+                |context, operand| operand.eval_node_profiled(*context, call_counts),
+            );
+        }
+
+        let mut arguments = Vec::with_capacity(self.children().len());
+        for child in self.children() {
+            arguments.push(child.eval_node_profiled(context, call_counts)?);
+        }
+        if let Operator::FunctionIdentifier { identifier } = self.operator() {
+            *call_counts.entry(identifier.to_string()).or_insert(0) += 1;
+        }
+        self.operator().eval_owned(arguments, context)
     }
 
     /// Evaluates the operator tree rooted at this node with the given mutable context.
@@ -335,11 +589,28 @@ impl Node {
         &self,
         context: &mut C,
     ) -> EvalexprResult<Value> {
-        let mut arguments = Vec::new();
+        function::with_evaluation_scope(context.max_evaluation_depth(), || {
+            self.eval_node_with_context_mut(context)
+        })
+    }
+
+    fn eval_node_with_context_mut<C: ContextWithMutableVariables>(
+        &self,
+        context: &mut C,
+    ) -> EvalexprResult<Value> {
+        if let Some(lazy_node) = self.lazy_node(context) {
+            return lazy_node.eval(
+                context,
+                |context| context,
+                |context, operand| operand.eval_node_with_context_mut(context),
+            );
+        }
+
+        let mut arguments = Vec::with_capacity(self.children().len());
         for child in self.children() {
-            arguments.push(child.eval_with_context_mut(context)?);
+            arguments.push(child.eval_node_with_context_mut(context)?);
         }
-        self.operator().eval_mut(&arguments, context)
+        self.operator().eval_mut_owned(arguments, context)
     }
 
     /// Evaluates the operator tree rooted at this node.
@@ -588,6 +859,51 @@ impl Node {
         &self.operator
     }
 
+    /// Returns the part of the expression this node was built from, including the parentheses if it is a parenthesized root node.
+    ///
+    /// This is `None` unless the operator tree was built with `ParseOptions::preserve_source_text`.
+    /// It is also `None` for nodes that were synthesized by a transformation like `Node::rewrite`,
+    /// while nodes that were kept by the transformation keep their source text.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let options = ParseOptions::new().preserve_source_text(true);
+    /// let tree = build_operator_tree_with_options("max(a,  b) > 1", &options).unwrap(); // Do proper error handling here
+    /// let function = &tree.children()[0].children()[0];
+    /// assert_eq!(function.source_text(), Some("max(a,  b)"));
+    /// assert_eq!(function.children()[0].children()[0].source_text(), Some("a,  b"));
+    /// ```
+    pub fn source_text(&self) -> Option<&str> {
+        self.source
+            .as_ref()
+            .map(|source| &source.expression[source.range.clone()])
+    }
+
+    /// Returns the whole expression this node was built from, shared by all nodes of the operator tree.
+    ///
+    /// This is `None` in the same cases as `Node::source_text`.
+    /// If the operator tree was built with `build_operator_tree_arc`, this is the given expression itself and not a copy.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    /// use std::sync::Arc;
+    ///
+    /// let expression: Arc<str> = Arc::from("a + b");
+    /// let options = ParseOptions::new().preserve_source_text(true);
+    /// let tree = build_operator_tree_arc(expression.clone(), &options).unwrap(); // Do proper error handling here
+    /// let b = &tree.children()[0].children()[1];
+    /// assert_eq!(b.source_text(), Some("b"));
+    /// assert!(Arc::ptr_eq(b.source_expression().unwrap(), &expression));
+    /// ```
+    pub fn source_expression(&self) -> Option<&Arc<str>> {
+        self.source.as_ref().map(|source| &source.expression)
+    }
+
     /// Returns a mutable reference to the vector containing the children of this node.
     ///
     /// WARNING: Writing to this might have unexpected results, as some operators require certain amounts and types of arguments.
@@ -602,6 +918,94 @@ impl Node {
         &mut self.operator
     }
 
+    /// Returns true if this operator tree is equal to the other one, up to the order of the operands of commutative operators.
+    ///
+    /// The operators `*`, `&&`, `||`, `==` and `!=` are considered commutative, and their operands are sorted canonically before comparing.
+    /// As string concatenation is not commutative, `+` is only considered commutative if both operands are known to be numbers,
+    /// like numeric constants and the results of `-`, `/`, `%` and `^`.
+    /// All other operators are compared structurally, like with `PartialEq`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let a = build_operator_tree("x == 2 * (a - b)").unwrap(); // Do proper error handling here
+    /// let b = build_operator_tree("(a - b) * 2 == x").unwrap(); // Do proper error handling here
+    /// assert!(a.semantically_eq(&b));
+    /// assert_ne!(a, b);
+    ///
+    /// // `a` and `b` may be strings
+    /// let a = build_operator_tree("a + b").unwrap(); // Do proper error handling here
+    /// let b = build_operator_tree("b + a").unwrap(); // Do proper error handling here
+    /// assert!(!a.semantically_eq(&b));
+    /// ```
+    pub fn semantically_eq(&self, other: &Node) -> bool {
+        self.normalized() == other.normalized()
+    }
+
+    /// Returns a copy of this operator tree with the operands of commutative operators sorted canonically.
+    fn normalized(&self) -> Node {
+        let mut children: Vec<_> = self.children.iter().map(Node::normalized).collect();
+        let is_commutative = match self.operator {
+            Operator::Add => self.is_numeric(),
+            Operator::Mul
+            | Operator::And
+            | Operator::Or
+            | Operator::KleeneAnd
+            | Operator::KleeneOr
+            | Operator::Eq
+            | Operator::Neq => true,
+            _ => false,
+        };
+        if is_commutative {
+            children.sort_by_cached_key(|child| format!("{:?}", child));
+        }
+        Node {
+            operator: self.operator.clone(),
+            children,
+            source: None,
+        }
+    }
+
+    /// Returns true if this operator tree is known to evaluate to a number if it evaluates successfully, without knowing the context.
+    /// Only `+` and `*` of known numbers are known to be numbers, as they also apply to strings or custom values.
+    fn is_numeric(&self) -> bool {
+        match &self.operator {
+            Operator::Const { value } => value.is_number(),
+            Operator::Sub
+            | Operator::Neg
+            | Operator::Div
+            | Operator::FloatDiv
+            | Operator::Mod
+            | Operator::PercentOf
+            | Operator::Exp => true,
+            Operator::RootNode | Operator::Add | Operator::Mul => {
+                !self.children.is_empty() && self.children.iter().all(Node::is_numeric)
+            },
+            _ => false,
+        }
+    }
+
+    /// Appends the given operand between `?` and `:` to the conditional operator on the rightmost path of this tree that is still missing it.
+    /// Returns false if there is no such conditional operator.
+    fn push_conditional_operand(&mut self, operand: Node) -> bool {
+        let mut node = self;
+        loop {
+            if node.operator == Operator::Conditional && node.children.len() < 2 {
+                // An empty operand is left out, such that the conditional operator lacks an argument
+                if !operand.children.is_empty() {
+                    node.children.push(operand);
+                }
+                return true;
+            }
+            match node.children.last_mut() {
+                Some(child) => node = child,
+                None => return false,
+            }
+        }
+    }
+
     fn has_enough_children(&self) -> bool {
         Some(self.children().len()) == self.operator().max_argument_amount()
     }
@@ -765,13 +1169,278 @@ fn collapse_all_sequences(root_stack: &mut Vec<Node>) -> EvalexprResult<()> {
     Ok(())
 }
 
-pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node> {
+/// Parses a map literal whose opening curly brace was just consumed, consuming the tokens up to and including the matching closing curly brace.
+/// Entries are separated by the commas on the top level of the map literal, so tuples need to be enclosed in parentheses to be used as values.
+fn map_literal_to_node<'a>(
+    tokens: &mut impl Iterator<Item = &'a Token>,
+    source: TokenSource,
+    options: &ParseOptions,
+) -> EvalexprResult<Node> {
+    let mut keys = Vec::new();
+    let mut values = Vec::new();
+    let mut entry = Vec::new();
+    let mut entry_start = 0;
+    let mut position = 0;
+    let mut depth = 0usize;
+
+    loop {
+        let token = tokens.next().ok_or(EvalexprError::UnmatchedLCurlyBrace)?;
+        position += 1;
+        match token {
+            Token::LBrace | Token::LCurlyBrace => depth += 1,
+            Token::RBrace | Token::RCurlyBrace if depth > 0 => depth -= 1,
+            Token::Comma | Token::RCurlyBrace if depth == 0 => {
+                let is_end = token == &Token::RCurlyBrace;
+                // The empty map `{}` has no entries
+                if !(is_end && keys.is_empty() && entry.is_empty()) {
+                    let entry_source = source.slice(entry_start..position - 1);
+                    let (key, value) =
+                        map_entry_to_node(&mem::take(&mut entry), entry_source, options)?;
+                    if keys.contains(&key) {
+                        return Err(EvalexprError::InvalidMapEntry);
+                    }
+                    keys.push(key);
+                    values.push(value);
+                }
+                entry_start = position;
+
+                if is_end {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            _ => {},
+        }
+        entry.push(token.clone());
+    }
+
+    Ok(Node {
+        operator: Operator::Map { keys },
+        children: values,
+        source: None,
+    })
+}
+
+/// Returns true if the given tokens that follow the identifier `record` start like `(name = `,
+/// such that they are the arguments of `record(name = value, ...)` instead of a call of a function named `record`.
+fn is_record_syntax<'a>(mut tokens: impl Iterator<Item = &'a Token>) -> bool {
+    matches!(
+        (tokens.next(), tokens.next(), tokens.next()),
+        (
+            Some(Token::LBrace),
+            Some(Token::Identifier(_)),
+            Some(Token::Assign)
+        )
+    )
+}
+
+/// Parses the arguments of `record(name = value, ...)` whose identifier `record` was just consumed,
+/// consuming the tokens up to and including the matching closing brace.
+/// The result is a map constructor, like for the map literal `{name: value, ...}`.
+fn record_to_node<'a>(
+    tokens: &mut impl Iterator<Item = &'a Token>,
+    source: TokenSource,
+    options: &ParseOptions,
+) -> EvalexprResult<Node> {
+    // Skip the opening brace
+    tokens.next();
+    let mut keys = Vec::new();
+    let mut values = Vec::new();
+    let mut field = Vec::new();
+    let mut field_start = 1;
+    let mut position = 1;
+    let mut depth = 0usize;
+
+    loop {
+        let token = tokens.next().ok_or(EvalexprError::UnmatchedLBrace)?;
+        position += 1;
+        match token {
+            Token::LBrace | Token::LCurlyBrace => depth += 1,
+            Token::RBrace | Token::RCurlyBrace if depth > 0 => depth -= 1,
+            Token::Comma | Token::RBrace if depth == 0 => {
+                let is_end = token == &Token::RBrace;
+                // Like other argument lists, the arguments may end with a trailing comma
+                if !(is_end && field.is_empty()) {
+                    let (key, value) = match field.as_slice() {
+                        [Token::Identifier(key), Token::Assign, value @ ..]
+                            if !value.is_empty() =>
+                        {
+                            // The value follows the identifier and the assignment
+                            let value_source = source.slice(field_start + 2..position - 1);
+                            (key.clone(), build_tree(value, value_source, options)?)
+                        },
+                        _ => return Err(EvalexprError::InvalidMapEntry),
+                    };
+                    if keys.contains(&key) {
+                        return Err(EvalexprError::DuplicateRecordField {
+                            name: key,
+                            span: source.span(field_start),
+                        });
+                    }
+                    keys.push(key);
+                    values.push(value);
+                    field.clear();
+                }
+                field_start = position;
+
+                if is_end {
+                    break;
+                } else {
+                    continue;
+                }
+            },
+            _ => {},
+        }
+        field.push(token.clone());
+    }
+
+    Ok(Node {
+        operator: Operator::Map { keys },
+        children: values,
+        source: None,
+    })
+}
+
+/// Parses a map entry of the form `key: value`, where the key is an identifier or a string.
+fn map_entry_to_node(
+    tokens: &[Token],
+    source: TokenSource,
+    options: &ParseOptions,
+) -> EvalexprResult<(String, Node)> {
+    match tokens {
+        [Token::Identifier(key) | Token::String(key), Token::Colon, value @ ..]
+            if !value.is_empty() =>
+        {
+            // The value follows the key and the colon
+            let value_source = source.slice(2..tokens.len());
+            Ok((key.clone(), build_tree(value, value_source, options)?))
+        },
+        _ => Err(EvalexprError::InvalidMapEntry),
+    }
+}
+
+/// The positions of a sequence of tokens in the expression they were read from,
+/// used to locate errors and to preserve the source text of the nodes built from the tokens.
+#[derive(Clone, Copy)]
+struct TokenSource<'a> {
+    /// The expression, if the source text of the nodes is preserved.
+    expression: Option<&'a Arc<str>>,
+    /// The byte range in the expression of each token.
+    ranges: &'a [Range<usize>],
+}
+
+impl<'a> TokenSource<'a> {
+    /// Returns the source of the tokens with the given indices.
+    fn slice(self, tokens: Range<usize>) -> Self {
+        Self {
+            expression: self.expression,
+            ranges: &self.ranges[tokens],
+        }
+    }
+
+    /// Returns the byte range of the token with the given index.
+    fn span(self, token: usize) -> Range<usize> {
+        self.ranges[token].clone()
+    }
+
+    /// Returns the byte range spanning the tokens with the given indices.
+    fn range(self, tokens: Range<usize>) -> Range<usize> {
+        self.ranges[tokens.start].start..self.ranges[tokens.end - 1].end
+    }
+
+    /// Returns the source text spanning the tokens with the given indices, if the source text of the nodes is preserved.
+    fn source_text(self, tokens: Range<usize>) -> Option<Box<SourceText>> {
+        self.expression.map(|expression| {
+            Box::new(SourceText {
+                expression: expression.clone(),
+                range: self.range(tokens),
+            })
+        })
+    }
+}
+
+impl Node {
+    /// Widens the source text of each node such that it spans the source texts of its children,
+    /// and gives nodes without source text the source text spanning their children.
+    /// The nodes built from a single token, e.g. an operator, only have the source text of their token before.
+    fn widen_source_texts(&mut self) {
+        for child in &mut self.children {
+            child.widen_source_texts();
+        }
+
+        for child in &self.children {
+            if let Some(child_source) = &child.source {
+                if let Some(source) = &mut self.source {
+                    source.range.start = source.range.start.min(child_source.range.start);
+                    source.range.end = source.range.end.max(child_source.range.end);
+                } else {
+                    self.source = Some(child_source.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Builds the operator tree of the given tokens.
+/// The ranges are the byte ranges in the expression of each token, which are used to locate errors.
+pub(crate) fn tokens_to_operator_tree(
+    tokens: &[Token],
+    ranges: &[Range<usize>],
+    options: &ParseOptions,
+) -> EvalexprResult<Node> {
+    let source = TokenSource {
+        expression: None,
+        ranges,
+    };
+    build_tree(tokens, source, options)
+}
+
+/// Like `tokens_to_operator_tree`, but each node keeps the part of the given expression it was built from.
+/// The ranges are the byte ranges in the expression of each token.
+pub(crate) fn tokens_to_operator_tree_with_source(
+    tokens: &[Token],
+    ranges: &[Range<usize>],
+    expression: Arc<str>,
+    options: &ParseOptions,
+) -> EvalexprResult<Node> {
+    let source = TokenSource {
+        expression: Some(&expression),
+        ranges,
+    };
+    build_tree(tokens, source, options)
+}
+
+fn build_tree(
+    tokens: &[Token],
+    source: TokenSource,
+    options: &ParseOptions,
+) -> EvalexprResult<Node> {
     let mut root_stack = vec![Node::root_node()];
+    // The length of the root stack with the root node of the operand between `?` and `:`, and the index of the `?`, for each open conditional operator
+    let mut conditionals: Vec<(usize, usize)> = Vec::new();
     let mut last_token_is_rightsided_value = false;
     let mut token_iter = tokens.iter().peekable();
 
     while let Some(token) = token_iter.next().cloned() {
         let next = token_iter.peek().cloned();
+        let index = tokens.len() - token_iter.len() - 1;
+
+        // A single trailing comma after the last element of a tuple or argument list is ignored
+        if token == Token::Comma && matches!(next, None | Some(Token::RBrace | Token::Semicolon)) {
+            if last_token_is_rightsided_value {
+                continue;
+            } else {
+                return Err(EvalexprError::InvalidTrailingComma);
+            }
+        }
+
+        // Only identifiers can be called, so a literal must not be followed by an argument list
+        if next == Some(&Token::LBrace) {
+            if let Some(value) = token::tokens_to_literal(std::slice::from_ref(&token)) {
+                return Err(EvalexprError::not_callable(value));
+            }
+        }
 
         let node = match token.clone() {
             Token::Plus => Some(Node::new(Operator::Add)),
@@ -783,9 +1452,16 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
                 }
             },
             Token::Star => Some(Node::new(Operator::Mul)),
-            Token::Slash => Some(Node::new(Operator::Div)),
+            Token::Slash => {
+                if options.has_float_division() {
+                    Some(Node::new(Operator::FloatDiv))
+                } else {
+                    Some(Node::new(Operator::Div))
+                }
+            },
             Token::Percent => Some(Node::new(Operator::Mod)),
             Token::Hat => Some(Node::new(Operator::Exp)),
+            Token::PercentOf => Some(Node::new(Operator::PercentOf)),
 
             Token::Eq => Some(Node::new(Operator::Eq)),
             Token::Neq => Some(Node::new(Operator::Neq)),
@@ -793,12 +1469,33 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
             Token::Lt => Some(Node::new(Operator::Lt)),
             Token::Geq => Some(Node::new(Operator::Geq)),
             Token::Leq => Some(Node::new(Operator::Leq)),
-            Token::And => Some(Node::new(Operator::And)),
-            Token::Or => Some(Node::new(Operator::Or)),
-            Token::Not => Some(Node::new(Operator::Not)),
+            Token::Cmp => Some(Node::new(Operator::Cmp)),
+            Token::And => {
+                if options.has_three_valued_logic() {
+                    Some(Node::new(Operator::KleeneAnd))
+                } else {
+                    Some(Node::new(Operator::And))
+                }
+            },
+            Token::Or => {
+                if options.has_three_valued_logic() {
+                    Some(Node::new(Operator::KleeneOr))
+                } else {
+                    Some(Node::new(Operator::Or))
+                }
+            },
+            Token::Not => {
+                if options.has_three_valued_logic() {
+                    Some(Node::new(Operator::KleeneNot))
+                } else {
+                    Some(Node::new(Operator::Not))
+                }
+            },
 
             Token::LBrace => {
-                root_stack.push(Node::root_node());
+                let mut root = Node::root_node();
+                root.source = source.source_text(index..index + 1);
+                root_stack.push(root);
                 None
             },
             Token::RBrace => {
@@ -806,15 +1503,57 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
                     return Err(EvalexprError::UnmatchedRBrace);
                 } else {
                     collapse_all_sequences(&mut root_stack)?;
-                    root_stack.pop()
+                    if let Some(&(depth, question_mark)) = conditionals.last() {
+                        if depth == root_stack.len() {
+                            return Err(EvalexprError::unmatched_question_mark(
+                                source.span(question_mark),
+                            ));
+                        }
+                    }
+                    let mut root = root_stack.pop();
+                    // The source text of the root node spans up to and including its closing brace
+                    if let Some(root_source) = root.as_mut().and_then(|root| root.source.as_mut()) {
+                        root_source.range.end = source.ranges[index].end;
+                    }
+                    root
                 }
             },
 
+            Token::LCurlyBrace => Some(map_literal_to_node(
+                &mut token_iter,
+                source.slice(index + 1..tokens.len()),
+                options,
+            )?),
+            Token::RCurlyBrace => return Err(EvalexprError::UnmatchedRCurlyBrace),
+            Token::Question => Some(Node::new(Operator::Conditional)),
+            Token::Colon => {
+                // Like a closing brace, a colon ends the operand between `?` and `:` of the innermost conditional operator
+                collapse_all_sequences(&mut root_stack)?;
+                match conditionals.pop() {
+                    Some((depth, _)) if depth == root_stack.len() => {
+                        // Unwrap cannot fail because the root node of the operand was pushed on top of another root
+                        let operand = root_stack.pop().unwrap();
+                        let root = root_stack.last_mut().unwrap();
+                        if !root.push_conditional_operand(operand) {
+                            return Err(EvalexprError::unmatched_colon(source.span(index)));
+                        }
+                    },
+                    _ => return Err(EvalexprError::unmatched_colon(source.span(index))),
+                }
+                None
+            },
+
             Token::Assign => Some(Node::new(Operator::Assign)),
             Token::PlusAssign => Some(Node::new(Operator::AddAssign)),
             Token::MinusAssign => Some(Node::new(Operator::SubAssign)),
             Token::StarAssign => Some(Node::new(Operator::MulAssign)),
-            Token::SlashAssign => Some(Node::new(Operator::DivAssign)),
+            Token::SlashAssign => {
+                if options.has_float_division() {
+                    Some(Node::new(Operator::FloatDivAssign))
+                } else {
+                    Some(Node::new(Operator::DivAssign))
+                }
+            },
             Token::PercentAssign => Some(Node::new(Operator::ModAssign)),
             Token::HatAssign => Some(Node::new(Operator::ExpAssign)),
             Token::AndAssign => Some(Node::new(Operator::AndAssign)),
@@ -823,7 +1562,20 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
             Token::Comma => Some(Node::new(Operator::Tuple)),
             Token::Semicolon => Some(Node::new(Operator::Chain)),
 
+            Token::Identifier(identifier)
+                if options.has_record_syntax()
+                    && identifier == "record"
+                    && is_record_syntax(token_iter.clone()) =>
+            {
+                Some(record_to_node(
+                    &mut token_iter,
+                    source.slice(index + 1..tokens.len()),
+                    options,
+                )?)
+            },
             Token::Identifier(identifier) => {
+                options.validate_identifier(&identifier, source.span(index))?;
+                let identifier = options.intern_identifier(&identifier);
                 let mut result = Some(Node::new(Operator::variable_identifier_read(
                     identifier.clone(),
                 )));
@@ -832,7 +1584,9 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
                         result = Some(Node::new(Operator::variable_identifier_write(
                             identifier.clone(),
                         )));
-                    } else if next.is_leftsided_value() {
+                    } else if next.is_leftsided_value()
+                        && (next == &Token::LBrace || !options.has_required_parentheses_for_calls())
+                    {
                         result = Some(Node::new(Operator::function_identifier(identifier)));
                     }
                 }
@@ -845,6 +1599,13 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
         };
 
         if let Some(mut node) = node {
+            // Map literals and records span all tokens consumed up to here
+            let end = tokens.len() - token_iter.len();
+            options.check_operator(node.operator(), source.range(index..end))?;
+            if node.source.is_none() {
+                node.source = source.source_text(index..end);
+            }
+
             // Need to pop and then repush here, because Rust 1.33.0 cannot release the mutable borrow of root_stack before the end of this complete if-statement
             if let Some(mut root) = root_stack.pop() {
                 if node.operator().is_sequence() {
@@ -857,9 +1618,12 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
                         root_stack.push(root);
                     } else if root.operator() == &Operator::RootNode {
                         // If the current root is an actual root node, we start a new sequence
+                        let mut new_root = Node::root_node();
+                        // The source text of a brace belongs to the root node that encloses the sequence
+                        new_root.source = root.source.take();
                         node.children.push(root);
                         node.children.push(Node::root_node());
-                        root_stack.push(Node::root_node());
+                        root_stack.push(new_root);
                         root_stack.push(node);
                     } else {
                         // Otherwise, we combine the sequences based on their precedences
@@ -901,17 +1665,84 @@ pub(crate) fn tokens_to_operator_tree(tokens: Vec<Token>) -> EvalexprResult<Node
             }
         }
 
-        last_token_is_rightsided_value = token.is_rightsided_value();
+        if token == Token::Question {
+            // The operand between `?` and `:` is parsed like an expression in braces
+            root_stack.push(Node::root_node());
+            conditionals.push((root_stack.len(), index));
+        }
+
+        // A map literal was consumed up to its closing curly brace, which is a rightsided value
+        last_token_is_rightsided_value = token == Token::LCurlyBrace || token.is_rightsided_value();
     }
 
     // In the end, all sequences are implicitly terminated
     collapse_all_sequences(&mut root_stack)?;
 
+    if let Some(&(depth, question_mark)) = conditionals.last() {
+        if depth == root_stack.len() {
+            return Err(EvalexprError::unmatched_question_mark(
+                source.span(question_mark),
+            ));
+        }
+    }
     if root_stack.len() > 1 {
         Err(EvalexprError::UnmatchedLBrace)
-    } else if let Some(root) = root_stack.pop() {
+    } else if let Some(mut root) = root_stack.pop() {
+        root.check_argument_amounts()?;
+        root.check_tuple_sizes(options)?;
+        if source.expression.is_some() {
+            root.widen_source_texts();
+        }
         Ok(root)
     } else {
         Err(EvalexprError::UnmatchedRBrace)
     }
 }
+
+/// Like `tokens_to_operator_tree`, but collects the errors of all independent parts of the expression if building the tree fails.
+///
+/// To recover from an error, the tokens are split at the commas and semicolons outside of parentheses, map literals and conditional operators,
+/// and each part is built on its own.
+/// If all parts can be built on their own, the error is caused by their combination, and only the original error is returned.
+pub(crate) fn tokens_to_operator_tree_all_errors(
+    tokens: &[Token],
+    ranges: &[Range<usize>],
+    options: &ParseOptions,
+) -> Result<Node, Vec<EvalexprError>> {
+    let error = match tokens_to_operator_tree(tokens, ranges, options) {
+        Ok(root) => return Ok(root),
+        Err(error) => error,
+    };
+
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    // The amount of `?` outside of parentheses and map literals whose `:` is still missing
+    let mut open_conditionals = 0usize;
+    for (index, token) in tokens.iter().enumerate() {
+        match token {
+            Token::LBrace | Token::LCurlyBrace => depth += 1,
+            Token::RBrace | Token::RCurlyBrace => depth = depth.saturating_sub(1),
+            Token::Question if depth == 0 => open_conditionals += 1,
+            Token::Colon if depth == 0 => open_conditionals = open_conditionals.saturating_sub(1),
+            Token::Comma | Token::Semicolon if depth == 0 && open_conditionals == 0 => {
+                parts.push(start..index);
+                start = index + 1;
+            },
+            _ => {},
+        }
+    }
+    parts.push(start..tokens.len());
+
+    let mut errors: Vec<_> = parts
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .filter_map(|part| {
+            tokens_to_operator_tree(&tokens[part.clone()], &ranges[part], options).err()
+        })
+        .collect();
+    if errors.is_empty() {
+        errors.push(error);
+    }
+    Err(errors)
+}
@@ -0,0 +1,119 @@
+use std::{fmt, sync::Arc};
+
+use crate::{Context, ContextWithMutableVariables, EvalexprResult, HashMapContext, Node, Value};
+
+/// A function that post-processes the result of an evaluation.
+type Postprocessor = dyn Fn(Value) -> EvalexprResult<Value> + Send + Sync;
+
+/// An operator tree together with postprocessors that are applied to the result of each evaluation.
+///
+/// A `ProcessedNode` is created with `Node::with_postprocessor`.
+/// Its postprocessors are arbitrary functions, so unlike a `Node`, it cannot be serialized.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let score = build_operator_tree("a * 2").unwrap() // Do proper error handling here
+///     .with_postprocessor(|value| Ok(Value::Float(value.as_number()?.clamp(0.0, 1.0))));
+///
+/// let context = context_map! { "a" => 0.75 }.unwrap(); // Do proper error handling here
+/// assert_eq!(score.eval_with_context(&context), Ok(Value::from(1.0)));
+/// ```
+#[derive(Clone)]
+pub struct ProcessedNode {
+    node: Node,
+    postprocessors: Vec<Arc<Postprocessor>>,
+}
+
+impl Node {
+    /// Attaches a postprocessor to this operator tree, which is applied to the result of each successful evaluation.
+    /// If the postprocessor returns an error, the evaluation fails with it.
+    ///
+    /// More postprocessors can be attached with `ProcessedNode::with_postprocessor`.
+    pub fn with_postprocessor<F>(self, postprocessor: F) -> ProcessedNode
+    where
+        F: Fn(Value) -> EvalexprResult<Value> + Send + Sync + 'static,
+    {
+        ProcessedNode {
+            node: self,
+            postprocessors: Vec::new(),
+        }
+        .with_postprocessor(postprocessor)
+    }
+}
+
+impl ProcessedNode {
+    /// Attaches another postprocessor, which is applied to the result of the postprocessors attached before.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let node = build_operator_tree("3").unwrap() // Do proper error handling here
+    ///     .with_postprocessor(|value| Ok(Value::Int(value.as_int()? + 1)))
+    ///     .with_postprocessor(|value| Ok(Value::Int(value.as_int()? * 2)));
+    /// assert_eq!(node.eval(), Ok(Value::from(8)));
+    /// ```
+    pub fn with_postprocessor<F>(mut self, postprocessor: F) -> Self
+    where
+        F: Fn(Value) -> EvalexprResult<Value> + Send + Sync + 'static,
+    {
+        self.postprocessors.push(Arc::new(postprocessor));
+        self
+    }
+
+    /// Returns the operator tree without the postprocessors.
+    pub fn node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Removes the postprocessors and returns the operator tree.
+    pub fn into_node(self) -> Node {
+        self.node
+    }
+
+    fn postprocess(&self, result: EvalexprResult<Value>) -> EvalexprResult<Value> {
+        self.postprocessors
+            .iter()
+            .try_fold(result?, |value, postprocessor| postprocessor(value))
+    }
+
+    /// Evaluates the operator tree with the given context and applies the postprocessors to the result.
+    ///
+    /// Fails, if one of the operators in the expression tree or one of the postprocessors fails.
+    pub fn eval_with_context<C: Context>(&self, context: &C) -> EvalexprResult<Value> {
+        self.postprocess(self.node.eval_with_context(context))
+    }
+
+    /// Evaluates the operator tree with the given mutable context and applies the postprocessors to the result.
+    ///
+    /// Fails, if one of the operators in the expression tree or one of the postprocessors fails.
+    pub fn eval_with_context_mut<C: ContextWithMutableVariables>(
+        &self,
+        context: &mut C,
+    ) -> EvalexprResult<Value> {
+        self.postprocess(self.node.eval_with_context_mut(context))
+    }
+
+    /// Evaluates the operator tree and applies the postprocessors to the result.
+    ///
+    /// Fails, if one of the operators in the expression tree or one of the postprocessors fails.
+    pub fn eval(&self) -> EvalexprResult<Value> {
+        self.eval_with_context_mut(&mut HashMapContext::new())
+    }
+}
+
+impl fmt::Debug for ProcessedNode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("ProcessedNode")
+            .field("node", &self.node)
+            .field(
+                "postprocessors",
+                &format!("[{} postprocessors]", self.postprocessors.len()),
+            )
+            .finish()
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use crate::{operator::Operator, value::Value, Node};
+
+/// The maximum amount of passes over the tree made by `Node::rewrite`.
+/// This bounds the work for rule sets that never reach a fixed point, like a rule together with its inverse.
+const MAX_REWRITE_PASSES: usize = 100;
+
+/// A pattern that matches operator trees, used on both sides of a rewrite `Rule`.
+///
+/// Root nodes with a single child, as created for each pair of parentheses, are transparent to patterns,
+/// so the pattern for `x * 1` also matches `(x) * (1)`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Pattern {
+    /// Matches any subtree and binds it to the given name.
+    /// If the same name appears multiple times in a pattern, all occurrences must match equal subtrees.
+    /// In a replacement, the wildcard is replaced by the subtree bound to its name.
+    Wildcard(String),
+    /// Matches a node with the given operator whose children match the given patterns.
+    Node(Operator, Vec<Pattern>),
+}
+
+impl Pattern {
+    /// Constructs a wildcard pattern with the given name.
+    pub fn wildcard(name: &str) -> Self {
+        Pattern::Wildcard(name.to_string())
+    }
+
+    /// Constructs a pattern matching a node with the given operator and children.
+    pub fn node(operator: Operator, children: Vec<Pattern>) -> Self {
+        Pattern::Node(operator, children)
+    }
+
+    /// Constructs a pattern matching a constant with the given value.
+    /// Values of different types never match, so the pattern for `1` does not match `1.0`.
+    pub fn value<V: Into<Value>>(value: V) -> Self {
+        Pattern::Node(
+            Operator::Const {
+                value: value.into(),
+            },
+            Vec::new(),
+        )
+    }
+
+    /// Constructs a pattern matching a call of the function with the given identifier.
+    /// Multiple arguments are matched by passing a pattern with the operator `Operator::Tuple` as argument.
+    pub fn function(identifier: &str, argument: Pattern) -> Self {
+        Pattern::Node(
+            Operator::FunctionIdentifier {
+                identifier: identifier.into(),
+            },
+            vec![argument],
+        )
+    }
+
+    /// Matches this pattern against the given node, adding the bound subtrees to `bindings`.
+    fn matches(&self, node: &Node, bindings: &mut HashMap<String, Node>) -> bool {
+        let node = unwrap_root_nodes(node);
+        match self {
+            Pattern::Wildcard(name) => {
+                if let Some(bound) = bindings.get(name) {
+                    bound == node
+                } else {
+                    bindings.insert(name.clone(), node.clone());
+                    true
+                }
+            },
+            Pattern::Node(operator, children) => {
+                &node.operator == operator
+                    && node.children.len() == children.len()
+                    && children
+                        .iter()
+                        .zip(&node.children)
+                        .all(|(pattern, child)| pattern.matches(child, bindings))
+            },
+        }
+    }
+
+    /// Builds the operator tree described by this pattern, replacing wildcards by their bound subtrees.
+    fn instantiate(&self, bindings: &HashMap<String, Node>) -> Node {
+        match self {
+            // Rule::new ensures that all wildcards of a replacement are bound
+            Pattern::Wildcard(name) => bindings[name].clone(),
+            Pattern::Node(operator, children) => Node {
+                operator: operator.clone(),
+                children: children
+                    .iter()
+                    .map(|child| child.instantiate(bindings))
+                    .collect(),
+                // The node is synthesized, so it is not part of the source
+                source: None,
+            },
+        }
+    }
+
+    fn wildcards(&self) -> Vec<&str> {
+        match self {
+            Pattern::Wildcard(name) => vec![name.as_str()],
+            Pattern::Node(_, children) => children.iter().flat_map(Pattern::wildcards).collect(),
+        }
+    }
+}
+
+/// Returns the node below all root nodes that have exactly one child.
+fn unwrap_root_nodes(mut node: &Node) -> &Node {
+    while node.operator == Operator::RootNode && node.children.len() == 1 {
+        node = &node.children[0];
+    }
+    node
+}
+
+/// A rewrite rule that replaces subtrees matching a pattern by a replacement, see `Node::rewrite`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// // legacy_fn(a) -> new_fn(a, 0)
+/// let rule = Rule::new(
+///     Pattern::function("legacy_fn", Pattern::wildcard("a")),
+///     Pattern::function(
+///         "new_fn",
+///         Pattern::node(Operator::Tuple, vec![Pattern::wildcard("a"), Pattern::value(0)]),
+///     ),
+/// );
+///
+/// let mut tree = build_operator_tree("legacy_fn(x + 1) * 2").unwrap(); // Do proper error handling here
+/// assert_eq!(tree.rewrite(&[rule]), 1);
+/// assert_eq!(tree.iter_function_identifiers().collect::<Vec<_>>(), ["new_fn"]);
+///
+/// let mut context = HashMapContext::new();
+/// context.set_value("x".into(), 2.into()).unwrap(); // Do proper error handling here
+/// context.set_function("new_fn".into(), Function::new(|argument| {
+///     let arguments = argument.as_fixed_len_tuple(2)?;
+///     Ok(Value::Int(arguments[0].as_int()? - arguments[1].as_int()?))
+/// })).unwrap(); // Do proper error handling here
+/// assert_eq!(tree.eval_with_context(&context), Ok(Value::from(6)));
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rule {
+    pattern: Pattern,
+    replacement: Pattern,
+}
+
+impl Rule {
+    /// Constructs a rule that replaces subtrees matching `pattern` by `replacement`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the replacement contains a wildcard that does not appear in the pattern.
+    pub fn new(pattern: Pattern, replacement: Pattern) -> Self {
+        let bound = pattern.wildcards();
+        if let Some(unbound) = replacement
+            .wildcards()
+            .into_iter()
+            .find(|wildcard| !bound.contains(wildcard))
+        {
+            panic!(
+                "the wildcard {:?} of the replacement does not appear in the pattern",
+                unbound
+            );
+        }
+        Rule {
+            pattern,
+            replacement,
+        }
+    }
+
+    /// Returns the pattern of this rule.
+    pub fn pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    /// Returns the replacement of this rule.
+    pub fn replacement(&self) -> &Pattern {
+        &self.replacement
+    }
+
+    /// Returns the standard rules that remove identity operations:
+    /// `x * 1`, `1 * x`, `x / 1`, `x + 0`, `0 + x`, `x - 0`, `!!x`, `x && true`, `true && x`, `x || false` and `false || x` are replaced by `x`.
+    ///
+    /// The rules preserve the result of evaluations where the operands have the types the operators expect,
+    /// i.e. numbers for arithmetic operators and booleans for logical operators.
+    /// Otherwise, a rewritten tree may return a value where the original tree returns a type error.
+    pub fn identities() -> Vec<Rule> {
+        let x = || Pattern::wildcard("x");
+        let binary = |operator: Operator, left: Pattern, right: Pattern| {
+            Rule::new(Pattern::node(operator, vec![left, right]), x())
+        };
+
+        vec![
+            binary(Operator::Mul, x(), Pattern::value(1)),
+            binary(Operator::Mul, Pattern::value(1), x()),
+            binary(Operator::Div, x(), Pattern::value(1)),
+            binary(Operator::Add, x(), Pattern::value(0)),
+            binary(Operator::Add, Pattern::value(0), x()),
+            binary(Operator::Sub, x(), Pattern::value(0)),
+            Rule::new(
+                Pattern::node(Operator::Not, vec![Pattern::node(Operator::Not, vec![x()])]),
+                x(),
+            ),
+            binary(Operator::And, x(), Pattern::value(true)),
+            binary(Operator::And, Pattern::value(true), x()),
+            binary(Operator::Or, x(), Pattern::value(false)),
+            binary(Operator::Or, Pattern::value(false), x()),
+        ]
+    }
+}
+
+impl Node {
+    /// Rewrites this operator tree with the given rules until none of them matches anymore, and returns the amount of applied rewrites.
+    ///
+    /// The tree is traversed bottom-up, and at each node the first matching rule is applied.
+    /// The traversal is repeated until no rule matches, but at most 100 times, so rule sets that never reach a fixed point still terminate.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let mut tree = build_operator_tree("(a + 0) * 1 + !!b").unwrap(); // Do proper error handling here
+    /// assert_eq!(tree.rewrite(&Rule::identities()), 3);
+    /// assert!(tree.semantically_eq(&build_operator_tree("a + b").unwrap()));
+    /// ```
+    pub fn rewrite(&mut self, rules: &[Rule]) -> usize {
+        let mut applications = 0;
+        for _ in 0..MAX_REWRITE_PASSES {
+            let pass_applications = self.rewrite_pass(rules);
+            if pass_applications == 0 {
+                break;
+            }
+            applications += pass_applications;
+        }
+        applications
+    }
+
+    fn rewrite_pass(&mut self, rules: &[Rule]) -> usize {
+        let mut applications = self
+            .children
+            .iter_mut()
+            .map(|child| child.rewrite_pass(rules))
+            .sum();
+
+        // Root nodes are transparent to patterns, so rules are matched at their child instead
+        if self.operator == Operator::RootNode {
+            return applications;
+        }
+
+        let mut bindings = HashMap::new();
+        for rule in rules {
+            bindings.clear();
+            if rule.pattern.matches(self, &mut bindings) {
+                *self = rule.replacement.instantiate(&bindings);
+                applications += 1;
+                break;
+            }
+        }
+        applications
+    }
+}
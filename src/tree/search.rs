@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use crate::{
+    ContextWithMutableVariables, FloatType, HashMapContext, IntType, Node, Operator, Value,
+    ValueType,
+};
+
+/// The types of the values that `Node::find_satisfying_bindings` tries for variables.
+///
+/// Variables without a hint are bound to values of any type.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypeHints {
+    types: HashMap<String, ValueType>,
+}
+
+impl TypeHints {
+    /// Creates type hints without any hint.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hints that the variable with the given identifier has the given type.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let hints = TypeHints::new().with("age", ValueType::Int);
+    /// assert_eq!(hints.get("age"), Some(ValueType::Int));
+    /// assert_eq!(hints.get("name"), None);
+    /// ```
+    pub fn with(mut self, identifier: impl Into<String>, value_type: ValueType) -> Self {
+        self.types.insert(identifier.into(), value_type);
+        self
+    }
+
+    /// Returns the type hinted for the variable with the given identifier.
+    pub fn get(&self, identifier: &str) -> Option<ValueType> {
+        self.types.get(identifier).copied()
+    }
+}
+
+/// The SplitMix64 generator, which is small and good enough to pick candidate values reproducibly.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`, where the bound must not be zero.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    fn int(&mut self) -> IntType {
+        self.below(21) as IntType - 10
+    }
+
+    fn random_value(&mut self, value_type: ValueType) -> Value {
+        match value_type {
+            ValueType::Int => Value::Int(self.int()),
+            ValueType::Float => Value::Float(self.below(201) as FloatType / 10.0 - 10.0),
+            ValueType::Boolean => Value::Boolean(self.below(2) == 0),
+            ValueType::String => Value::String(
+                (0..self.below(4))
+                    .map(|_| char::from(b'a' + self.below(26) as u8))
+                    .collect(),
+            ),
+            ValueType::Tuple => {
+                Value::Tuple((0..self.below(4)).map(|_| Value::Int(self.int())).collect())
+            },
+            ValueType::Map => Value::Map(Vec::new()),
+            _ => Value::Empty,
+        }
+    }
+}
+
+/// Returns the values derived from the given literal, which are likely to decide comparisons with it:
+/// integers and floats with their neighbours and their negation, strings, and booleans with their negation.
+fn derived_candidates(literal: &Value) -> Vec<Value> {
+    match literal {
+        Value::Int(int) => vec![
+            Value::Int(*int),
+            Value::Int(int.saturating_sub(1)),
+            Value::Int(int.saturating_add(1)),
+            Value::Int(int.saturating_neg()),
+            Value::Float(*int as FloatType),
+        ],
+        Value::Float(float) => vec![
+            Value::Float(*float),
+            Value::Float(float - 1.0),
+            Value::Float(float + 1.0),
+            Value::Float(-float),
+        ],
+        Value::Boolean(boolean) => vec![Value::Boolean(*boolean), Value::Boolean(!boolean)],
+        Value::String(_) => vec![literal.clone()],
+        _ => Vec::new(),
+    }
+}
+
+fn push_unique(candidates: &mut Vec<Value>, values: Vec<Value>) {
+    for value in values {
+        if !candidates.contains(&value) {
+            candidates.push(value);
+        }
+    }
+}
+
+/// Returns the node within any parentheses around it.
+fn unparenthesized(mut node: &Node) -> &Node {
+    while let (Operator::RootNode, [child]) = (node.operator(), node.children()) {
+        node = child;
+    }
+    node
+}
+
+/// Returns the candidates derived from all literals of the given tree,
+/// and the candidates derived from the literals that each variable is directly compared with, like `"open"` in `status == "open"`.
+fn mine_literals(node: &Node) -> (Vec<Value>, HashMap<&str, Vec<Value>>) {
+    let mut candidates = Vec::new();
+    let mut compared = HashMap::new();
+    for node in std::iter::once(node).chain(node.iter()) {
+        match (node.operator(), node.children()) {
+            (Operator::Const { value }, _) => {
+                push_unique(&mut candidates, derived_candidates(value))
+            },
+            (
+                Operator::Eq
+                | Operator::Neq
+                | Operator::Gt
+                | Operator::Lt
+                | Operator::Geq
+                | Operator::Leq
+                | Operator::Cmp,
+                [left, right],
+            ) => {
+                let (left, right) = (unparenthesized(left), unparenthesized(right));
+                for (variable, literal) in [(left, right), (right, left)] {
+                    if let (
+                        Operator::VariableIdentifierRead { identifier },
+                        Operator::Const { value },
+                    ) = (variable.operator(), literal.operator())
+                    {
+                        push_unique(
+                            compared.entry(identifier.as_ref()).or_default(),
+                            derived_candidates(value),
+                        );
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+    (candidates, compared)
+}
+
+/// The types of random values for variables without a type hint.
+const UNHINTED_TYPES: [ValueType; 4] = [
+    ValueType::Int,
+    ValueType::Float,
+    ValueType::Boolean,
+    ValueType::String,
+];
+
+impl Node {
+    /// Searches for values of the variables read by this expression such that it evaluates to `true`,
+    /// e.g. to check that a rule can be satisfied at all.
+    ///
+    /// The search makes up to `budget` attempts, each binding all variables in a fresh `HashMapContext` and evaluating the expression.
+    /// The value of each variable is picked at random, where three out of four picks are taken from values derived from the literals
+    /// of the expression, if there are any of the hinted type.
+    /// These are the literals themselves, the neighbours and the negation of numbers, and the negation of booleans,
+    /// like `"open"` for `status == "open"` or `4`, `5`, `6` and `-5` for `count > 5`.
+    /// If a variable is directly compared with literals, only the values derived from these literals are picked for it.
+    /// The other picks are small random values of the hinted type: integers from -10 to 10, floats from -10 to 10 in steps of 0.1,
+    /// strings of up to three lowercase letters, booleans, and tuples of up to three integers.
+    /// Variables hinted as maps are bound to empty maps, and variables hinted with other types to the empty value.
+    /// Variables without hint get values of the type of the first literal they are compared with,
+    /// or of any of the types integer, float, boolean and string.
+    ///
+    /// The same seed results in the same attempts, so the result is reproducible.
+    /// Returns the bindings of the first attempt that evaluates to `true`, or `None` if no attempt does.
+    /// Functions are looked up in the builtin functions only, so expressions that call other functions are never satisfied.
+    /// Assignments in the expression are evaluated, but the returned context contains the bindings of the attempt before the evaluation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let rule = build_operator_tree("status == \"open\" && priority > 3").unwrap(); // Do proper error handling here
+    /// let hints = TypeHints::new().with("priority", ValueType::Int);
+    /// let bindings = rule.find_satisfying_bindings(&hints, 100, 42).unwrap();
+    /// assert_eq!(rule.eval_with_context(&bindings), Ok(Value::from(true)));
+    ///
+    /// let contradiction = build_operator_tree("x > 0 && x < 0").unwrap(); // Do proper error handling here
+    /// assert!(contradiction.find_satisfying_bindings(&hints, 100, 42).is_none());
+    /// ```
+    pub fn find_satisfying_bindings(
+        &self,
+        hints: &TypeHints,
+        budget: usize,
+        seed: u64,
+    ) -> Option<HashMapContext> {
+        let mut identifiers: Vec<_> = self.iter_read_variable_identifiers().collect();
+        identifiers.sort_unstable();
+        identifiers.dedup();
+        let (literals, compared) = mine_literals(self);
+        // The type and the literal candidates of each variable, preferring the literals it is compared with
+        let variables: Vec<_> = identifiers
+            .into_iter()
+            .map(|identifier| {
+                let hint = hints.get(identifier);
+                let matches_hint =
+                    |literal: &&Value| hint.map_or(true, |hint| ValueType::from(*literal) == hint);
+                let mut candidates: Vec<_> = compared
+                    .get(identifier)
+                    .into_iter()
+                    .flatten()
+                    .filter(matches_hint)
+                    .collect();
+                if candidates.is_empty() {
+                    candidates = literals.iter().filter(matches_hint).collect();
+                }
+                let value_type = hint.or_else(|| {
+                    let compared = compared.get(identifier)?.first()?;
+                    Some(ValueType::from(compared))
+                });
+                (identifier, value_type, candidates)
+            })
+            .collect();
+        let mut random = SplitMix64(seed);
+
+        for _ in 0..budget {
+            let mut bindings = HashMapContext::new();
+            for (identifier, value_type, candidates) in &variables {
+                let value = if !candidates.is_empty() && random.below(4) != 0 {
+                    candidates[random.below(candidates.len())].clone()
+                } else {
+                    let value_type = value_type
+                        .unwrap_or_else(|| UNHINTED_TYPES[random.below(UNHINTED_TYPES.len())]);
+                    random.random_value(value_type)
+                };
+                bindings.set_value(identifier.to_string(), value).ok()?;
+            }
+
+            let mut context = bindings.clone();
+            if self.eval_boolean_with_context_mut(&mut context) == Ok(true) {
+                return Some(bindings);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{function, Context, EvalexprResult, Node, Operator, Value};
+
+/// The amount and total duration of evaluations of one kind of operator or of one function, as collected in `EvalStats`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OperationStats {
+    /// How often the operator or function was evaluated.
+    pub count: u64,
+    /// The total time spent evaluating the operator or function, excluding the evaluation of its arguments.
+    pub duration: Duration,
+}
+
+impl OperationStats {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.duration += duration;
+    }
+
+    fn merge(&mut self, other: &OperationStats) {
+        self.count += other.count;
+        self.duration += other.duration;
+    }
+}
+
+/// Statistics about one or more evaluations, as returned by `Node::eval_with_stats`.
+///
+/// Operators are keyed by the name of their `Operator` variant, e.g. `"Add"` or `"FunctionIdentifier"`,
+/// and functions by their identifier.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalStats {
+    /// The statistics per kind of operator.
+    pub operators: HashMap<&'static str, OperationStats>,
+    /// The statistics per called function.
+    /// Like for other functions, the duration of the lazily evaluated `try_or` excludes the evaluation of its arguments.
+    pub functions: HashMap<String, OperationStats>,
+    /// The largest amount of elements of a tuple produced by an operator.
+    pub peak_tuple_len: usize,
+    /// The largest length in bytes of a string produced by an operator.
+    pub peak_string_len: usize,
+}
+
+impl EvalStats {
+    /// Constructs empty statistics.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds the statistics of another evaluation to these, such that they describe both evaluations.
+    pub fn merge(&mut self, other: &EvalStats) {
+        for (kind, stats) in &other.operators {
+            self.operators.entry(kind).or_default().merge(stats);
+        }
+        for (identifier, stats) in &other.functions {
+            self.functions
+                .entry(identifier.clone())
+                .or_default()
+                .merge(stats);
+        }
+        self.peak_tuple_len = self.peak_tuple_len.max(other.peak_tuple_len);
+        self.peak_string_len = self.peak_string_len.max(other.peak_string_len);
+    }
+
+    fn record(&mut self, operator: &Operator, duration: Duration) {
+        self.operators
+            .entry(operator_kind(operator))
+            .or_default()
+            .record(duration);
+        if let Operator::FunctionIdentifier { identifier } = operator {
+            // Avoid allocating the key for functions that were called before
+            if let Some(stats) = self.functions.get_mut(identifier.as_ref()) {
+                stats.record(duration);
+            } else {
+                let mut stats = OperationStats::default();
+                stats.record(duration);
+                self.functions.insert(identifier.to_string(), stats);
+            }
+        }
+    }
+
+    fn record_result(&mut self, result: &EvalexprResult<Value>) {
+        match result {
+            Ok(Value::Tuple(tuple)) => self.peak_tuple_len = self.peak_tuple_len.max(tuple.len()),
+            Ok(Value::String(string)) => {
+                self.peak_string_len = self.peak_string_len.max(string.len())
+            },
+            _ => {},
+        }
+    }
+}
+
+/// Returns the name of the variant of the given operator.
+fn operator_kind(operator: &Operator) -> &'static str {
+    use crate::operator::Operator::*;
+    match operator {
+        RootNode => "RootNode",
+        Add => "Add",
+        Sub => "Sub",
+        Neg => "Neg",
+        Mul => "Mul",
+        Div => "Div",
+        FloatDiv => "FloatDiv",
+        Mod => "Mod",
+        PercentOf => "PercentOf",
+        Exp => "Exp",
+        Eq => "Eq",
+        Neq => "Neq",
+        Gt => "Gt",
+        Lt => "Lt",
+        Geq => "Geq",
+        Leq => "Leq",
+        Cmp => "Cmp",
+        And => "And",
+        Or => "Or",
+        Not => "Not",
+        KleeneAnd => "KleeneAnd",
+        KleeneOr => "KleeneOr",
+        KleeneNot => "KleeneNot",
+        Conditional => "Conditional",
+        Assign => "Assign",
+        AddAssign => "AddAssign",
+        SubAssign => "SubAssign",
+        MulAssign => "MulAssign",
+        DivAssign => "DivAssign",
+        FloatDivAssign => "FloatDivAssign",
+        ModAssign => "ModAssign",
+        ExpAssign => "ExpAssign",
+        AndAssign => "AndAssign",
+        OrAssign => "OrAssign",
+        Tuple => "Tuple",
+        Chain => "Chain",
+        Map { .. } => "Map",
+        Const { .. } => "Const",
+        VariableIdentifierWrite { .. } => "VariableIdentifierWrite",
+        VariableIdentifierRead { .. } => "VariableIdentifierRead",
+        FunctionIdentifier { .. } => "FunctionIdentifier",
+    }
+}
+
+impl Node {
+    /// Evaluates the operator tree rooted at this node with the given context, collecting statistics about the evaluated operators and called functions.
+    ///
+    /// Returns the result of the evaluation together with the statistics.
+    /// Like for `Node::eval_profiled`, the statistics include all operators up to and including a failing one.
+    /// The plain evaluation methods do not collect any statistics, so they are not slowed down by this method.
+    ///
+    /// # Examples
+    ///
+    /// Statistics of multiple evaluations can be aggregated with `EvalStats::merge`:
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let tree = build_operator_tree("str::to_uppercase(name) + \"!\"").unwrap(); // Do proper error handling here
+    /// let mut total = EvalStats::new();
+    /// for name in ["a", "bcd"] {
+    ///     let context = context_map! { "name" => name }.unwrap();
+    ///     let (result, stats) = tree.eval_with_stats(&context);
+    ///     assert!(result.is_ok());
+    ///     total.merge(&stats);
+    /// }
+    ///
+    /// assert_eq!(total.operators["Add"].count, 2);
+    /// assert_eq!(total.functions["str::to_uppercase"].count, 2);
+    /// assert_eq!(total.peak_string_len, 4);
+    /// ```
+    pub fn eval_with_stats<C: Context>(&self, context: &C) -> (EvalexprResult<Value>, EvalStats) {
+        let mut stats = EvalStats::new();
+        let result = function::with_evaluation_scope(context.max_evaluation_depth(), || {
+            self.eval_node_with_stats(context, &mut stats)
+        });
+        (result, stats)
+    }
+
+    fn eval_node_with_stats<C: Context>(
+        &self,
+        context: &C,
+        stats: &mut EvalStats,
+    ) -> EvalexprResult<Value> {
+        if let Some(lazy_node) = self.lazy_node(context) {
+            // The time of the lazy node excludes the evaluation of its operands, like for other operators
+            let start = Instant::now();
+            let mut operand_duration = Duration::ZERO;
+            let result = lazy_node.eval(
+                &mut &*context,
+                |context| *context,
+                |context, operand| {
+                    let operand_start = Instant::now();
+                    let result = operand.eval_node_with_stats(*context, stats);
+                    operand_duration += operand_start.elapsed();
+                    result
+                },
+            );
+            stats.record(
+                self.operator(),
+                start.elapsed().saturating_sub(operand_duration),
+            );
+            stats.record_result(&result);
+            return result;
+        }
+
+        let mut arguments = Vec::with_capacity(self.children().len());
+        for child in self.children() {
+            arguments.push(child.eval_node_with_stats(context, stats)?);
+        }
+        let start = Instant::now();
+        let result = self.operator().eval_owned(arguments, context);
+        stats.record(self.operator(), start.elapsed());
+        stats.record_result(&result);
+        result
+    }
+}
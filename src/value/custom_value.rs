@@ -0,0 +1,84 @@
+use std::{any::Any, fmt};
+
+use crate::{error::EvalexprResult, Value};
+
+/// A domain type that can be embedded into expressions as `Value::Custom`.
+///
+/// The operators `+` and `*` dispatch to `CustomValue::add` and `CustomValue::mul` if one of their operands is a custom value.
+/// If only the right operand is a custom value, the method is called on it with the left operand as `other`,
+/// so the implementation should be commutative.
+/// The operators `==` and `!=` compare custom values with `CustomValue::eq`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+/// use std::{any::Any, fmt, sync::Arc};
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Meters(f64);
+///
+/// impl CustomValue for Meters {
+///     fn type_name(&self) -> &str {
+///         "meters"
+///     }
+///
+///     fn display(&self, f: &mut fmt::Formatter) -> fmt::Result {
+///         write!(f, "{}m", self.0)
+///     }
+///
+///     fn eq(&self, other: &dyn CustomValue) -> bool {
+///         other.as_any().downcast_ref::<Self>() == Some(self)
+///     }
+///
+///     fn mul(&self, other: &Value) -> Option<EvalexprResult<Value>> {
+///         let factor = other.as_number().ok()?;
+///         Some(Ok(Value::Custom(Arc::new(Meters(self.0 * factor)))))
+///     }
+///
+///     fn as_any(&self) -> &dyn Any {
+///         self
+///     }
+/// }
+///
+/// let context = context_map! {
+///     "distance" => Value::Custom(Arc::new(Meters(1.5)))
+/// }.unwrap(); // Do proper error handling here
+///
+/// assert_eq!(
+///     eval_with_context("2 * distance", &context),
+///     Ok(Value::Custom(Arc::new(Meters(3.0))))
+/// );
+/// assert_eq!(eval_with_context("str::from(distance)", &context), Ok(Value::from("1.5m")));
+/// ```
+pub trait CustomValue: fmt::Debug + Send + Sync {
+    /// Returns the name of this type, as returned by the builtin function `typeof`.
+    fn type_name(&self) -> &str;
+
+    /// Formats this value, as used by the `Display` implementation of `Value`.
+    fn display(&self, f: &mut fmt::Formatter) -> fmt::Result;
+
+    /// Returns true if this value is equal to the other custom value.
+    fn eq(&self, other: &dyn CustomValue) -> bool;
+
+    /// Adds the other value to this value.
+    /// Returns `None` if the addition is not defined for the other value, which makes the `+` operator fail with a type error.
+    fn add(&self, _other: &Value) -> Option<EvalexprResult<Value>> {
+        None
+    }
+
+    /// Multiplies this value with the other value.
+    /// Returns `None` if the multiplication is not defined for the other value, which makes the `*` operator fail with a type error.
+    fn mul(&self, _other: &Value) -> Option<EvalexprResult<Value>> {
+        None
+    }
+
+    /// Returns this value as `Any`, such that `CustomValue::eq` can downcast the other value to the concrete type.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl PartialEq for dyn CustomValue {
+    fn eq(&self, other: &Self) -> bool {
+        CustomValue::eq(self, other)
+    }
+}
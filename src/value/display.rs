@@ -1,28 +1,112 @@
-use std::fmt::{Display, Error, Formatter};
+use std::fmt::{Display, Error, Formatter, Write};
 
 use crate::Value;
 
 impl Display for Value {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        match self {
-            Value::String(string) => write!(f, "\"{}\"", string),
-            Value::Float(float) => write!(f, "{}", float),
-            Value::Int(int) => write!(f, "{}", int),
-            Value::Boolean(boolean) => write!(f, "{}", boolean),
-            Value::Tuple(tuple) => {
-                write!(f, "(")?;
-                let mut once = false;
-                for value in tuple {
-                    if once {
-                        write!(f, ", ")?;
-                    } else {
-                        once = true;
-                    }
-                    value.fmt(f)?;
+        write_value(self, f, false)
+    }
+}
+
+impl Value {
+    /// Returns a representation of this value that is parsed back into an identical value.
+    ///
+    /// Floats are written in the shortest form that parses back into the same bits, always with a decimal point or an exponent,
+    /// such that they are not parsed as integers, e.g. `1.0` or `1e300`.
+    /// The only exception is `NaN`, which is written as `NaN` regardless of its sign and payload.
+    /// Strings are written with `"` and `\` escaped.
+    /// Tuples and maps are written like by the `Display` implementation, with their elements written as above.
+    ///
+    /// Literals can be parsed back with `Value::from_str`, and tuples by evaluating the representation as expression.
+    /// In contrast, the `Display` implementation writes floats with all their digits and without exponent, e.g. `1` or `1000…000`,
+    /// and does not escape strings.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// assert_eq!(Value::from(1.0).to_round_trip_string(), "1.0");
+    /// assert_eq!(Value::from(1.0).to_string(), "1");
+    /// for float in [0.1, -0.0, 1e300, 5e-324, std::f64::consts::PI] {
+    ///     let string = Value::from(float).to_round_trip_string();
+    ///     assert_eq!(string.parse::<Value>().unwrap().as_float().unwrap().to_bits(), float.to_bits());
+    /// }
+    /// assert_eq!(
+    ///     eval(&Value::from(vec![Value::from(2.0), Value::from("\"")]).to_round_trip_string()),
+    ///     Ok(Value::from(vec![Value::from(2.0), Value::from("\"")]))
+    /// );
+    /// ```
+    pub fn to_round_trip_string(&self) -> String {
+        RoundTrip(self).to_string()
+    }
+}
+
+/// A value that is displayed as by `Value::to_round_trip_string`.
+struct RoundTrip<'a>(&'a Value);
+
+impl Display for RoundTrip<'_> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write_value(self.0, f, true)
+    }
+}
+
+/// Writes the given value, for `Value::to_round_trip_string` if `round_trip` is true, and for the `Display` implementation otherwise.
+fn write_value(value: &Value, f: &mut Formatter, round_trip: bool) -> Result<(), Error> {
+    match value {
+        Value::String(string) if round_trip => {
+            f.write_char('"')?;
+            for c in string.chars() {
+                if c == '"' || c == '\\' {
+                    f.write_char('\\')?;
+                }
+                f.write_char(c)?;
+            }
+            f.write_char('"')
+        },
+        Value::String(string) => write!(f, "\"{}\"", string),
+        // Write special floats as the literals the tokenizer parses them from, independent of how Rust formats them.
+        Value::Float(float) if float.is_nan() => write!(f, "NaN"),
+        Value::Float(float) if float.is_infinite() && *float > 0.0 => write!(f, "inf"),
+        Value::Float(float) if float.is_infinite() => write!(f, "-inf"),
+        // The debug representation is the shortest that round-trips, and keeps a decimal point or exponent
+        Value::Float(float) if round_trip => write!(f, "{:?}", float),
+        Value::Float(float) => write!(f, "{}", float),
+        Value::Int(int) => write!(f, "{}", int),
+        Value::Boolean(boolean) => write!(f, "{}", boolean),
+        Value::Tuple(tuple) => {
+            write!(f, "(")?;
+            let mut once = false;
+            for value in tuple {
+                if once {
+                    write!(f, ", ")?;
+                } else {
+                    once = true;
+                }
+                write_value(value, f, round_trip)?;
+            }
+            write!(f, ")")
+        },
+        Value::Map(map) => {
+            write!(f, "{{")?;
+            let mut once = false;
+            for (key, value) in map {
+                if once {
+                    write!(f, ", ")?;
+                } else {
+                    once = true;
                 }
-                write!(f, ")")
-            },
-            Value::Empty => write!(f, "()"),
-        }
+                write!(f, "{}: ", key)?;
+                write_value(value, f, round_trip)?;
+            }
+            write!(f, "}}")
+        },
+        Value::Empty => write!(f, "()"),
+        Value::Custom(custom) => custom.display(f),
+        #[cfg(feature = "units_support")]
+        Value::Quantity { value, unit } => {
+            write_value(&Value::Float(*value), f, round_trip)?;
+            write!(f, " {}", unit.name())
+        },
     }
 }
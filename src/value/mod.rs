@@ -1,9 +1,18 @@
-use crate::error::{EvalexprError, EvalexprResult};
-use std::{convert::TryFrom, ops::RangeInclusive};
+use crate::{
+    error::{EvalexprError, EvalexprResult},
+    token, EmptyContext, Operator, ParseOptions,
+};
+use std::{cmp::Ordering, convert::TryFrom, ops::RangeInclusive, str::FromStr, sync::Arc};
 
+pub mod custom_value;
 mod display;
+mod ordered;
 pub mod value_type;
 
+pub use ordered::OrderedValue;
+
+use custom_value::CustomValue;
+
 /// The type used to represent integers in `Value::Int`.
 pub type IntType = i64;
 
@@ -13,6 +22,10 @@ pub type FloatType = f64;
 /// The type used to represent tuples in `Value::Tuple`.
 pub type TupleType = Vec<Value>;
 
+/// The type used to represent maps in `Value::Map`.
+/// The entries are kept in the order in which they were written, and their keys are unique.
+pub type MapType = Vec<(String, Value)>;
+
 /// The type used to represent empty values in `Value::Empty`.
 pub type EmptyType = ();
 
@@ -34,8 +47,24 @@ pub enum Value {
     Boolean(bool),
     /// A tuple value.
     Tuple(TupleType),
+    /// A map value, i.e. a list of values labeled with unique keys.
+    Map(MapType),
     /// An empty value.
     Empty,
+    /// A value of a user-defined type, see `CustomValue`.
+    /// Custom values cannot be serialized.
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    Custom(Arc<dyn CustomValue>),
+    /// A number with a unit of measurement, see `UnitRegistry`.
+    /// Quantities cannot be serialized.
+    #[cfg(feature = "units_support")]
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    Quantity {
+        /// The amount of the unit.
+        value: FloatType,
+        /// The unit.
+        unit: crate::UnitId,
+    },
 }
 
 impl Value {
@@ -68,11 +97,27 @@ impl Value {
         matches!(self, Value::Tuple(_))
     }
 
+    /// Returns true if `self` is a `Value::Map`.
+    pub fn is_map(&self) -> bool {
+        matches!(self, Value::Map(_))
+    }
+
     /// Returns true if `self` is a `Value::Empty`.
     pub fn is_empty(&self) -> bool {
         matches!(self, Value::Empty)
     }
 
+    /// Returns true if `self` is a `Value::Custom`.
+    pub fn is_custom(&self) -> bool {
+        matches!(self, Value::Custom(_))
+    }
+
+    /// Returns true if `self` is a `Value::Quantity`.
+    #[cfg(feature = "units_support")]
+    pub fn is_quantity(&self) -> bool {
+        matches!(self, Value::Quantity { .. })
+    }
+
     /// Clones the value stored in `self` as `String`, or returns `Err` if `self` is not a `Value::String`.
     pub fn as_string(&self) -> EvalexprResult<String> {
         match self {
@@ -123,6 +168,32 @@ impl Value {
         }
     }
 
+    /// Borrows the elements of the value stored in `self`, or returns `Err` if `self` is not a `Value::Tuple`.
+    /// Unlike `as_tuple`, this does not clone the elements.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// let context = context_map! {
+    ///     "sum" => Function::new(|argument| {
+    ///         let mut sum = 0;
+    ///         for element in argument.as_slice()? {
+    ///             sum += element.as_int()?;
+    ///         }
+    ///         Ok(Value::Int(sum))
+    ///     }),
+    /// }.unwrap(); // Do proper error handling here
+    /// assert_eq!(eval_with_context("sum(1, 2, 3)", &context), Ok(Value::from(6)));
+    /// ```
+    pub fn as_slice(&self) -> EvalexprResult<&[Value]> {
+        match self {
+            Value::Tuple(tuple) => Ok(tuple),
+            value => Err(EvalexprError::expected_tuple(value.clone())),
+        }
+    }
+
     /// Clones the value stored in `self` as `TupleType` or returns `Err` if `self` is not a `Value::Tuple` of the required length.
     pub fn as_fixed_len_tuple(&self, len: usize) -> EvalexprResult<TupleType> {
         match self {
@@ -154,6 +225,25 @@ impl Value {
         }
     }
 
+    /// Clones the value stored in `self` as `MapType`, or returns `Err` if `self` is not a `Value::Map`.
+    pub fn as_map(&self) -> EvalexprResult<MapType> {
+        match self {
+            Value::Map(map) => Ok(map.clone()),
+            value => Err(EvalexprError::expected_map(value.clone())),
+        }
+    }
+
+    /// Returns a reference to the value stored under the given key, or `None` if `self` is not a `Value::Map` or has no such key.
+    pub fn get_field(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(map) => map
+                .iter()
+                .find(|(entry_key, _)| entry_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
     /// Returns `()`, or returns`Err` if `self` is not a `Value::Tuple`.
     pub fn as_empty(&self) -> EvalexprResult<()> {
         match self {
@@ -161,6 +251,131 @@ impl Value {
             value => Err(EvalexprError::expected_empty(value.clone())),
         }
     }
+
+    /// Adds `other` to `self` like the `+` operator, concatenating strings and failing on integer overflow.
+    ///
+    /// The arithmetic methods of `Value` evaluate the operators of the expression format,
+    /// so they return the same values and errors as an expression with the operator,
+    /// including for custom values and, with the `units_support` feature, for quantities of the standard unit registry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use evalexpr::*;
+    ///
+    /// assert_eq!(Value::from(1).checked_add(&Value::from(2.5)), Ok(Value::from(3.5)));
+    /// assert_eq!(Value::from("a").checked_add(&Value::from("b")), Ok(Value::from("ab")));
+    /// assert_eq!(
+    ///     Value::from(IntType::MAX).checked_add(&Value::from(1)),
+    ///     eval("9223372036854775807 + 1")
+    /// );
+    /// ```
+    pub fn checked_add(&self, other: &Value) -> EvalexprResult<Value> {
+        self.eval_operator(Operator::Add, Some(other))
+    }
+
+    /// Subtracts `other` from `self` like the `-` operator, failing on integer overflow.
+    pub fn checked_sub(&self, other: &Value) -> EvalexprResult<Value> {
+        self.eval_operator(Operator::Sub, Some(other))
+    }
+
+    /// Multiplies `self` with `other` like the `*` operator, failing on integer overflow.
+    pub fn checked_mul(&self, other: &Value) -> EvalexprResult<Value> {
+        self.eval_operator(Operator::Mul, Some(other))
+    }
+
+    /// Divides `self` by `other` like the `/` operator, which divides integers as integers and fails on an integer division by zero.
+    pub fn checked_div(&self, other: &Value) -> EvalexprResult<Value> {
+        self.eval_operator(Operator::Div, Some(other))
+    }
+
+    /// Negates `self` like the unary `-` operator, failing on integer overflow.
+    pub fn checked_neg(&self) -> EvalexprResult<Value> {
+        self.eval_operator(Operator::Neg, None)
+    }
+
+    /// Negates `self` like the `!` operator, failing if `self` is not a `Value::Boolean`.
+    pub fn checked_not(&self) -> EvalexprResult<Value> {
+        self.eval_operator(Operator::Not, None)
+    }
+
+    /// Evaluates the given operator with `self` and the optional second operand as arguments.
+    fn eval_operator(&self, operator: Operator, other: Option<&Value>) -> EvalexprResult<Value> {
+        let arguments = match other {
+            Some(other) => vec![self.clone(), other.clone()],
+            None => vec![self.clone()],
+        };
+        operator.eval(&arguments, &EmptyContext)
+    }
+}
+
+/// Compares values like the comparison operators of expressions, except that `Value::Empty` is less than every other value and equal to itself.
+///
+/// Integers and floats are compared numerically, strings lexicographically, and tuples lexicographically element by element,
+/// where a tuple that is a prefix of another tuple is the lesser one.
+/// Other values of the same type, like booleans and maps, are only comparable if they are equal,
+/// and values of different types are incomparable, except for `Value::Empty` and numbers.
+/// To agree with the equality of `Value`, an integer and a float with the same numerical value are incomparable, as are NaNs.
+/// For a total order over all values, use `OrderedValue`.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// let mut values = vec![Value::from(2.5), Value::from(2), Value::Empty, Value::from(-1)];
+/// values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+/// assert_eq!(
+///     values,
+///     vec![Value::Empty, Value::from(-1), Value::from(2), Value::from(2.5)]
+/// );
+/// assert!(Value::from(5) > Value::from(1.0));
+/// assert_eq!(Value::from(true).partial_cmp(&Value::from(0)), None);
+/// ```
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Value::Empty, Value::Empty) => Some(Ordering::Equal),
+            (Value::Empty, _) => Some(Ordering::Less),
+            (_, Value::Empty) => Some(Ordering::Greater),
+            (Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+            (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+            (Value::Int(a), Value::Float(b)) => (*a as FloatType)
+                .partial_cmp(b)
+                .filter(|ordering| ordering.is_ne()),
+            (Value::Float(a), Value::Int(b)) => a
+                .partial_cmp(&(*b as FloatType))
+                .filter(|ordering| ordering.is_ne()),
+            (Value::String(a), Value::String(b)) => a.partial_cmp(b),
+            (Value::Tuple(a), Value::Tuple(b)) => a.partial_cmp(b),
+            (a, b) => Some(Ordering::Equal).filter(|_| a == b),
+        }
+    }
+}
+
+/// Parses a single literal in the expression format, i.e. a string, integer, float or boolean literal, where numbers may be negative.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+///
+/// assert_eq!("-0x1f".parse(), Ok(Value::Int(-31)));
+/// assert_eq!("\"a b\"".parse(), Ok(Value::from("a b")));
+/// assert_eq!(
+///     "1 + 2".parse::<Value>(),
+///     Err(EvalexprError::InvalidLiteral("1 + 2".to_string()))
+/// );
+/// ```
+impl FromStr for Value {
+    type Err = EvalexprError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        token::tokenize(string, &ParseOptions::default())
+            .ok()
+            .and_then(|tokens| token::tokens_to_literal(&tokens))
+            .ok_or_else(|| EvalexprError::InvalidLiteral(string.to_string()))
+    }
 }
 
 impl From<String> for Value {
@@ -199,6 +414,20 @@ impl From<TupleType> for Value {
     }
 }
 
+/// Converts a pair into a `Value::Tuple` of two elements, such that a function can return multiple values with `Ok(Value::from((a, b)))`.
+impl<A: Into<Value>, B: Into<Value>> From<(A, B)> for Value {
+    fn from((a, b): (A, B)) -> Self {
+        Value::Tuple(vec![a.into(), b.into()])
+    }
+}
+
+/// Converts a triple into a `Value::Tuple` of three elements.
+impl<A: Into<Value>, B: Into<Value>, C: Into<Value>> From<(A, B, C)> for Value {
+    fn from((a, b, c): (A, B, C)) -> Self {
+        Value::Tuple(vec![a.into(), b.into(), c.into()])
+    }
+}
+
 impl From<Value> for EvalexprResult<Value> {
     fn from(value: Value) -> Self {
         Ok(value)
@@ -271,6 +500,18 @@ impl TryFrom<Value> for TupleType {
     }
 }
 
+impl TryFrom<Value> for MapType {
+    type Error = EvalexprError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        if let Value::Map(value) = value {
+            Ok(value)
+        } else {
+            Err(EvalexprError::ExpectedMap { actual: value })
+        }
+    }
+}
+
 impl TryFrom<Value> for () {
     type Error = EvalexprError;
 
@@ -285,7 +526,10 @@ impl TryFrom<Value> for () {
 
 #[cfg(test)]
 mod tests {
-    use crate::value::{TupleType, Value};
+    use crate::{
+        value::{TupleType, Value},
+        EvalexprError,
+    };
 
     #[test]
     fn test_value_conversions() {
@@ -302,6 +546,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tuple_conversions() {
+        assert_eq!(
+            Value::from((1, "a")),
+            Value::Tuple(vec![Value::Int(1), Value::String("a".to_string())])
+        );
+        assert_eq!(
+            Value::from((2.5, true, ())),
+            Value::Tuple(vec![Value::Float(2.5), Value::Boolean(true), Value::Empty])
+        );
+        assert_eq!(
+            Value::from(((1, 2), Value::from(3))),
+            Value::Tuple(vec![
+                Value::Tuple(vec![Value::Int(1), Value::Int(2)]),
+                Value::Int(3)
+            ])
+        );
+    }
+
+    #[test]
+    fn test_as_slice() {
+        let tuple = Value::from(vec![Value::from(1), Value::from("a")]);
+        let elements = tuple.as_slice().unwrap();
+        assert_eq!(elements, &[Value::from(1), Value::from("a")]);
+        assert!(elements
+            .iter()
+            .zip(tuple.as_slice().unwrap())
+            .all(|(a, b)| std::ptr::eq(a, b)));
+        assert_eq!(Value::from(TupleType::new()).as_slice(), Ok(&[][..]));
+
+        assert_eq!(
+            Value::from(1).as_slice(),
+            Err(EvalexprError::expected_tuple(Value::from(1)))
+        );
+        assert_eq!(
+            Value::Empty.as_slice(),
+            Err(EvalexprError::expected_tuple(Value::Empty))
+        );
+    }
+
     #[test]
     fn test_value_checks() {
         assert!(Value::from("string").is_string());
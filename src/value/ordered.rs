@@ -0,0 +1,136 @@
+use std::cmp::Ordering;
+
+use crate::Value;
+
+/// A value with a total order, such that it can be used as key of a `BTreeMap` or be sorted.
+///
+/// Values of different types are ordered by the rank of their type, which is in ascending order
+/// `Value::Empty`, `Value::Boolean`, `Value::Int`, `Value::Float`, `Value::String`, `Value::Tuple`, `Value::Map`, `Value::Quantity` and `Value::Custom`.
+/// So unlike the operators of expressions, this order does not compare integers with floats numerically, and `1 < 1.0`.
+/// In particular, `Value::Empty` is less than every other value and equal to itself, such that sorting puts empty values first.
+///
+/// Values of the same type are ordered as follows:
+///  * booleans with `false < true`,
+///  * integers numerically,
+///  * floats by `f64::total_cmp`, such that `-0.0 < 0.0` and positive NaN is greater than infinity,
+///  * strings lexicographically by their bytes,
+///  * tuples lexicographically by their elements, such that a prefix is less than the tuple,
+///  * maps lexicographically by their entries in their stored order, comparing the key before the value of each entry,
+///  * quantities by the name of their unit, then by the amount,
+///  * custom values by their type name, then by their display representation.
+///
+/// Two ordered values are equal if they are neither less nor greater than each other.
+/// This differs from the equality of `Value` for NaN, which is equal to itself here,
+/// for quantities with units of the same name but different dimensions or factors, which are equal here if their amounts are,
+/// and for custom values with the same type name and display representation, which are equal here.
+///
+/// # Examples
+///
+/// ```rust
+/// use evalexpr::*;
+/// use std::collections::BTreeMap;
+///
+/// let mut counts = BTreeMap::new();
+/// for value in [Value::from(2), Value::from("a"), Value::from(1), Value::from(2)] {
+///     *counts.entry(OrderedValue(value)).or_insert(0) += 1;
+/// }
+///
+/// let counts: Vec<_> = counts.into_iter().map(|(key, count)| (key.0, count)).collect();
+/// assert_eq!(
+///     counts,
+///     vec![(Value::from(1), 1), (Value::from(2), 2), (Value::from("a"), 1)]
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct OrderedValue(pub Value);
+
+impl OrderedValue {
+    /// Returns the wrapped value.
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+}
+
+impl From<Value> for OrderedValue {
+    fn from(value: Value) -> Self {
+        OrderedValue(value)
+    }
+}
+
+impl PartialEq for OrderedValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OrderedValue {}
+
+impl PartialOrd for OrderedValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        total_cmp(&self.0, &other.0)
+    }
+}
+
+/// Returns the rank of the type of the given value in the total order of `OrderedValue`.
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Empty => 0,
+        Value::Boolean(_) => 1,
+        Value::Int(_) => 2,
+        Value::Float(_) => 3,
+        Value::String(_) => 4,
+        Value::Tuple(_) => 5,
+        Value::Map(_) => 6,
+        #[cfg(feature = "units_support")]
+        Value::Quantity { .. } => 7,
+        Value::Custom(_) => 8,
+    }
+}
+
+/// Compares two values in the total order of `OrderedValue`.
+fn total_cmp(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Empty, Value::Empty) => Ordering::Equal,
+        (Value::Boolean(a), Value::Boolean(b)) => a.cmp(b),
+        (Value::Int(a), Value::Int(b)) => a.cmp(b),
+        (Value::Float(a), Value::Float(b)) => a.total_cmp(b),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Tuple(a), Value::Tuple(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| total_cmp(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Map(a), Value::Map(b)) => a
+            .iter()
+            .zip(b)
+            .map(|((a_key, a), (b_key, b))| a_key.cmp(b_key).then_with(|| total_cmp(a, b)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        #[cfg(feature = "units_support")]
+        (
+            Value::Quantity {
+                value: a,
+                unit: a_unit,
+            },
+            Value::Quantity {
+                value: b,
+                unit: b_unit,
+            },
+        ) => a_unit
+            .name()
+            .cmp(b_unit.name())
+            .then_with(|| a.total_cmp(b)),
+        (Value::Custom(a_custom), Value::Custom(b_custom)) => a_custom
+            .type_name()
+            .cmp(b_custom.type_name())
+            .then_with(|| a.to_string().cmp(&b.to_string())),
+        (a, b) => type_rank(a).cmp(&type_rank(b)),
+    }
+}
@@ -13,8 +13,15 @@ pub enum ValueType {
     Boolean,
     /// The `Value::Tuple` type.
     Tuple,
+    /// The `Value::Map` type.
+    Map,
     /// The `Value::Empty` type.
     Empty,
+    /// The `Value::Custom` type.
+    Custom,
+    /// The `Value::Quantity` type.
+    #[cfg(feature = "units_support")]
+    Quantity,
 }
 
 impl From<&Value> for ValueType {
@@ -25,7 +32,11 @@ impl From<&Value> for ValueType {
             Value::Int(_) => ValueType::Int,
             Value::Boolean(_) => ValueType::Boolean,
             Value::Tuple(_) => ValueType::Tuple,
+            Value::Map(_) => ValueType::Map,
             Value::Empty => ValueType::Empty,
+            Value::Custom(_) => ValueType::Custom,
+            #[cfg(feature = "units_support")]
+            Value::Quantity { .. } => ValueType::Quantity,
         }
     }
 }
@@ -0,0 +1,186 @@
+#![cfg(not(tarpaulin_include))]
+#![cfg(feature = "async_support")]
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use evalexpr::*;
+
+#[tokio::test]
+async fn test_async_functions() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let recorded_calls = calls.clone();
+    let mut context = AsyncFunctionContext::new(
+        context_map! {
+            "a" => 2,
+            "f" => Function::new(|argument| Ok(Value::from(argument.as_int()? + 1)))
+        }
+        .unwrap(),
+    );
+    context.set_async_function(
+        "slow_double".into(),
+        AsyncFunction::new(move |argument| {
+            let argument = argument.clone();
+            let calls = recorded_calls.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                calls.lock().unwrap().push(argument.clone());
+                Ok(Value::from(argument.as_int()? * 2))
+            }
+        }),
+    );
+
+    let eval = |string: &str| build_operator_tree(string).unwrap();
+
+    // Asynchronous, synchronous and builtin functions can be mixed.
+    assert_eq!(
+        eval("slow_double(f(a)) + slow_double(1)")
+            .eval_async(&context)
+            .await,
+        Ok(Value::from(8))
+    );
+    assert_eq!(*calls.lock().unwrap(), vec![Value::from(3), Value::from(1)]);
+    assert_eq!(
+        eval("max(slow_double(a), f(5))").eval_async(&context).await,
+        Ok(Value::from(6))
+    );
+    assert_eq!(
+        eval("slow_double(\"a\")").eval_async(&context).await,
+        Err(EvalexprError::expected_int("a".into()))
+    );
+    assert_eq!(
+        eval("try_or(slow_double(b), 7)").eval_async(&context).await,
+        Ok(Value::from(7))
+    );
+    context.set_async_function(
+        "try_or".into(),
+        AsyncFunction::new(|_| async { Ok(Value::from("async")) }),
+    );
+    assert_eq!(
+        eval("try_or(1, 2)").eval_async(&context).await,
+        Ok(Value::from("async"))
+    );
+    assert_eq!(
+        eval("g(1)").eval_async(&context).await,
+        Err(EvalexprError::FunctionIdentifierNotFound("g".to_string()))
+    );
+
+    // Asynchronous evaluation gives the same results as synchronous evaluation without asynchronous functions.
+    let tree = eval("f(a) * 2 + len(\"abc\")");
+    assert_eq!(
+        tree.eval_async(&context).await,
+        tree.eval_with_context(&context)
+    );
+    assert_eq!(tree.eval_async(context.inner()).await, Ok(Value::from(9)));
+    assert_eq!(
+        eval("1 + 2").eval_async(&EmptyContext).await,
+        Ok(Value::from(3))
+    );
+}
+
+#[tokio::test]
+async fn test_async_evaluation_order() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut inner = HashMapContext::new();
+    for name in ["b", "f"] {
+        let log = log.clone();
+        inner
+            .set_function(
+                name.into(),
+                Function::new(move |_| {
+                    log.lock().unwrap().push(name);
+                    Ok(Value::from(1))
+                }),
+            )
+            .unwrap();
+    }
+    let mut context = AsyncFunctionContext::new(inner);
+    for name in ["a", "c"] {
+        let log = log.clone();
+        context.set_async_function(
+            name.into(),
+            AsyncFunction::new(move |_| {
+                let log = log.clone();
+                async move {
+                    // The faster function is still called later
+                    let delay = if name == "a" { 20 } else { 1 };
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    log.lock().unwrap().push(name);
+                    Ok(Value::from(1))
+                }
+            }),
+        );
+    }
+
+    let tree = build_operator_tree("f(a(), b()) + c()").unwrap();
+    assert_eq!(tree.eval_async(&context).await, Ok(Value::from(2)));
+    assert_eq!(*log.lock().unwrap(), ["a", "b", "f", "c"]);
+}
+
+#[tokio::test]
+async fn test_async_evaluation_is_send() {
+    fn assert_send<T: Send>(_: &T) {}
+
+    let mut context = AsyncFunctionContext::new(context_map! { "a" => 2 }.unwrap());
+    context.set_async_function(
+        "double".into(),
+        AsyncFunction::new(|argument| {
+            let argument = argument.clone();
+            async move { Ok(Value::from(argument.as_int()? * 2)) }
+        }),
+    );
+    let tree = build_operator_tree("try_or(double(a), 0) + (a > 1 ? double(3) : 0)").unwrap();
+    assert_send(&tree.eval_async(&context));
+
+    let result = tokio::spawn(async move { tree.eval_async(&context).await })
+        .await
+        .unwrap();
+    assert_eq!(result, Ok(Value::from(10)));
+}
+
+#[tokio::test]
+async fn test_async_evaluation_depth() {
+    // Expressions evaluated by synchronous functions count as nested evaluations
+    let recursive = Arc::new(std::sync::OnceLock::<AsyncFunctionContext<HashMapContext>>::new());
+    let depths = Arc::new(Mutex::new(Vec::new()));
+    let mut context = HashMapContext::new();
+    context.set_max_evaluation_depth(3);
+    let (inner, recorded_depths) = (recursive.clone(), depths.clone());
+    context
+        .set_function(
+            "recurse".into(),
+            Function::new_with_eval_context(move |_, eval_context| {
+                recorded_depths
+                    .lock()
+                    .unwrap()
+                    .push(eval_context.current_depth());
+                eval_context.evaluate_sub_expression(
+                    &build_operator_tree("recurse()").unwrap(),
+                    inner.get().unwrap(),
+                )
+            }),
+        )
+        .unwrap();
+    recursive.set(AsyncFunctionContext::new(context)).unwrap();
+    let context = recursive.get().unwrap();
+
+    assert_eq!(
+        build_operator_tree("recurse()")
+            .unwrap()
+            .eval_async(context)
+            .await,
+        Err(EvalexprError::EvaluationTooDeep { limit: 3 })
+    );
+    assert_eq!(*depths.lock().unwrap(), vec![1, 2, 3]);
+    // The depth is reset after each synchronous part of the evaluation
+    depths.lock().unwrap().clear();
+    assert_eq!(
+        build_operator_tree("recurse()")
+            .unwrap()
+            .eval_with_context(context),
+        Err(EvalexprError::EvaluationTooDeep { limit: 3 })
+    );
+    assert_eq!(*depths.lock().unwrap(), vec![1, 2, 3]);
+}
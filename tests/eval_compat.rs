@@ -0,0 +1,178 @@
+#![cfg(not(tarpaulin_include))]
+#![cfg(feature = "eval_compat_support")]
+
+use evalexpr::{
+    compat::eval_crate::{parse_compat, translate, CompatIssue},
+    *,
+};
+
+/// Typical expressions of the `eval` crate with their translation and their value in the context of `corpus_context`.
+fn corpus() -> Vec<(&'static str, &'static str, Value)> {
+    let tuple = |values: &[Value]| Value::from(values.to_vec());
+    vec![
+        // Literals
+        ("42", "42", Value::from(42)),
+        ("2.5e1", "2.5e1", Value::from(25.0)),
+        ("true && !false", "true && !false", Value::from(true)),
+        ("null", "()", Value::Empty),
+        ("null == null", "() == ()", Value::from(true)),
+        // Arithmetic divides as floats
+        ("10 / 4", "10 / 4", Value::from(2.5)),
+        ("(price + 5) * 2 % 7", "(price + 5) * 2 % 7", Value::from(2)),
+        ("-price + 1", "-price + 1", Value::from(-9)),
+        // Comparisons of values of different types
+        ("1 == 1.0", "1 == 1.0", Value::from(false)),
+        ("'1' != 1", "\"1\" != 1", Value::from(true)),
+        (
+            "price >= 10 && price < 100",
+            "price >= 10 && price < 100",
+            Value::from(true),
+        ),
+        (
+            "status == 'open' || status == \"closed\"",
+            "status == \"open\" || status == \"closed\"",
+            Value::from(true),
+        ),
+        // Strings
+        (
+            "'it\\'s' + \" \\\"ok\\\"\"",
+            "\"it's\" + \" \\\"ok\\\"\"",
+            Value::from("it's \"ok\""),
+        ),
+        // Member access and indexing
+        ("user.name", "user.name", Value::from("Ada")),
+        (
+            "user['name'] + '!'",
+            "user.name + \"!\"",
+            Value::from("Ada!"),
+        ),
+        ("items[1]", "items.1", Value::from(20)),
+        (
+            "orders[0][\"total\"] > 5",
+            "orders.0.total > 5",
+            Value::from(true),
+        ),
+        ("orders[1].total", "orders.1.total", Value::from(3)),
+        // Arrays
+        (
+            "[1, 2, 3]",
+            "(1, 2, 3)",
+            tuple(&[Value::from(1), Value::from(2), Value::from(3)]),
+        ),
+        ("len([price, 'a'])", "len((price, \"a\"))", Value::from(2)),
+        (
+            "array(1, [2, 3])",
+            "(1, (2, 3))",
+            tuple(&[Value::from(1), tuple(&[Value::from(2), Value::from(3)])]),
+        ),
+        ("[]", "()", Value::Empty),
+        // Functions
+        ("len(items) == 3", "len(items) == 3", Value::from(true)),
+        (
+            "is_empty(status)",
+            "((status) == () ? true : len(status) == 0)",
+            Value::from(false),
+        ),
+        (
+            "!is_empty(items) && max(items) > 25",
+            "!((items) == () ? true : len(items) == 0) && max(items) > 25",
+            Value::from(true),
+        ),
+        (
+            "is_empty([])",
+            "((()) == () ? true : len(()) == 0)",
+            Value::from(true),
+        ),
+        (
+            "is_empty(null)",
+            "((()) == () ? true : len(()) == 0)",
+            Value::from(true),
+        ),
+        (
+            "is_empty(price > 5 ? '' : 'a')",
+            "((price > 5 ? \"\" : \"a\") == () ? true : len(price > 5 ? \"\" : \"a\") == 0)",
+            Value::from(true),
+        ),
+        ("min(price, 3)", "min(price, 3)", Value::from(3)),
+        // Conditional operator
+        (
+            "price > 5 ? 'high' : 'low'",
+            "price > 5 ? \"high\" : \"low\"",
+            Value::from("high"),
+        ),
+    ]
+}
+
+fn corpus_context() -> HashMapContext {
+    let mut context = HashMapContext::new();
+    eval_with_context_mut(
+        "price = 10; status = \"open\"; items = (10, 20, 30); user = {name: \"Ada\"}; orders = ({total: 12}, {total: 3})",
+        &mut context,
+    )
+    .unwrap();
+    context
+}
+
+#[test]
+fn test_eval_crate_corpus() {
+    let context = corpus_context();
+
+    for (expression, translation, value) in corpus() {
+        assert_eq!(
+            translate(expression).as_deref(),
+            Ok(translation),
+            "{:?}",
+            expression
+        );
+        let tree = parse_compat(expression).unwrap_or_else(|issues| {
+            panic!("{:?} is not parsed: {:?}", expression, issues);
+        });
+        assert_eq!(
+            tree.eval_with_context(&context),
+            Ok(value),
+            "{:?}",
+            expression
+        );
+    }
+}
+
+#[test]
+fn test_eval_crate_issues() {
+    let issue = |construct: &str, span: std::ops::Range<usize>| (construct.to_string(), span);
+    let issues = |expression: &str| {
+        parse_compat(expression)
+            .unwrap_err()
+            .into_iter()
+            .map(|issue| (issue.construct, issue.span))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(issues("sum(0..n)"), vec![issue("..", 5..7)]);
+    assert_eq!(issues("[x]"), vec![issue("[x]", 0..3)]);
+    assert_eq!(issues("array(x) == y"), vec![issue("(x)", 5..8)]);
+    assert_eq!(issues("items[i + 1]"), vec![issue("[i + 1]", 5..12)]);
+    assert_eq!(
+        issues("f(x)[0] + a ^ 2"),
+        vec![issue("[0]", 4..7), issue("^", 12..13)]
+    );
+    assert_eq!(issues("f(x).a"), vec![issue(".a", 4..6)]);
+    assert_eq!(issues("a = 1"), vec![issue("=", 2..3)]);
+    assert_eq!(issues("a // b"), vec![issue("//", 2..4)]);
+    assert_eq!(issues("(a + b]"), vec![issue("(a + b]", 0..7)]);
+    assert_eq!(issues("[1, (2"), vec![issue("[", 0..1), issue("(", 4..5)]);
+    assert_eq!(issues("a)"), vec![issue(")", 1..2)]);
+    assert_eq!(issues("'abc"), vec![issue("'abc", 0..4)]);
+    // Invalid expressions are reported as a whole
+    assert_eq!(issues("1 +"), vec![issue("1 +", 0..3)]);
+
+    let issue = CompatIssue {
+        construct: "..".to_string(),
+        span: 5..7,
+        suggestion: "write the elements of the range as an array, like `[0, 1, 2]`".to_string(),
+    };
+    assert_eq!(parse_compat("sum(0..n)"), Err(vec![issue.clone()]));
+    assert_eq!(
+        issue.to_string(),
+        "`..` at 5..7: write the elements of the range as an array, like `[0, 1, 2]`"
+    );
+}
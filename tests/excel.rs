@@ -0,0 +1,190 @@
+#![cfg(not(tarpaulin_include))]
+#![cfg(feature = "excel_support")]
+
+use evalexpr::*;
+
+/// Excel formulas with the expressions they are converted into, which defines the supported subset of Excel formulas.
+const ROUND_TRIPS: &[(&str, &str)] = &[
+    // Literals and names
+    ("=42", "42"),
+    ("=1.5E3", "1.5e3"),
+    ("=.5", "0.5"),
+    ("=TRUE", "true"),
+    ("=false", "false"),
+    ("=\"say \"\"hi\"\"\"", "\"say \\\"hi\\\"\""),
+    ("=net_price * tax.rate", "net_price * tax.rate"),
+    // The leading `=` is optional
+    ("1 + 2", "1 + 2"),
+    ("  = 1 + 2", "1 + 2"),
+    // Arithmetic with Excel's precedence
+    ("=1 + 2 * 3 - 4", "1 + 2 * 3 - 4"),
+    ("=(a + b) * c", "(a + b) * c"),
+    ("=7 / 2", "7 / 2"),
+    ("=2^3^2", "2^3^2"),
+    ("=-2^2", "(-2)^2"),
+    ("=2^-1", "2^-1"),
+    ("=-a * +b", "-a * b"),
+    ("=a - -b", "a - -b"),
+    // Comparisons
+    ("=a = b", "a == b"),
+    ("=a <> b", "a != b"),
+    ("=a < b", "a < b"),
+    ("=a <= b", "a <= b"),
+    ("=a > b", "a > b"),
+    ("=a >= b + 1", "a >= b + 1"),
+    // Concatenation
+    ("=\"Total: \" & a", "str::concat(\"Total: \", a)"),
+    ("=a & \"-\" & b + 1", "str::concat(a, \"-\", b + 1)"),
+    ("=a & b = \"34\"", "str::concat(a, b) == \"34\""),
+    // Functions
+    ("=IF(a > b, \"yes\", \"no\")", "(a > b ? \"yes\" : \"no\")"),
+    ("=if(a > b, 1)", "(a > b ? 1 : false)"),
+    (
+        "=IF(a, IF(b, 1, 2), IF(c, 3, 4))",
+        "(a ? (b ? 1 : 2) : (c ? 3 : 4))",
+    ),
+    ("=1 + IF(a, b, c) * 2", "1 + (a ? b : c) * 2"),
+    ("=AND(a > 0, b > 0)", "all(a > 0, b > 0)"),
+    ("=Or(a > 5)", "any(a > 5)"),
+    ("=NOT(a = b)", "!(a == b)"),
+    ("=MIN(a, b, 2)", "min(a, b, 2)"),
+    ("=MAX(a)", "max(a)"),
+    ("=ABS(b - a * 3)", "math::abs(b - a * 3)"),
+    ("=ROUND(c, 0)", "round(c)"),
+    ("=ROUND(a / b, 2)", "round(a / b * 100) / 100"),
+    ("=ROUND(a + c, 1)", "round((a + c) * 10) / 10"),
+    ("=ROUND(1234, -2)", "round(1234 / 100) * 100"),
+    (
+        "=IF(AND(a >= 1, NOT(b = 0)), ROUND(a / b, 1), -1)",
+        "(all(a >= 1, !(b == 0)) ? round(a / b * 10) / 10 : -1)",
+    ),
+    // Empty formulas
+    ("=", ""),
+    ("", ""),
+];
+
+#[test]
+fn test_excel_round_trips() {
+    let options = ParseOptions::new().float_division(true);
+    let context =
+        context_map! { "a" => 3, "b" => 4, "c" => 2.5, "net_price" => 10, "tax.rate" => 0.2 }
+            .unwrap();
+
+    for (formula, expression) in ROUND_TRIPS {
+        let converted = from_excel_formula(formula).unwrap_or_else(|error| {
+            panic!("{:?} is not converted: {}", formula, error);
+        });
+        let expected = build_operator_tree_with_options(expression, &options).unwrap();
+        assert_eq!(
+            converted, expected,
+            "{:?} is not converted into {:?}",
+            formula, expression
+        );
+        assert_eq!(
+            converted.eval_with_context(&context),
+            expected.eval_with_context(&context),
+            "{:?}",
+            formula
+        );
+    }
+}
+
+#[test]
+fn test_excel_semantics() {
+    let context = context_map! { "a" => 3, "b" => 4, "name" => "Ada" }.unwrap();
+    let eval = |formula: &str| {
+        from_excel_formula(formula)
+            .unwrap()
+            .eval_with_context(&context)
+    };
+
+    // Division is not truncating
+    assert_eq!(eval("=7 / 2"), Ok(Value::from(3.5)));
+    // Negation binds stronger than exponentiation
+    assert_eq!(eval("=-2^2"), Ok(Value::from(4.0)));
+    assert_eq!(eval("=0 - 2^2"), Ok(Value::from(-4.0)));
+    // Concatenation converts numbers into text and does not quote strings
+    assert_eq!(
+        eval("=\"Hi \" & name & \", \" & a + b"),
+        Ok(Value::from("Hi Ada, 7"))
+    );
+    assert_eq!(eval("=IF(a < b, \"a\")"), Ok(Value::from("a")));
+    assert_eq!(eval("=IF(a > b, \"a\")"), Ok(Value::from(false)));
+    // Only the chosen value of IF is evaluated
+    assert_eq!(eval("=IF(TRUE, 1, missing)"), Ok(Value::from(1)));
+    assert_eq!(eval("=IF(a > b, 1 / missing, 2)"), Ok(Value::from(2)));
+    assert_eq!(eval("=ROUND(2 / 3, 2)"), Ok(Value::from(0.67)));
+    assert_eq!(eval("=ROUND(1250, -2)"), Ok(Value::from(1300.0)));
+    assert_eq!(eval("=ROUND(-2.5, 0)"), Ok(Value::from(-3.0)));
+}
+
+#[test]
+fn test_excel_errors() {
+    let unsupported = |construct: &str, span: std::ops::Range<usize>| {
+        Err(EvalexprError::UnsupportedExcelConstruct {
+            construct: construct.to_string(),
+            span,
+        })
+    };
+    let cell_reference = |reference: &str, span: std::ops::Range<usize>| {
+        Err(EvalexprError::ExcelCellReference {
+            reference: reference.to_string(),
+            span,
+        })
+    };
+
+    assert_eq!(from_excel_formula("=A1 + 1"), cell_reference("A1", 1..3));
+    assert_eq!(
+        from_excel_formula("= 2 * $B$12"),
+        cell_reference("$B$12", 6..11)
+    );
+    assert_eq!(
+        from_excel_formula("=SUM(XFD7)"),
+        cell_reference("XFD7", 5..9)
+    );
+    assert_eq!(from_excel_formula("=SUM(a, b)"), unsupported("SUM", 1..4));
+    assert_eq!(from_excel_formula("=a%"), unsupported("%", 2..3));
+    assert_eq!(from_excel_formula("=first:last"), unsupported(":", 6..7));
+    assert_eq!(from_excel_formula("=Sheet2!total"), unsupported("!", 7..8));
+    assert_eq!(from_excel_formula("={1, 2}"), unsupported("{", 1..2));
+    assert_eq!(from_excel_formula("=a$b"), unsupported("a$b", 1..4));
+    assert_eq!(
+        from_excel_formula("=ROUND(a, b)"),
+        unsupported("ROUND", 1..6)
+    );
+    assert_eq!(from_excel_formula("=a b"), unsupported("b", 3..4));
+    assert_eq!(from_excel_formula("=1 +"), unsupported("+", 3..4));
+    assert_eq!(from_excel_formula("=IF(a, , 1)"), unsupported(",", 7..8));
+    assert_eq!(
+        from_excel_formula("=(1 + 2"),
+        Err(EvalexprError::UnmatchedLBrace)
+    );
+    assert_eq!(
+        from_excel_formula("=MAX("),
+        Err(EvalexprError::UnmatchedLBrace)
+    );
+    assert_eq!(
+        from_excel_formula("=1 + 2)"),
+        Err(EvalexprError::UnmatchedRBrace)
+    );
+    assert_eq!(
+        from_excel_formula("=\"abc"),
+        Err(EvalexprError::UnmatchedDoubleQuote)
+    );
+    assert_eq!(
+        from_excel_formula("=IF(a)"),
+        Err(EvalexprError::wrong_function_argument_amount_range(
+            1,
+            2..=3
+        ))
+    );
+
+    let error = from_excel_formula("=A1").unwrap_err();
+    assert!(error.is_parse_error());
+    assert_eq!(error.code(), "excel_cell_reference");
+    assert!(error.to_string().contains("named variable"));
+    assert_eq!(
+        from_excel_formula("=a%").unwrap_err().code(),
+        "unsupported_excel_construct"
+    );
+}
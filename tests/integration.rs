@@ -1,7 +1,13 @@
 #![cfg(not(tarpaulin_include))]
 
 use evalexpr::{error::*, *};
-use std::convert::TryFrom;
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    convert::TryFrom,
+    sync::Arc,
+};
 
 #[test]
 fn test_unary_examples() {
@@ -469,11 +475,11 @@ fn test_builtin_functions() {
     );
     assert_eq!(
         eval("contains_any(\"foo\", \"bar\")"),
-        Err(EvalexprError::expected_tuple(Value::String("foo".into())))
+        Ok(Value::Boolean(false))
     );
     assert_eq!(
-        eval("contains_any((\"foo\", \"bar\"), \"buzz\")"),
-        Err(EvalexprError::expected_tuple(Value::String("buzz".into())))
+        eval("contains_any((\"foo\", \"bar\"), \"bar\")"),
+        Ok(Value::Boolean(true))
     );
     assert_eq!(
         eval("contains_any((\"foo\", \"bar\"), (\"buzz\", (1, 2, 3)))"),
@@ -515,6 +521,19 @@ fn test_builtin_functions() {
         Ok(Value::String(String::from("(1, 2, 3)")))
     );
     assert_eq!(eval("str::from()"), Ok(Value::String(String::from("()"))));
+    assert_eq!(
+        eval("str::concat(\"a\", 1, 2.5, true)"),
+        Ok(Value::String(String::from("a12.5true")))
+    );
+    assert_eq!(
+        eval("str::concat((\"a\", \"b\"))"),
+        Ok(Value::String(String::from("ab")))
+    );
+    assert_eq!(
+        eval("str::concat(\"a\", (1, 2))"),
+        Ok(Value::String(String::from("a(1, 2)")))
+    );
+    assert_eq!(eval("str::concat()"), Ok(Value::String(String::new())));
     assert_eq!(
         eval("str::substring(\"foobar\", 3)"),
         Ok(Value::String(String::from("bar")))
@@ -582,6 +601,7 @@ fn test_errors() {
 }
 
 #[test]
+#[allow(clippy::legacy_numeric_constants)]
 fn test_no_panic() {
     assert!(eval(&format!(
         "{} + {}",
@@ -707,7 +727,7 @@ fn test_shortcut_functions() {
         })
     );
     assert_eq!(
-        eval_int("(,);."),
+        eval_int("((), ());."),
         Err(EvalexprError::VariableIdentifierNotFound(".".to_owned()))
     );
     assert_eq!(eval_int_with_context("3", &context), Ok(3));
@@ -718,7 +738,7 @@ fn test_shortcut_functions() {
         })
     );
     assert_eq!(
-        eval_int_with_context("(,);.", &context),
+        eval_int_with_context("((), ());.", &context),
         Err(EvalexprError::VariableIdentifierNotFound(".".to_owned()))
     );
     assert_eq!(eval_int_with_context_mut("3", &mut context), Ok(3));
@@ -729,7 +749,7 @@ fn test_shortcut_functions() {
         })
     );
     assert_eq!(
-        eval_int_with_context_mut("(,);.", &mut context),
+        eval_int_with_context_mut("((), ());.", &mut context),
         Err(EvalexprError::VariableIdentifierNotFound(".".to_owned()))
     );
 
@@ -855,7 +875,7 @@ fn test_shortcut_functions() {
     assert_eq!(eval_empty(""), Ok(EMPTY_VALUE));
     assert_eq!(eval_empty("()"), Ok(EMPTY_VALUE));
     assert_eq!(
-        eval_empty("(,)"),
+        eval_empty("((), ())"),
         Err(EvalexprError::ExpectedEmpty {
             actual: Value::Tuple(vec![Value::Empty, Value::Empty])
         })
@@ -867,7 +887,7 @@ fn test_shortcut_functions() {
     assert_eq!(eval_empty_with_context("", &context), Ok(EMPTY_VALUE));
     assert_eq!(eval_empty_with_context("()", &context), Ok(EMPTY_VALUE));
     assert_eq!(
-        eval_empty_with_context("(,)", &context),
+        eval_empty_with_context("((), ())", &context),
         Err(EvalexprError::ExpectedEmpty {
             actual: Value::Tuple(vec![Value::Empty, Value::Empty])
         })
@@ -885,7 +905,7 @@ fn test_shortcut_functions() {
         Ok(EMPTY_VALUE)
     );
     assert_eq!(
-        eval_empty_with_context_mut("(,)", &mut context),
+        eval_empty_with_context_mut("((), ())", &mut context),
         Err(EvalexprError::ExpectedEmpty {
             actual: Value::Tuple(vec![Value::Empty, Value::Empty])
         })
@@ -1012,7 +1032,7 @@ fn test_shortcut_functions() {
         })
     );
     assert_eq!(
-        build_operator_tree("(,);.").unwrap().eval_int(),
+        build_operator_tree("((), ());.").unwrap().eval_int(),
         Err(EvalexprError::VariableIdentifierNotFound(".".to_owned()))
     );
     assert_eq!(
@@ -1030,7 +1050,7 @@ fn test_shortcut_functions() {
         })
     );
     assert_eq!(
-        build_operator_tree("(,);.")
+        build_operator_tree("((), ());.")
             .unwrap()
             .eval_int_with_context(&context),
         Err(EvalexprError::VariableIdentifierNotFound(".".to_owned()))
@@ -1050,7 +1070,7 @@ fn test_shortcut_functions() {
         })
     );
     assert_eq!(
-        build_operator_tree("(,);.")
+        build_operator_tree("((), ());.")
             .unwrap()
             .eval_int_with_context_mut(&mut context),
         Err(EvalexprError::VariableIdentifierNotFound(".".to_owned()))
@@ -1233,7 +1253,7 @@ fn test_shortcut_functions() {
         Ok(EMPTY_VALUE)
     );
     assert_eq!(
-        build_operator_tree("(,)").unwrap().eval_empty(),
+        build_operator_tree("((), ())").unwrap().eval_empty(),
         Err(EvalexprError::ExpectedEmpty {
             actual: Value::Tuple(vec![Value::Empty, Value::Empty])
         })
@@ -1255,7 +1275,7 @@ fn test_shortcut_functions() {
         Ok(EMPTY_VALUE)
     );
     assert_eq!(
-        build_operator_tree("(,)")
+        build_operator_tree("((), ())")
             .unwrap()
             .eval_empty_with_context(&context),
         Err(EvalexprError::ExpectedEmpty {
@@ -1281,7 +1301,7 @@ fn test_shortcut_functions() {
         Ok(EMPTY_VALUE)
     );
     assert_eq!(
-        build_operator_tree("(,)")
+        build_operator_tree("((), ())")
             .unwrap()
             .eval_empty_with_context_mut(&mut context),
         Err(EvalexprError::ExpectedEmpty {
@@ -1296,6 +1316,46 @@ fn test_shortcut_functions() {
     );
 }
 
+#[test]
+fn test_eval_or() {
+    let default = Value::from("n/a");
+    let mut context = context_map! { "a" => 6, "b" => 0 }.unwrap();
+
+    // A valid expression returns its value
+    assert_eq!(eval_or("1 + 2", default.clone()), Value::from(3));
+    assert_eq!(
+        eval_or_with_context("a * 2", default.clone(), &context),
+        Value::from(12)
+    );
+    // A parse error returns the default
+    assert_eq!(eval_or("(1 + 2", default.clone()), default);
+    assert_eq!(
+        eval_or_with_context("a *", default.clone(), &context),
+        default
+    );
+    // An evaluation error returns the default
+    assert_eq!(eval_or("1 + true", default.clone()), default);
+    assert_eq!(
+        eval_or_with_context("a / b", default.clone(), &context),
+        default
+    );
+    assert_eq!(
+        eval_or_with_context("unknown", default.clone(), &context),
+        default
+    );
+
+    // Assignments before the error are kept in a mutable context
+    assert_eq!(
+        eval_or_with_context_mut("c = a + 1; c / b", default.clone(), &mut context),
+        default
+    );
+    assert_eq!(context.get_value("c"), Some(&Value::from(7)));
+    assert_eq!(
+        eval_or_with_context_mut("c += 1; c", default, &mut context),
+        Value::from(8)
+    );
+}
+
 #[test]
 fn test_whitespace() {
     assert!(eval_boolean("2 < = 3").is_err());
@@ -1787,6 +1847,116 @@ fn test_iterators() {
     let mut iter = tree.iter_function_identifiers();
     assert_eq!(iter.next(), Some("fun"));
     assert_eq!(iter.next(), None);
+
+    let mut iter = tree.iter_literals();
+    assert_eq!(iter.next(), Some(&Value::from(5)));
+    assert_eq!(iter.next(), Some(&Value::from(3)));
+    assert_eq!(iter.next(), Some(&Value::from(4)));
+    assert_eq!(iter.next(), None);
+}
+
+#[test]
+fn test_function_calls() {
+    let calls = |expression: &str| {
+        let tree = build_operator_tree(expression).unwrap();
+        tree.function_calls()
+            .map(|call| {
+                assert_eq!(call.span, None);
+                (call.name.to_string(), call.argument_count)
+            })
+            .collect::<Vec<_>>()
+    };
+    let call = |name: &str, argument_count| (name.to_string(), argument_count);
+
+    assert_eq!(calls("1 + a"), vec![]);
+    assert_eq!(calls("f()"), vec![call("f", Some(0))]);
+    assert_eq!(calls("f(a)"), vec![call("f", None)]);
+    assert_eq!(calls("f(a, b)"), vec![call("f", Some(2))]);
+    assert_eq!(calls("f(a, b, c + 1)"), vec![call("f", Some(3))]);
+    // A variable might hold a tuple, which is passed as multiple arguments
+    assert_eq!(calls("t = (1, 2); f(t)"), vec![call("f", None)]);
+    assert_eq!(calls("f((a, b))"), vec![call("f", Some(2))]);
+    assert_eq!(calls("f(a + 1)"), vec![call("f", None)]);
+    assert_eq!(calls("f(1)"), vec![call("f", Some(1))]);
+    assert_eq!(calls("f(\"a\")"), vec![call("f", Some(1))]);
+    assert_eq!(calls("f(a > 1)"), vec![call("f", Some(1))]);
+    assert_eq!(calls("f(!a)"), vec![call("f", Some(1))]);
+    assert_eq!(calls("f({a: 1})"), vec![call("f", Some(1))]);
+    // Nested calls, also in branches, in pre-order
+    assert_eq!(
+        calls("f(g(a), h(1, i()))"),
+        vec![
+            call("f", Some(2)),
+            call("g", None),
+            call("h", Some(2)),
+            call("i", Some(0)),
+        ]
+    );
+    assert_eq!(
+        calls("if(a, g(b, c), try_or(h(1), 0))"),
+        vec![
+            call("if", Some(3)),
+            call("g", Some(2)),
+            call("try_or", Some(2)),
+            call("h", Some(1)),
+        ]
+    );
+    assert_eq!(calls("f(g(a))"), vec![call("f", None), call("g", None)]);
+    assert_eq!(
+        calls("x = f(1); g(2)"),
+        vec![call("f", Some(1)), call("g", Some(1))]
+    );
+
+    // The node of a call includes itself
+    let tree = build_operator_tree("f(1, 2)").unwrap();
+    let f = &tree.children()[0];
+    assert_eq!(f.function_calls().count(), 1);
+
+    // Spans are known if the source text is preserved
+    let options = ParseOptions::new().preserve_source_text(true);
+    let tree = build_operator_tree_with_options("1 + f( a , g(b) )", &options).unwrap();
+    let calls: Vec<_> = tree.function_calls().collect();
+    assert_eq!(
+        calls,
+        vec![
+            FunctionCall {
+                name: "f",
+                argument_count: Some(2),
+                span: Some(4..17),
+            },
+            FunctionCall {
+                name: "g",
+                argument_count: None,
+                span: Some(11..15),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_literals() {
+    assert_eq!(
+        build_operator_tree("2 * x + 3.5").unwrap().literals(),
+        vec![Value::Int(2), Value::Float(3.5)]
+    );
+    // `()` is an empty pair of parentheses rather than a literal
+    assert_eq!(
+        build_operator_tree("a = \"s\"; b = (true, ()); 2 * 2")
+            .unwrap()
+            .literals(),
+        vec![
+            Value::from("s"),
+            Value::from(true),
+            Value::Int(2),
+            Value::Int(2)
+        ]
+    );
+    assert_eq!(
+        build_operator_tree("-1 + f(x)").unwrap().literals(),
+        vec![Value::Int(1)]
+    );
+    assert_eq!(build_operator_tree("a + b").unwrap().literals(), vec![]);
+    assert_eq!(build_operator_tree("").unwrap().literals(), vec![]);
 }
 
 #[test]
@@ -1830,6 +2000,78 @@ fn test_long_expression_i89() {
     );
 }
 
+#[test]
+fn test_value_arithmetic() {
+    let values = [
+        Value::Int(0),
+        Value::Int(7),
+        Value::Int(-2),
+        Value::Int(IntType::MAX),
+        Value::Int(IntType::MIN),
+        Value::Float(1.5),
+        Value::Float(-0.25),
+        Value::from("ab"),
+        Value::Boolean(true),
+        Value::Empty,
+        Value::from(vec![Value::Int(1)]),
+    ];
+    type Binary = fn(&Value, &Value) -> EvalexprResult<Value>;
+    type Unary = fn(&Value) -> EvalexprResult<Value>;
+    let binary: [(&str, Binary); 4] = [
+        ("a + b", Value::checked_add),
+        ("a - b", Value::checked_sub),
+        ("a * b", Value::checked_mul),
+        ("a / b", Value::checked_div),
+    ];
+    let unary: [(&str, Unary); 2] = [("-a", Value::checked_neg), ("!a", Value::checked_not)];
+
+    // The methods agree with the operators on all combinations of values, including the errors
+    for a in &values {
+        let context = context_map! { "a" => a.clone() }.unwrap();
+        for (expression, method) in unary {
+            assert_eq!(
+                method(a),
+                eval_with_context(expression, &context),
+                "{} with a = {}",
+                expression,
+                a
+            );
+        }
+        for b in &values {
+            let context = context_map! { "a" => a.clone(), "b" => b.clone() }.unwrap();
+            for (expression, method) in binary {
+                assert_eq!(
+                    method(a, b),
+                    eval_with_context(expression, &context),
+                    "{} with a = {}, b = {}",
+                    expression,
+                    a,
+                    b
+                );
+            }
+        }
+    }
+
+    assert_eq!(Value::Int(7).checked_div(&Value::Int(2)), Ok(Value::Int(3)));
+    assert_eq!(
+        Value::Int(7).checked_div(&Value::Int(0)),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::Int(7),
+            divisor: Value::Int(0)
+        })
+    );
+    assert_eq!(
+        Value::Int(IntType::MIN).checked_neg(),
+        Err(EvalexprError::NegationError {
+            argument: Value::Int(IntType::MIN)
+        })
+    );
+    assert_eq!(
+        Value::Boolean(true).checked_not(),
+        Ok(Value::Boolean(false))
+    );
+}
+
 #[test]
 fn test_value_type() {
     assert_eq!(
@@ -2011,25 +2253,26 @@ fn test_value_type() {
 
 #[test]
 fn test_parenthese_combinations() {
-    // These are from issue #94
+    // These are from issue #94, literals followed by parentheses are calls of values that are not functions
     assert_eq!(
         eval("123(1*2)"),
-        Err(EvalexprError::MissingOperatorOutsideOfBrace)
-    );
-    assert_eq!(
-        eval("1()"),
-        Err(EvalexprError::MissingOperatorOutsideOfBrace)
+        Err(EvalexprError::NotCallable(Value::Int(123)))
     );
+    assert_eq!(eval("1()"), Err(EvalexprError::NotCallable(Value::Int(1))));
     assert_eq!(
         eval("1()()()()"),
-        Err(EvalexprError::MissingOperatorOutsideOfBrace)
+        Err(EvalexprError::NotCallable(Value::Int(1)))
     );
     assert_eq!(
         eval("1()()()(9)()()"),
-        Err(EvalexprError::MissingOperatorOutsideOfBrace)
+        Err(EvalexprError::NotCallable(Value::Int(1)))
     );
     assert_eq!(
         eval_with_context("a+100(a*2)", &context_map! {"a" => 4}.unwrap()),
+        Err(EvalexprError::NotCallable(Value::Int(100)))
+    );
+    assert_eq!(
+        eval("(1)(2)"),
         Err(EvalexprError::MissingOperatorOutsideOfBrace)
     );
     assert_eq!(eval_int("(((1+2)*(3+4)+(5-(6)))/((7-8)))"), Ok(-20));
@@ -2260,7 +2503,7 @@ fn assignment_lhs_is_identifier() {
                 Operator::Const {
                     value: Value::Int(1)
                 }
-            ] if value == "a"
+            ] if &**value == "a"
         ),
         "actual: {:#?}",
         operators
@@ -2345,12 +2588,7 @@ fn test_comments() {
         Ok(Value::Int(3))
     );
 
-    assert_eq!(
-        eval("0 /*"),
-        Err(EvalexprError::CustomMessage(
-            "unmatched inline comment".into()
-        ))
-    );
+    assert_eq!(eval("0 /*"), Err(EvalexprError::UnmatchedInlineComment));
 
     assert_eq!(
         eval("1 % 4 + /*inline comment*/ 6 /*END*/"),
@@ -2406,3 +2644,5206 @@ fn test_clear() {
     assert!(context.get_value("five").is_none());
     assert!(eval_with_context("abc(5)", &context).is_err());
 }
+
+#[test]
+fn test_keyword_logical_operators() {
+    let options = ParseOptions::new().keyword_logical_operators(true);
+    assert_eq!(
+        build_operator_tree_with_options("true and false or not false", &options)
+            .unwrap()
+            .eval_boolean(),
+        Ok(true)
+    );
+    assert_eq!(
+        build_operator_tree_with_options("not true or false", &options)
+            .unwrap()
+            .eval_boolean(),
+        Ok(false)
+    );
+    // Keywords have the same precedence as the operators they stand for
+    assert_eq!(
+        build_operator_tree_with_options("false and true or true", &options).unwrap(),
+        build_operator_tree("false && true || true").unwrap()
+    );
+
+    // Without the option, `and` is an ordinary identifier
+    let context = context_map! {
+        "and" => 5,
+    }
+    .unwrap();
+    assert_eq!(eval_int_with_context("and + 1", &context), Ok(6));
+    assert_eq!(
+        build_operator_tree("and")
+            .unwrap()
+            .iter_identifiers()
+            .next(),
+        Some("and")
+    );
+}
+
+#[test]
+fn test_identifier_validator() {
+    let options = ParseOptions::new().identifier_validator(snake_case_identifier_validator);
+    assert_eq!(
+        build_operator_tree_with_options("userName > 3", &options),
+        Err(EvalexprError::InvalidIdentifier {
+            identifier: "userName".to_string(),
+            reason: "identifiers must be lower-case snake_case".to_string(),
+            span: 0..8,
+        })
+    );
+    assert!(build_operator_tree_with_options("user_name2 > 3", &options).is_ok());
+    assert!(build_operator_tree_with_options("math::sqrt(user_name)", &options).is_ok());
+    assert!(build_operator_tree_with_options("_a", &options).is_err());
+    assert!(build_operator_tree_with_options("Math::sqrt(a)", &options).is_err());
+
+    let known = ["a", "f"].iter().map(|s| s.to_string()).collect();
+    let options = ParseOptions::new().identifier_validator(known_identifier_validator(known));
+    assert!(build_operator_tree_with_options("a = f(a)", &options).is_ok());
+    assert_eq!(
+        build_operator_tree_with_options("a + g(a)", &options),
+        Err(EvalexprError::InvalidIdentifier {
+            identifier: "g".to_string(),
+            reason: "unknown identifier".to_string(),
+            span: 4..5,
+        })
+    );
+
+    // Without a validator, all identifiers are accepted
+    assert!(build_operator_tree("userName > 3").is_ok());
+}
+
+#[test]
+fn test_three_way_comparison() {
+    assert_eq!(eval("1 <=> 2"), Ok(Value::Int(-1)));
+    assert_eq!(eval("2 <=> 2"), Ok(Value::Int(0)));
+    assert_eq!(eval("3 <=> 1"), Ok(Value::Int(1)));
+    // Integers and floats are compared numerically
+    assert_eq!(eval("2 <=> 2.0"), Ok(Value::Int(0)));
+    assert_eq!(eval("1.5 <=> 2"), Ok(Value::Int(-1)));
+    assert_eq!(eval("\"apple\" <=> \"banana\""), Ok(Value::Int(-1)));
+    assert_eq!(eval("\"b\" <=> \"b\""), Ok(Value::Int(0)));
+    assert_eq!(eval("(1, 3) <=> (1, 2)"), Ok(Value::Int(1)));
+    // It binds like the other comparators
+    assert_eq!(eval("1 + 2 <=> 2 * 2"), Ok(Value::Int(-1)));
+    assert_eq!(eval("(3 <=> 1) == 1"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("2<=>1"), Ok(Value::Int(1)));
+    assert_eq!(eval("1 <= 2"), Ok(Value::Boolean(true)));
+    assert_eq!(
+        build_operator_tree("a <=> b").unwrap().children()[0].operator(),
+        &Operator::Cmp
+    );
+    let tree = build_operator_tree("a <=> b").unwrap();
+    assert_eq!(Node::from_bytes(&tree.to_bytes().unwrap()), Ok(tree));
+
+    assert_eq!(
+        eval("1 <=> \"1\""),
+        Err(EvalexprError::wrong_type_combination(
+            Operator::Cmp,
+            vec![ValueType::Int, ValueType::String]
+        ))
+    );
+    assert_eq!(
+        eval("true <=> false"),
+        Err(EvalexprError::wrong_type_combination(
+            Operator::Cmp,
+            vec![ValueType::Boolean, ValueType::Boolean]
+        ))
+    );
+    // NaN is neither lower than, equal to nor greater than any number
+    assert!(matches!(
+        eval("1 <=> math::sqrt(-1)"),
+        Err(EvalexprError::IncomparableValues { index: 1, actual: Value::Float(nan), .. }) if nan.is_nan()
+    ));
+    assert!(matches!(
+        eval("(math::sqrt(-1), 1) <=> (1, 1)"),
+        Err(EvalexprError::IncomparableValues { index: 0, .. })
+    ));
+}
+
+#[test]
+fn test_tuple_ordering() {
+    assert_eq!(eval_boolean("(1, 2) < (1, 3)"), Ok(true));
+    assert_eq!(eval_boolean("(1, 2) > (1, 3)"), Ok(false));
+    assert_eq!(eval_boolean("(2, 0) > (1, 3)"), Ok(true));
+    assert_eq!(eval_boolean("(1, 2) <= (1, 2)"), Ok(true));
+    assert_eq!(eval_boolean("(1, 2) >= (1, 2)"), Ok(true));
+    assert_eq!(eval_boolean("(1, 2.5) < (1, 3)"), Ok(true));
+    assert_eq!(eval_boolean("(\"a\", 2) < (\"b\", 1)"), Ok(true));
+    assert_eq!(eval_boolean("((1, 2), 3) < ((1, 3), 0)"), Ok(true));
+
+    // A tuple that is a prefix of another tuple is the lesser one
+    assert_eq!(eval_boolean("(1, 2) < (1, 2, 3)"), Ok(true));
+    assert_eq!(eval_boolean("(1, 2, 3) > (1, 2)"), Ok(true));
+    assert_eq!(eval_boolean("(1, 3) > (1, 2, 3)"), Ok(true));
+
+    assert_eq!(
+        eval_boolean("(1, \"a\") < (1, 2)"),
+        Err(EvalexprError::wrong_type_combination(
+            Operator::Lt,
+            vec![ValueType::String, ValueType::Int]
+        ))
+    );
+    // Elements after the first difference are not compared
+    assert_eq!(eval_boolean("(1, \"a\") < (2, 2)"), Ok(true));
+    assert_eq!(
+        eval_boolean("(1, 2) < 3"),
+        Err(EvalexprError::expected_number_or_string(Value::from(vec![
+            Value::from(1),
+            Value::from(2)
+        ])))
+    );
+}
+
+#[test]
+fn test_round_modes() {
+    let ties = [0.5, 1.5, 2.5, -0.5, -1.5];
+    let expected = [
+        ("half_up", [1.0, 2.0, 3.0, -1.0, -2.0]),
+        ("half_even", [0.0, 2.0, 2.0, -0.0, -2.0]),
+        ("half_down", [0.0, 1.0, 2.0, -0.0, -1.0]),
+        ("toward_zero", [0.0, 1.0, 2.0, -0.0, -1.0]),
+    ];
+
+    for (mode, results) in expected.iter() {
+        for (tie, result) in ties.iter().zip(results.iter()) {
+            assert_eq!(
+                eval_float(&format!("round({:?}, \"{}\")", tie, mode)),
+                Ok(*result),
+                "round({}, {})",
+                tie,
+                mode
+            );
+        }
+    }
+
+    assert_eq!(eval("round(2.6, \"half_even\")"), Ok(Value::Float(3.0)));
+    assert_eq!(eval("round(-2.6, \"half_down\")"), Ok(Value::Float(-3.0)));
+    assert_eq!(eval("round(-2.6, \"toward_zero\")"), Ok(Value::Float(-2.0)));
+    assert_eq!(eval("round(3, \"half_even\")"), Ok(Value::Float(3.0)));
+    // Without a mode, half-way cases are rounded away from zero
+    assert_eq!(eval("round(-2.5)"), Ok(Value::Float(-3.0)));
+
+    let error = eval("round(1.5, \"up\")").unwrap_err();
+    assert_eq!(
+        error,
+        EvalexprError::InvalidRoundingMode {
+            mode: "up".to_string()
+        }
+    );
+    assert!(error.to_string().contains("\"half_even\""));
+    assert_eq!(
+        eval("round(1.5, 2)"),
+        Err(EvalexprError::expected_string(Value::Int(2)))
+    );
+}
+
+#[test]
+fn test_between() {
+    assert_eq!(eval("between(5, 1, 10)"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("between(2.5, 1, 3)"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("between(1, 1, 10)"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("between(10, 1, 10)"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("between(1.0, 1, 10.0)"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("between(0, 1, 10)"), Ok(Value::Boolean(false)));
+    assert_eq!(eval("between(10.5, 1, 10)"), Ok(Value::Boolean(false)));
+    assert_eq!(eval("between(5, 10, 1)"), Ok(Value::Boolean(false)));
+    assert_eq!(
+        eval("between(1 + 1, 2 * 1, 4 - 2)"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval("between(\"b\", 1, 10)"),
+        Err(EvalexprError::expected_number(Value::from("b")))
+    );
+    assert_eq!(
+        eval("between(5, 1)"),
+        Err(EvalexprError::expected_fixed_len_tuple(
+            3,
+            Value::from(vec![Value::from(5), Value::from(1)])
+        ))
+    );
+}
+
+#[test]
+fn test_integer_division() {
+    assert_eq!(eval("int_div(7, 2)"), Ok(Value::Int(3)));
+    // Integer division truncates towards zero, like in Rust
+    assert_eq!(eval("int_div(-7, 2)"), Ok(Value::Int(-3)));
+    assert_eq!(
+        eval("int_div(7, 0)"),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::Int(7),
+            divisor: Value::Int(0)
+        })
+    );
+    assert_eq!(
+        eval("int_div(7.0, 2)"),
+        Err(EvalexprError::expected_int(Value::Float(7.0)))
+    );
+
+    let options = ParseOptions::new().float_division(true);
+    assert_eq!(eval("5 / 4"), Ok(Value::Int(1)));
+    assert_eq!(
+        build_operator_tree_with_options("5 / 4", &options)
+            .unwrap()
+            .eval(),
+        Ok(Value::Float(1.25))
+    );
+    assert_eq!(
+        build_operator_tree_with_options("5.0 / 2", &options)
+            .unwrap()
+            .eval(),
+        Ok(Value::Float(2.5))
+    );
+    assert_eq!(
+        build_operator_tree_with_options("a = 5.0; a /= 4; a", &options)
+            .unwrap()
+            .eval(),
+        Ok(Value::Float(1.25))
+    );
+    assert_eq!(
+        build_operator_tree_with_options("int_div(5, 4)", &options)
+            .unwrap()
+            .eval(),
+        Ok(Value::Int(1))
+    );
+
+    // Integers divided by zero fail like the integer division, while floats follow IEEE 754
+    for expression in ["1 / 0", "0 / 0", "a = 1; a /= 0"] {
+        assert!(
+            matches!(
+                build_operator_tree_with_options(expression, &options)
+                    .unwrap()
+                    .eval(),
+                Err(EvalexprError::DivisionError { .. })
+            ),
+            "{}",
+            expression
+        );
+    }
+    assert_eq!(
+        build_operator_tree_with_options("1.0 / 0", &options)
+            .unwrap()
+            .eval(),
+        Ok(Value::Float(f64::INFINITY))
+    );
+    assert!(build_operator_tree_with_options("0 / 0.0", &options)
+        .unwrap()
+        .eval()
+        .unwrap()
+        .as_float()
+        .unwrap()
+        .is_nan());
+}
+
+#[test]
+fn test_value_args_macro() {
+    fn extract(arguments: &[Value]) -> EvalexprResult<Value> {
+        value_args!(arguments, (Int(a), Float(b), String(c), Boolean(d)));
+        Ok(Value::from(format!("{} {} {} {}", a, b, c, d)))
+    }
+
+    assert_eq!(
+        extract(&[
+            Value::from(1),
+            Value::from(2.5),
+            Value::from("c"),
+            Value::from(true)
+        ]),
+        Ok(Value::from("1 2.5 c true"))
+    );
+    assert_eq!(
+        extract(&[
+            Value::from(1),
+            Value::from(2),
+            Value::from("c"),
+            Value::from(true)
+        ]),
+        Err(EvalexprError::expected_float(Value::from(2)))
+    );
+    assert_eq!(
+        extract(&[Value::from(1), Value::from(2.5)]),
+        Err(EvalexprError::wrong_function_argument_amount(2, 4))
+    );
+
+    let context = context_map! {
+        "avg" => Function::new(|argument| {
+            value_args!(&argument.as_tuple()?, (Number(a), Number(b)));
+            Ok(Value::from((a + b) / 2.0))
+        })
+    }
+    .unwrap();
+    assert_eq!(
+        eval_with_context("avg(2, 5.0)", &context),
+        Ok(Value::from(3.5))
+    );
+    assert_eq!(
+        eval_with_context("avg(2, \"5\")", &context),
+        Err(EvalexprError::expected_number(Value::from("5")))
+    );
+
+    let first = |arguments: &[Value]| -> EvalexprResult<Value> {
+        value_args!(arguments, (Tuple(tuple),));
+        Ok(tuple[0].clone())
+    };
+    assert_eq!(
+        first(&[Value::from(vec![Value::from(1), Value::from(2)])]),
+        Ok(Value::from(1))
+    );
+    assert_eq!(
+        first(&[Value::from(1)]),
+        Err(EvalexprError::expected_tuple(Value::from(1)))
+    );
+}
+
+#[test]
+fn test_sum_and_avg() {
+    assert_eq!(eval("sum(1, 2, 3)"), Ok(Value::Int(6)));
+    assert_eq!(eval("sum(1, 2.5, 3)"), Ok(Value::Float(6.5)));
+    assert_eq!(eval("avg(1, 2, 3, 4)"), Ok(Value::Float(2.5)));
+    assert_eq!(eval("avg(1, 2.5)"), Ok(Value::Float(1.75)));
+    assert_eq!(
+        eval(&format!("sum({}, 1)", IntType::MAX)),
+        Err(EvalexprError::AdditionError {
+            augend: Value::Int(IntType::MAX),
+            addend: Value::Int(1)
+        })
+    );
+    assert_eq!(
+        eval("sum(1, \"2\")"),
+        Err(EvalexprError::expected_number(Value::from("2")))
+    );
+
+    let mut context = HashMapContext::new();
+    context
+        .set_value("empty".into(), Value::Tuple(Vec::new()))
+        .unwrap();
+    for function in ["sum", "avg"] {
+        assert_eq!(
+            eval_with_context(&format!("{}(empty)", function), &context),
+            Err(EvalexprError::wrong_function_argument_amount_range(
+                0,
+                1..=usize::MAX
+            ))
+        );
+    }
+
+    // Naive summation of a million times 0.1 drifts by more than 1e-6
+    context
+        .set_value(
+            "values".into(),
+            Value::Tuple(vec![Value::Float(0.1); 1_000_000]),
+        )
+        .unwrap();
+    let sum = eval_float_with_context("sum(values)", &context).unwrap();
+    assert!((sum - 100_000.0).abs() < 1e-9, "{}", sum);
+    let avg = eval_float_with_context("avg(values)", &context).unwrap();
+    assert!((avg - 0.1).abs() < 1e-15, "{}", avg);
+}
+
+#[test]
+fn test_literal_evaluation_matches_operator_tree() {
+    for expression in [
+        "42",
+        "-42",
+        "3.5",
+        "-3.5",
+        "-2e-3",
+        "0x1f",
+        "-0x1f",
+        "9223372036854775807",
+        "-9223372036854775808",
+        "true",
+        "false",
+        "\"abc\"",
+        " 7 ",
+    ]
+    .iter()
+    {
+        assert_eq!(
+            eval(expression),
+            build_operator_tree(expression).unwrap().eval(),
+            "{}",
+            expression
+        );
+    }
+    assert_eq!(eval("-5"), Ok(Value::Int(-5)));
+    assert_eq!(
+        eval("-true"),
+        Err(EvalexprError::expected_number(Value::Boolean(true)))
+    );
+}
+
+#[test]
+fn test_reductions_skip_empty() {
+    let mut context = HashMapContext::new();
+    context
+        .set_function("empty_fn".into(), Function::new(|_| Ok(Value::Empty)))
+        .unwrap();
+
+    assert_eq!(
+        eval_with_context("sum(1, empty_fn(), 3)", &context),
+        Ok(Value::Int(4))
+    );
+    assert_eq!(
+        eval_with_context("avg(1, empty_fn(), 3)", &context),
+        Ok(Value::Float(2.0))
+    );
+    assert_eq!(
+        eval_with_context("min(empty_fn(), 3, 1.5)", &context),
+        Ok(Value::Float(1.5))
+    );
+    assert_eq!(
+        eval_with_context("max(2, empty_fn(), 3)", &context),
+        Ok(Value::Int(3))
+    );
+
+    for function in ["sum", "avg", "min", "max"] {
+        assert_eq!(
+            eval_with_context(&format!("{}(empty_fn(), ())", function), &context),
+            Err(EvalexprError::wrong_function_argument_amount_range(
+                0,
+                1..=usize::MAX
+            )),
+            "{}",
+            function
+        );
+    }
+}
+
+#[test]
+fn test_expression_arena() {
+    let expressions = [
+        "1 + 2 * 3",
+        "a = 5; b = a * 2; (a, b)",
+        "f(b + c) - max(a, 3.5)",
+        "if(a > 2, \"yes\", \"no\")",
+        "((1, 2), 3)",
+        "a += 1; a",
+    ];
+
+    for string in &expressions {
+        let node = build_operator_tree(string).unwrap();
+        let expression = build_expression(string).unwrap();
+        assert_eq!(expression.to_node(), node);
+        assert_eq!(Node::from(&expression.clone()), node);
+        assert_eq!(expression.len(), node.iter().count() + 1);
+        assert!(expression
+            .iter_operators()
+            .eq(std::iter::once(node.operator()).chain(node.iter().map(|node| node.operator()))));
+        assert!(expression.iter_identifiers().eq(node.iter_identifiers()));
+        assert!(expression
+            .iter_variable_identifiers()
+            .eq(node.iter_variable_identifiers()));
+        assert!(expression
+            .iter_function_identifiers()
+            .eq(node.iter_function_identifiers()));
+        assert_eq!(expression.to_string(), node.to_string());
+
+        let mut node_context = context_map! {
+            "a" => 4,
+            "b" => 1.5,
+            "c" => 2,
+            "f" => Function::new(|argument| argument.as_number().map(|n| Value::Float(n * 2.0))),
+        }
+        .unwrap();
+        let mut expression_context = node_context.clone();
+        assert_eq!(
+            expression.eval_with_context_mut(&mut expression_context),
+            node.eval_with_context_mut(&mut node_context)
+        );
+        assert_eq!(
+            expression_context.get_value("a"),
+            node_context.get_value("a")
+        );
+        assert_eq!(
+            expression.eval_with_context(&expression_context),
+            node.eval_with_context(&node_context)
+        );
+    }
+
+    assert_eq!(build_expression("(1"), Err(EvalexprError::UnmatchedLBrace));
+}
+
+#[test]
+fn test_tree_string() {
+    let tree = build_operator_tree("1 + 2 * 3").unwrap();
+    assert_eq!(tree.to_tree_string(), "+\n  1\n  *\n    2\n    3\n");
+
+    let tree = build_operator_tree("a = (1 + 2) * 3; f(a, ())").unwrap();
+    assert_eq!(
+        tree.to_tree_string(),
+        "\
+;
+  =
+    a
+    *
+      +
+        1
+        2
+      3
+  f
+    ,
+      a
+      ()
+"
+    );
+}
+
+#[test]
+fn test_function_return_type() {
+    let parse = Function::new(|argument| match argument.as_string()?.parse() {
+        Ok(int) => Ok(Value::Int(int)),
+        // A bug that returns the input instead of failing
+        Err(_) => Ok(argument.clone()),
+    })
+    .with_return_type(ValueType::Int);
+    assert_eq!(parse.return_type(), Some(ValueType::Int));
+    assert_eq!(Function::new(|_| Ok(Value::Empty)).return_type(), None);
+
+    let mut context = HashMapContext::new();
+    context.set_function("parse".into(), parse.clone()).unwrap();
+    assert_eq!(
+        eval_with_context("parse(\"42\") + 1", &context),
+        Ok(Value::Int(43))
+    );
+    let error = eval_with_context("parse(\"4x2\") + 1", &context).unwrap_err();
+    assert_eq!(
+        error,
+        EvalexprError::FunctionReturnedWrongType {
+            identifier: "parse".to_string(),
+            expected: ValueType::Int,
+            actual: Value::from("4x2"),
+        }
+    );
+    assert_eq!(error.code(), "function_returned_wrong_type");
+    assert!(error.is_type_error());
+
+    // Partially applied functions keep the return type
+    let second = Function::new(|argument| Ok(argument.as_tuple()?[1].clone()))
+        .with_argument_amount(2)
+        .with_return_type(ValueType::Boolean);
+    context
+        .set_function("flag".into(), second.partial(vec![Value::Empty]).unwrap())
+        .unwrap();
+    assert_eq!(
+        eval_with_context("flag(true)", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval_with_context("flag(1)", &context),
+        Err(EvalexprError::function_returned_wrong_type(
+            "flag",
+            ValueType::Boolean,
+            Value::from(1)
+        ))
+    );
+}
+
+#[test]
+fn test_function_partial() {
+    let clamp = Function::new(|argument| {
+        let arguments = argument.as_fixed_len_tuple(3)?;
+        let (lo, hi, x) = (
+            arguments[0].as_int()?,
+            arguments[1].as_int()?,
+            arguments[2].as_int()?,
+        );
+        Ok(Value::Int(x.max(lo).min(hi)))
+    })
+    .with_argument_amount(3);
+
+    let at_least_zero = clamp.clone().partial(vec![Value::Int(0)]).unwrap();
+    assert_eq!(at_least_zero.argument_amount(), Some(2));
+    let percent = clamp
+        .clone()
+        .partial(vec![Value::Int(0), Value::Int(100)])
+        .unwrap();
+    assert_eq!(percent.argument_amount(), Some(1));
+    let mut context = HashMapContext::new();
+    context.set_function("clamp".into(), clamp.clone()).unwrap();
+    context
+        .set_function("at_least_zero".into(), at_least_zero)
+        .unwrap();
+    context.set_function("percent".into(), percent).unwrap();
+
+    assert_eq!(
+        eval_with_context("at_least_zero(10, 15)", &context),
+        Ok(Value::Int(10))
+    );
+    assert_eq!(
+        eval_with_context("at_least_zero(10, -5)", &context),
+        Ok(Value::Int(0))
+    );
+    assert_eq!(
+        eval_with_context("percent(150)", &context),
+        Ok(Value::Int(100))
+    );
+    assert_eq!(
+        eval_with_context("at_least_zero(10)", &context),
+        Err(EvalexprError::wrong_function_argument_amount(1, 2))
+    );
+    assert_eq!(
+        eval_with_context("at_least_zero(1, 2, 3)", &context),
+        Err(EvalexprError::wrong_function_argument_amount(3, 2))
+    );
+    assert_eq!(
+        eval_with_context("clamp(1, 2)", &context),
+        Err(EvalexprError::wrong_function_argument_amount(2, 3))
+    );
+    assert_eq!(
+        clamp
+            .partial(vec![Value::Int(0); 4])
+            .map(|function| function.argument_amount()),
+        Err(EvalexprError::wrong_function_argument_amount(4, 3))
+    );
+
+    // Without a declared amount of arguments, the arguments are only prepended.
+    let tuple = Function::new(|argument| Ok(argument.clone()));
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "prefixed".into(),
+            tuple.partial(vec![Value::Int(1)]).unwrap(),
+        )
+        .unwrap();
+    assert_eq!(eval_with_context("prefixed()", &context), Ok(Value::Int(1)));
+    assert_eq!(
+        eval_with_context("prefixed(2, 3)", &context),
+        Ok(Value::Tuple(vec![
+            Value::Int(1),
+            Value::Int(2),
+            Value::Int(3)
+        ]))
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: FloatType,
+    y: FloatType,
+}
+
+impl CustomValue for Point {
+    fn type_name(&self) -> &str {
+        "point"
+    }
+
+    fn display(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "[{}, {}]", self.x, self.y)
+    }
+
+    fn eq(&self, other: &dyn CustomValue) -> bool {
+        other.as_any().downcast_ref::<Self>() == Some(self)
+    }
+
+    fn add(&self, other: &Value) -> Option<EvalexprResult<Value>> {
+        match other {
+            Value::Custom(other) => {
+                let other = other.as_any().downcast_ref::<Self>()?;
+                Some(Ok(point(self.x + other.x, self.y + other.y)))
+            },
+            _ => None,
+        }
+    }
+
+    fn mul(&self, other: &Value) -> Option<EvalexprResult<Value>> {
+        let factor = other.as_number().ok()?;
+        Some(Ok(point(self.x * factor, self.y * factor)))
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+fn point(x: FloatType, y: FloatType) -> Value {
+    Value::Custom(std::sync::Arc::new(Point { x, y }))
+}
+
+#[test]
+fn test_custom_values() {
+    let mut context = context_map! {
+        "a" => point(1.0, 2.0),
+        "b" => point(0.5, -1.0),
+    }
+    .unwrap();
+
+    assert_eq!(eval_with_context("a + b", &context), Ok(point(1.5, 1.0)));
+    assert_eq!(
+        eval_with_context("2 * a + b * 2", &context),
+        Ok(point(3.0, 2.0))
+    );
+    assert_eq!(eval_with_context("a == a", &context), Ok(Value::from(true)));
+    assert_eq!(eval_with_context("a != b", &context), Ok(Value::from(true)));
+    assert_eq!(
+        eval_with_context("a == 1", &context),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        eval_with_context("typeof(a)", &context),
+        Ok(Value::from("point"))
+    );
+    assert_eq!(
+        eval_with_context("str::from(a)", &context),
+        Ok(Value::from("[1, 2]"))
+    );
+    assert_eq!(
+        eval_with_context_mut("a += b; a", &mut context),
+        Ok(point(1.5, 1.0))
+    );
+
+    assert_eq!(
+        eval_with_context("a + 1", &context),
+        Err(EvalexprError::wrong_type_combination(
+            Operator::Add,
+            vec![ValueType::Custom, ValueType::Int]
+        ))
+    );
+    assert_eq!(
+        eval_with_context("a - b", &context),
+        Err(EvalexprError::expected_number(point(1.5, 1.0)))
+    );
+}
+
+#[test]
+fn test_context_from_assignments() {
+    let context = HashMapContext::from_assignments(&[
+        "a=1",
+        " b = -2.5 ",
+        "name=\"x = y\"",
+        "flag=true",
+        "hex=0x1f",
+    ])
+    .unwrap();
+    assert_eq!(context.get_value("a"), Some(&Value::Int(1)));
+    assert_eq!(context.get_value("b"), Some(&Value::Float(-2.5)));
+    assert_eq!(context.get_value("name"), Some(&Value::from("x = y")));
+    assert_eq!(context.get_value("flag"), Some(&Value::Boolean(true)));
+    assert_eq!(context.get_value("hex"), Some(&Value::Int(31)));
+    assert_eq!(eval_with_context("a + b", &context), Ok(Value::Float(-1.5)));
+
+    for line in [
+        "a",
+        "=1",
+        "a b=1",
+        "1=1",
+        "a=",
+        "a=1 + 2",
+        "a=b",
+        "a=\"unclosed",
+    ] {
+        assert_eq!(
+            HashMapContext::from_assignments(&["x=1", line]).map(|_| ()),
+            Err(EvalexprError::InvalidAssignment(line.to_string())),
+            "{}",
+            line
+        );
+    }
+    assert_eq!(
+        HashMapContext::from_assignments(&["a=1", "a=true"]).map(|_| ()),
+        Err(EvalexprError::expected_int(Value::Boolean(true)))
+    );
+
+    assert_eq!("2.5".parse(), Ok(Value::Float(2.5)));
+    assert_eq!(
+        "()".parse::<Value>(),
+        Err(EvalexprError::InvalidLiteral("()".to_string()))
+    );
+}
+
+#[test]
+fn test_node_binary_format() {
+    let expressions = [
+        "1 + 2 * 3 - -4 / 5 % 6 ^ 7",
+        "a = 5; b += a; c -= 1; d *= 2; e /= 3; f %= 4; g ^= 5; h &&= true; i ||= false",
+        "(1 == 2) != (3 > 4) && 5 < 6 || !(7 >= 8) && 9 <= 10",
+        "f(\"a \\\"quoted\\\" string\", 1.5e3, false, ())",
+        "math::sin(x); ; 0x7fffffffffffffff",
+    ];
+    for string in &expressions {
+        let tree = build_operator_tree(string).unwrap();
+        let bytes = tree.to_bytes().unwrap();
+        assert_eq!(&bytes[..5], b"EVXP\x01");
+        assert_eq!(Node::from_bytes(&bytes).as_ref(), Ok(&tree), "{}", string);
+    }
+
+    let options = ParseOptions::new().float_division(true);
+    let tree = build_operator_tree_with_options("a /= 2; a / 4", &options).unwrap();
+    assert_eq!(Node::from_bytes(&tree.to_bytes().unwrap()), Ok(tree));
+
+    // The encoding of operators and values is stable.
+    assert_eq!(
+        build_operator_tree("-x + 1").unwrap().to_bytes().unwrap(),
+        vec![
+            b'E', b'V', b'X', b'P', 1, // header
+            0, 1, 0, 0, 0, // root node
+            1, 2, 0, 0, 0, // addition
+            3, 1, 0, 0, 0, // negation
+            30, 1, 0, 0, 0, b'x', 0, 0, 0, 0, // variable x
+            28, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // constant 1
+        ]
+    );
+
+    let mut bytes = build_operator_tree("1").unwrap().to_bytes().unwrap();
+    bytes[4] = 2;
+    assert_eq!(
+        Node::from_bytes(&bytes),
+        Err(EvalexprError::UnsupportedFormatVersion {
+            found: 2,
+            supported: 1
+        })
+    );
+    assert!(matches!(
+        Node::from_bytes(b"EVXQ\x01"),
+        Err(EvalexprError::InvalidBinaryFormat(_))
+    ));
+    let bytes = build_operator_tree("a + 1").unwrap().to_bytes().unwrap();
+    for len in 0..bytes.len() {
+        assert!(matches!(
+            Node::from_bytes(&bytes[..len]),
+            Err(EvalexprError::InvalidBinaryFormat(_))
+        ));
+    }
+    let mut bytes = bytes;
+    bytes.push(0);
+    assert!(matches!(
+        Node::from_bytes(&bytes),
+        Err(EvalexprError::InvalidBinaryFormat(_))
+    ));
+    assert!(matches!(
+        Node::from_bytes(b"EVXP\x01\xff\x00\x00\x00\x00"),
+        Err(EvalexprError::InvalidBinaryFormat(_))
+    ));
+
+    // Constants with custom values cannot be encoded
+    let tree = NodeBuilder::value(point(1.0, 2.0)).build();
+    assert_eq!(
+        tree.to_bytes(),
+        Err(EvalexprError::UnencodableValue(point(1.0, 2.0)))
+    );
+    let tree = NodeBuilder::value(Value::from(vec![Value::from(1), point(1.0, 2.0)])).build();
+    assert_eq!(tree.to_bytes().unwrap_err().code(), "unencodable_value");
+}
+
+#[test]
+fn test_node_binary_format_nesting() {
+    let header = b"EVXP\x01".to_vec();
+    let root = [0, 1, 0, 0, 0];
+    let constant_zero = [28, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    // Deeply nested nodes are rejected instead of overflowing the stack
+    let mut bytes = header.clone();
+    for _ in 0..1_000_000 {
+        bytes.extend_from_slice(&root);
+    }
+    assert_eq!(
+        Node::from_bytes(&bytes),
+        Err(EvalexprError::InvalidBinaryFormat(
+            "nested too deeply".to_string()
+        ))
+    );
+
+    // As are deeply nested values
+    let mut bytes = header.clone();
+    bytes.push(28);
+    for _ in 0..1_000_000 {
+        bytes.extend_from_slice(&[5, 1, 0, 0, 0]);
+    }
+    assert_eq!(
+        Node::from_bytes(&bytes),
+        Err(EvalexprError::InvalidBinaryFormat(
+            "nested too deeply".to_string()
+        ))
+    );
+
+    // Up to 256 levels are decoded
+    let mut bytes = header;
+    for _ in 0..255 {
+        bytes.extend_from_slice(&root);
+    }
+    bytes.extend_from_slice(&constant_zero);
+    let tree = Node::from_bytes(&bytes).unwrap();
+    assert_eq!(tree.to_bytes(), Ok(bytes.clone()));
+    assert_eq!(tree.eval(), Ok(Value::from(0)));
+    bytes.splice(5..5, root);
+    assert!(matches!(
+        Node::from_bytes(&bytes),
+        Err(EvalexprError::InvalidBinaryFormat(_))
+    ));
+}
+
+#[test]
+fn test_special_float_display() {
+    let specials = [
+        (FloatType::INFINITY, "inf"),
+        (FloatType::NEG_INFINITY, "-inf"),
+        (FloatType::NAN, "NaN"),
+        (-FloatType::NAN, "NaN"),
+    ];
+    for (float, expected) in specials {
+        let value = Value::Float(float);
+        assert_eq!(value.to_string(), expected);
+
+        let parsed = eval_float(&value.to_string()).unwrap();
+        assert_eq!(parsed.is_nan(), float.is_nan());
+        if !float.is_nan() {
+            assert_eq!(parsed, float);
+        }
+    }
+
+    assert_eq!(eval("1.0 / 0.0").unwrap().to_string(), "inf");
+    assert_eq!(eval("-1.0 / 0.0").unwrap().to_string(), "-inf");
+    assert_eq!(eval("0.0 / 0.0").unwrap().to_string(), "NaN");
+    let tuple = Value::from(vec![
+        Value::Float(FloatType::INFINITY),
+        Value::Float(FloatType::NEG_INFINITY),
+    ]);
+    assert_eq!(tuple.to_string(), "(inf, -inf)");
+    assert_eq!(eval(&tuple.to_string()), Ok(tuple));
+    assert_eq!(eval("Infinity"), Ok(Value::Float(FloatType::INFINITY)));
+}
+
+#[test]
+fn test_memoized_functions() {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted_calls = calls.clone();
+    let dist = Function::new(move |argument| {
+        counted_calls.fetch_add(1, Ordering::SeqCst);
+        let arguments = argument.as_fixed_len_tuple(2)?;
+        Ok(Value::Float(
+            (arguments[0].as_number()? - arguments[1].as_number()?).abs(),
+        ))
+    });
+    assert!(!dist.is_memoized());
+    let dist = dist.memoized();
+    assert!(dist.is_memoized());
+
+    let mut context = context_map! {
+        "a" => 1.5,
+        "b" => 4,
+    }
+    .unwrap();
+    context.set_function("dist".into(), dist).unwrap();
+
+    let tree = build_operator_tree("dist(a, b) + dist(a, b) * dist(a, b)").unwrap();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::Float(8.75)));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    // The cache does not outlive an evaluation.
+    context.set_value("a".into(), 2.0.into()).unwrap();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::Float(6.0)));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        build_expression("dist(a, b) + dist(a, b)")
+            .unwrap()
+            .eval_with_context_mut(&mut context),
+        Ok(Value::Float(4.0))
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    // Floats are compared by bit pattern, so 0.0 and -0.0 are different arguments.
+    assert_eq!(
+        eval_with_context("dist(0.0, 1) + dist(-0.0, 1) + dist(0.0, 1)", &context),
+        Ok(Value::Float(3.0))
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+    // Functions without captures do not share their cache entries.
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "double".into(),
+            Function::new(|argument| Ok(Value::Int(argument.as_int()? * 2))).memoized(),
+        )
+        .unwrap();
+    context
+        .set_function(
+            "triple".into(),
+            Function::new(|argument| Ok(Value::Int(argument.as_int()? * 3))).memoized(),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context("(double(5), triple(5), double(5))", &context),
+        Ok(Value::from(vec![
+            Value::Int(10),
+            Value::Int(15),
+            Value::Int(10)
+        ]))
+    );
+}
+
+#[test]
+fn test_macro_functions() {
+    let mut context = HashMapContext::new();
+    context
+        .insert_macro(
+            "assert".into(),
+            MacroFunction::new(|arguments, context| {
+                let [condition] = arguments else {
+                    return Err(EvalexprError::wrong_function_argument_amount(
+                        arguments.len(),
+                        1,
+                    ));
+                };
+                if condition.eval_boolean_with_context(&context)? {
+                    Ok(Value::Empty)
+                } else {
+                    Err(EvalexprError::CustomMessage(format!(
+                        "assertion failed: {}",
+                        condition
+                    )))
+                }
+            }),
+        )
+        .unwrap();
+    context
+        .insert_macro(
+            "count_identifiers".into(),
+            MacroFunction::new(|arguments, _| {
+                let count: usize = arguments
+                    .iter()
+                    .map(|argument| argument.iter_identifiers().count())
+                    .sum();
+                Ok(Value::from(count as IntType))
+            }),
+        )
+        .unwrap();
+    context.set_value("x".into(), Value::from(5)).unwrap();
+
+    assert_eq!(
+        eval_with_context("assert(x > 0)", &context),
+        Ok(Value::Empty)
+    );
+    context.set_value("x".into(), Value::from(-5)).unwrap();
+    let error = eval_with_context("assert(x > 0)", &context).unwrap_err();
+    let condition = build_operator_tree("x > 0").unwrap().to_string();
+    assert!(error.to_string().contains(&condition), "{}", error);
+    assert_eq!(
+        error,
+        EvalexprError::CustomMessage(format!("assertion failed: {}", condition))
+    );
+    assert_eq!(
+        eval_with_context("assert(x > 0, x < 0)", &context),
+        Err(EvalexprError::wrong_function_argument_amount(2, 1))
+    );
+
+    // The arguments are not evaluated, so they may refer to undefined identifiers
+    assert_eq!(
+        eval_with_context("count_identifiers()", &context),
+        Ok(Value::from(0))
+    );
+    assert_eq!(
+        eval_with_context("count_identifiers(a + f(b))", &context),
+        Ok(Value::from(3))
+    );
+    assert_eq!(
+        eval_with_context("count_identifiers(a, b, (c, d))", &context),
+        Ok(Value::from(4))
+    );
+    assert_eq!(
+        eval_with_context("count_identifiers(1 + 2) + count_identifiers(3)", &context),
+        Ok(Value::from(0))
+    );
+
+    // Macros are dispatched in all evaluation methods and through wrapping contexts
+    assert_eq!(
+        eval_with_context_mut("y = count_identifiers(a, b); y + 1", &mut context),
+        Ok(Value::from(3))
+    );
+    assert_eq!(
+        build_expression(
+            "count_identifiers(a, b) + count_identifiers(((c))) + count_identifiers()"
+        )
+        .unwrap()
+        .eval_with_context(&context),
+        Ok(Value::from(3))
+    );
+    let scoped = ScopedContext::new(&context);
+    assert_eq!(
+        eval_with_context("count_identifiers(a)", &scoped),
+        Ok(Value::from(1))
+    );
+    let layers: &[&dyn Context] = &[&HashMapContext::new(), &context];
+    assert_eq!(
+        eval_with_context("count_identifiers(a)", &layers),
+        Ok(Value::from(1))
+    );
+    let (result, call_counts) = build_operator_tree("count_identifiers(a) + count_identifiers(b)")
+        .unwrap()
+        .eval_profiled(&context);
+    assert_eq!(result, Ok(Value::from(2)));
+    assert_eq!(call_counts["count_identifiers"], 2);
+
+    // Macros take precedence over functions and builtin functions
+    context
+        .set_function(
+            "count_identifiers".into(),
+            Function::new(|_| Ok(Value::from(-1))),
+        )
+        .unwrap();
+    context
+        .insert_macro(
+            "max".into(),
+            MacroFunction::new(|arguments, _| Ok(Value::from(arguments.len() as IntType))),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context("count_identifiers(a) + max(5, 7)", &context),
+        Ok(Value::from(3))
+    );
+    context.clear_functions();
+    assert_eq!(eval_with_context("max(5, 7)", &context), Ok(Value::from(7)));
+
+    let mut strict = HashMapContext::strict();
+    let macro_function = MacroFunction::new(|_, _| Ok(Value::Empty));
+    strict
+        .insert_macro("m".into(), macro_function.clone())
+        .unwrap();
+    assert_eq!(
+        strict.insert_macro("m".into(), macro_function),
+        Err(EvalexprError::DuplicateBinding("m".to_string()))
+    );
+}
+
+#[test]
+fn test_eval_ref() {
+    let context = context_map! {
+        "x" => 3,
+        "name" => "Ada",
+        "user" => Value::Map(vec![("tags".into(), Value::from((1, 2)))]),
+    }
+    .unwrap();
+    let borrowed = |expression: &str| {
+        let tree = build_operator_tree(expression).unwrap();
+        match tree.eval_ref(&context) {
+            Ok(Cow::Borrowed(value)) => value.clone(),
+            result => panic!("{:?} is not borrowed: {:?}", expression, result),
+        }
+    };
+    let owned = |expression: &str| {
+        let tree = build_operator_tree(expression).unwrap();
+        match tree.eval_ref(&context) {
+            Ok(Cow::Owned(value)) => value,
+            result => panic!("{:?} is not owned: {:?}", expression, result),
+        }
+    };
+
+    assert_eq!(borrowed("x"), Value::from(3));
+    assert_eq!(borrowed("((name))"), Value::from("Ada"));
+    assert_eq!(borrowed("user.tags"), Value::from((1, 2)));
+    assert_eq!(borrowed("user.tags.1"), Value::from(2));
+    assert_eq!(borrowed("\"literal\""), Value::from("literal"));
+    assert_eq!(borrowed("4.5"), Value::from(4.5));
+
+    assert_eq!(owned("x + 1"), Value::from(4));
+    assert_eq!(owned("-x"), Value::from(-3));
+    assert_eq!(owned("str::to_uppercase(name)"), Value::from("ADA"));
+    assert_eq!(owned("(x, name)"), Value::from((3, "Ada")));
+    assert_eq!(owned(""), Value::Empty);
+
+    // The borrowed value lives as long as the context and the tree
+    let tree = build_operator_tree("name").unwrap();
+    let name = tree.eval_ref(&context).unwrap();
+    assert_eq!(name.as_string(), Ok("Ada".to_string()));
+    assert!(matches!(
+        &name,
+        Cow::Borrowed(value) if std::ptr::eq(*value, context.get_value("name").unwrap())
+    ));
+
+    let tree = build_operator_tree("y").unwrap();
+    assert_eq!(
+        tree.eval_ref(&context),
+        Err(EvalexprError::VariableIdentifierNotFound("y".to_string()))
+    );
+    let tree = build_operator_tree("user.age").unwrap();
+    assert_eq!(
+        tree.eval_ref(&context),
+        Err(EvalexprError::VariableIdentifierNotFound(
+            "user.age".to_string()
+        ))
+    );
+    let tree = build_operator_tree("x / 0").unwrap();
+    assert_eq!(
+        tree.eval_ref(&context).unwrap_err().code(),
+        "division_by_zero"
+    );
+}
+
+#[test]
+fn test_shared_operator_tree_across_threads() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const THREADS: usize = 8;
+    const EVALUATIONS: usize = 200;
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted_calls = calls.clone();
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "square".into(),
+            Function::new(move |argument| {
+                counted_calls.fetch_add(1, Ordering::SeqCst);
+                let x = argument.as_int()?;
+                Ok(Value::Int(x * x))
+            })
+            .memoized(),
+        )
+        .unwrap();
+    context.set_value("x".into(), Value::Int(3)).unwrap();
+    let context = Arc::new(context);
+    let tree = Arc::new(build_operator_tree("square(x) + square(x) + square(y)").unwrap());
+
+    let threads: Vec<_> = (0..THREADS)
+        .map(|thread| {
+            let tree = Arc::clone(&tree);
+            let context = Arc::clone(&context);
+            std::thread::spawn(move || {
+                // Each thread binds `y` in its own scope over the shared context
+                let mut scoped = ScopedContext::new(&*context);
+                scoped.push_scope();
+                for evaluation in 0..EVALUATIONS {
+                    let y = (10 + thread * EVALUATIONS + evaluation) as IntType;
+                    scoped
+                        .insert_in_current_scope("y".into(), Value::Int(y))
+                        .unwrap();
+                    // The tree is evaluated through the `Arc` by deref
+                    assert_eq!(tree.eval_with_context(&scoped), Ok(Value::Int(18 + y * y)));
+                    assert_eq!(
+                        tree.eval_with_context(&*context),
+                        Err(EvalexprError::VariableIdentifierNotFound("y".into()))
+                    );
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+
+    // The tree is not changed by the evaluations, and the memoization cache is never shared between evaluations:
+    // `square` is called for `x` and `y` in the first evaluation, and for `x` in the second before `y` is not found
+    assert_eq!(
+        *tree,
+        build_operator_tree("square(x) + square(x) + square(y)").unwrap()
+    );
+    assert_eq!(calls.load(Ordering::SeqCst), THREADS * EVALUATIONS * 3);
+}
+
+#[test]
+fn test_nested_evaluation_depth() {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, OnceLock,
+        },
+    };
+
+    let rules: Arc<HashMap<&str, Node>> = Arc::new(
+        [
+            ("ping", "eval_rule(\"pong\") + 1"),
+            ("pong", "eval_rule(\"ping\")"),
+            ("answer", "eval_rule(\"half\") * 2"),
+            ("half", "21"),
+        ]
+        .iter()
+        .map(|(name, rule)| (*name, build_operator_tree(rule).unwrap()))
+        .collect(),
+    );
+    // The rules are evaluated with the context that contains the function evaluating them
+    let rule_context = Arc::new(OnceLock::<HashMapContext>::new());
+    let max_depth = Arc::new(AtomicUsize::new(0));
+    let eval_rule = {
+        let (rules, rule_context, max_depth) =
+            (rules.clone(), rule_context.clone(), max_depth.clone());
+        Function::new_with_eval_context(move |argument, eval_context| {
+            max_depth.fetch_max(eval_context.current_depth(), Ordering::SeqCst);
+            let rule = &rules[argument.as_string()?.as_str()];
+            eval_context.evaluate_sub_expression(rule, rule_context.get().unwrap())
+        })
+    };
+    let mut context = HashMapContext::new();
+    context.set_function("eval_rule".into(), eval_rule).unwrap();
+    rule_context.set(context.clone()).unwrap();
+
+    assert_eq!(
+        eval_with_context("eval_rule(\"answer\")", &context),
+        Ok(Value::from(42))
+    );
+    assert_eq!(max_depth.load(Ordering::SeqCst), 2);
+
+    // Mutually referencing rules fail when they reach the limit, instead of overflowing the stack
+    let error = eval_with_context("eval_rule(\"ping\")", &context).unwrap_err();
+    assert_eq!(
+        error,
+        EvalexprError::EvaluationTooDeep {
+            limit: MAX_EVALUATION_DEPTH
+        }
+    );
+    assert_eq!(error.code(), "evaluation_too_deep");
+    assert!(error.is_runtime_error());
+    assert_eq!(max_depth.load(Ordering::SeqCst), MAX_EVALUATION_DEPTH);
+    // The same failure is reported for every evaluation method, and the depth is reset afterwards
+    let tree = build_operator_tree("eval_rule(\"pong\")").unwrap();
+    assert_eq!(tree.eval_with_context(&context), Err(error.clone()));
+    assert_eq!(
+        tree.eval_with_context_mut(&mut context.clone()),
+        Err(error.clone())
+    );
+    assert_eq!(tree.eval_profiled(&context).0, Err(error.clone()));
+    assert_eq!(tree.eval_with_stats(&context).0, Err(error.clone()));
+    assert_eq!(
+        build_expression("eval_rule(\"pong\")")
+            .unwrap()
+            .eval_with_context(&context),
+        Err(error)
+    );
+    assert_eq!(
+        eval_with_context("eval_rule(\"half\")", &context),
+        Ok(Value::from(21))
+    );
+
+    // Functions that evaluate expressions without the handle are limited as well
+    let recursive = Arc::new(OnceLock::<HashMapContext>::new());
+    let mut context = HashMapContext::new();
+    let inner = recursive.clone();
+    context
+        .set_function(
+            "recurse".into(),
+            Function::new(move |_| eval_with_context("recurse()", inner.get().unwrap())),
+        )
+        .unwrap();
+    recursive.set(context.clone()).unwrap();
+    assert_eq!(
+        eval_with_context("recurse()", &context),
+        Err(EvalexprError::EvaluationTooDeep {
+            limit: MAX_EVALUATION_DEPTH
+        })
+    );
+
+    // The limit is configurable per context
+    let limited = Arc::new(OnceLock::<HashMapContext>::new());
+    let mut context = HashMapContext::new();
+    context.set_max_evaluation_depth(4);
+    let (inner, depth) = (limited.clone(), max_depth.clone());
+    depth.store(0, Ordering::SeqCst);
+    context
+        .set_function(
+            "recurse".into(),
+            Function::new_with_eval_context(move |_, eval_context| {
+                depth.fetch_max(eval_context.current_depth(), Ordering::SeqCst);
+                eval_context.evaluate_sub_expression(
+                    &build_operator_tree("recurse()").unwrap(),
+                    inner.get().unwrap(),
+                )
+            }),
+        )
+        .unwrap();
+    limited.set(context.clone()).unwrap();
+    assert_eq!(context.max_evaluation_depth(), 4);
+    assert_eq!(
+        eval_with_context("recurse()", &context),
+        Err(EvalexprError::EvaluationTooDeep { limit: 4 })
+    );
+    assert_eq!(max_depth.load(Ordering::SeqCst), 4);
+    assert_eq!(
+        HashMapContext::new().max_evaluation_depth(),
+        MAX_EVALUATION_DEPTH
+    );
+
+    // Nested evaluations with a context of a larger limit cannot escape the limit of the outer evaluation
+    let mut outer = HashMapContext::new();
+    outer.set_max_evaluation_depth(3);
+    let escape = {
+        let (inner, depth) = (limited.clone(), max_depth.clone());
+        Function::new_with_eval_context(move |_, eval_context| {
+            depth.fetch_max(eval_context.current_depth(), Ordering::SeqCst);
+            let mut inner = inner.get().unwrap().clone();
+            inner.set_max_evaluation_depth(MAX_EVALUATION_DEPTH);
+            eval_context.evaluate_sub_expression(&build_operator_tree("recurse()").unwrap(), &inner)
+        })
+    };
+    outer.set_function("escape".into(), escape).unwrap();
+    max_depth.store(0, Ordering::SeqCst);
+    assert_eq!(
+        eval_with_context("escape()", &outer),
+        Err(EvalexprError::EvaluationTooDeep { limit: 3 })
+    );
+    assert_eq!(max_depth.load(Ordering::SeqCst), 3);
+    // The limit of the outer evaluation does not outlive it
+    assert_eq!(
+        eval_with_context("recurse()", &context),
+        Err(EvalexprError::EvaluationTooDeep { limit: 4 })
+    );
+}
+
+#[test]
+fn test_semantic_equality() {
+    let semantically_eq = |a: &str, b: &str| {
+        build_operator_tree(a)
+            .unwrap()
+            .semantically_eq(&build_operator_tree(b).unwrap())
+    };
+
+    assert!(semantically_eq("1 + 2.5", "2.5 + 1"));
+    assert!(semantically_eq("(a - 1) + b / 2", "b / 2 + (a - 1)"));
+    assert!(semantically_eq("a * 2", "2 * a"));
+    assert!(semantically_eq("x && y || z", "z || y && x"));
+    assert!(semantically_eq("f(a * b) == 1", "1 == f(b * a)"));
+    assert!(semantically_eq("a != b", "b != a"));
+    assert!(semantically_eq("a - b", "a - b"));
+
+    assert!(!semantically_eq("a - b", "b - a"));
+    assert!(!semantically_eq("a / b", "b / a"));
+    assert!(!semantically_eq("a < b", "b < a"));
+    assert!(!semantically_eq("(a, b)", "(b, a)"));
+    assert!(!semantically_eq("f(a, b)", "f(b, a)"));
+    assert!(!semantically_eq("a + b", "a + c"));
+    assert!(!semantically_eq("a + (b - c)", "a + (c - b)"));
+
+    // String concatenation is not commutative, so `+` is only commutative for operands known to be numbers
+    assert_eq!(eval("\"a\" + \"b\""), Ok(Value::from("ab")));
+    assert!(!semantically_eq("\"a\" + \"b\"", "\"b\" + \"a\""));
+    assert!(!semantically_eq("a + b", "b + a"));
+    assert!(!semantically_eq("a + 1", "1 + a"));
+    assert!(!semantically_eq("(a * b) + 1", "1 + (b * a)"));
+    assert!(semantically_eq("(a - b) + 1", "1 + (a - b)"));
+}
+
+#[test]
+fn test_map_literals() {
+    let mut context = context_map! { "a" => 1, "b" => 2.0 }.unwrap();
+    assert_eq!(
+        eval_with_context("{ total: a + b, avg: (a + b) / 2 }", &context),
+        Ok(Value::Map(vec![
+            ("total".to_string(), Value::Float(3.0)),
+            ("avg".to_string(), Value::Float(1.5)),
+        ]))
+    );
+    assert_eq!(eval("{}"), Ok(Value::Map(Vec::new())));
+    assert_eq!(
+        eval("{\"a b\": (1, 2), c: {d: true}}"),
+        Ok(Value::Map(vec![
+            (
+                "a b".to_string(),
+                Value::Tuple(vec![Value::Int(1), Value::Int(2)])
+            ),
+            (
+                "c".to_string(),
+                Value::Map(vec![("d".to_string(), Value::Boolean(true))])
+            ),
+        ]))
+    );
+    assert_eq!(eval("typeof({a: 1})"), Ok(Value::from("map")));
+    assert_eq!(eval("{a: 1} == {a: 1}"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("{a: 1, b: 2}").unwrap().to_string(), "{a: 1, b: 2}");
+
+    assert_eq!(
+        eval_with_context_mut(
+            "result = { total: a + b, nested: { x: 4 } }; result.total + result.nested.x",
+            &mut context
+        ),
+        Ok(Value::Float(7.0))
+    );
+    assert_eq!(
+        eval_with_context("result.missing", &context),
+        Err(EvalexprError::VariableIdentifierNotFound(
+            "result.missing".to_string()
+        ))
+    );
+    assert_eq!(
+        eval_with_context("a.b", &context),
+        Err(EvalexprError::expected_map(Value::Int(1)))
+    );
+    // Variables whose identifier contains a dot take precedence over fields
+    context.set_value("result.total".into(), 0.into()).unwrap();
+    assert_eq!(
+        eval_with_context("result.total", &context),
+        Ok(Value::Int(0))
+    );
+
+    let tree = build_operator_tree("{a: 1, b: x}").unwrap();
+    assert_eq!(Node::from_bytes(&tree.to_bytes().unwrap()), Ok(tree));
+
+    assert_eq!(eval("{a: 1"), Err(EvalexprError::UnmatchedLCurlyBrace));
+    assert_eq!(eval("1}"), Err(EvalexprError::UnmatchedRCurlyBrace));
+    assert_eq!(eval("{a: 1, a: 2}"), Err(EvalexprError::InvalidMapEntry));
+    assert_eq!(eval("{1: 2}"), Err(EvalexprError::InvalidMapEntry));
+    assert_eq!(eval("{a}"), Err(EvalexprError::InvalidMapEntry));
+    assert_eq!(eval("{a: 1,}"), Err(EvalexprError::InvalidMapEntry));
+    assert_eq!(
+        eval("a: 1"),
+        Err(EvalexprError::UnmatchedColon { span: 1..2 })
+    );
+    assert_eq!(eval("{a: 1} 2"), Err(EvalexprError::AppendedToLeafNode));
+}
+
+#[test]
+fn test_field_paths() {
+    let mut context = HashMapContext::new();
+    eval_with_context_mut(
+        "user = {name: \"Ada\", address: {city: \"London\"}}; items = ({price: 3}, {price: 5})",
+        &mut context,
+    )
+    .unwrap();
+
+    assert_eq!(
+        eval_with_context("user.address.city", &context),
+        Ok(Value::from("London"))
+    );
+    assert_eq!(
+        eval_with_context("user.address.zip", &context),
+        Err(EvalexprError::VariableIdentifierNotFound(
+            "user.address.zip".to_string()
+        ))
+    );
+    assert_eq!(
+        eval_with_context("items.1.price", &context),
+        Ok(Value::Int(5))
+    );
+    assert_eq!(
+        eval_with_context("items.0.price + items.1.price", &context),
+        Ok(Value::Int(8))
+    );
+    assert_eq!(
+        eval_with_context("items.2.price", &context),
+        Err(EvalexprError::VariableIdentifierNotFound(
+            "items.2.price".to_string()
+        ))
+    );
+    assert_eq!(
+        eval_with_context("items.first", &context),
+        Err(EvalexprError::VariableIdentifierNotFound(
+            "items.first".to_string()
+        ))
+    );
+    assert_eq!(
+        eval_with_context("user.name.0", &context),
+        Err(EvalexprError::expected_map(Value::from("Ada")))
+    );
+}
+
+#[test]
+fn test_contains_any_and_all() {
+    assert_eq!(
+        eval("contains_all((1, 2, 3), (3, 1))"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval("contains_all((1, 2, 3), (3, 4))"),
+        Ok(Value::Boolean(false))
+    );
+    assert_eq!(
+        eval("contains_all((\"a\", \"b\"), \"a\")"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval("contains_all(\"a\", (\"a\", \"a\"))"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(eval("contains_any(1, (0, 1))"), Ok(Value::Boolean(true)));
+
+    // Empty needles: no needle is contained, and all of the needles are contained
+    assert_eq!(eval("contains_any((1, 2), ())"), Ok(Value::Boolean(false)));
+    assert_eq!(eval("contains_all((1, 2), ())"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("contains_any((), ())"), Ok(Value::Boolean(false)));
+    assert_eq!(eval("contains_all((), ())"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("contains_all((), 1)"), Ok(Value::Boolean(false)));
+
+    // The equality semantics of `==`
+    assert_eq!(eval("contains_any((1, 2), 1.0)"), Ok(Value::Boolean(false)));
+    assert_eq!(
+        eval("contains_any((1.0, 2), 1.0)"),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(eval("contains_any(0.0, -0.0)"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("contains_any(NaN, NaN)"), Ok(Value::Boolean(false)));
+    assert_eq!(
+        eval("contains_any(((1, 2), 3), 3)"),
+        Ok(Value::Boolean(true))
+    );
+
+    assert_eq!(
+        eval("contains_all((1, 2), (1, ()))"),
+        Err(EvalexprError::type_error(
+            Value::Empty,
+            vec![
+                ValueType::String,
+                ValueType::Int,
+                ValueType::Float,
+                ValueType::Boolean
+            ]
+        ))
+    );
+
+    let context = context_map! {
+        "haystack" => Value::Tuple((0..10_000).map(Value::Int).collect()),
+        "needles" => Value::Tuple((5_000..15_000).map(Value::Int).collect()),
+    }
+    .unwrap();
+    assert_eq!(
+        eval_with_context("contains_any(haystack, needles)", &context),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval_with_context("contains_all(haystack, needles)", &context),
+        Ok(Value::Boolean(false))
+    );
+}
+
+#[test]
+fn test_eval_profiled() {
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "square".into(),
+            Function::new(|argument| {
+                let x = argument.as_int()?;
+                Ok(Value::Int(x * x))
+            }),
+        )
+        .unwrap();
+
+    let tree =
+        build_operator_tree("sum(square(1), square(2), square(3), square(4)) + square(5)").unwrap();
+    let (result, call_counts) = tree.eval_profiled(&context);
+    assert_eq!(result, Ok(Value::Int(55)));
+    assert_eq!(call_counts.len(), 2);
+    assert_eq!(call_counts["square"], 5);
+    assert_eq!(call_counts["sum"], 1);
+
+    // Calls up to and including the failing one are counted
+    let tree = build_operator_tree("square(1) + square(\"a\") + square(2)").unwrap();
+    let (result, call_counts) = tree.eval_profiled(&context);
+    assert_eq!(result, Err(EvalexprError::expected_int(Value::from("a"))));
+    assert_eq!(call_counts["square"], 2);
+
+    let (result, call_counts) = build_operator_tree("1 + 2")
+        .unwrap()
+        .eval_profiled(&context);
+    assert_eq!(result, Ok(Value::Int(3)));
+    assert!(call_counts.is_empty());
+}
+
+#[test]
+fn test_strings_are_indexed_by_characters() {
+    assert_eq!(eval("len(\"héllo 🦀\")"), Ok(Value::Int(7)));
+    assert_eq!(
+        eval("str::substring(\"héllo 🦀!\", 1, 2)"),
+        Ok(Value::from("é"))
+    );
+    assert_eq!(
+        eval("str::substring(\"héllo 🦀!\", 6)"),
+        Ok(Value::from("🦀!"))
+    );
+    assert_eq!(
+        eval("str::substring(\"héllo 🦀!\", 6, 7)"),
+        Ok(Value::from("🦀"))
+    );
+    assert_eq!(
+        eval("str::substring(\"héllo 🦀!\", 8)"),
+        Ok(Value::from(""))
+    );
+    assert_eq!(
+        eval("str::substring(\"héllo 🦀!\", 9)"),
+        Err(EvalexprError::OutOfBoundsAccess)
+    );
+    assert_eq!(
+        eval("str::substring(\"héllo 🦀!\", 0, 9)"),
+        Err(EvalexprError::OutOfBoundsAccess)
+    );
+    // The byte length of the string is out of bounds
+    assert_eq!(
+        eval("str::substring(\"🦀\", 0, 4)"),
+        Err(EvalexprError::OutOfBoundsAccess)
+    );
+}
+
+#[test]
+fn test_rewrite_identities_preserve_evaluation() {
+    use rand::{Rng, SeedableRng};
+
+    let expressions = [
+        "a * 1 + 0 * b",
+        "(a + 0) * (1 * b) - 0",
+        "a / 1 - (b - 0)",
+        "!!p && true || false",
+        "true && (q || false) == !!p",
+        "if(!!p, a * 1, b + 0)",
+        "(a * 1, b / 1, !!(p && true))",
+        "0 + (1 * (a + 0))",
+        "!!!!q",
+    ];
+    let rules = Rule::identities();
+    let mut rng = rand_pcg::Pcg32::seed_from_u64(455);
+
+    for expression in &expressions {
+        let original = build_operator_tree(expression).unwrap();
+        let mut rewritten = original.clone();
+        assert!(rewritten.rewrite(&rules) > 0, "{}", expression);
+        assert!(rewritten.iter().count() < original.iter().count());
+
+        for _ in 0..100 {
+            let mut number = || {
+                if rng.gen() {
+                    Value::Int(rng.gen_range(-1000..1000))
+                } else {
+                    Value::Float(rng.gen_range(-1000.0..1000.0))
+                }
+            };
+            let (a, b) = (number(), number());
+            let context = context_map! {
+                "a" => a,
+                "b" => b,
+                "p" => rng.gen::<bool>(),
+                "q" => rng.gen::<bool>(),
+            }
+            .unwrap();
+            assert_eq!(
+                rewritten.eval_with_context(&context),
+                original.eval_with_context(&context),
+                "{}",
+                expression
+            );
+        }
+    }
+}
+
+#[test]
+fn test_rewrite() {
+    let mut tree = build_operator_tree("a * 1.0 + 0 * b").unwrap();
+    assert_eq!(tree.rewrite(&Rule::identities()), 0);
+
+    // Wildcards with the same name match equal subtrees
+    let rule = Rule::new(
+        Pattern::node(
+            Operator::Sub,
+            vec![Pattern::wildcard("x"), Pattern::wildcard("x")],
+        ),
+        Pattern::value(0),
+    );
+    let mut tree = build_operator_tree("(a + 1) - (a + 1) + (a - b)").unwrap();
+    assert_eq!(tree.rewrite(&[rule]), 1);
+    assert_eq!(tree.to_tree_string(), "+\n  0\n  -\n    a\n    b\n");
+
+    // Rules that never reach a fixed point stop after a bounded amount of passes
+    let rule = Rule::new(
+        Pattern::node(
+            Operator::Add,
+            vec![Pattern::wildcard("x"), Pattern::wildcard("y")],
+        ),
+        Pattern::node(
+            Operator::Add,
+            vec![Pattern::wildcard("y"), Pattern::wildcard("x")],
+        ),
+    );
+    let mut tree = build_operator_tree("a + b").unwrap();
+    assert_eq!(tree.rewrite(&[rule]), 100);
+}
+
+#[test]
+#[should_panic]
+fn test_rewrite_rule_with_unbound_wildcard() {
+    Rule::new(Pattern::wildcard("x"), Pattern::wildcard("y"));
+}
+
+#[test]
+fn test_node_builder() {
+    let variable = NodeBuilder::variable;
+    let value = NodeBuilder::value::<Value>;
+    let operation = |operator, operands| NodeBuilder::operation(operator, operands).unwrap();
+    // Each built tree with the expression that parses to the same tree
+    let cases = vec![
+        (value(Value::Int(3)), "3"),
+        (variable("a"), "a"),
+        (
+            operation(
+                Operator::Mul,
+                vec![
+                    operation(Operator::Add, vec![variable("a"), value(Value::Int(1))]),
+                    variable("b"),
+                ],
+            ),
+            "(a + 1) * b",
+        ),
+        (
+            operation(
+                Operator::Neg,
+                vec![operation(
+                    Operator::Exp,
+                    vec![variable("a"), value(Value::Int(2))],
+                )],
+            ),
+            "-(a ^ 2)",
+        ),
+        (
+            operation(
+                Operator::Sub,
+                vec![
+                    variable("a"),
+                    operation(Operator::Sub, vec![variable("b"), variable("c")]),
+                ],
+            ),
+            "a - (b - c)",
+        ),
+        (
+            variable("a")
+                .compare(
+                    Operator::Lt,
+                    operation(Operator::Mod, vec![variable("b"), value(Value::Int(3))]),
+                )
+                .unwrap(),
+            "a < (b % 3)",
+        ),
+        (
+            NodeBuilder::all([
+                variable("p"),
+                !variable("q"),
+                NodeBuilder::any([variable("q"), variable("p")]),
+            ]),
+            "p && (!q) && (q || p)",
+        ),
+        (NodeBuilder::all([variable("p")]), "p"),
+        (NodeBuilder::all([]), "true"),
+        (NodeBuilder::any([]), "false"),
+        (
+            operation(
+                Operator::Conditional,
+                vec![
+                    variable("p"),
+                    variable("a"),
+                    operation(Operator::Neg, vec![variable("a")]),
+                ],
+            ),
+            "p ? a : (-a)",
+        ),
+        (
+            NodeBuilder::function(
+                "max",
+                vec![variable("a"), variable("b"), value(Value::Int(0))],
+            ),
+            "max(a, b, 0)",
+        ),
+        (
+            NodeBuilder::function("str::to_uppercase", vec![value(Value::from("x"))]),
+            "str::to_uppercase(\"x\")",
+        ),
+        (NodeBuilder::function("random", vec![]), "random()"),
+        (
+            operation(Operator::Tuple, vec![variable("a"), variable("b")]),
+            "a, b",
+        ),
+        (
+            operation(
+                Operator::Map {
+                    keys: vec!["x".into()],
+                },
+                vec![variable("a")],
+            ),
+            "{x: a}",
+        ),
+    ];
+    let contexts = [
+        context_map! { "a" => 2, "b" => 5, "c" => 1, "p" => true, "q" => false }.unwrap(),
+        context_map! { "a" => -3, "b" => 4, "c" => 8, "p" => false, "q" => true }.unwrap(),
+        context_map! { "a" => 2.5, "b" => 7.0, "c" => 0.5, "p" => true, "q" => true }.unwrap(),
+    ];
+
+    for (builder, expression) in cases {
+        let built = builder.build();
+        let parsed = build_operator_tree(expression).unwrap();
+        assert_eq!(built, parsed, "{}", expression);
+        if expression.contains("random") {
+            continue;
+        }
+        for context in &contexts {
+            assert_eq!(
+                built.eval_with_context(context),
+                parsed.eval_with_context(context),
+                "{}",
+                expression
+            );
+        }
+    }
+
+    // Fragments are embedded as a whole, unlike concatenated strings
+    let fragment = build_operator_tree("q || true").unwrap();
+    let rule = NodeBuilder::all([variable("p"), NodeBuilder::from(fragment.clone())]).build();
+    let concatenated = build_operator_tree(&format!("p && {}", "q || true")).unwrap();
+    let context = context_map! { "p" => false, "q" => false }.unwrap();
+    assert_eq!(rule.eval_with_context(&context), Ok(Value::from(false)));
+    assert_eq!(
+        concatenated.eval_with_context(&context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(NodeBuilder::from(fragment.clone()).build(), fragment);
+
+    // The amount of operands is checked
+    assert_eq!(
+        NodeBuilder::operation(Operator::Add, vec![variable("a")]),
+        Err(EvalexprError::WrongOperatorArgumentAmount {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        NodeBuilder::operation(Operator::Not, vec![variable("a"), variable("b")]),
+        Err(EvalexprError::WrongOperatorArgumentAmount {
+            expected: 1,
+            actual: 2
+        })
+    );
+    assert_eq!(
+        NodeBuilder::operation(Operator::Tuple, vec![variable("a")]),
+        Err(EvalexprError::WrongOperatorArgumentAmount {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        NodeBuilder::operation(
+            Operator::Map {
+                keys: vec!["x".into(), "y".into()]
+            },
+            vec![variable("a")]
+        ),
+        Err(EvalexprError::WrongOperatorArgumentAmount {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        NodeBuilder::operation(
+            Operator::Const {
+                value: Value::Int(1)
+            },
+            vec![variable("a")]
+        ),
+        Err(EvalexprError::WrongOperatorArgumentAmount {
+            expected: 0,
+            actual: 1
+        })
+    );
+    let error = NodeBuilder::operation(Operator::Assign, vec![variable("a"), value(Value::Int(1))])
+        .unwrap_err();
+    assert_eq!(error.code(), "unbuildable_operator");
+    assert_eq!(
+        error.to_string(),
+        "A node with the operator Assign cannot be built: assignments cannot be built"
+    );
+    assert_eq!(
+        variable("a").compare(Operator::Add, Value::Int(1)),
+        Err(EvalexprError::UnbuildableOperator {
+            operator: Operator::Add,
+            reason: "expected a comparison".to_string()
+        })
+    );
+}
+
+#[test]
+fn test_parse_number_builtins() {
+    assert_eq!(eval("parse_int(\"42\")"), Ok(Value::Int(42)));
+    assert_eq!(eval("parse_int(\"  +1_000  \")"), Ok(Value::Int(1000)));
+    assert_eq!(eval("parse_int(\"-17\")"), Ok(Value::Int(-17)));
+    assert_eq!(eval("parse_int(\"ff\", 16)"), Ok(Value::Int(255)));
+    assert_eq!(eval("parse_int(\"FF\", 16)"), Ok(Value::Int(255)));
+    assert_eq!(eval("parse_int(\"1010_1010\", 2)"), Ok(Value::Int(170)));
+    assert_eq!(eval("parse_int(\"z\", 36)"), Ok(Value::Int(35)));
+    assert_eq!(
+        eval("parse_int(\"1\", 1)"),
+        Err(EvalexprError::InvalidRadix { radix: 1 })
+    );
+    assert_eq!(
+        eval("parse_int(\"1\", 37)"),
+        Err(EvalexprError::InvalidRadix { radix: 37 })
+    );
+    for string in ["", " ", "abc", "1.5", "0x10", "+-1", "1 2"] {
+        assert_eq!(
+            eval(&format!("parse_int({:?})", string)),
+            Err(EvalexprError::InvalidNumber {
+                string: string.to_string()
+            })
+        );
+    }
+    assert_eq!(
+        eval("parse_int(\"2\", 2)"),
+        Err(EvalexprError::InvalidNumber {
+            string: "2".to_string()
+        })
+    );
+    assert_eq!(
+        eval("parse_int(\"99999999999999999999\")"),
+        Err(EvalexprError::InvalidNumber {
+            string: "99999999999999999999".to_string()
+        })
+    );
+    assert_eq!(
+        eval("parse_int(42)"),
+        Err(EvalexprError::ExpectedString {
+            actual: Value::Int(42)
+        })
+    );
+
+    assert_eq!(
+        eval("parse_float(\" +1_000.5 \")"),
+        Ok(Value::Float(1000.5))
+    );
+    assert_eq!(eval("parse_float(\"-2e3\")"), Ok(Value::Float(-2000.0)));
+    assert_eq!(eval("parse_float(\"3\")"), Ok(Value::Float(3.0)));
+    assert_eq!(
+        eval("parse_float(\"1.2.3\")"),
+        Err(EvalexprError::InvalidNumber {
+            string: "1.2.3".to_string()
+        })
+    );
+
+    assert_eq!(eval("parse_int_or(\"12\", 0)"), Ok(Value::Int(12)));
+    assert_eq!(eval("parse_int_or(\"x\", 0)"), Ok(Value::Int(0)));
+    assert_eq!(eval("parse_int_or(\"x\", ())"), Ok(Value::Empty));
+    assert_eq!(eval("parse_int_or(\"ff\", 16, -1)"), Ok(Value::Int(255)));
+    assert_eq!(eval("parse_int_or(\"fg\", 16, -1)"), Ok(Value::Int(-1)));
+    assert_eq!(
+        eval("parse_int_or(\"1\", 37, -1)"),
+        Err(EvalexprError::InvalidRadix { radix: 37 })
+    );
+    assert_eq!(eval("parse_float_or(\"1.5\", 0.0)"), Ok(Value::Float(1.5)));
+    assert_eq!(eval("parse_float_or(\"one\", 0.0)"), Ok(Value::Float(0.0)));
+    assert_eq!(
+        eval("parse_float_or(1, 0.0)"),
+        Err(EvalexprError::ExpectedString {
+            actual: Value::Int(1)
+        })
+    );
+}
+
+#[test]
+fn test_try_or() {
+    assert_eq!(eval("try_or(1 / 0, -1)"), Ok(Value::Int(-1)));
+    assert_eq!(eval("try_or(1 + \"a\", 0)"), Ok(Value::Int(0)));
+    assert_eq!(eval("try_or(unknown, \"x\")"), Ok(Value::from("x")));
+    assert_eq!(eval("try_or(try_or(1 / 0, 2 / 0), 3)"), Ok(Value::Int(3)));
+    assert_eq!(
+        eval("try_or(1 / 0, 2 / 0)"),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::Int(2),
+            divisor: Value::Int(0)
+        })
+    );
+    assert_eq!(
+        eval("try_or(1, 2, 3)"),
+        Err(EvalexprError::ExpectedFixedLengthTuple {
+            expected_length: 2,
+            actual: Value::from(vec![1.into(), 2.into(), 3.into()])
+        })
+    );
+
+    // The fallback is only evaluated on error
+    let mut context = HashMapContext::new();
+    assert_eq!(
+        eval_with_context_mut("try_or(5, a = 1)", &mut context),
+        Ok(Value::Int(5))
+    );
+    assert_eq!(context.get_value("a"), None);
+    assert_eq!(
+        eval_with_context_mut("try_or(1 / 0, a = 1); a", &mut context),
+        Ok(Value::Int(1))
+    );
+
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut context = HashMapContext::new();
+    let counter = calls.clone();
+    context
+        .set_function(
+            "fallback".into(),
+            Function::new(move |_| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(Value::Int(-1))
+            }),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context("try_or(5, fallback())", &context),
+        Ok(Value::Int(5))
+    );
+    assert_eq!(
+        build_expression("try_or(5, fallback())")
+            .unwrap()
+            .eval_with_context(&context),
+        Ok(Value::Int(5))
+    );
+    let (result, call_counts) = build_operator_tree("try_or(5, fallback())")
+        .unwrap()
+        .eval_profiled(&context);
+    assert_eq!(result, Ok(Value::Int(5)));
+    assert_eq!(call_counts.get("fallback"), None);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+    assert_eq!(
+        build_expression("try_or((1 / 0), (fallback()))")
+            .unwrap()
+            .eval_with_context(&context),
+        Ok(Value::Int(-1))
+    );
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    context.set_builtin_functions_disabled(true).unwrap();
+    assert_eq!(
+        eval_with_context("try_or(5, fallback())", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound("try_or".into()))
+    );
+}
+
+#[test]
+fn test_try_or_context_function() {
+    // A context function named `try_or` takes precedence over the lazy builtin function
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "try_or".into(),
+            Function::new(|argument| Ok(Value::from(argument.as_tuple()?.len() as IntType))),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context("try_or(1, 2)", &context),
+        Ok(Value::Int(2))
+    );
+    assert_eq!(
+        build_expression("try_or(1, 2)")
+            .unwrap()
+            .eval_with_context(&context),
+        Ok(Value::Int(2))
+    );
+    let tree = build_operator_tree("try_or(1, 2)").unwrap();
+    assert_eq!(tree.eval_profiled(&context).0, Ok(Value::Int(2)));
+    assert_eq!(tree.eval_with_stats(&context).0, Ok(Value::Int(2)));
+    let layers: &[&dyn Context] = &[&EmptyContextWithBuiltinFunctions, &context];
+    assert_eq!(
+        eval_with_context("try_or(1, 2)", &layers),
+        Ok(Value::Int(2))
+    );
+
+    // The arguments are evaluated eagerly, like for any other function
+    assert_eq!(
+        eval_with_context("try_or(1 / 0, 2)", &context),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::Int(1),
+            divisor: Value::Int(0)
+        })
+    );
+    assert_eq!(
+        eval_with_context("try_or(1 / 0, 2)", &EmptyContextWithBuiltinFunctions),
+        Ok(Value::Int(2))
+    );
+}
+
+#[test]
+fn test_has_function_does_not_call_functions() {
+    struct CallCountingContext {
+        calls: std::cell::Cell<usize>,
+    }
+
+    impl Context for CallCountingContext {
+        fn get_value(&self, _identifier: &str) -> Option<&Value> {
+            None
+        }
+
+        fn call_function(&self, _identifier: &str, argument: &Value) -> EvalexprResult<Value> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(argument.clone())
+        }
+
+        fn are_builtin_functions_disabled(&self) -> bool {
+            false
+        }
+
+        fn set_builtin_functions_disabled(&mut self, _disabled: bool) -> EvalexprResult<()> {
+            Ok(())
+        }
+    }
+
+    // The default implementation does not call the function to find out if it exists
+    let context = CallCountingContext {
+        calls: std::cell::Cell::new(0),
+    };
+    assert!(!context.has_function("try_or"));
+    assert_eq!(
+        eval_with_context("try_or(1 / 0, 2)", &context),
+        Ok(Value::Int(2))
+    );
+    assert_eq!(context.calls.get(), 0);
+    assert_eq!(eval_with_context("f(3)", &context), Ok(Value::Int(3)));
+    assert_eq!(context.calls.get(), 1);
+}
+
+#[test]
+fn test_format_bytes() {
+    for (bytes, expected) in [
+        (0, "0 B"),
+        (1, "1 B"),
+        (1023, "1023 B"),
+        (1024, "1 KiB"),
+        (1536, "1.5 KiB"),
+        (1075, "1 KiB"),
+        (1076, "1.1 KiB"),
+        (1024 * 1024 - 1, "1 MiB"),
+        (1024 * 1024 * 1024 * 5 / 2, "2.5 GiB"),
+        (IntType::MAX, "8 EiB"),
+    ] {
+        assert_eq!(
+            eval(&format!("format_bytes({})", bytes)),
+            Ok(Value::from(expected)),
+            "{}",
+            bytes
+        );
+    }
+    assert_eq!(
+        eval("format_bytes(-1)"),
+        Err(EvalexprError::InvalidQuantity {
+            actual: Value::Int(-1)
+        })
+    );
+    assert_eq!(
+        eval("format_bytes(1.5)"),
+        Err(EvalexprError::ExpectedInt {
+            actual: Value::Float(1.5)
+        })
+    );
+}
+
+#[test]
+fn test_format_duration() {
+    for (seconds, expected) in [
+        ("0", "0s"),
+        ("0.0", "0s"),
+        ("0.04", "0s"),
+        ("0.5", "0.5s"),
+        ("5", "5s"),
+        ("59", "59s"),
+        ("59.9", "59.9s"),
+        ("59.96", "1m"),
+        ("60", "1m"),
+        ("3600", "1h"),
+        ("3725", "1h 2m 5s"),
+        ("3725.25", "1h 2m 5.3s"),
+        ("86400", "1d"),
+        ("93605", "1d 2h 5s"),
+    ] {
+        assert_eq!(
+            eval(&format!("format_duration({})", seconds)),
+            Ok(Value::from(expected)),
+            "{}",
+            seconds
+        );
+    }
+    assert_eq!(
+        eval("format_duration(-1)"),
+        Err(EvalexprError::InvalidQuantity {
+            actual: Value::Int(-1)
+        })
+    );
+    assert_eq!(
+        eval("format_duration(-0.5)"),
+        Err(EvalexprError::InvalidQuantity {
+            actual: Value::Float(-0.5)
+        })
+    );
+    assert_eq!(
+        eval("format_duration(math::sqrt(-1))").unwrap_err().code(),
+        "invalid_quantity"
+    );
+    assert_eq!(
+        eval("format_duration(\"5\")"),
+        Err(EvalexprError::ExpectedNumber {
+            actual: Value::from("5")
+        })
+    );
+}
+
+#[test]
+fn test_layered_context() {
+    let mut overrides = context_map! { "a" => 10 }.unwrap();
+    overrides
+        .set_function("f".into(), Function::new(|_| Ok(Value::Int(1))))
+        .unwrap();
+    let defaults = context_map! {
+        "a" => 1,
+        "b" => 2,
+        "f" => Function::new(|_| Ok(Value::Int(2))),
+        "g" => Function::new(|_| Ok(Value::Int(3)))
+    }
+    .unwrap();
+    let mut layers: &[&dyn Context] = &[&overrides, &defaults];
+
+    assert_eq!(eval_with_context("a + b", &layers), Ok(Value::Int(12)));
+    assert_eq!(eval_with_context("f() + g()", &layers), Ok(Value::Int(4)));
+    assert_eq!(
+        eval_with_context("h()", &layers),
+        Err(EvalexprError::FunctionIdentifierNotFound("h".into()))
+    );
+    assert_eq!(eval_with_context("max(a, b)", &layers), Ok(Value::Int(10)));
+    assert_eq!(
+        layers.set_builtin_functions_disabled(true),
+        Err(EvalexprError::BuiltinFunctionsCannotBeDisabled)
+    );
+
+    let empty: &[&dyn Context] = &[];
+    assert_eq!(empty.get_value("a"), None);
+    let disabled: &[&dyn Context] = &[&defaults, &EmptyContext];
+    assert!(disabled.are_builtin_functions_disabled());
+}
+
+#[test]
+fn test_caching_context() {
+    struct CountingContext {
+        value: Value,
+        lookups: std::cell::Cell<usize>,
+    }
+
+    impl Context for CountingContext {
+        fn get_value(&self, identifier: &str) -> Option<&Value> {
+            self.lookups.set(self.lookups.get() + 1);
+            (identifier == "a").then_some(&self.value)
+        }
+
+        fn call_function(&self, identifier: &str, _argument: &Value) -> EvalexprResult<Value> {
+            Err(EvalexprError::FunctionIdentifierNotFound(
+                identifier.to_string(),
+            ))
+        }
+
+        fn are_builtin_functions_disabled(&self) -> bool {
+            false
+        }
+
+        fn set_builtin_functions_disabled(&mut self, _disabled: bool) -> EvalexprResult<()> {
+            Ok(())
+        }
+    }
+
+    let inner = CountingContext {
+        value: Value::Int(3),
+        lookups: std::cell::Cell::new(0),
+    };
+    let mut cached = CachingContext::new(&inner);
+    let tree = build_operator_tree("a * a + a").unwrap();
+    for _ in 0..10 {
+        assert_eq!(tree.eval_with_context(&cached), Ok(Value::Int(12)));
+    }
+    assert_eq!(inner.lookups.get(), 1);
+
+    // Missing identifiers are cached too
+    for _ in 0..3 {
+        assert_eq!(
+            eval_with_context("b", &cached),
+            Err(EvalexprError::VariableIdentifierNotFound("b".into()))
+        );
+    }
+    assert_eq!(inner.lookups.get(), 2);
+    assert_eq!(eval_with_context("min(a, 1)", &cached), Ok(Value::Int(1)));
+
+    cached.clear_cache();
+    assert_eq!(eval_with_context("a", &cached), Ok(Value::Int(3)));
+    assert_eq!(inner.lookups.get(), 3);
+    assert_eq!(
+        cached.set_builtin_functions_disabled(false),
+        Err(EvalexprError::BuiltinFunctionsCannotBeEnabled)
+    );
+
+    let defaults = context_map! { "a" => 1 }.unwrap();
+    let layers: &[&dyn Context] = &[&inner, &defaults];
+    let cached_layers = CachingContext::new(&layers);
+    assert_eq!(
+        eval_with_context("a + a", &cached_layers),
+        Ok(Value::Int(6))
+    );
+    assert_eq!(inner.lookups.get(), 4);
+}
+
+#[test]
+fn test_expression_size_limits() {
+    let options = ParseOptions::new().max_length(5);
+    assert!(build_operator_tree_with_options("1 + 2", &options).is_ok());
+    assert_eq!(
+        build_operator_tree_with_options("1 + 23", &options),
+        Err(EvalexprError::ExpressionTooLarge {
+            limit: 5,
+            actual: 6
+        })
+    );
+    // The length is counted in bytes
+    assert_eq!(
+        build_operator_tree_with_options("\"äöü\"", &options),
+        Err(EvalexprError::ExpressionTooLarge {
+            limit: 5,
+            actual: 8
+        })
+    );
+
+    let options = ParseOptions::new().max_tokens(5);
+    for string in [
+        "a &&= b",
+        "1e-3 + 1.5e+3",
+        "f(1)",
+        "  a   +   b   /* comment */  ",
+        "\"a b c d e f\"",
+    ] {
+        assert!(
+            build_operator_tree_with_options(string, &options).is_ok(),
+            "{}",
+            string
+        );
+    }
+    assert_eq!(
+        build_operator_tree_with_options("f(1, 2, 3)", &options),
+        Err(EvalexprError::ExpressionTooLarge {
+            limit: 5,
+            actual: 8
+        })
+    );
+    let error = build_operator_tree_with_options(&"a+".repeat(100), &options).unwrap_err();
+    assert!(error.is_parse_error());
+    assert_eq!(error.code(), "expression_too_large");
+
+    let options = ParseOptions::new().max_tokens(0);
+    assert_eq!(
+        build_operator_tree_with_options("  ", &options).map(|tree| tree.eval()),
+        Ok(Ok(Value::Empty))
+    );
+}
+
+#[test]
+fn test_tokenize_and_syntax_errors() {
+    for string in ["a & b", "a | b", "\"abc", "\"\\q\"", "1 /* 2"] {
+        let error = build_operator_tree(string).unwrap_err();
+        assert!(error.is_tokenize_error(), "{}: {:?}", string, error);
+        assert!(!error.is_syntax_error(), "{}: {:?}", string, error);
+        assert!(error.is_parse_error(), "{}: {:?}", string, error);
+    }
+    let error =
+        build_operator_tree_with_options("1 + 2", &ParseOptions::new().max_tokens(2)).unwrap_err();
+    assert!(error.is_tokenize_error());
+
+    for string in ["1 +", "* 2", "(1 + 2", "1 + 2)", "(4)(5)", "{a: 1", "{a 1}"] {
+        let error = build_operator_tree(string).unwrap_err();
+        assert!(error.is_syntax_error(), "{}: {:?}", string, error);
+        assert!(!error.is_tokenize_error(), "{}: {:?}", string, error);
+        assert!(error.is_parse_error(), "{}: {:?}", string, error);
+    }
+
+    let error = eval("1 / 0").unwrap_err();
+    assert!(!error.is_tokenize_error());
+    assert!(!error.is_syntax_error());
+}
+
+#[test]
+fn test_operator_filter() {
+    let expressions = [
+        ("2 ^ 3", Operator::Exp, 2..3),
+        ("1 + 2 * 3", Operator::Add, 2..3),
+        ("-a", Operator::Neg, 0..1),
+        ("a > 1 && b", Operator::Gt, 2..3),
+        ("!b", Operator::Not, 0..1),
+        ("a = 1", Operator::Assign, 2..3),
+        ("a += 1", Operator::AddAssign, 2..4),
+        ("1, 2", Operator::Tuple, 1..2),
+        ("1; 2", Operator::Chain, 1..2),
+        (
+            "{a: 1}",
+            Operator::Map {
+                keys: vec!["a".into()],
+            },
+            0..6,
+        ),
+        (
+            "f(1)",
+            Operator::FunctionIdentifier {
+                identifier: "f".into(),
+            },
+            0..1,
+        ),
+    ];
+    for (string, operator, span) in &expressions {
+        assert!(build_operator_tree(string).is_ok(), "{}", string);
+
+        let options = ParseOptions::new().allowed_operators(
+            OperatorFilter::all().exclude_category(operator.category().unwrap()),
+        );
+        assert_eq!(
+            build_operator_tree_with_options(string, &options),
+            Err(EvalexprError::OperatorDisabled {
+                operator: operator.clone(),
+                span: span.clone(),
+            }),
+            "{}",
+            string
+        );
+    }
+
+    let options = ParseOptions::new().allowed_operators(OperatorFilter::arithmetic_only());
+    assert_eq!(
+        build_operator_tree_with_options("(1 + a) * -2 ^ 3 % 4 / b - c", &options)
+            .unwrap()
+            .eval_with_context(&context_map! { "a" => 1, "b" => 2, "c" => 3 }.unwrap()),
+        Ok(Value::Float(-3.0))
+    );
+    assert_eq!(
+        build_operator_tree_with_options("1 + math::abs(2)", &options),
+        Err(EvalexprError::OperatorDisabled {
+            operator: Operator::FunctionIdentifier {
+                identifier: "math::abs".into()
+            },
+            span: 4..13,
+        })
+    );
+    assert_eq!(
+        build_operator_tree_with_options("1 < 2", &options),
+        Err(EvalexprError::OperatorDisabled {
+            operator: Operator::Lt,
+            span: 2..3,
+        })
+    );
+
+    let options =
+        ParseOptions::new().allowed_operators(OperatorFilter::comparison_and_logic_only());
+    assert!(build_operator_tree_with_options("a == b || !(c >= 1.5)", &options).is_ok());
+    assert_eq!(
+        build_operator_tree_with_options("a == b + 1", &options),
+        Err(EvalexprError::OperatorDisabled {
+            operator: Operator::Add,
+            span: 7..8,
+        })
+    );
+
+    // Specific operators are matched independent of their fields
+    let options = ParseOptions::new().allowed_operators(
+        OperatorFilter::all()
+            .exclude_operator(Operator::Exp)
+            .exclude_operator(Operator::FunctionIdentifier {
+                identifier: "".into(),
+            }),
+    );
+    assert!(build_operator_tree_with_options("2 ^= 3", &options).is_ok());
+    assert!(build_operator_tree_with_options("(1, 2 * 3)", &options).is_ok());
+    let error = build_operator_tree_with_options("{a: 2 ^ 3}", &options).unwrap_err();
+    assert_eq!(
+        error,
+        EvalexprError::OperatorDisabled {
+            operator: Operator::Exp,
+            span: 6..7,
+        }
+    );
+    assert!(error.is_parse_error());
+    assert_eq!(
+        build_operator_tree_with_options("f()", &options),
+        Err(EvalexprError::OperatorDisabled {
+            operator: Operator::FunctionIdentifier {
+                identifier: "f".into()
+            },
+            span: 0..1,
+        })
+    );
+}
+
+#[test]
+fn test_postprocessors() {
+    let to_boolean = |value: Value| match value {
+        Value::Boolean(_) => Ok(value),
+        Value::Int(int) => Ok(Value::Boolean(int != 0)),
+        value => Err(EvalexprError::expected_boolean(value)),
+    };
+    let node = build_operator_tree("a")
+        .unwrap()
+        .with_postprocessor(to_boolean);
+    let mut context = context_map! { "a" => 2 }.unwrap();
+    assert_eq!(node.eval_with_context(&context), Ok(Value::Boolean(true)));
+    context.set_value("a".into(), 0.into()).unwrap();
+    assert_eq!(node.eval_with_context(&context), Ok(Value::Boolean(false)));
+
+    // Errors of the postprocessor are returned
+    let context = context_map! { "a" => "yes" }.unwrap();
+    assert_eq!(
+        node.eval_with_context(&context),
+        Err(EvalexprError::ExpectedBoolean {
+            actual: Value::from("yes")
+        })
+    );
+
+    // Postprocessors compose in the order they are attached
+    let append =
+        |suffix: &'static str| move |value: Value| Ok(Value::String(value.as_string()? + suffix));
+    let node = build_operator_tree("\"x\"")
+        .unwrap()
+        .with_postprocessor(append("1"))
+        .with_postprocessor(append("2"))
+        .with_postprocessor(append("3"));
+    assert_eq!(node.eval(), Ok(Value::from("x123")));
+    assert_eq!(node.clone().into_node().eval(), Ok(Value::from("x")));
+
+    // Later postprocessors are skipped after an error, and so are all postprocessors after an evaluation error
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let counter = calls.clone();
+    let count = move |value: Value| {
+        counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(value)
+    };
+    let node = build_operator_tree("a = a + 1; a")
+        .unwrap()
+        .with_postprocessor(|_| Err(EvalexprError::CustomMessage("rejected".into())))
+        .with_postprocessor(count.clone());
+    let mut context = context_map! { "a" => 1 }.unwrap();
+    assert_eq!(
+        node.eval_with_context_mut(&mut context),
+        Err(EvalexprError::CustomMessage("rejected".into()))
+    );
+    assert_eq!(context.get_value("a"), Some(&Value::Int(2)));
+    let node = build_operator_tree("1 / 0")
+        .unwrap()
+        .with_postprocessor(count);
+    assert_eq!(
+        node.eval(),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::Int(1),
+            divisor: Value::Int(0)
+        })
+    );
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_min_by_max_by() {
+    let mut context = HashMapContext::new();
+    eval_with_context_mut(
+        "players = (
+            {name: \"a\", score: 3, stats: {age: 30}},
+            {name: \"b\", score: 7.5, stats: {age: 20}},
+            {name: \"c\", score: 7.5, stats: {age: 40}},
+            {name: \"d\", score: -1, stats: {age: 20}}
+        )",
+        &mut context,
+    )
+    .unwrap();
+    let name = |string: &str| {
+        eval_with_context(string, &context).map(|value| value.get_field("name").cloned())
+    };
+
+    assert_eq!(name("max_by(players, \"score\")"), Ok(Some("b".into())));
+    assert_eq!(name("min_by(players, \"score\")"), Ok(Some("d".into())));
+    assert_eq!(name("max_by(players, \"name\")"), Ok(Some("d".into())));
+    assert_eq!(name("min_by(players, \"stats.age\")"), Ok(Some("b".into())));
+    assert_eq!(name("max_by(players, \"stats.age\")"), Ok(Some("c".into())));
+    assert_eq!(
+        name("max_by({name: \"x\", score: 1}, \"score\")"),
+        Ok(Some("x".into()))
+    );
+    assert_eq!(
+        name("min_by(({name: \"x\", s: 0.0 / 0.0}, {name: \"y\", s: 2}), \"s\")"),
+        Ok(Some("y".into()))
+    );
+
+    assert_eq!(
+        eval("max_by((), \"score\")"),
+        Err(EvalexprError::WrongFunctionArgumentAmount {
+            expected: 1..=usize::MAX,
+            actual: 0
+        })
+    );
+    assert_eq!(
+        eval_with_context("max_by(players, score)", &context),
+        Err(EvalexprError::VariableIdentifierNotFound("score".into()))
+    );
+    assert_eq!(
+        eval_with_context("max_by(players, \"height\")", &context),
+        Err(EvalexprError::VariableIdentifierNotFound("height".into()))
+    );
+    assert_eq!(
+        eval_with_context("max_by(players, \"score.x\")", &context),
+        Err(EvalexprError::ExpectedMap {
+            actual: Value::Int(3)
+        })
+    );
+    assert_eq!(
+        eval("min_by(({a: 1}, {a: \"1\"}), \"a\")"),
+        Err(EvalexprError::IncomparableValues {
+            index: 1,
+            expected: vec![ValueType::Int, ValueType::Float],
+            actual: Value::from("1")
+        })
+    );
+    assert_eq!(
+        eval("min_by({a: true}, \"a\")"),
+        Err(EvalexprError::IncomparableValues {
+            index: 0,
+            expected: vec![ValueType::String, ValueType::Int, ValueType::Float],
+            actual: Value::Boolean(true)
+        })
+    );
+}
+
+#[test]
+fn test_min_max_comparable_values() {
+    assert_eq!(eval("min(3, -1, 2)"), Ok(Value::Int(-1)));
+    assert_eq!(eval("max(0.5, 2.5, 1.5)"), Ok(Value::Float(2.5)));
+    assert_eq!(eval("min(2, 1.5, 3)"), Ok(Value::Float(1.5)));
+    assert_eq!(eval("max(2, 1.5, 3)"), Ok(Value::Int(3)));
+    assert_eq!(eval("min(1, 1.0)"), Ok(Value::Int(1)));
+    assert_eq!(eval("max(1.0, 1)"), Ok(Value::Float(1.0)));
+    assert_eq!(
+        eval("min(\"pear\", \"apple\", \"fig\")"),
+        Ok(Value::from("apple"))
+    );
+    assert_eq!(
+        eval("max(\"pear\", \"apple\", \"fig\")"),
+        Ok(Value::from("pear"))
+    );
+    assert_eq!(eval("min(7)"), Ok(Value::Int(7)));
+    assert_eq!(eval("max(\"a\")"), Ok(Value::from("a")));
+    assert_eq!(eval("min(0.0 / 0.0, 2, 1)"), Ok(Value::Int(1)));
+    assert!(eval_float("max(0.0 / 0.0, 0.0 / 0.0)").unwrap().is_nan());
+
+    assert_eq!(
+        eval("min(1, 2, \"3\", true)"),
+        Err(EvalexprError::IncomparableValues {
+            index: 2,
+            expected: vec![ValueType::Int, ValueType::Float],
+            actual: Value::from("3")
+        })
+    );
+    assert_eq!(
+        eval("max(\"a\", 1)"),
+        Err(EvalexprError::IncomparableValues {
+            index: 1,
+            expected: vec![ValueType::String],
+            actual: Value::Int(1)
+        })
+    );
+    assert_eq!(
+        eval("max(true)"),
+        Err(EvalexprError::IncomparableValues {
+            index: 0,
+            expected: vec![ValueType::String, ValueType::Int, ValueType::Float],
+            actual: Value::Boolean(true)
+        })
+    );
+    assert!(eval("max(\"a\", 1)").unwrap_err().is_type_error());
+}
+
+#[test]
+fn test_three_valued_logic() {
+    let options = ParseOptions::new().three_valued_logic(true);
+    let context = context_map! {
+        "t" => true,
+        "f" => false,
+        "u" => Value::Empty,
+    }
+    .unwrap();
+    let eval_3vl = |string: &str| {
+        build_operator_tree_with_options(string, &options)
+            .unwrap()
+            .eval_with_context(&context)
+    };
+    let t = Ok(Value::Boolean(true));
+    let f = Ok(Value::Boolean(false));
+    let u = Ok(Value::Empty);
+
+    for (a, b, and, or) in [
+        ("t", "t", &t, &t),
+        ("t", "f", &f, &t),
+        ("t", "u", &u, &t),
+        ("f", "t", &f, &t),
+        ("f", "f", &f, &f),
+        ("f", "u", &f, &u),
+        ("u", "t", &u, &t),
+        ("u", "f", &f, &u),
+        ("u", "u", &u, &u),
+    ] {
+        assert_eq!(&eval_3vl(&format!("{} && {}", a, b)), and, "{} && {}", a, b);
+        assert_eq!(&eval_3vl(&format!("{} || {}", a, b)), or, "{} || {}", a, b);
+    }
+    assert_eq!(eval_3vl("!t"), f);
+    assert_eq!(eval_3vl("!f"), t);
+    assert_eq!(eval_3vl("!u"), u);
+    assert_eq!(eval_3vl("!(u && f) || u"), t);
+    assert_eq!(eval_3vl("f || t && u"), u);
+
+    assert_eq!(
+        eval_3vl("1 && u"),
+        Err(EvalexprError::TypeError {
+            expected: vec![ValueType::Boolean, ValueType::Empty],
+            actual: Value::Int(1)
+        })
+    );
+    assert_eq!(
+        eval_with_context("t && u", &context),
+        Err(EvalexprError::ExpectedBoolean {
+            actual: Value::Empty
+        })
+    );
+    assert_eq!(
+        build_operator_tree_with_options("a && !b", &options)
+            .unwrap()
+            .to_string(),
+        build_operator_tree("a && !b").unwrap().to_string()
+    );
+}
+
+#[test]
+fn test_long_tuple_literal() {
+    let literal = (0..1000)
+        .map(|element| element.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let expected = Value::Tuple((0..1000).map(Value::Int).collect());
+
+    assert_eq!(eval(&literal), Ok(expected.clone()));
+    assert_eq!(
+        build_operator_tree(&literal)
+            .unwrap()
+            .eval_with_context(&HashMapContext::new()),
+        Ok(expected.clone())
+    );
+    assert_eq!(build_expression(&literal).unwrap().eval(), Ok(expected));
+}
+
+#[test]
+fn test_build_operator_tree_all_errors() {
+    let tree = build_operator_tree_all_errors("a = 2; max(a, 3), {b: a}").unwrap();
+    assert_eq!(
+        tree.eval_with_context_mut(&mut HashMapContext::new()),
+        Ok(Value::from(vec![
+            Value::Int(3),
+            Value::Map(vec![("b".to_string(), Value::Int(2))])
+        ]))
+    );
+
+    assert_eq!(
+        build_operator_tree_all_errors("1 + )); x = 2; 3 * * 4, (5"),
+        Err(vec![
+            EvalexprError::UnmatchedRBrace,
+            EvalexprError::WrongOperatorArgumentAmount {
+                expected: 2,
+                actual: 1
+            },
+            EvalexprError::UnmatchedLBrace,
+        ])
+    );
+    assert_eq!(
+        build_operator_tree_all_errors("f(1 +, 2)"),
+        Err(vec![EvalexprError::WrongOperatorArgumentAmount {
+            expected: 2,
+            actual: 1
+        }])
+    );
+    assert_eq!(
+        build_operator_tree_all_errors("1 2"),
+        Err(vec![build_operator_tree("1 2").unwrap_err()])
+    );
+    assert_eq!(
+        build_operator_tree_all_errors("1 + \"a; 2 *"),
+        Err(vec![EvalexprError::UnmatchedDoubleQuote])
+    );
+}
+
+#[test]
+fn test_any_all() {
+    let context = context_map! {
+        "a" => 2,
+        "b" => 1,
+        "flags" => Value::from(vec![Value::from(false), Value::from(true)]),
+        "none" => Value::from(Vec::new()),
+    }
+    .unwrap();
+
+    assert_eq!(
+        eval_with_context("any(flags)", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval_with_context("all(flags)", &context),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        eval_with_context("all(a > 1, b > 0, a != b)", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval_with_context("any(a > 5, b > 5)", &context),
+        Ok(Value::from(false))
+    );
+    assert_eq!(eval("any(true)"), Ok(Value::from(true)));
+    assert_eq!(eval("all(false)"), Ok(Value::from(false)));
+
+    assert_eq!(
+        eval_with_context("any(none)", &context),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        eval_with_context("all(none)", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(eval("any(())"), Ok(Value::from(false)));
+    assert_eq!(eval("all(())"), Ok(Value::from(true)));
+
+    assert_eq!(
+        eval("any(false, true, 1)"),
+        Err(EvalexprError::ElementTypeError {
+            index: 2,
+            expected: vec![ValueType::Boolean],
+            actual: Value::Int(1)
+        })
+    );
+    assert_eq!(
+        eval("all(\"yes\")"),
+        Err(EvalexprError::ElementTypeError {
+            index: 0,
+            expected: vec![ValueType::Boolean],
+            actual: Value::from("yes")
+        })
+    );
+    assert!(eval("all(true, ())").unwrap_err().is_type_error());
+}
+
+#[test]
+fn test_max_tuple_size() {
+    let options = ParseOptions::new().max_tuple_size(3);
+    let build = |string: &str| build_operator_tree_with_options(string, &options);
+
+    assert_eq!(
+        build("1, 2, 3").unwrap().eval(),
+        Ok(Value::from(vec![
+            Value::from(1),
+            Value::from(2),
+            Value::from(3)
+        ]))
+    );
+    assert_eq!(
+        build("1, 2, 3, 4"),
+        Err(EvalexprError::TupleTooLarge {
+            limit: 3,
+            actual: 4
+        })
+    );
+    assert_eq!(
+        build("a = 1; f((a, 2, 3, 4))"),
+        Err(EvalexprError::TupleTooLarge {
+            limit: 3,
+            actual: 4
+        })
+    );
+    assert!(build("(1, 2, 3), (4, 5, 6), 7").is_ok());
+    assert!(build("a = (1, 2, 3); b = (a, a, a); c = (b, b, b)").is_ok());
+    assert!(build("1, 2, 3, 4").unwrap_err().is_parse_error());
+    assert!(build_operator_tree("1, 2, 3, 4").is_ok());
+}
+
+#[test]
+fn test_trailing_commas() {
+    let context = context_map! {
+        "a" => 1,
+        "b" => 2,
+        "f" => Function::new(|argument| Ok(argument.clone()))
+    }
+    .unwrap();
+    let assert_same_tree = |with_comma: &str, without_comma: &str| {
+        assert_eq!(
+            build_operator_tree(with_comma),
+            build_operator_tree(without_comma),
+            "{}",
+            with_comma
+        );
+    };
+
+    assert_same_tree("f(a, b, )", "f(a, b)");
+    assert_same_tree("f(a,)", "f(a)");
+    assert_same_tree("(1, 2, 3, )", "(1, 2, 3)");
+    assert_same_tree("((1, 2,), (3,),)", "((1, 2), (3))");
+    assert_same_tree("f(g(a, ), b, )", "f(g(a), b)");
+    assert_same_tree("1, 2, 3,", "1, 2, 3");
+    assert_same_tree("x = (1, 2,); x, 3,; 4", "x = (1, 2); x, 3; 4");
+    assert_same_tree("f(), {k: 1},", "f(), {k: 1}");
+    assert_eq!(
+        eval_with_context("f(a, b, )", &context),
+        Ok(Value::from(vec![Value::from(1), Value::from(2)]))
+    );
+
+    for string in [
+        "(,)",
+        ",",
+        "f(,)",
+        "(1,,)",
+        "f(a, b,,)",
+        "1, 2,,",
+        "(1 +,)",
+        "1;,",
+    ] {
+        assert_eq!(
+            build_operator_tree(string),
+            Err(EvalexprError::InvalidTrailingComma),
+            "{}",
+            string
+        );
+    }
+    assert!(eval("(1,,)").unwrap_err().is_syntax_error());
+}
+
+#[test]
+fn test_parse_int_group_separator() {
+    assert_eq!(eval("parse_int(\"1,234\", \",\")"), Ok(Value::Int(1234)));
+    assert_eq!(
+        eval("parse_int(\"-12,345,678\", \",\")"),
+        Ok(Value::Int(-12_345_678))
+    );
+    assert_eq!(eval("parse_int(\"1 234 \", \" \")"), Ok(Value::Int(1234)));
+    assert_eq!(eval("parse_int(\"1.234\", \".\")"), Ok(Value::Int(1234)));
+    assert_eq!(
+        eval("parse_int(\"ff'ff\", 16, \"'\")"),
+        Ok(Value::Int(0xffff))
+    );
+    assert_eq!(eval("parse_int(\"1234\", \",\")"), Ok(Value::Int(1234)));
+    assert_eq!(eval("parse_int(\"12\", 10, \"\")"), Ok(Value::Int(12)));
+
+    assert_eq!(
+        eval("parse_int(\"1,234\")"),
+        Err(EvalexprError::InvalidNumber {
+            string: "1,234".into()
+        })
+    );
+    assert_eq!(
+        eval("parse_int(\"1,234.5\", \",\")"),
+        Err(EvalexprError::InvalidNumber {
+            string: "1,234.5".into()
+        })
+    );
+    assert_eq!(
+        eval("parse_int(\"1,234\", 10, 3)"),
+        Err(EvalexprError::ExpectedString {
+            actual: Value::Int(3)
+        })
+    );
+    assert_eq!(
+        eval("parse_int(\"1,234\", \",\", 10)"),
+        Err(EvalexprError::ExpectedInt {
+            actual: Value::from(",")
+        })
+    );
+}
+
+#[test]
+fn test_duplicate_bindings() {
+    let duplicate = |identifier: &str| Err(EvalexprError::DuplicateBinding(identifier.to_string()));
+
+    // A plain context overwrites, unless `try_set_*` is used.
+    let mut context = HashMapContext::new();
+    assert!(!context.is_strict());
+    assert_eq!(context.set_value("a".into(), 1.into()), Ok(()));
+    assert_eq!(context.set_value("a".into(), 2.into()), Ok(()));
+    assert_eq!(context.try_set_value("a".into(), 3.into()), duplicate("a"));
+    assert_eq!(context.get_value("a"), Some(&2.into()));
+    assert_eq!(context.try_set_value("b".into(), 3.into()), Ok(()));
+    assert_eq!(context.get_value("b"), Some(&3.into()));
+    assert_eq!(
+        context.try_set_function("f".into(), Function::new(|_| Ok(1.into()))),
+        Ok(())
+    );
+    assert_eq!(
+        context.try_set_function("f".into(), Function::new(|_| Ok(2.into()))),
+        duplicate("f")
+    );
+    assert_eq!(eval_with_context("f()", &context), Ok(1.into()));
+    assert_eq!(
+        eval_empty_with_context_mut("a = 4", &mut context),
+        Ok(EMPTY_VALUE)
+    );
+    assert_eq!(context.get_value("a"), Some(&4.into()));
+
+    // Values and functions are bound independently.
+    assert_eq!(context.try_set_value("f".into(), 5.into()), Ok(()));
+    assert_eq!(
+        context.try_set_function("a".into(), Function::new(|_| Ok(6.into()))),
+        Ok(())
+    );
+    assert_eq!(eval_with_context("a(f)", &context), Ok(6.into()));
+
+    // A strict context never overwrites, neither directly nor by assignment.
+    let mut context = HashMapContext::strict();
+    assert!(context.is_strict());
+    assert_eq!(context.set_value("a".into(), 1.into()), Ok(()));
+    assert_eq!(context.set_value("a".into(), 2.into()), duplicate("a"));
+    assert_eq!(context.set_value("a".into(), "two".into()), duplicate("a"));
+    assert_eq!(
+        context.set_function("f".into(), Function::new(|_| Ok(1.into()))),
+        Ok(())
+    );
+    assert_eq!(
+        context.set_function("f".into(), Function::new(|_| Ok(2.into()))),
+        duplicate("f")
+    );
+    assert_eq!(
+        eval_empty_with_context_mut("b = 1; b += 1", &mut context),
+        duplicate("b")
+    );
+    assert_eq!(context.get_value("a"), Some(&1.into()));
+    assert_eq!(context.get_value("b"), Some(&1.into()));
+    assert_eq!(eval_with_context("f()", &context), Ok(1.into()));
+    assert_eq!(context.clone().set_value("c".into(), 1.into()), Ok(()));
+    assert!(context.clone().is_strict());
+
+    // Clearing the context makes its identifiers available again.
+    context.clear();
+    assert_eq!(context.set_value("a".into(), 2.into()), Ok(()));
+    assert_eq!(
+        EvalexprError::DuplicateBinding("a".into()).code(),
+        "duplicate_binding"
+    );
+}
+
+#[test]
+fn test_percentage_of() {
+    let options = ParseOptions::new().percentage_of(true);
+    let eval = |string: &str| build_operator_tree_with_options(string, &options)?.eval();
+
+    assert_eq!(eval("20% of 150"), Ok(Value::from(30.0)));
+    assert_eq!(eval("20 %of 150"), Ok(Value::from(30.0)));
+    assert_eq!(eval("0.5 of 10"), Ok(Value::from(5.0)));
+    assert_eq!(eval("0.2 of 150 == 30.0"), Ok(Value::from(true)));
+    assert_eq!(eval("2 of 3"), Ok(Value::from(6)));
+    assert_eq!(eval("1 + 20% of 150 * 2"), Ok(Value::from(61.0)));
+    assert_eq!(eval("7 % 4"), Ok(Value::from(3)));
+    assert_eq!(
+        eval("\"a\" of 10"),
+        Err(EvalexprError::expected_number("a".into()))
+    );
+    assert_eq!(
+        eval("20% of true"),
+        Err(EvalexprError::expected_number(true.into()))
+    );
+    assert_eq!(
+        build_operator_tree_with_options("20% of 150", &options)
+            .unwrap()
+            .to_tree_string(),
+        "% of\n  20\n  150\n"
+    );
+
+    // Without the option, `of` is an identifier.
+    let context = context_map! { "of" => 2 }.unwrap();
+    assert_eq!(eval_with_context("of * 3", &context), Ok(Value::from(6)));
+    assert_eq!(eval_with_context("20 % of", &context), Ok(Value::from(0)));
+}
+
+#[test]
+fn test_require_parentheses_for_calls() {
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "sub2".into(),
+            Function::new(|argument| Ok(Value::Int(argument.as_int()? - 2))),
+        )
+        .unwrap();
+    context.set_value("five".into(), Value::Int(5)).unwrap();
+    let options = ParseOptions::new().require_parentheses_for_calls(true);
+    let eval = |string: &str| {
+        build_operator_tree_with_options(string, &options)?.eval_with_context(&context)
+    };
+
+    assert_eq!(eval("sub2(5)"), Ok(Value::Int(3)));
+    assert_eq!(eval("sub2 (five)"), Ok(Value::Int(3)));
+    assert_eq!(eval("sub2(sub2(five)) * 2"), Ok(Value::Int(2)));
+    assert_eq!(eval("max(1, 2)"), Ok(Value::Int(2)));
+    assert_eq!(eval("sub2 5"), Err(EvalexprError::AppendedToLeafNode));
+    assert_eq!(eval("sub2 five"), Err(EvalexprError::AppendedToLeafNode));
+    assert_eq!(eval("sub2 \"a\""), Err(EvalexprError::AppendedToLeafNode));
+    assert_eq!(eval("five five"), Err(EvalexprError::AppendedToLeafNode));
+    assert!(eval("sub2 5").unwrap_err().is_parse_error());
+    // Other uses of identifiers are unaffected
+    assert_eq!(eval("five + 1"), Ok(Value::Int(6)));
+    assert_eq!(eval("-five"), Ok(Value::Int(-5)));
+    assert_eq!(
+        build_operator_tree_with_options("a = 1; a", &options)
+            .unwrap()
+            .eval_with_context_mut(&mut HashMapContext::new()),
+        Ok(Value::Int(1))
+    );
+
+    // Without the option, juxtaposition calls the function
+    assert_eq!(eval_with_context("sub2 5", &context), Ok(Value::Int(3)));
+    assert_eq!(eval_with_context("sub2 five", &context), Ok(Value::Int(3)));
+}
+
+#[test]
+fn test_remap_operator() {
+    let pascal = ParseOptions::new()
+        .remap_operator("=", Operator::Eq)
+        .and_then(|options| options.remap_operator(":=", Operator::Assign))
+        .and_then(|options| options.remap_operator("<>", Operator::Neq))
+        .unwrap();
+    let build = |string: &str| build_operator_tree_with_options(string, &pascal);
+
+    assert_eq!(build("a = b"), build_operator_tree("a == b"));
+    assert_eq!(build("a := 3"), build_operator_tree("a = 3"));
+    assert_eq!(build("a<>b"), build_operator_tree("a != b"));
+    // The default symbols of the operators are kept
+    assert_eq!(build("a == b"), build_operator_tree("a == b"));
+    assert_eq!(build("a += 1"), build_operator_tree("a += 1"));
+    assert_eq!(build("a <= b"), build_operator_tree("a <= b"));
+
+    let mut context = HashMapContext::new();
+    assert_eq!(
+        build("a := 3; b := a + 1; a = 3 && b <> 3")
+            .unwrap()
+            .eval_with_context_mut(&mut context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(context.get_value("a"), Some(&Value::from(3)));
+    assert_eq!(context.get_value("b"), Some(&Value::from(4)));
+    assert_eq!(
+        build("a = 3").unwrap().eval_with_context_mut(&mut context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(context.get_value("a"), Some(&Value::from(3)));
+    // Symbols only match without whitespace between their tokens
+    assert_eq!(
+        build("a : = 3"),
+        Err(EvalexprError::UnmatchedColon { span: 2..3 })
+    );
+    // Maps keep their colons
+    assert_eq!(
+        build("{x: 1} = {x: 1}").unwrap().eval(),
+        Ok(Value::from(true))
+    );
+
+    // Symbols can be swapped
+    let swapped = ParseOptions::new()
+        .remap_operator("+", Operator::Sub)
+        .and_then(|options| options.remap_operator("-", Operator::Add))
+        .unwrap();
+    let tree = build_operator_tree_with_options("5 + 3 - 1", &swapped).unwrap();
+    assert_eq!(tree.eval(), Ok(Value::from(3)));
+    assert_eq!(
+        build_operator_tree_with_options("+2", &swapped)
+            .unwrap()
+            .eval(),
+        Ok(Value::from(-2))
+    );
+
+    let invalid = |symbol: &str, operator: Operator| match ParseOptions::new()
+        .remap_operator(symbol, operator)
+    {
+        Err(EvalexprError::InvalidOperatorRemap {
+            symbol: rejected,
+            reason,
+        }) => {
+            assert_eq!(rejected, symbol);
+            reason
+        },
+        result => panic!("{:?} was not rejected: {:?}", symbol, result),
+    };
+    assert!(invalid("", Operator::Eq).contains("operator characters"));
+    assert!(invalid("eq", Operator::Eq).contains("operator characters"));
+    assert!(invalid("=1", Operator::Eq).contains("operator characters"));
+    assert!(invalid("(=", Operator::Eq).contains("operator characters"));
+    assert!(invalid("&", Operator::And).contains("operator characters"));
+    assert!(invalid(": =", Operator::Assign).contains("whitespace"));
+    assert!(invalid(" :=", Operator::Assign).contains("whitespace"));
+    assert!(invalid(
+        "=>",
+        Operator::FunctionIdentifier {
+            identifier: "f".into()
+        }
+    )
+    .contains("no symbol"));
+    assert!(invalid("--", Operator::Neg).contains("no symbol"));
+
+    // A symbol cannot be remapped to two operators, but remapping it twice to the same operator is allowed
+    let options = ParseOptions::new()
+        .remap_operator("=", Operator::Eq)
+        .unwrap();
+    let error = options
+        .clone()
+        .remap_operator("=", Operator::Assign)
+        .unwrap_err();
+    assert_eq!(
+        error,
+        EvalexprError::InvalidOperatorRemap {
+            symbol: "=".to_string(),
+            reason: "it is already remapped to the operator Eq".to_string()
+        }
+    );
+    assert_eq!(error.code(), "invalid_operator_remap");
+    assert!(!error.is_parse_error());
+    assert!(options.remap_operator("=", Operator::Eq).is_ok());
+}
+
+#[test]
+fn test_redacted_string() {
+    let display = |string: &str| build_operator_tree(string).unwrap().to_string();
+
+    let tree = build_operator_tree(
+        "api_key == \"sk_live_123\" && f(\"a\", g(\"b\", 2.5), -3) == concat(\"c\", name)",
+    )
+    .unwrap();
+    assert!(tree.contains_string_literals());
+    assert_eq!(
+        tree.redacted_string(),
+        display("api_key == \"***\" && f(\"***\", g(\"***\", 2.5), -3) == concat(\"***\", name)")
+    );
+    assert_eq!(
+        tree.redacted_string_with_numbers(),
+        display("api_key == \"***\" && f(\"***\", g(\"***\", 0), -0) == concat(\"***\", name)")
+    );
+    assert!(!tree.redacted_string().contains("sk_live"));
+
+    let tree = build_operator_tree("a = f(1, b); {key: true}").unwrap();
+    assert!(!tree.contains_string_literals());
+    assert_eq!(tree.redacted_string(), tree.to_string());
+
+    // Literals that are introduced after parsing are redacted as well.
+    let mut tree = build_operator_tree("secret").unwrap();
+    *tree.children_mut()[0].operator_mut() = Operator::Const {
+        value: Value::from(vec![Value::from("s"), Value::from(vec![Value::from(1)])]),
+    };
+    assert!(tree.contains_string_literals());
+    assert!(tree.redacted_string().ends_with("(\"***\", (1))"));
+    assert!(tree
+        .redacted_string_with_numbers()
+        .ends_with("(\"***\", (0))"));
+}
+
+#[test]
+fn test_lazy_context() {
+    let calls = RefCell::new(Vec::new());
+    let mut context = LazyContext::new(|identifier| {
+        calls.borrow_mut().push(identifier.to_string());
+        match identifier {
+            "a" => Some(Value::from(3)),
+            "b" => Some(Value::from("b")),
+            _ => None,
+        }
+    });
+
+    assert_eq!(
+        eval_with_context("a * a + 1", &context),
+        Ok(Value::from(10))
+    );
+    assert_eq!(*calls.borrow(), vec!["a"]);
+    assert_eq!(eval_with_context("a", &context), Ok(Value::from(3)));
+    assert_eq!(
+        eval_with_context("(a, a)", &context),
+        Ok(Value::from(vec![3.into(), 3.into()]))
+    );
+    assert_eq!(*calls.borrow(), vec!["a"]);
+    assert_eq!(
+        eval_with_context("c + c", &context),
+        Err(EvalexprError::VariableIdentifierNotFound("c".to_string()))
+    );
+    assert_eq!(
+        eval_with_context("str::to_uppercase(b) + b", &context),
+        Ok(Value::from("Bb"))
+    );
+    assert!(eval_with_context("c", &context).is_err());
+    assert_eq!(*calls.borrow(), vec!["a", "c", "b"]);
+    assert_eq!(
+        format!("{:?}", context),
+        "{\"a\": Some(Int(3)), \"b\": Some(String(\"b\")), \"c\": None}"
+    );
+
+    // Calls of unknown functions look the identifier up as variable, to tell if a value was called.
+    assert_eq!(
+        eval_with_context("f()", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound("f".to_string()))
+    );
+    assert_eq!(
+        eval_with_context("a(1)", &context),
+        Err(EvalexprError::NotCallable(Value::from(3)))
+    );
+    context.set_builtin_functions_disabled(true).unwrap();
+    assert_eq!(
+        eval_with_context("min(a, 1)", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound("min".to_string()))
+    );
+    assert_eq!(*calls.borrow(), vec!["a", "c", "b", "f", "min"]);
+
+    context.clear_cache();
+    assert_eq!(eval_with_context("a", &context), Ok(Value::from(3)));
+    assert_eq!(*calls.borrow(), vec!["a", "c", "b", "f", "min", "a"]);
+
+    // Memoized values stay in place while more variables are memoized
+    let context = LazyContext::new(|identifier| Some(Value::from(identifier)));
+    let identifiers: Vec<_> = (0..1000).map(|index| format!("v{}", index)).collect();
+    let values: Vec<_> = identifiers
+        .iter()
+        .map(|identifier| context.get_value(identifier).unwrap())
+        .collect();
+    for (identifier, value) in identifiers.iter().zip(values) {
+        assert_eq!(value, &Value::from(identifier.as_str()));
+    }
+}
+
+#[test]
+fn test_multi_line_expressions() {
+    let context = context_map! {
+        "a" => 2,
+        "f" => Function::new(|argument| {
+            let arguments = argument.as_tuple()?;
+            Ok(Value::from(arguments[0].as_int()? - arguments[1].as_int()?))
+        })
+    }
+    .unwrap();
+
+    assert_eq!(eval("1 +\n2"), Ok(Value::from(3)));
+    assert_eq!(eval("1\n+ 2"), Ok(Value::from(3)));
+    assert_eq!(eval("1 +\r\n2 *\n\n\t3"), Ok(Value::from(7)));
+    assert_eq!(eval("\n  1 + 2\n"), Ok(Value::from(3)));
+    assert_eq!(
+        eval_with_context("f(\n  10,\n  a\n)", &context),
+        Ok(Value::from(8))
+    );
+    assert_eq!(
+        eval_with_context("f(10,\n  a,\n)", &context),
+        Ok(Value::from(8))
+    );
+    assert_eq!(
+        eval_with_context("f\n(10, a)", &context),
+        Ok(Value::from(8))
+    );
+    assert_eq!(
+        eval_with_context("a >\n1 &&\na <\n3", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(eval("b = 1;\nb +\n2"), Ok(Value::from(3)));
+
+    // Comments end at the line break, so the expression continues on the next line.
+    assert_eq!(eval("1 + // first\n2 // second\n* 3"), Ok(Value::from(7)));
+    assert_eq!(eval("1 + /* multi-line\ncomment */\n2"), Ok(Value::from(3)));
+    assert_eq!(eval("1 // + 2\n"), Ok(Value::from(1)));
+    assert_eq!(
+        eval("1 +\n// 2"),
+        Err(EvalexprError::wrong_operator_argument_amount(1, 2))
+    );
+
+    // Line breaks do not separate expressions.
+    assert_eq!(eval("1\n2"), Err(EvalexprError::AppendedToLeafNode));
+    assert_eq!(eval("\"a\nb\""), Ok(Value::from("a\nb")));
+}
+
+#[test]
+fn test_eval_boolean_explained() {
+    let context = context_map! {
+        "a" => 1,
+        "b" => 2,
+        "s" => "x",
+        "fail" => Function::new(|_| Err(EvalexprError::CustomMessage("evaluated".to_string())))
+    }
+    .unwrap();
+    let explain = |string: &str| {
+        build_operator_tree(string)
+            .unwrap()
+            .eval_boolean_explained(&context)
+    };
+    let clause = |outcome: EvalexprResult<BooleanOutcome>| {
+        let clause = outcome.unwrap().deciding_clause().cloned().unwrap();
+        (clause.index, clause.node.to_string(), clause.variables)
+    };
+
+    // Conjunctions report the first failing clause and skip the others.
+    let outcome = explain("a == 1 && (b > a && s == \"y\") && fail()");
+    assert_eq!(
+        outcome,
+        Ok(BooleanOutcome::False {
+            failing_clause: Some(DecidingClause {
+                index: 2,
+                node: build_operator_tree("s == \"y\"").unwrap().children()[0].clone(),
+                variables: vec![("s".to_string(), Value::from("x"))],
+            })
+        })
+    );
+    assert!(!outcome.unwrap().as_bool());
+    assert_eq!(
+        clause(explain("a < b && b + a > a * b + a")),
+        (
+            1,
+            build_operator_tree("b + a > a * b + a").unwrap().children()[0].to_string(),
+            vec![
+                ("b".to_string(), Value::from(2)),
+                ("a".to_string(), Value::from(1))
+            ]
+        )
+    );
+    assert_eq!(
+        explain("a == 1 && b == 2 && true"),
+        Ok(BooleanOutcome::True {
+            succeeding_clause: None
+        })
+    );
+
+    // Disjunctions report the first succeeding clause and skip the others.
+    assert_eq!(clause(explain("a > 1 || (b == 2) || fail()")).0, 1);
+    assert_eq!(
+        explain("a > 1 || b > 2"),
+        Ok(BooleanOutcome::False {
+            failing_clause: None
+        })
+    );
+
+    // Mixed chains are decomposed at the top-level operator only.
+    assert_eq!(clause(explain("a > 1 || b == 2 && s == \"x\"")).0, 1);
+    assert_eq!(clause(explain("(a > 1 || b > 2) && true")).0, 0);
+
+    // Other expressions are a single clause.
+    assert_eq!(clause(explain("!(a == 1)")).0, 0);
+    assert_eq!(
+        explain("a == 1"),
+        Ok(BooleanOutcome::True {
+            succeeding_clause: None
+        })
+    );
+    assert_eq!(
+        clause(explain("try_or(c, a) == 1 || false")).2,
+        vec![("a".to_string(), Value::from(1))]
+    );
+
+    // Errors of evaluated clauses are returned.
+    assert_eq!(
+        explain("a == 1 && fail()"),
+        Err(EvalexprError::CustomMessage("evaluated".to_string()))
+    );
+    assert_eq!(
+        explain("a == 1 && b"),
+        Err(EvalexprError::expected_boolean(Value::from(2)))
+    );
+    assert_eq!(
+        explain("a + 1"),
+        Err(EvalexprError::expected_boolean(Value::from(2)))
+    );
+}
+
+#[test]
+fn test_context_snapshot_diff() {
+    let mut context = context_map! {
+        "kept" => 1,
+        "modified" => 2,
+        "removed" => "gone",
+        "retyped" => 1,
+        "f" => Function::new(|_| Ok(Value::Empty))
+    }
+    .unwrap();
+    let old = context.snapshot();
+    assert_eq!(old.diff(&context.snapshot()), vec![]);
+    assert_eq!(old, context.snapshot());
+
+    let mut new_context = HashMapContext::new();
+    for (name, value) in [
+        ("kept", Value::from(1)),
+        ("modified", Value::from(3)),
+        ("retyped", Value::from(1.0)),
+        ("added", Value::from(true)),
+    ] {
+        new_context.set_value(name.into(), value).unwrap();
+    }
+    let new = new_context.snapshot();
+    let change = |name: &str, old: Option<Value>, new: Option<Value>| BindingChange {
+        name: name.to_string(),
+        old,
+        new,
+    };
+    // The changes are sorted by name and compare values strictly
+    assert_eq!(
+        old.diff(&new),
+        vec![
+            change("added", None, Some(Value::from(true))),
+            change("modified", Some(Value::from(2)), Some(Value::from(3))),
+            change("removed", Some(Value::from("gone")), None),
+            change("retyped", Some(Value::from(1)), Some(Value::from(1.0))),
+        ]
+    );
+    assert_eq!(
+        new.diff(&old)[0],
+        change("added", Some(Value::from(true)), None)
+    );
+    assert_eq!(
+        old.iter_variables()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>(),
+        vec!["kept", "modified", "removed", "retyped"]
+    );
+
+    // The snapshot is a copy of the variables without functions
+    context.set_value("kept".into(), 5.into()).unwrap();
+    assert_eq!(old.get_value("kept"), Some(&Value::from(1)));
+    assert_eq!(
+        eval_with_context("f()", &old),
+        Err(EvalexprError::FunctionIdentifierNotFound("f".to_string()))
+    );
+    assert_eq!(eval_with_context("max(kept, 0)", &old), Ok(Value::from(1)));
+
+    let nan = context_map! { "x" => f64::NAN }.unwrap().snapshot();
+    assert_eq!(nan.diff(&nan).len(), 1);
+}
+
+#[test]
+fn test_eval_explaining_change() {
+    let mut context = context_map! {
+        "age" => 17,
+        "country" => "DE",
+        "unrelated" => 1,
+        "user" => Value::Map(vec![("score".to_string(), Value::from(10))]),
+        "user_count" => 1
+    }
+    .unwrap();
+    let old = context.snapshot();
+    for (name, value) in [
+        ("age", Value::from(18)),
+        ("unrelated", Value::from(2)),
+        (
+            "user",
+            Value::Map(vec![("score".to_string(), Value::from(20))]),
+        ),
+        ("user_count", Value::from(2)),
+    ] {
+        context.set_value(name.into(), value).unwrap();
+    }
+    context.set_value("added".into(), 1.into()).unwrap();
+    let new = context.snapshot();
+
+    let rule = build_operator_tree("age >= 18 && country == \"DE\"").unwrap();
+    let explanation = rule.eval_explaining_change(&old, &new);
+    assert_eq!(explanation.old, Ok(Value::from(false)));
+    assert_eq!(explanation.new, Ok(Value::from(true)));
+    assert!(explanation.is_result_changed());
+    assert_eq!(
+        explanation.changes,
+        vec![BindingChange {
+            name: "age".to_string(),
+            old: Some(Value::from(17)),
+            new: Some(Value::from(18)),
+        }]
+    );
+
+    // Fields of maps read their map, but identifiers that only share a prefix do not
+    let explanation = build_operator_tree("user.score > 15")
+        .unwrap()
+        .eval_explaining_change(&old, &new);
+    assert!(explanation.is_result_changed());
+    assert_eq!(
+        explanation
+            .changes
+            .iter()
+            .map(|change| change.name.as_str())
+            .collect::<Vec<_>>(),
+        vec!["user"]
+    );
+
+    // Changed bindings that are not read do not explain anything
+    let explanation = build_operator_tree("country == \"DE\"")
+        .unwrap()
+        .eval_explaining_change(&old, &new);
+    assert!(!explanation.is_result_changed());
+    assert_eq!(explanation.changes, vec![]);
+
+    // Added bindings are reported, and errors are results as well
+    let explanation = build_operator_tree("added + 1")
+        .unwrap()
+        .eval_explaining_change(&old, &new);
+    assert_eq!(
+        explanation.old,
+        Err(EvalexprError::VariableIdentifierNotFound(
+            "added".to_string()
+        ))
+    );
+    assert_eq!(explanation.new, Ok(Value::from(2)));
+    assert_eq!(explanation.changes[0].old, None);
+}
+
+#[test]
+fn test_not_callable() {
+    let context = context_map! {
+        "a" => 1,
+        "s" => "text",
+        "f" => Function::new(|argument| Ok(argument.clone()))
+    }
+    .unwrap();
+
+    // Literals are rejected while building the operator tree.
+    assert_eq!(
+        build_operator_tree("5(3)"),
+        Err(EvalexprError::NotCallable(Value::from(5)))
+    );
+    assert_eq!(
+        build_operator_tree("1 + 2.5 (3)"),
+        Err(EvalexprError::NotCallable(Value::from(2.5)))
+    );
+    assert_eq!(
+        build_operator_tree("\"f\"()"),
+        Err(EvalexprError::NotCallable(Value::from("f")))
+    );
+    assert_eq!(
+        build_operator_tree("(1, true(2))"),
+        Err(EvalexprError::NotCallable(Value::from(true)))
+    );
+
+    // Variables are rejected during evaluation.
+    assert_eq!(
+        eval_with_context("a(3)", &context),
+        Err(EvalexprError::NotCallable(Value::from(1)))
+    );
+    assert_eq!(
+        eval_with_context("1 + s 3", &context),
+        Err(EvalexprError::NotCallable(Value::from("text")))
+    );
+    assert_eq!(
+        eval_with_context("a()", &context),
+        Err(EvalexprError::NotCallable(Value::from(1)))
+    );
+    assert_eq!(
+        eval_with_context_mut("b = 2; b(1)", &mut context.clone()),
+        Err(EvalexprError::NotCallable(Value::from(2)))
+    );
+
+    // Functions, builtin functions and unknown identifiers behave as before.
+    assert_eq!(eval_with_context("f(3)", &context), Ok(Value::from(3)));
+    assert_eq!(eval_with_context("min(a, 0)", &context), Ok(Value::from(0)));
+    assert_eq!(
+        eval_with_context("h(3)", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound("h".to_string()))
+    );
+    assert_eq!(
+        eval("(1)(2)"),
+        Err(EvalexprError::MissingOperatorOutsideOfBrace)
+    );
+
+    let error = EvalexprError::NotCallable(Value::from(5));
+    assert!(error.is_type_error());
+    assert_eq!(error.code(), "not_callable");
+    assert_eq!(
+        error.to_string(),
+        "Only functions can be called, but Int(5) was called like a function."
+    );
+}
+
+#[test]
+fn test_float_equality() {
+    let mut context = HashMapContext::new();
+    assert_eq!(context.float_equality(), FloatEquality::Exact);
+    assert_eq!(
+        eval_with_context("0.1 + 0.2 == 0.3", &context),
+        Ok(false.into())
+    );
+    assert_eq!(
+        eval_with_context("0.1 + 0.2 != 0.3", &context),
+        Ok(true.into())
+    );
+    assert_eq!(eval_with_context("1 == 1.0", &context), Ok(false.into()));
+    assert_eq!(eval_with_context("1.5 == 1.5", &context), Ok(true.into()));
+
+    context.set_float_equality(FloatEquality::Absolute(0.01));
+    assert_eq!(eval_with_context("1.0 == 1.009", &context), Ok(true.into()));
+    assert_eq!(
+        eval_with_context("1.0 != 1.009", &context),
+        Ok(false.into())
+    );
+    assert_eq!(
+        eval_with_context("1.0 == 1.011", &context),
+        Ok(false.into())
+    );
+    assert_eq!(eval_with_context("1.0 != 1.011", &context), Ok(true.into()));
+    assert_eq!(
+        eval_with_context("1000.0 == 1000.011", &context),
+        Ok(false.into())
+    );
+    assert_eq!(eval_with_context("1 == 1.009", &context), Ok(true.into()));
+    assert_eq!(eval_with_context("1.011 == 1", &context), Ok(false.into()));
+
+    context.set_float_equality(FloatEquality::Relative(0.01));
+    assert_eq!(
+        eval_with_context("100.0 == 100.9", &context),
+        Ok(true.into())
+    );
+    assert_eq!(
+        eval_with_context("100.0 == 101.1", &context),
+        Ok(false.into())
+    );
+    assert_eq!(
+        eval_with_context("100.0 != 101.1", &context),
+        Ok(true.into())
+    );
+    assert_eq!(
+        eval_with_context("0.001 == 0.00101", &context),
+        Ok(true.into())
+    );
+    assert_eq!(
+        eval_with_context("0.001 == 0.00102", &context),
+        Ok(false.into())
+    );
+    assert_eq!(eval_with_context("100 == 100.9", &context), Ok(true.into()));
+    assert_eq!(
+        eval_with_context("101.1 == 100", &context),
+        Ok(false.into())
+    );
+
+    // Infinities are only equal to themselves, and NaN is equal to nothing
+    for float_equality in [FloatEquality::Relative(1e-9), FloatEquality::Absolute(1e-9)] {
+        context.set_float_equality(float_equality);
+        context
+            .set_value("inf".into(), FloatType::INFINITY.into())
+            .unwrap();
+        context
+            .set_value("nan".into(), FloatType::NAN.into())
+            .unwrap();
+        assert_eq!(
+            eval_with_context("1.0 / 0.0 == 1", &context),
+            Ok(false.into())
+        );
+        assert_eq!(
+            eval_with_context("inf == 1e308", &context),
+            Ok(false.into())
+        );
+        assert_eq!(eval_with_context("inf == -inf", &context), Ok(false.into()));
+        assert_eq!(eval_with_context("-inf != inf", &context), Ok(true.into()));
+        assert_eq!(eval_with_context("inf == inf", &context), Ok(true.into()));
+        assert_eq!(eval_with_context("-inf == -inf", &context), Ok(true.into()));
+        assert_eq!(eval_with_context("nan == nan", &context), Ok(false.into()));
+        assert_eq!(eval_with_context("nan == inf", &context), Ok(false.into()));
+        assert_eq!(eval_with_context("nan == 1", &context), Ok(false.into()));
+        assert_eq!(eval_with_context("nan != 1.0", &context), Ok(true.into()));
+        assert!(!float_equality.are_equal(FloatType::INFINITY, FloatType::MAX));
+        assert!(!float_equality.are_equal(FloatType::NAN, FloatType::NAN));
+    }
+    context.set_float_equality(FloatEquality::Relative(0.01));
+
+    // Other comparisons and values are unaffected
+    assert_eq!(
+        eval_with_context("100.0 >= 100.9", &context),
+        Ok(false.into())
+    );
+    assert_eq!(
+        eval_with_context("100.9 <= 100.0", &context),
+        Ok(false.into())
+    );
+    assert_eq!(eval_with_context("1 == 1", &context), Ok(true.into()));
+    assert_eq!(eval_with_context("1 == 2", &context), Ok(false.into()));
+    assert_eq!(
+        eval_with_context("(100.0, 1) == (100.9, 1)", &context),
+        Ok(false.into())
+    );
+    assert_eq!(
+        eval_with_context("\"1.0\" == 1.0", &context),
+        Ok(false.into())
+    );
+    assert_eq!(
+        eval_with_context("math::sqrt(-1.0) == math::sqrt(-1.0)", &context),
+        Ok(false.into())
+    );
+
+    // The option applies at evaluation time, so the same tree can be evaluated with either comparison
+    let tree = build_operator_tree("a == 0.3").unwrap();
+    context.set_value("a".into(), (0.1 + 0.2).into()).unwrap();
+    assert_eq!(tree.eval_with_context(&context), Ok(true.into()));
+    context.set_float_equality(FloatEquality::Exact);
+    assert_eq!(tree.eval_with_context(&context), Ok(false.into()));
+
+    // Wrapping contexts use the comparison of the wrapped context
+    context.set_float_equality(FloatEquality::Absolute(1e-9));
+    assert_eq!(
+        tree.eval_with_context(&CachingContext::new(&context)),
+        Ok(true.into())
+    );
+    let layers: &[&dyn Context] = &[&context, &EmptyContext];
+    assert_eq!(tree.eval_with_context(&layers), Ok(true.into()));
+}
+
+#[test]
+fn test_simplify_fraction() {
+    assert_eq!(
+        eval("simplify_fraction((6, 8))"),
+        Ok(Value::from(vec![Value::Int(3), Value::Int(4)]))
+    );
+    assert_eq!(
+        eval("simplify_fraction(6, 8)"),
+        Ok(Value::from(vec![Value::Int(3), Value::Int(4)]))
+    );
+    // The sign moves to the numerator
+    assert_eq!(
+        eval("simplify_fraction((6, -8))"),
+        Ok(Value::from(vec![Value::Int(-3), Value::Int(4)]))
+    );
+    assert_eq!(
+        eval("simplify_fraction((-6, -8))"),
+        Ok(Value::from(vec![Value::Int(3), Value::Int(4)]))
+    );
+    assert_eq!(
+        eval("simplify_fraction((0, -5))"),
+        Ok(Value::from(vec![Value::Int(0), Value::Int(1)]))
+    );
+    assert_eq!(
+        eval("simplify_fraction((7, 3))"),
+        Ok(Value::from(vec![Value::Int(7), Value::Int(3)]))
+    );
+    assert_eq!(
+        eval(&format!(
+            "min = -{} - 1; simplify_fraction((min, min))",
+            IntType::MAX
+        )),
+        Ok(Value::from(vec![Value::Int(1), Value::Int(1)]))
+    );
+
+    assert_eq!(
+        eval("simplify_fraction((6, 0))"),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::Int(6),
+            divisor: Value::Int(0)
+        })
+    );
+    assert_eq!(
+        eval(&format!(
+            "min = -{} - 1; simplify_fraction((min, -1))",
+            IntType::MAX
+        )),
+        Err(EvalexprError::NegationError {
+            argument: Value::Int(IntType::MIN)
+        })
+    );
+    assert_eq!(
+        eval("simplify_fraction((6, 8, 10))"),
+        Err(EvalexprError::ExpectedFixedLengthTuple {
+            expected_length: 2,
+            actual: Value::from(vec![Value::Int(6), Value::Int(8), Value::Int(10)])
+        })
+    );
+    assert_eq!(
+        eval("simplify_fraction(6)"),
+        Err(EvalexprError::expected_tuple(Value::Int(6)))
+    );
+    assert_eq!(
+        eval("simplify_fraction((6.0, 8))"),
+        Err(EvalexprError::expected_int(Value::Float(6.0)))
+    );
+}
+
+#[test]
+fn test_scoped_context() {
+    let base = context_map! {
+        "x" => 1,
+        "y" => 2,
+        "double" => Function::new(|argument| Ok(Value::from(argument.as_int()? * 2))),
+    }
+    .unwrap();
+    let mut scoped = ScopedContext::new(&base);
+    assert_eq!(scoped.depth(), 0);
+    assert_eq!(eval_with_context("x + y", &scoped), Ok(Value::from(3)));
+    assert_eq!(
+        scoped.insert_in_current_scope("x".into(), 10.into()),
+        Err(EvalexprError::ContextNotMutable)
+    );
+
+    // Inner scopes shadow outer scopes and the wrapped context
+    scoped.push_scope();
+    scoped
+        .insert_in_current_scope("x".into(), 10.into())
+        .unwrap();
+    assert_eq!(eval_with_context("x + y", &scoped), Ok(Value::from(12)));
+    scoped.push_scope();
+    scoped
+        .insert_in_current_scope("x".into(), "shadowed".into())
+        .unwrap();
+    scoped
+        .insert_in_current_scope("z".into(), 100.into())
+        .unwrap();
+    assert_eq!(scoped.depth(), 2);
+    assert_eq!(eval_with_context("x", &scoped), Ok(Value::from("shadowed")));
+    assert_eq!(eval_with_context("y + z", &scoped), Ok(Value::from(102)));
+
+    // Popping a scope restores the previous bindings
+    let popped = scoped.pop_scope().unwrap();
+    assert_eq!(popped.get("z"), Some(&Value::from(100)));
+    assert_eq!(eval_with_context("x", &scoped), Ok(Value::from(10)));
+    assert_eq!(
+        eval_with_context("z", &scoped),
+        Err(EvalexprError::VariableIdentifierNotFound("z".into()))
+    );
+    scoped.pop_scope().unwrap();
+    assert_eq!(eval_with_context("x", &scoped), Ok(Value::from(1)));
+    assert_eq!(scoped.pop_scope(), None);
+
+    // Functions always come from the wrapped context
+    scoped.push_scope();
+    scoped
+        .insert_in_current_scope("double".into(), 0.into())
+        .unwrap();
+    assert_eq!(eval_with_context("double(x)", &scoped), Ok(Value::from(2)));
+
+    // Assignments bind in the current scope and leave the wrapped context unchanged
+    assert_eq!(
+        eval_with_context_mut("x = \"local\"; x", &mut scoped),
+        Ok(Value::from("local"))
+    );
+    assert_eq!(base.get_value("x"), Some(&Value::from(1)));
+    scoped.pop_scope();
+    assert_eq!(eval_with_context("x", &scoped), Ok(Value::from(1)));
+    assert_eq!(
+        eval_with_context_mut("x = 5", &mut scoped),
+        Err(EvalexprError::ContextNotMutable)
+    );
+}
+
+#[test]
+fn test_round_trip_string() {
+    let assert_round_trips = |float: FloatType| {
+        let string = Value::from(float).to_round_trip_string();
+        let parsed = string.parse::<Value>().unwrap().as_float().unwrap();
+        assert_eq!(parsed.to_bits(), float.to_bits(), "{}", string);
+        // Rust's shortest representation round-trips as well, but is not always parsed as float by this crate
+        assert_eq!(
+            format!("{}", float).parse::<FloatType>().unwrap().to_bits(),
+            float.to_bits()
+        );
+    };
+
+    for float in [
+        0.1,
+        0.1 + 0.2,
+        1.0 / 3.0,
+        1.0,
+        -1.0,
+        0.0,
+        -0.0,
+        1e16,
+        123_456_789_012_345_680.0,
+        9_007_199_254_740_993.0,
+        1e300,
+        -1e-300,
+        5e-324,
+        FloatType::MAX,
+        FloatType::MIN,
+        FloatType::MIN_POSITIVE,
+        FloatType::EPSILON,
+        std::f64::consts::PI,
+        FloatType::INFINITY,
+        FloatType::NEG_INFINITY,
+    ] {
+        assert_round_trips(float);
+    }
+
+    // Pseudo-random bit patterns, skipping NaNs
+    let mut bits: u64 = 0x1234_5678_9abc_def0;
+    for _ in 0..10_000 {
+        bits = bits
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        let float = FloatType::from_bits(bits);
+        if !float.is_nan() {
+            assert_round_trips(float);
+        }
+    }
+
+    assert_eq!(Value::from(1.0).to_round_trip_string(), "1.0");
+    assert_eq!(Value::from(-0.0).to_round_trip_string(), "-0.0");
+    assert_eq!(Value::from(1e300).to_round_trip_string(), "1e300");
+    assert_eq!(Value::from(1e-7).to_round_trip_string(), "1e-7");
+    assert_eq!(Value::from(FloatType::NAN).to_round_trip_string(), "NaN");
+    assert_eq!(
+        Value::from(FloatType::NEG_INFINITY).to_round_trip_string(),
+        "-inf"
+    );
+    // The display representation does not round-trip
+    assert_eq!(Value::from(1.0).to_string(), "1");
+    assert_eq!("1".parse(), Ok(Value::Int(1)));
+    assert_eq!(Value::from(1e300).to_string().len(), 301);
+
+    for value in [
+        Value::Int(-5),
+        Value::Boolean(true),
+        Value::from("a \"quoted\" back\\slash"),
+    ] {
+        assert_eq!(value.to_round_trip_string().parse(), Ok(value));
+    }
+    let tuple = Value::from(vec![
+        Value::from(2.0),
+        Value::from(vec![Value::from(1e-7), Value::from("\\")]),
+        Value::Empty,
+    ]);
+    assert_eq!(tuple.to_round_trip_string(), "(2.0, (1e-7, \"\\\\\"), ())");
+    assert_eq!(eval(&tuple.to_round_trip_string()), Ok(tuple));
+}
+
+#[test]
+fn test_map_functions() {
+    let map = Value::Map(vec![
+        ("b".to_string(), Value::Int(2)),
+        ("a".to_string(), Value::Int(1)),
+    ]);
+    assert_eq!(eval("to_map((\"b\", 2), (\"a\", 1))"), Ok(map.clone()));
+    assert_eq!(eval("to_map(((\"b\", 2), (\"a\", 1)))"), Ok(map.clone()));
+    assert_eq!(eval("to_map(())"), Ok(Value::Map(vec![])));
+    assert_eq!(eval("to_map(entries({}))"), Ok(Value::Map(vec![])));
+    // Of duplicate keys, the first position and the last value are kept
+    assert_eq!(
+        eval("to_map((\"b\", 0), (\"a\", 1), (\"b\", 2))"),
+        Ok(map.clone())
+    );
+
+    let context = context_map! { "m" => map }.unwrap();
+    assert_eq!(
+        eval_with_context("keys(m)", &context),
+        Ok(Value::from(vec![Value::from("a"), Value::from("b")]))
+    );
+    assert_eq!(
+        eval_with_context("values(m)", &context),
+        Ok(Value::from(vec![Value::from(1), Value::from(2)]))
+    );
+    assert_eq!(
+        eval_with_context("entries(m)", &context),
+        Ok(Value::from(vec![
+            Value::from(("a", 1)),
+            Value::from(("b", 2))
+        ]))
+    );
+    assert_eq!(
+        eval("entries({z: (1, 2), y: {x: true}})"),
+        eval("((\"y\", {x: true}), (\"z\", (1, 2)))")
+    );
+    assert_eq!(
+        eval_with_context("to_map(entries(m)) == {a: 1, b: 2}", &context),
+        Ok(Value::from(true))
+    );
+    assert_eq!(eval("keys({})"), Ok(Value::Tuple(vec![])));
+
+    assert_eq!(
+        eval("to_map((\"a\", 1), (\"b\", 2, 3))"),
+        Err(EvalexprError::InvalidMapPair {
+            index: 1,
+            actual: Value::from(("b", 2, 3))
+        })
+    );
+    assert_eq!(
+        eval("to_map((\"a\", 1), (2, \"b\"))"),
+        Err(EvalexprError::InvalidMapPair {
+            index: 1,
+            actual: Value::from((2, "b"))
+        })
+    );
+    assert_eq!(
+        eval("to_map((\"a\", 1))"),
+        Err(EvalexprError::InvalidMapPair {
+            index: 0,
+            actual: Value::from("a")
+        })
+    );
+    assert_eq!(
+        eval("to_map(1)"),
+        Err(EvalexprError::expected_tuple(Value::from(1)))
+    );
+    assert_eq!(
+        eval("keys((1, 2))"),
+        Err(EvalexprError::expected_map(Value::from((1, 2))))
+    );
+    let error = eval("to_map((\"a\", 1), (2, \"b\"))").unwrap_err();
+    assert_eq!(error.code(), "invalid_map_pair");
+    assert!(error.is_type_error());
+    assert_eq!(
+        error.to_string(),
+        "Expected the element at index 1 to be a pair of a string key and a value, but got \
+         Tuple([Int(2), String(\"b\")])."
+    );
+}
+
+#[test]
+fn test_zip_and_unzip() {
+    assert_eq!(
+        eval("zip((1, 2, 3), (4, 5, 6))"),
+        eval("((1, 4), (2, 5), (3, 6))")
+    );
+    assert_eq!(
+        eval("zip((\"a\", (1, 2)), (true, ()))"),
+        eval("((\"a\", true), ((1, 2), ()))")
+    );
+    assert_eq!(eval("zip((), ())"), Ok(Value::Tuple(vec![])));
+    assert_eq!(
+        eval("unzip(((1, 4), (2, 5), (3, 6)))"),
+        eval("((1, 2, 3), (4, 5, 6))")
+    );
+    assert_eq!(eval("unzip(zip((1, 2), (3, 4)))"), eval("((1, 2), (3, 4))"));
+    assert_eq!(
+        eval("unzip(())"),
+        Ok(Value::from((Value::Tuple(vec![]), Value::Tuple(vec![]))))
+    );
+
+    assert_eq!(
+        eval("zip((1, 2, 3), (4, 5))"),
+        Err(EvalexprError::expected_fixed_len_tuple(
+            3,
+            Value::from((4, 5))
+        ))
+    );
+    assert_eq!(
+        eval("zip((1, 2), 3)"),
+        Err(EvalexprError::expected_tuple(Value::from(3)))
+    );
+    assert_eq!(
+        eval("unzip(((1, 4), (2, 5, 6)))"),
+        Err(EvalexprError::expected_fixed_len_tuple(
+            2,
+            Value::from((2, 5, 6))
+        ))
+    );
+    assert_eq!(
+        eval("unzip((1, 2))"),
+        Err(EvalexprError::expected_fixed_len_tuple(2, Value::from(1)))
+    );
+}
+
+#[test]
+fn test_divmod() {
+    assert_eq!(eval("divmod(17, 5)"), Ok(Value::from((3, 2))));
+    assert_eq!(eval("divmod(15, 5)"), Ok(Value::from((3, 0))));
+    assert_eq!(eval("divmod(3, 5)"), Ok(Value::from((0, 3))));
+    // The quotient is truncated, so the remainder has the sign of the dividend
+    assert_eq!(eval("divmod(-17, 5)"), Ok(Value::from((-3, -2))));
+    assert_eq!(eval("divmod(17, -5)"), Ok(Value::from((-3, 2))));
+    assert_eq!(eval("divmod(-17, -5)"), Ok(Value::from((3, -2))));
+    assert_eq!(eval("divmod(-17, 5)"), eval("(-17 / 5, -17 % 5)"));
+
+    assert_eq!(
+        eval("divmod(17, 0)"),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::from(17),
+            divisor: Value::from(0),
+        })
+    );
+    let min = format!("-{} - 1", IntType::MAX);
+    assert_eq!(
+        eval(&format!("divmod({}, -1)", min)),
+        Err(EvalexprError::DivisionError {
+            dividend: Value::from(IntType::MIN),
+            divisor: Value::from(-1),
+        })
+    );
+    assert_eq!(
+        eval("divmod(17.0, 5)"),
+        Err(EvalexprError::expected_int(Value::from(17.0)))
+    );
+    assert_eq!(
+        eval("divmod(17)"),
+        Err(EvalexprError::expected_tuple(Value::from(17)))
+    );
+}
+
+#[test]
+fn test_bit_reinterpretation() {
+    assert_eq!(
+        eval("float_bits(1.5)"),
+        Ok(Value::from(1.5f64.to_bits() as IntType))
+    );
+    assert_eq!(eval("bits_float(float_bits(1.5))"), Ok(Value::from(1.5)));
+    assert_eq!(
+        eval("bits_float(float_bits(1.5)) == 1.5"),
+        Ok(Value::from(true))
+    );
+    assert_eq!(eval("float_bits(bits_float(-7))"), Ok(Value::from(-7)));
+    // The sign bit is the sign of the integer
+    assert_eq!(eval("float_bits(-0.0) < 0"), Ok(Value::from(true)));
+    assert_eq!(eval("bits_float(0)"), Ok(Value::from(0.0)));
+    assert_eq!(eval_boolean("math::is_nan(bits_float(-1))"), Ok(true));
+
+    assert_eq!(
+        eval("float_bits(1)"),
+        Err(EvalexprError::expected_float(Value::from(1)))
+    );
+    assert_eq!(
+        eval("bits_float(1.5)"),
+        Err(EvalexprError::expected_int(Value::from(1.5)))
+    );
+    assert_eq!(
+        eval("bits_float(\"1\")"),
+        Err(EvalexprError::expected_int(Value::from("1")))
+    );
+}
+
+#[test]
+fn test_find_satisfying_bindings() {
+    let rule = build_operator_tree("status == \"open\" && count > 5 && !archived").unwrap();
+    let hints = TypeHints::new()
+        .with("count", ValueType::Int)
+        .with("archived", ValueType::Boolean);
+    let bindings = rule.find_satisfying_bindings(&hints, 100, 7).unwrap();
+    assert_eq!(rule.eval_with_context(&bindings), Ok(Value::from(true)));
+    assert_eq!(bindings.get_value("status"), Some(&Value::from("open")));
+    assert!(bindings.get_value("count").unwrap().as_int().unwrap() > 5);
+    assert_eq!(bindings.iter_variable_names().count(), 3);
+
+    // The same seed results in the same bindings
+    for seed in 0..10 {
+        let first = rule.find_satisfying_bindings(&hints, 100, seed).unwrap();
+        let second = rule.find_satisfying_bindings(&hints, 100, seed).unwrap();
+        for identifier in ["status", "count", "archived"] {
+            assert_eq!(first.get_value(identifier), second.get_value(identifier));
+        }
+    }
+
+    // Literals guide variables without hints
+    let rule = build_operator_tree("name == \"bob\" || level >= 2.5").unwrap();
+    let bindings = rule
+        .find_satisfying_bindings(&TypeHints::new(), 100, 1)
+        .unwrap();
+    assert_eq!(rule.eval_with_context(&bindings), Ok(Value::from(true)));
+
+    // Assignments are evaluated
+    let rule = build_operator_tree("y = x * 2; y == 8").unwrap();
+    let hints = TypeHints::new().with("x", ValueType::Int);
+    let bindings = rule.find_satisfying_bindings(&hints, 1000, 3).unwrap();
+    assert_eq!(bindings.get_value("x"), Some(&Value::from(4)));
+
+    let tree = build_operator_tree("1 < 2").unwrap();
+    let bindings = tree
+        .find_satisfying_bindings(&TypeHints::new(), 1, 0)
+        .unwrap();
+    assert_eq!(bindings.iter_variable_names().count(), 0);
+    assert!(tree
+        .find_satisfying_bindings(&TypeHints::new(), 0, 0)
+        .is_none());
+    assert!(build_operator_tree("x > 0 && x < 0")
+        .unwrap()
+        .find_satisfying_bindings(&TypeHints::new(), 1000, 0)
+        .is_none());
+    assert!(build_operator_tree("x + 1")
+        .unwrap()
+        .find_satisfying_bindings(&TypeHints::new(), 1000, 0)
+        .is_none());
+}
+
+#[test]
+fn test_estimated_cost() {
+    let cost = |expression: &str| build_operator_tree(expression).unwrap().estimated_cost();
+
+    assert_eq!(cost(""), 0);
+    assert_eq!(cost("1"), 0);
+    assert_eq!(cost("(1, \"a\")"), 0);
+    assert_eq!(cost("a"), 1);
+    assert_eq!(cost("a < 1 && !b"), 5);
+    assert_eq!(cost("a * 2 + 1"), 5);
+    assert_eq!(cost("a = 2; a += 1"), 6);
+    assert_eq!(cost("{a: 1}"), 4);
+    assert_eq!(cost("2 ^ a"), 9);
+    assert_eq!(cost("f()"), 16);
+    assert_eq!(cost("f(g(a), 1)"), 33);
+    // Operators that might be skipped are counted as well
+    assert_eq!(cost("try_or(f(), 0)"), 32);
+
+    let arithmetic = cost("a * b + c - 1");
+    let functions = cost("max(a, b) + math::sqrt(c) - min(1, a)");
+    assert!(functions > arithmetic);
+    assert!(cost("a ^ 2") > cost("a * a"));
+    assert!(cost("a == b") < cost("a + b"));
+
+    // The cost of a subtree is at most the cost of the whole tree
+    let tree = build_operator_tree("f(a) + 1").unwrap();
+    assert_eq!(tree.children()[0].children()[0].estimated_cost(), 17);
+    assert_eq!(tree.estimated_cost(), 19);
+}
+
+#[test]
+fn test_eval_with_stats() {
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "slow".into(),
+            Function::new(|argument| {
+                std::thread::sleep(std::time::Duration::from_millis(5));
+                Ok(argument.clone())
+            }),
+        )
+        .unwrap();
+
+    let tree = build_operator_tree("slow(1) + slow(2) * 3; (\"ab\", \"abcd\", x)").unwrap();
+    context.set_value("x".into(), Value::from(1)).unwrap();
+    let (result, stats) = tree.eval_with_stats(&context);
+    assert_eq!(result, Ok(Value::from(("ab", "abcd", 1))));
+    let counts: HashMap<_, _> = stats
+        .operators
+        .iter()
+        .map(|(kind, stats)| (*kind, stats.count))
+        .collect();
+    assert_eq!(
+        counts,
+        HashMap::from([
+            ("RootNode", 9),
+            ("Add", 1),
+            ("Mul", 1),
+            ("Chain", 1),
+            ("Tuple", 1),
+            ("Const", 5),
+            ("VariableIdentifierRead", 1),
+            ("FunctionIdentifier", 2),
+        ])
+    );
+    assert_eq!(stats.functions.len(), 1);
+    assert_eq!(stats.functions["slow"].count, 2);
+    assert!(stats.functions["slow"].duration >= std::time::Duration::from_millis(10));
+    assert_eq!(
+        stats.operators["FunctionIdentifier"],
+        stats.functions["slow"]
+    );
+    // The duration of an operator excludes its arguments
+    assert!(stats.operators["Add"].duration < stats.functions["slow"].duration);
+    assert_eq!(stats.peak_tuple_len, 3);
+    assert_eq!(stats.peak_string_len, 4);
+
+    // Operators up to and including the failing one are recorded
+    let (result, failed_stats) = build_operator_tree("slow(1) + slow(2) * 3; (\"ab\", y)")
+        .unwrap()
+        .eval_with_stats(&context);
+    assert_eq!(
+        result,
+        Err(EvalexprError::VariableIdentifierNotFound("y".to_string()))
+    );
+    assert_eq!(failed_stats.operators["VariableIdentifierRead"].count, 1);
+    assert!(!failed_stats.operators.contains_key("Tuple"));
+    assert_eq!(failed_stats.peak_tuple_len, 0);
+
+    let mut total = EvalStats::new();
+    total.merge(&stats);
+    total.merge(&failed_stats);
+    assert_eq!(total.functions["slow"].count, 4);
+    assert_eq!(total.operators["Chain"].count, 1);
+    assert_eq!(total.peak_tuple_len, 3);
+
+    let (result, stats) = build_operator_tree("try_or(1 / 0, 2)")
+        .unwrap()
+        .eval_with_stats(&context);
+    assert_eq!(result, Ok(Value::from(2)));
+    assert_eq!(stats.functions["try_or"].count, 1);
+    assert_eq!(stats.operators["Div"].count, 1);
+}
+
+#[test]
+fn test_recording_context() {
+    let context = context_map! { "a" => true, "b" => false, "c" => 3 }.unwrap();
+    let mut recording = RecordingContext::new(&context);
+    assert!(recording.accessed_variables().is_empty());
+
+    // The fallback of a succeeding `try_or` is skipped, so its variables are not recorded
+    let tree = build_operator_tree("try_or(a && b, c && d)").unwrap();
+    assert_eq!(
+        tree.iter_variable_identifiers().collect::<Vec<_>>(),
+        vec!["a", "b", "c", "d"]
+    );
+    assert_eq!(tree.eval_with_context(&recording), Ok(Value::from(false)));
+    assert_eq!(
+        recording.accessed_variables(),
+        HashSet::from(["a".to_string(), "b".to_string()])
+    );
+
+    // Variables are recorded even if they are unbound, and only once
+    recording.clear_accessed_variables();
+    assert!(recording.accessed_variables().is_empty());
+    assert_eq!(
+        eval_with_context("try_or(x, c) + c", &recording),
+        Ok(Value::from(6))
+    );
+    assert_eq!(
+        recording.accessed_variables(),
+        HashSet::from(["x".to_string(), "c".to_string()])
+    );
+
+    // Lookups are delegated, and functions are not recorded
+    assert_eq!(recording.inner().get_value("c"), Some(&Value::from(3)));
+    recording.clear_accessed_variables();
+    assert_eq!(
+        eval_with_context("max(c, 4)", &recording),
+        Ok(Value::from(4))
+    );
+    assert_eq!(
+        recording.accessed_variables(),
+        HashSet::from(["c".to_string()])
+    );
+    assert_eq!(
+        recording.set_builtin_functions_disabled(true),
+        Err(EvalexprError::BuiltinFunctionsCannotBeDisabled)
+    );
+}
+
+#[test]
+fn test_record() {
+    let options = ParseOptions::new().record_syntax(true);
+    let build = |string: &str| build_operator_tree_with_options(string, &options);
+    let context = context_map! { "a" => 3, "b" => 2 }.unwrap();
+    let tree = build("record(score = a * b, flag = a > b, pair = (a, b))").unwrap();
+    let result = tree.eval_with_context(&context).unwrap();
+    assert_eq!(
+        result,
+        Value::Map(vec![
+            ("score".to_string(), Value::from(6)),
+            ("flag".to_string(), Value::from(true)),
+            ("pair".to_string(), Value::from((3, 2))),
+        ])
+    );
+    assert_eq!(result.get_field("score"), Some(&Value::from(6)));
+    assert_eq!(
+        tree,
+        build_operator_tree("{score: a * b, flag: a > b, pair: (a, b)}").unwrap()
+    );
+
+    // Intermediate results can be shared between the fields
+    let mut context = context_map! { "a" => 3, "b" => 2 }.unwrap();
+    assert_eq!(
+        build("product = a * b; record(score = product + 1, double = 2 * product,)")
+            .unwrap()
+            .eval_with_context_mut(&mut context),
+        Ok(Value::Map(vec![
+            ("score".to_string(), Value::from(7)),
+            ("double".to_string(), Value::from(12)),
+        ]))
+    );
+    assert_eq!(
+        build("record(x = record(y = 1))").unwrap().eval(),
+        eval("{x: {y: 1}}")
+    );
+
+    assert_eq!(
+        build("record(score = 1, flag = 2, score = 3)"),
+        Err(EvalexprError::DuplicateRecordField {
+            name: "score".to_string(),
+            span: 28..33,
+        })
+    );
+    assert!(build("record(score = 1, flag = 2, score = 3)")
+        .unwrap_err()
+        .is_syntax_error());
+    assert_eq!(
+        build("record(score = 1, 2)"),
+        Err(EvalexprError::InvalidMapEntry)
+    );
+    assert_eq!(
+        build("record(score = )"),
+        Err(EvalexprError::InvalidMapEntry)
+    );
+    assert_eq!(
+        build("record(score = 1"),
+        Err(EvalexprError::UnmatchedLBrace)
+    );
+
+    // Other arguments call a function named `record`
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "record".into(),
+            Function::new(|argument| Ok(Value::from(argument.as_int()? + 1))),
+        )
+        .unwrap();
+    assert_eq!(
+        build("record(1)").unwrap().eval_with_context(&context),
+        Ok(Value::from(2))
+    );
+    assert_eq!(
+        build("record(x == 1)").unwrap().eval_with_context(&context),
+        Err(EvalexprError::VariableIdentifierNotFound("x".to_string()))
+    );
+
+    // Without the option, `record` with assignments as arguments calls a function named `record`
+    let mut context = HashMapContext::new();
+    context
+        .set_function(
+            "record".into(),
+            Function::new(|argument| Ok(Value::from(argument.as_tuple()?.len() as IntType))),
+        )
+        .unwrap();
+    assert_eq!(
+        eval_with_context_mut("record(x = 1, y = 2)", &mut context),
+        Ok(Value::from(2))
+    );
+    assert_eq!(context.get_value("x"), Some(&Value::from(1)));
+    assert_eq!(
+        eval("record(x = 1, x = 2)"),
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            "record".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_ordered_value() {
+    let values = vec![
+        Value::from("b"),
+        Value::from(FloatType::NAN),
+        Value::from((1, 2)),
+        Value::from(1.0),
+        Value::Empty,
+        Value::from(2),
+        Value::from(-FloatType::NAN),
+        Value::from("a"),
+        Value::from(FloatType::INFINITY),
+        Value::from(true),
+        Value::from(-0.0),
+        Value::from(0.0),
+        Value::from(1),
+        Value::from((1, 2, 0)),
+        Value::from(vec![Value::from(1)]),
+        Value::Map(vec![("a".to_string(), Value::from(1))]),
+        Value::Map(vec![]),
+        Value::from(false),
+        Value::from(FloatType::NAN),
+        Value::from(2),
+    ];
+
+    let mut counts = BTreeMap::new();
+    for value in values {
+        *counts.entry(OrderedValue(value)).or_insert(0) += 1;
+    }
+    let counts: Vec<_> = counts
+        .into_iter()
+        .map(|(value, count)| (value.into_inner().to_round_trip_string(), count))
+        .collect();
+    let expected = vec![
+        ("()", 1),
+        ("false", 1),
+        ("true", 1),
+        ("1", 1),
+        ("2", 2),
+        ("NaN", 1),
+        ("-0.0", 1),
+        ("0.0", 1),
+        ("1.0", 1),
+        ("inf", 1),
+        ("NaN", 2),
+        (r#""a""#, 1),
+        (r#""b""#, 1),
+        ("(1)", 1),
+        ("(1, 2)", 1),
+        ("(1, 2, 0)", 1),
+        ("{}", 1),
+        ("{a: 1}", 1),
+    ];
+    assert_eq!(
+        counts,
+        expected
+            .into_iter()
+            .map(|(value, count)| (value.to_string(), count))
+            .collect::<Vec<_>>()
+    );
+
+    // NaN is equal to itself, unlike in `Value`
+    assert_ne!(Value::from(FloatType::NAN), Value::from(FloatType::NAN));
+    assert_eq!(
+        OrderedValue(Value::from(FloatType::NAN)),
+        OrderedValue::from(Value::from(FloatType::NAN))
+    );
+    // Integers are less than floats, even if they are numerically greater
+    assert!(OrderedValue(Value::from(5)) < OrderedValue(Value::from(1.0)));
+    assert!(
+        OrderedValue(Value::Map(vec![("a".to_string(), Value::from(2))]))
+            < OrderedValue(Value::Map(vec![("b".to_string(), Value::from(1))]))
+    );
+}
+
+#[test]
+fn test_empty_sorts_first() {
+    let mut values = vec![
+        Value::from("b"),
+        Value::from(3),
+        Value::Empty,
+        Value::from("a"),
+        Value::from(-1),
+        Value::Empty,
+        Value::from(2),
+    ];
+    let expected = vec![
+        Value::Empty,
+        Value::Empty,
+        Value::from(-1),
+        Value::from(2),
+        Value::from(3),
+        Value::from("a"),
+        Value::from("b"),
+    ];
+
+    let mut ordered: Vec<_> = values.iter().cloned().map(OrderedValue).collect();
+    ordered.sort();
+    assert_eq!(
+        ordered
+            .into_iter()
+            .map(OrderedValue::into_inner)
+            .collect::<Vec<_>>(),
+        expected
+    );
+    // Strings and numbers are incomparable, so only values of each type are sorted with `PartialOrd`
+    values.retain(|value| !value.is_string());
+    values.push(Value::from(2.5));
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    assert_eq!(
+        values,
+        vec![
+            Value::Empty,
+            Value::Empty,
+            Value::from(-1),
+            Value::from(2),
+            Value::from(2.5),
+            Value::from(3),
+        ]
+    );
+
+    assert_eq!(
+        Value::Empty.partial_cmp(&Value::Empty),
+        Some(std::cmp::Ordering::Equal)
+    );
+    for value in [
+        Value::from(false),
+        Value::from(IntType::MIN),
+        Value::from(FloatType::NEG_INFINITY),
+        Value::from(FloatType::NAN),
+        Value::from(""),
+        Value::from(vec![]),
+        Value::Map(vec![]),
+    ] {
+        assert!(Value::Empty < value);
+        assert!(value > Value::Empty);
+        assert!(OrderedValue(Value::Empty) < OrderedValue(value));
+    }
+
+    // The order of `Value` agrees with its equality
+    assert_eq!(
+        Value::from(-0.0).partial_cmp(&Value::from(0.0)),
+        Some(std::cmp::Ordering::Equal)
+    );
+    assert_eq!(
+        Value::from(FloatType::NAN).partial_cmp(&Value::from(FloatType::NAN)),
+        None
+    );
+
+    // Unlike `OrderedValue`, `Value` compares like the comparison operators
+    assert!(Value::from(5) > Value::from(1.0));
+    assert!(Value::from(1) < Value::from(1.5));
+    assert!(OrderedValue(Value::from(5)) < OrderedValue(Value::from(1.0)));
+    assert_eq!(Value::from(1).partial_cmp(&Value::from(1.0)), None);
+    assert_eq!(Value::from(true).partial_cmp(&Value::from(0)), None);
+    assert_eq!(Value::from("a").partial_cmp(&Value::from(1)), None);
+    assert!(Value::from("ab") < Value::from("b"));
+    assert!(Value::from((1, 2)) < Value::from((1, 2.5)));
+    assert!(Value::from((1, 2)) < Value::from((1, 2, 0)));
+    assert_eq!(
+        Value::from(true).partial_cmp(&Value::from(true)),
+        Some(std::cmp::Ordering::Equal)
+    );
+    assert_eq!(Value::from(false).partial_cmp(&Value::from(true)), None);
+}
+
+#[test]
+fn test_evaluation_order() {
+    use std::sync::{Arc, Mutex};
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut context = HashMapContext::new();
+    for name in ["a", "b", "c", "f", "g"] {
+        let log = log.clone();
+        context
+            .set_function(
+                name.into(),
+                Function::new(move |argument| {
+                    log.lock().unwrap().push(name);
+                    Ok(match argument {
+                        Value::Empty => Value::from(1),
+                        argument => argument.clone(),
+                    })
+                }),
+            )
+            .unwrap();
+    }
+    let log_clone = log.clone();
+    context
+        .set_function(
+            "fail".into(),
+            Function::new(move |_| {
+                log_clone.lock().unwrap().push("fail");
+                Err(EvalexprError::CustomMessage("fail".to_string()))
+            }),
+        )
+        .unwrap();
+
+    let cases: &[(&str, &[&str])] = &[
+        ("f(a(), b()) + c()", &["a", "b", "f", "c"]),
+        ("f(g(a()), b())", &["a", "g", "b", "f"]),
+        ("c() * (a() - b())", &["c", "a", "b"]),
+        ("-a() ^ b() % c()", &["a", "b", "c"]),
+        ("(a(), (b(), c()))", &["a", "b", "c"]),
+        ("{x: c(), y: a(), z: b()}", &["c", "a", "b"]),
+        ("record(x = b(), y = a())", &["b", "a"]),
+        ("b() == a() || c() != a()", &["b", "a", "c", "a"]),
+        ("a(); b(); c()", &["a", "b", "c"]),
+        ("x = c(); y = a(); x + y + b()", &["c", "a", "b"]),
+        // `&&` and `||` evaluate both operands, even if the left one decides the result
+        ("a() == 2 && b() == 1", &["a", "b"]),
+        ("a() == 1 || b() == 1", &["a", "b"]),
+        // The fallback of `try_or` is evaluated only if the first argument fails
+        ("try_or(a(), b())", &["a"]),
+        ("try_or(fail(a()), b())", &["a", "fail", "b"]),
+        // Operands after a failing one are not evaluated
+        ("a() + fail() + b()", &["a", "fail"]),
+        ("f(a(), fail(), b())", &["a", "fail"]),
+    ];
+
+    let check = |expression: &str, expected: &[&str], evaluation: &str| {
+        assert_eq!(
+            std::mem::take(&mut *log.lock().unwrap()),
+            expected,
+            "{} with {}",
+            expression,
+            evaluation
+        );
+    };
+    for (expression, expected) in cases {
+        let tree = build_operator_tree(expression).unwrap();
+        let _ = tree.eval_with_context_mut(&mut context.clone());
+        check(expression, expected, "eval_with_context_mut");
+        let _ = build_expression(expression)
+            .unwrap()
+            .eval_with_context_mut(&mut context.clone());
+        check(expression, expected, "Expression");
+
+        // Assignments need a mutable context
+        if tree.iter_write_variable_identifiers().next().is_none() {
+            let _ = tree.eval_with_context(&context);
+            check(expression, expected, "eval_with_context");
+            let _ = tree.eval_profiled(&context);
+            check(expression, expected, "eval_profiled");
+            let _ = tree.eval_with_stats(&context);
+            check(expression, expected, "eval_with_stats");
+        }
+    }
+}
+
+#[test]
+fn test_source_text() {
+    let expression = "f( \"a \\\"b\\\"\" , x ) + ((y+1) - (y+1)) * (z * 1)";
+    let options = ParseOptions::new().preserve_source_text(true);
+    let mut tree = build_operator_tree_with_options(expression, &options).unwrap();
+    assert_eq!(tree.source_text(), Some(expression));
+    // The source text does not affect equality
+    assert_eq!(tree, build_operator_tree(expression).unwrap());
+    assert_eq!(build_operator_tree(expression).unwrap().source_text(), None);
+
+    let call = |tree: &Node| {
+        tree.iter()
+            .find(|node| matches!(node.operator(), Operator::FunctionIdentifier { .. }))
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(call(&tree).source_text(), Some("f( \"a \\\"b\\\"\" , x )"));
+
+    // Fold `x - x` to zero, and remove `* 1`
+    let mut rules = Rule::identities();
+    rules.push(Rule::new(
+        Pattern::node(
+            Operator::Sub,
+            vec![Pattern::wildcard("x"), Pattern::wildcard("x")],
+        ),
+        Pattern::value(0),
+    ));
+    assert_eq!(tree.rewrite(&rules), 2);
+    assert_eq!(
+        tree.to_string(),
+        build_operator_tree("f(\"a \\\"b\\\"\", x) + (0) * (z)")
+            .unwrap()
+            .to_string()
+    );
+
+    // The sibling of the folded subtree keeps its source text including the quoting
+    assert_eq!(call(&tree).source_text(), Some("f( \"a \\\"b\\\"\" , x )"));
+    let string = tree
+        .iter()
+        .find(|node| {
+            node.operator()
+                == &Operator::Const {
+                    value: Value::from("a \"b\""),
+                }
+        })
+        .unwrap();
+    assert_eq!(string.source_text(), Some("\"a \\\"b\\\"\""));
+    // The folded subtree is synthesized, while the subtree kept by the identity rule is not
+    let zero = tree
+        .iter()
+        .find(|node| {
+            node.operator()
+                == &Operator::Const {
+                    value: Value::from(0),
+                }
+        })
+        .unwrap();
+    assert_eq!(zero.source_text(), None);
+    let z = tree
+        .iter()
+        .find(|node| {
+            node.operator()
+                == &Operator::VariableIdentifierRead {
+                    identifier: "z".into(),
+                }
+        })
+        .unwrap();
+    assert_eq!(z.source_text(), Some("z"));
+}
+
+#[test]
+fn test_build_operator_tree_arc() {
+    let expression: Arc<str> = Arc::from("a * (b + 1)");
+    let options = ParseOptions::new().preserve_source_text(true);
+    let tree = build_operator_tree_arc(expression.clone(), &options).unwrap();
+    assert_eq!(tree, build_operator_tree(&expression).unwrap());
+    assert_eq!(tree.source_text(), Some("a * (b + 1)"));
+    // All nodes share the given expression
+    assert!(tree
+        .iter()
+        .filter_map(Node::source_expression)
+        .all(|source| Arc::ptr_eq(source, &expression)));
+    assert_eq!(tree.iter().filter_map(Node::source_expression).count(), 6);
+    assert_eq!(
+        tree.children()[0].children()[1].source_text(),
+        Some("(b + 1)")
+    );
+
+    // Other types are converted
+    let tree = build_operator_tree_arc(String::from("a +  1"), &options).unwrap();
+    assert_eq!(tree.source_text(), Some("a +  1"));
+    let tree = build_operator_tree_arc("a +  1", &options).unwrap();
+    assert_eq!(tree.source_expression().map(AsRef::as_ref), Some("a +  1"));
+
+    // Without source text, the expression is not kept
+    let references = Arc::strong_count(&expression);
+    let tree = build_operator_tree_arc(expression.clone(), &ParseOptions::new()).unwrap();
+    assert_eq!(tree.source_expression(), None);
+    assert_eq!(Arc::strong_count(&expression), references);
+    let tree = build_operator_tree_with_options("a", &options).unwrap();
+    assert_eq!(tree.source_expression().map(AsRef::as_ref), Some("a"));
+
+    assert_eq!(
+        build_operator_tree_arc(Arc::from("a +"), &options),
+        Err(EvalexprError::WrongOperatorArgumentAmount {
+            expected: 2,
+            actual: 1
+        })
+    );
+}
+
+#[test]
+fn test_conditional_operator() {
+    let context = context_map! { "t" => true, "f" => false, "a" => 1, "b" => 2 }.unwrap();
+    let eval = |string: &str| eval_with_context(string, &context);
+
+    assert_eq!(eval("t ? a : b"), Ok(Value::Int(1)));
+    assert_eq!(eval("f ? a : b"), Ok(Value::Int(2)));
+    assert_eq!(eval("a < b ? a + b : a - b"), Ok(Value::Int(3)));
+    // The conditional operator binds weaker than `||` and `&&`
+    assert_eq!(eval("f || t ? a : b"), Ok(Value::Int(1)));
+    assert_eq!(eval("t && f ? a : b"), Ok(Value::Int(2)));
+    assert_eq!(eval("f ? t : f || t"), Ok(Value::Boolean(true)));
+    // It is right-associative and can be nested in its middle operand
+    assert_eq!(eval("f ? 1 : f ? 2 : 3"), Ok(Value::Int(3)));
+    assert_eq!(eval("f ? 1 : t ? 2 : 3"), Ok(Value::Int(2)));
+    assert_eq!(eval("t ? 1 : t ? 2 : 3"), Ok(Value::Int(1)));
+    assert_eq!(eval("t ? f ? 1 : 2 : 3"), Ok(Value::Int(2)));
+    assert_eq!(eval("(t ? 1 : 2) + 10"), Ok(Value::Int(11)));
+    assert_eq!(eval("-(f ? 1 : 2)"), Ok(Value::Int(-2)));
+    // Sequences end at the colon and bind weaker than the conditional operator
+    assert_eq!(
+        eval("t ? 1, 2 : 3"),
+        Ok(Value::from(vec![1.into(), 2.into()]))
+    );
+    assert_eq!(
+        eval("f ? 1 : 2, 3"),
+        Ok(Value::from(vec![2.into(), 3.into()]))
+    );
+    assert_eq!(eval("t ? a : b; 4"), Ok(Value::Int(4)));
+    assert_eq!(eval("max(t ? a : b, 0)"), Ok(Value::Int(1)));
+    assert_eq!(eval("{x: f ? a : b} == {x: 2}"), Ok(Value::Boolean(true)));
+    assert_eq!(eval("t ? {x: a} : {x: b}"), eval("{x: 1}"));
+
+    // Assignments bind weaker than the conditional operator
+    let mut context = HashMapContext::new();
+    assert_eq!(
+        eval_with_context_mut(
+            "c = true; x = c ? 1 : 2; y = !c ? 1 : 2; x + y",
+            &mut context
+        ),
+        Ok(Value::Int(3))
+    );
+    assert_eq!(
+        eval_with_context_mut("c ? (z = 1) : (z = 2); z", &mut context),
+        Ok(Value::Int(1))
+    );
+
+    let tree = build_operator_tree("c ? a : b").unwrap();
+    assert_eq!(tree.children()[0].operator(), &Operator::Conditional);
+    assert_eq!(tree.children()[0].children().len(), 3);
+
+    // The condition must be a boolean
+    assert_eq!(
+        eval("a ? 1 : 2"),
+        Err(EvalexprError::ExpectedBoolean {
+            actual: Value::Int(1)
+        })
+    );
+    // Missing operands
+    assert_eq!(
+        eval("t ? : 2"),
+        Err(EvalexprError::WrongOperatorArgumentAmount {
+            expected: 3,
+            actual: 2
+        })
+    );
+    // Orphan question marks and colons
+    assert_eq!(
+        eval("t ? a"),
+        Err(EvalexprError::UnmatchedQuestionMark { span: 2..3 })
+    );
+    assert_eq!(
+        eval("(t ? a) : b"),
+        Err(EvalexprError::UnmatchedQuestionMark { span: 3..4 })
+    );
+    assert_eq!(
+        eval("t ? 1 : 2 ? 3"),
+        Err(EvalexprError::UnmatchedQuestionMark { span: 10..11 })
+    );
+    assert_eq!(
+        eval("t : a"),
+        Err(EvalexprError::UnmatchedColon { span: 2..3 })
+    );
+    assert_eq!(
+        eval("t ? (a : b)"),
+        Err(EvalexprError::UnmatchedColon { span: 7..8 })
+    );
+    assert_eq!(
+        eval("t ? a : b : c"),
+        Err(EvalexprError::UnmatchedColon { span: 10..11 })
+    );
+    // Error recovery does not split the operands of a conditional operator
+    assert!(build_operator_tree_all_errors("c ? 1, 2 : 3; 4").is_ok());
+    assert_eq!(
+        build_operator_tree_all_errors("c ? 1; 2 +"),
+        Err(vec![EvalexprError::UnmatchedQuestionMark { span: 2..3 }])
+    );
+    let error = eval("t ? a").unwrap_err();
+    assert!(error.is_parse_error());
+    assert_eq!(error.code(), "unmatched_question_mark");
+    assert_eq!(eval("t : a").unwrap_err().code(), "unmatched_colon");
+}
+
+#[test]
+fn test_conditional_operator_is_lazy() {
+    let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut context = HashMapContext::new();
+    let counter = calls.clone();
+    context
+        .set_function(
+            "count".into(),
+            Function::new(move |argument| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                Ok(argument.clone())
+            }),
+        )
+        .unwrap();
+
+    assert_eq!(
+        eval_with_context("true ? count(1) : count(2)", &context),
+        Ok(Value::Int(1))
+    );
+    assert_eq!(
+        eval_with_context("false ? count(1) : count(2)", &context),
+        Ok(Value::Int(2))
+    );
+    assert_eq!(
+        eval_with_context(
+            "count(false) ? count(1) : count(true) ? 2 : count(3)",
+            &context
+        ),
+        Ok(Value::Int(2))
+    );
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 4);
+
+    let tree = build_operator_tree("true ? count(1) : count(2)").unwrap();
+    assert_eq!(tree.eval_with_context(&context), Ok(Value::Int(1)));
+    assert_eq!(tree.eval_with_context_mut(&mut context), Ok(Value::Int(1)));
+    let (result, call_counts) = tree.eval_profiled(&context);
+    assert_eq!(result, Ok(Value::Int(1)));
+    assert_eq!(call_counts.get("count"), Some(&1));
+    let (result, stats) = tree.eval_with_stats(&context);
+    assert_eq!(result, Ok(Value::Int(1)));
+    assert_eq!(stats.functions["count"].count, 1);
+    let expression = build_expression("false ? count(1) : count(2)").unwrap();
+    assert_eq!(expression.eval_with_context(&context), Ok(Value::Int(2)));
+    assert_eq!(
+        expression.eval_with_context_mut(&mut context),
+        Ok(Value::Int(2))
+    );
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 10);
+
+    // Only the chosen branch assigns
+    let mut context = HashMapContext::new();
+    assert_eq!(
+        eval_with_context_mut("true ? (a = 1) : (b = 2)", &mut context),
+        Ok(Value::Empty)
+    );
+    assert_eq!(context.get_value("a"), Some(&Value::Int(1)));
+    assert_eq!(context.get_value("b"), None);
+}
+
+#[test]
+fn test_hash_map_context_sorted_output() {
+    let build = |identifiers: &[&str]| {
+        let mut context = HashMapContext::new();
+        for identifier in identifiers {
+            let value = match *identifier {
+                "b" => Value::from("two"),
+                "c" => Value::from(vec![Value::from(3), Value::from(3.5)]),
+                _ => Value::from(1),
+            };
+            context.set_value(identifier.to_string(), value).unwrap();
+            context
+                .set_function(
+                    format!("f_{}", identifier),
+                    Function::new(|argument| Ok(argument.clone())).with_argument_amount(2),
+                )
+                .unwrap();
+        }
+        context
+            .set_function("g".to_string(), Function::new(|_| Ok(Value::Empty)))
+            .unwrap();
+        context
+            .insert_macro("m".to_string(), MacroFunction::new(|_, _| Ok(Value::Empty)))
+            .unwrap();
+        context
+    };
+    let contexts = [
+        build(&["a", "b", "c"]),
+        build(&["c", "a", "b"]),
+        build(&["b", "c", "a"]),
+    ];
+
+    let expected_display = "a = 1\nb = \"two\"\nc = (3, 3.5)\nf_a = <function/2>\nf_b = \
+                            <function/2>\nf_c = <function/2>\ng = <function>\nm = <macro>";
+    for context in &contexts {
+        assert_eq!(context.to_string(), expected_display);
+        assert_eq!(
+            context
+                .iter_variables_sorted()
+                .map(|(identifier, _)| identifier)
+                .collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+        assert_eq!(format!("{:?}", context), format!("{:?}", contexts[0]));
+        assert_eq!(format!("{:#?}", context), format!("{:#?}", contexts[0]));
+    }
+
+    let debug = format!("{:?}", contexts[0]);
+    assert!(
+        debug.starts_with(
+            "HashMapContext { variables: {\"a\": Int(1), \"b\": String(\"two\"), \"c\": \
+             Tuple([Int(3), Float(3.5)])}, functions: {\"f_a\": <function/2>, \"f_b\": \
+             <function/2>, \"f_c\": <function/2>, \"g\": <function>}, macro_functions: {\"m\": \
+             <macro>}, without_builtin_functions: false, strict: false, "
+        ),
+        "{}",
+        debug
+    );
+    assert_eq!(HashMapContext::new().to_string(), "");
+}
@@ -0,0 +1,107 @@
+#![cfg(not(tarpaulin_include))]
+
+//! Measures the memory that an `IdentifierInterner` saves on a corpus of operator trees with the same identifiers.
+//!
+//! The allocations of this test binary are counted by a global allocator, so this file must contain only one test,
+//! as tests in the same binary run concurrently.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use evalexpr::*;
+
+struct CountingAllocator;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE_BYTES.fetch_add(new_size, Ordering::SeqCst);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the result of the given function together with the amount of bytes it allocated and did not free.
+fn count_retained_bytes<T>(function: impl FnOnce() -> T) -> (T, usize) {
+    let before = LIVE_BYTES.load(Ordering::SeqCst);
+    let result = function();
+    (result, LIVE_BYTES.load(Ordering::SeqCst) - before)
+}
+
+#[test]
+fn test_interned_identifiers_save_memory() {
+    const CORPUS_SIZE: usize = 10_000;
+    let corpus: Vec<_> = (0..CORPUS_SIZE)
+        .map(|index| {
+            format!(
+                "customer_id == {} && order_total_amount > {} || customer_region == \"r{}\" && \
+                 is_premium_customer",
+                index,
+                index % 100,
+                index % 5
+            )
+        })
+        .collect();
+
+    let (plain, plain_bytes) = count_retained_bytes(|| {
+        corpus
+            .iter()
+            .map(|rule| build_operator_tree(rule).unwrap())
+            .collect::<Vec<_>>()
+    });
+    let interner = IdentifierInterner::new();
+    let (interned, interned_bytes) = count_retained_bytes(|| {
+        corpus
+            .iter()
+            .map(|rule| build_operator_tree_with_interner(rule, &interner).unwrap())
+            .collect::<Vec<_>>()
+    });
+    println!(
+        "{} operator trees retain {} bytes, and {} bytes with an interner",
+        CORPUS_SIZE, plain_bytes, interned_bytes
+    );
+
+    assert_eq!(interner.len(), 4);
+    assert_eq!(plain, interned);
+    // Without an interner, each tree allocates each of its identifiers, together with the two reference counts of the `Arc`.
+    let identifier_bytes: usize = [
+        "customer_id",
+        "order_total_amount",
+        "customer_region",
+        "is_premium_customer",
+    ]
+    .iter()
+    .map(|identifier| identifier.len() + 2 * std::mem::size_of::<usize>())
+    .sum();
+    assert!(plain_bytes - interned_bytes >= (CORPUS_SIZE - 1) * identifier_bytes);
+
+    let context = context_map! {
+        "customer_id" => 3,
+        "order_total_amount" => 50,
+        "customer_region" => "r1",
+        "is_premium_customer" => false,
+    }
+    .unwrap();
+    for (plain, interned) in plain.iter().zip(&interned) {
+        assert_eq!(
+            plain.eval_with_context(&context),
+            interned.eval_with_context(&context)
+        );
+    }
+}
@@ -0,0 +1,87 @@
+#![cfg(not(tarpaulin_include))]
+
+//! Tests that the expression size limits of `ParseOptions` stop tokenization early.
+//!
+//! The allocations of this test binary are counted by a global allocator, so this file must contain only one test,
+//! as tests in the same binary run concurrently.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use evalexpr::*;
+
+struct CountingAllocator;
+
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the result of the given function together with the amount of bytes it allocated.
+fn count_allocations<T>(function: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED_BYTES.load(Ordering::SeqCst);
+    let result = function();
+    (result, ALLOCATED_BYTES.load(Ordering::SeqCst) - before)
+}
+
+#[test]
+fn test_huge_expressions_are_rejected_early() {
+    const INPUT_LENGTH: usize = 64 * 1024 * 1024;
+    const MAX_TOKENS: usize = 10_000;
+    let options = ParseOptions::new().max_tokens(MAX_TOKENS);
+
+    for pattern in ["a + ", "1+", "     ", "x  &&= ", "\"s\" "] {
+        let input = pattern.repeat(INPUT_LENGTH / pattern.len());
+        let (result, allocated_bytes) =
+            count_allocations(|| build_operator_tree_with_options(&input, &options));
+
+        if pattern.trim().is_empty() {
+            assert_eq!(result.map(|tree| tree.eval()), Ok(Ok(Value::Empty)));
+        } else {
+            match result {
+                Err(EvalexprError::ExpressionTooLarge { limit, actual }) => {
+                    assert_eq!(limit, MAX_TOKENS);
+                    assert!(actual > MAX_TOKENS, "{:?}: {}", pattern, actual);
+                },
+                result => panic!("{:?}: unexpected result {:?}", pattern, result),
+            }
+        }
+        assert!(
+            allocated_bytes < INPUT_LENGTH / 8,
+            "{:?}: allocated {} bytes",
+            pattern,
+            allocated_bytes
+        );
+    }
+
+    let input = "1".repeat(INPUT_LENGTH);
+    let options = ParseOptions::new().max_length(1024);
+    let (result, allocated_bytes) =
+        count_allocations(|| build_operator_tree_with_options(&input, &options));
+    assert_eq!(
+        result,
+        Err(EvalexprError::ExpressionTooLarge {
+            limit: 1024,
+            actual: INPUT_LENGTH
+        })
+    );
+    assert_eq!(allocated_bytes, 0);
+}
@@ -0,0 +1,409 @@
+#![cfg(not(tarpaulin_include))]
+
+//! Property tests that compare the evaluation of random expressions with a reference interpreter.
+//!
+//! The expressions are generated as syntax trees, rendered to strings with as few braces as the precedence rules allow,
+//! and evaluated both by this crate and by a small interpreter that works directly on the generated syntax trees.
+//! If the results differ, the failing expression is shrunk to a minimal one before the test fails.
+
+use evalexpr::*;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+const ITERATIONS: usize = 20_000;
+const MAX_DEPTH: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum UnaryOperator {
+    Neg,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Exp,
+    Eq,
+    Neq,
+    Gt,
+    Lt,
+    Geq,
+    Leq,
+    And,
+    Or,
+}
+
+const BINARY_OPERATORS: [BinaryOperator; 14] = [
+    BinaryOperator::Add,
+    BinaryOperator::Sub,
+    BinaryOperator::Mul,
+    BinaryOperator::Div,
+    BinaryOperator::Mod,
+    BinaryOperator::Exp,
+    BinaryOperator::Eq,
+    BinaryOperator::Neq,
+    BinaryOperator::Gt,
+    BinaryOperator::Lt,
+    BinaryOperator::Geq,
+    BinaryOperator::Leq,
+    BinaryOperator::And,
+    BinaryOperator::Or,
+];
+
+/// The variables of the expressions and their values.
+const VARIABLES: [(&str, Literal); 5] = [
+    ("a", Literal::Int(3)),
+    ("b", Literal::Float(-2.5)),
+    ("c", Literal::Boolean(true)),
+    ("d", Literal::Int(0)),
+    ("e", Literal::Int(IntType::MAX)),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Literal {
+    Int(IntType),
+    Float(FloatType),
+    Boolean(bool),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expression {
+    Literal(Literal),
+    Variable(usize),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+}
+
+/// The reference precedence of the operators, as documented in the crate doc.
+fn binary_precedence(operator: BinaryOperator) -> u32 {
+    use BinaryOperator::*;
+    match operator {
+        Exp => 120,
+        Mul | Div | Mod => 100,
+        Add | Sub => 95,
+        Eq | Neq | Gt | Lt | Geq | Leq => 80,
+        And => 75,
+        Or => 70,
+    }
+}
+
+const UNARY_PRECEDENCE: u32 = 110;
+
+fn precedence(expression: &Expression) -> u32 {
+    match expression {
+        Expression::Literal(_) | Expression::Variable(_) => 200,
+        Expression::Unary(..) => UNARY_PRECEDENCE,
+        Expression::Binary(operator, ..) => binary_precedence(*operator),
+    }
+}
+
+fn generate_literal<R: Rng>(rng: &mut R) -> Literal {
+    match rng.gen_range(0..3) {
+        0 => Literal::Int(*[0, 1, 2, 3, 7, 100, IntType::MAX].choose(rng).unwrap()),
+        1 => Literal::Float(*[0.0, 0.5, 1.5, 2.25, 10.0, 100.75].choose(rng).unwrap()),
+        _ => Literal::Boolean(rng.gen()),
+    }
+}
+
+fn generate_expression<R: Rng>(rng: &mut R, depth: usize) -> Expression {
+    if depth == 0 || rng.gen_bool(0.3) {
+        if rng.gen_bool(0.5) {
+            Expression::Literal(generate_literal(rng))
+        } else {
+            Expression::Variable(rng.gen_range(0..VARIABLES.len()))
+        }
+    } else if rng.gen_bool(0.2) {
+        let operator = if rng.gen_bool(0.5) {
+            UnaryOperator::Neg
+        } else {
+            UnaryOperator::Not
+        };
+        Expression::Unary(operator, Box::new(generate_expression(rng, depth - 1)))
+    } else {
+        Expression::Binary(
+            *BINARY_OPERATORS.choose(rng).unwrap(),
+            Box::new(generate_expression(rng, depth - 1)),
+            Box::new(generate_expression(rng, depth - 1)),
+        )
+    }
+}
+
+/// Renders the expression, adding braces only where the precedence and left-to-right associativity require them,
+/// and randomly adding redundant braces and whitespace.
+fn render<R: Rng>(expression: &Expression, rng: &mut R) -> String {
+    let operand = |operand: &Expression, needs_braces: bool, rng: &mut R| {
+        let rendered = render(operand, rng);
+        if needs_braces || rng.gen_bool(0.05) {
+            format!("({})", rendered)
+        } else {
+            rendered
+        }
+    };
+
+    match expression {
+        Expression::Literal(Literal::Int(int)) => int.to_string(),
+        // Debug formatting always writes a decimal point, so the literal is not parsed as integer.
+        Expression::Literal(Literal::Float(float)) => format!("{:?}", float),
+        Expression::Literal(Literal::Boolean(boolean)) => boolean.to_string(),
+        Expression::Variable(index) => VARIABLES[*index].0.to_string(),
+        Expression::Unary(operator, inner) => {
+            let symbol = match operator {
+                UnaryOperator::Neg => "-",
+                UnaryOperator::Not => "!",
+            };
+            let inner = operand(inner, precedence(inner) < UNARY_PRECEDENCE, rng);
+            format!("{}{}", symbol, inner)
+        },
+        Expression::Binary(operator, left, right) => {
+            use BinaryOperator::*;
+            let symbol = match operator {
+                Add => "+",
+                Sub => "-",
+                Mul => "*",
+                Div => "/",
+                Mod => "%",
+                Exp => "^",
+                Eq => "==",
+                Neq => "!=",
+                Gt => ">",
+                Lt => "<",
+                Geq => ">=",
+                Leq => "<=",
+                And => "&&",
+                Or => "||",
+            };
+            let own_precedence = binary_precedence(*operator);
+            // All binary operators are evaluated left-to-right, so the right operand needs braces if it has the same precedence.
+            // Unary operators need braces on the right, as they would otherwise bind to the binary operator.
+            let left = operand(left, precedence(left) < own_precedence, rng);
+            let right = operand(
+                right,
+                precedence(right) <= own_precedence || matches!(**right, Expression::Unary(..)),
+                rng,
+            );
+            let space = if rng.gen_bool(0.5) { " " } else { "" };
+            format!("{}{}{}{}{}", left, space, symbol, space, right)
+        },
+    }
+}
+
+/// The reference interpreter.
+/// Returns `None` if the evaluation fails.
+fn evaluate(expression: &Expression) -> Option<Literal> {
+    use Literal::*;
+    match expression {
+        Expression::Literal(literal) => Some(*literal),
+        Expression::Variable(index) => Some(VARIABLES[*index].1),
+        Expression::Unary(operator, inner) => match (operator, evaluate(inner)?) {
+            (UnaryOperator::Neg, Int(int)) => int.checked_neg().map(Int),
+            (UnaryOperator::Neg, Float(float)) => Some(Float(-float)),
+            (UnaryOperator::Not, Boolean(boolean)) => Some(Boolean(!boolean)),
+            _ => None,
+        },
+        Expression::Binary(operator, left, right) => {
+            // Both operands are always evaluated, there is no short-circuiting.
+            let (left, right) = (evaluate(left)?, evaluate(right)?);
+            evaluate_binary(*operator, left, right)
+        },
+    }
+}
+
+fn as_float(literal: Literal) -> Option<FloatType> {
+    match literal {
+        Literal::Int(int) => Some(int as FloatType),
+        Literal::Float(float) => Some(float),
+        Literal::Boolean(_) => None,
+    }
+}
+
+fn evaluate_binary(operator: BinaryOperator, left: Literal, right: Literal) -> Option<Literal> {
+    use BinaryOperator::*;
+    use Literal::*;
+
+    match operator {
+        Eq => return Some(Boolean(left == right)),
+        Neq => return Some(Boolean(left != right)),
+        And | Or => {
+            return match (left, right) {
+                (Boolean(a), Boolean(b)) => {
+                    Some(Boolean(if operator == And { a && b } else { a || b }))
+                },
+                _ => None,
+            }
+        },
+        _ => {},
+    }
+
+    if let (Int(a), Int(b)) = (left, right) {
+        return match operator {
+            Add => a.checked_add(b).map(Int),
+            Sub => a.checked_sub(b).map(Int),
+            Mul => a.checked_mul(b).map(Int),
+            Div => a.checked_div(b).map(Int),
+            Mod => a.checked_rem(b).map(Int),
+            Exp => Some(Float((a as FloatType).powf(b as FloatType))),
+            Gt => Some(Boolean(a > b)),
+            Lt => Some(Boolean(a < b)),
+            Geq => Some(Boolean(a >= b)),
+            Leq => Some(Boolean(a <= b)),
+            Eq | Neq | And | Or => unreachable!(),
+        };
+    }
+
+    let (a, b) = (as_float(left)?, as_float(right)?);
+    Some(match operator {
+        Add => Float(a + b),
+        Sub => Float(a - b),
+        Mul => Float(a * b),
+        Div => Float(a / b),
+        Mod => Float(a % b),
+        Exp => Float(a.powf(b)),
+        Gt => Boolean(a > b),
+        Lt => Boolean(a < b),
+        Geq => Boolean(a >= b),
+        Leq => Boolean(a <= b),
+        Eq | Neq | And | Or => unreachable!(),
+    })
+}
+
+fn literal_matches(literal: Literal, value: &Value) -> bool {
+    match (literal, value) {
+        (Literal::Int(a), Value::Int(b)) => a == *b,
+        (Literal::Float(a), Value::Float(b)) => a == *b || (a.is_nan() && b.is_nan()),
+        (Literal::Boolean(a), Value::Boolean(b)) => a == *b,
+        _ => false,
+    }
+}
+
+fn context() -> HashMapContext {
+    let mut context = HashMapContext::new();
+    for (identifier, literal) in &VARIABLES {
+        let value = match literal {
+            Literal::Int(int) => Value::Int(*int),
+            Literal::Float(float) => Value::Float(*float),
+            Literal::Boolean(boolean) => Value::Boolean(*boolean),
+        };
+        context.set_value(identifier.to_string(), value).unwrap();
+    }
+    context
+}
+
+/// Returns the rendered expression and the evaluation result of this crate if it differs from the reference interpreter.
+fn find_discrepancy(
+    expression: &Expression,
+    context: &HashMapContext,
+    seed: u64,
+) -> Option<(String, EvalexprResult<Value>)> {
+    let string = render(expression, &mut Pcg32::seed_from_u64(seed));
+    let expected = evaluate(expression);
+    let actual = eval_with_context(&string, context);
+    let matches = match (expected, &actual) {
+        (Some(expected), Ok(actual)) => literal_matches(expected, actual),
+        (None, Err(_)) => true,
+        _ => false,
+    };
+    if matches {
+        None
+    } else {
+        Some((string, actual))
+    }
+}
+
+/// Returns smaller variants of the expression, replacing each subexpression by its operands or by a literal.
+fn shrink(expression: &Expression) -> Vec<Expression> {
+    let mut candidates = Vec::new();
+    match expression {
+        Expression::Literal(Literal::Int(0)) => {},
+        Expression::Literal(_) | Expression::Variable(_) => {
+            candidates.push(Expression::Literal(Literal::Int(0)))
+        },
+        Expression::Unary(operator, inner) => {
+            candidates.push((**inner).clone());
+            for inner in shrink(inner) {
+                candidates.push(Expression::Unary(*operator, Box::new(inner)));
+            }
+        },
+        Expression::Binary(operator, left, right) => {
+            candidates.push((**left).clone());
+            candidates.push((**right).clone());
+            for left in shrink(left) {
+                candidates.push(Expression::Binary(*operator, Box::new(left), right.clone()));
+            }
+            for right in shrink(right) {
+                candidates.push(Expression::Binary(*operator, left.clone(), Box::new(right)));
+            }
+        },
+    }
+    candidates
+}
+
+#[test]
+fn test_eval_matches_reference_interpreter() {
+    let context = context();
+    let mut rng = Pcg32::seed_from_u64(452);
+
+    for _ in 0..ITERATIONS {
+        let mut expression = generate_expression(&mut rng, MAX_DEPTH);
+        let seed = rng.gen();
+        if find_discrepancy(&expression, &context, seed).is_none() {
+            continue;
+        }
+
+        // Shrink the expression as long as a smaller variant still fails.
+        while let Some(smaller) = shrink(&expression)
+            .into_iter()
+            .find(|candidate| find_discrepancy(candidate, &context, seed).is_some())
+        {
+            expression = smaller;
+        }
+        let (string, actual) = find_discrepancy(&expression, &context, seed).unwrap();
+        panic!(
+            "Evaluating {:?} returned {:?}, but the reference interpreter returned {:?}",
+            string,
+            actual,
+            evaluate(&expression)
+        );
+    }
+}
+
+#[test]
+fn test_shrinking_finds_minimal_expression() {
+    // A deliberately wrong reference for subtraction shows that failing expressions are shrunk to the failing operator.
+    let expression = Expression::Binary(
+        BinaryOperator::Mul,
+        Box::new(Expression::Binary(
+            BinaryOperator::Sub,
+            Box::new(Expression::Variable(0)),
+            Box::new(Expression::Literal(Literal::Int(2))),
+        )),
+        Box::new(Expression::Literal(Literal::Float(1.5))),
+    );
+    let fails = |expression: &Expression| {
+        fn contains_sub(expression: &Expression) -> bool {
+            match expression {
+                Expression::Binary(BinaryOperator::Sub, ..) => true,
+                Expression::Binary(_, left, right) => contains_sub(left) || contains_sub(right),
+                Expression::Unary(_, inner) => contains_sub(inner),
+                _ => false,
+            }
+        }
+        contains_sub(expression)
+    };
+
+    let mut expression = expression;
+    while let Some(smaller) = shrink(&expression).into_iter().find(|c| fails(c)) {
+        expression = smaller;
+    }
+    assert_eq!(
+        expression,
+        Expression::Binary(
+            BinaryOperator::Sub,
+            Box::new(Expression::Literal(Literal::Int(0))),
+            Box::new(Expression::Literal(Literal::Int(0))),
+        )
+    );
+}
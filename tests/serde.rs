@@ -1,7 +1,7 @@
 #![cfg(not(tarpaulin_include))]
 #![cfg(feature = "serde")]
 
-use evalexpr::{build_operator_tree, Node};
+use evalexpr::{build_operator_tree, eval, EvalexprError, Node, Value};
 
 #[test]
 fn test_serde() {
@@ -41,3 +41,139 @@ fn test_serde_errors() {
         ""
     );
 }
+
+/// Returns the expected ron serialization of the error, given its code and the fields after its message.
+fn serialized_error(error: &EvalexprError, code: &str, fields: &str) -> String {
+    format!(
+        "(code:\"{}\",message:{},{})",
+        code,
+        ron::ser::to_string(&error.to_string()).unwrap(),
+        fields
+    )
+}
+
+#[test]
+fn test_serde_error_serialization() {
+    let error = eval("1 / 0").unwrap_err();
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "division_by_zero",
+            "identifier:None,value:None,expected:None,actual:None,span:None"
+        )
+    );
+
+    // Parse error
+    let error = eval("(1").unwrap_err();
+    assert!(error.is_parse_error());
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "unmatched_left_brace",
+            "identifier:None,value:None,expected:None,actual:None,span:None"
+        )
+    );
+
+    // Runtime error
+    let error = eval("a + 1").unwrap_err();
+    assert!(error.is_runtime_error() && !error.is_type_error());
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "variable_not_found",
+            "identifier:Some(\"a\"),value:None,expected:None,actual:None,span:None"
+        )
+    );
+
+    // Type errors
+    let error = eval("1 && true").unwrap_err();
+    assert!(error.is_type_error());
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "expected_boolean",
+            "identifier:None,value:Some(Int(1)),expected:Some([\"boolean\"]),actual:Some([\"int\"\
+             ]),span:None"
+        )
+    );
+    let error = EvalexprError::expected_number(Value::from("a"));
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "expected_number",
+            "identifier:None,value:Some(String(\"a\")),expected:Some([\"int\",\"float\"]),actual:\
+             Some([\"string\"]),span:None"
+        )
+    );
+    let error = eval("true + ()").unwrap_err();
+    assert!(error.is_type_error());
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "expected_number_or_string",
+            "identifier:None,value:Some(Boolean(true)),expected:Some([\"string\",\"int\",\"float\"\
+             ]),actual:Some([\"boolean\"]),span:None"
+        )
+    );
+
+    // Errors with the location of the offending part of the expression
+    let error = eval("t ? a").unwrap_err();
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "unmatched_question_mark",
+            "identifier:None,value:None,expected:None,actual:None,span:Some((start:2,end:3))"
+        )
+    );
+    let error = eval("t : a").unwrap_err();
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "unmatched_colon",
+            "identifier:None,value:None,expected:None,actual:None,span:Some((start:2,end:3))"
+        )
+    );
+}
+
+#[test]
+#[cfg(feature = "excel_support")]
+fn test_serde_excel_error_serialization() {
+    let error = evalexpr::from_excel_formula("=A1 + 1").unwrap_err();
+    assert_eq!(
+        ron::ser::to_string(&error).unwrap(),
+        serialized_error(
+            &error,
+            "excel_cell_reference",
+            "identifier:Some(\"A1\"),value:None,expected:None,actual:None,span:Some((start:1,end:\
+             3))"
+        )
+    );
+}
+
+#[test]
+#[cfg(feature = "serde_support")]
+fn test_serde_hash_map_context_sorted() {
+    use evalexpr::{ContextWithMutableVariables, HashMapContext};
+
+    for identifiers in [["a", "b", "c"], ["c", "a", "b"], ["b", "c", "a"]] {
+        let mut context = HashMapContext::new();
+        for identifier in &identifiers {
+            context
+                .set_value(identifier.to_string(), Value::from(identifier.repeat(2)))
+                .unwrap();
+        }
+        assert_eq!(
+            ron::ser::to_string(&context).unwrap(),
+            "(variables:{\"a\":String(\"aa\"),\"b\":String(\"bb\"),\"c\":String(\"cc\")},\
+             without_builtin_functions:false,strict:false,float_equality:Exact)"
+        );
+    }
+}
@@ -0,0 +1,274 @@
+#![cfg(not(tarpaulin_include))]
+#![cfg(feature = "time_support")]
+
+use std::{
+    convert::TryFrom,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use evalexpr::*;
+
+/// 2023-11-14 22:13:20 UTC
+const NOW: IntType = 1_700_000_000;
+
+fn fixed_context() -> HashMapContext {
+    let mut context = HashMapContext::new();
+    context.set_clock(Clock::fixed(NOW));
+    context
+}
+
+#[test]
+fn test_fixed_clock() {
+    let context = fixed_context();
+    assert_eq!(eval_with_context("now()", &context), Ok(Value::from(NOW)));
+    assert_eq!(
+        eval_with_context("now() + days(1)", &context),
+        Ok(Value::from(NOW + 86_400))
+    );
+    assert_eq!(
+        eval_with_context("now() - hours(2) * 3", &context),
+        Ok(Value::from(NOW - 6 * 3600))
+    );
+    assert_eq!(
+        eval_with_context(
+            "date::format(now() + days(1), \"%Y-%m-%d %H:%M:%S\")",
+            &context
+        ),
+        Ok(Value::from("2023-11-15 22:13:20"))
+    );
+
+    // Wrapping contexts read the clock of the wrapped context
+    let scoped = ScopedContext::new(&context);
+    assert_eq!(eval_with_context("now()", &scoped), Ok(Value::from(NOW)));
+    let layers: [&dyn Context; 2] = [&context, &HashMapContext::new()];
+    assert_eq!(
+        eval_with_context("now()", &&layers[..]),
+        Ok(Value::from(NOW))
+    );
+}
+
+#[test]
+fn test_custom_clock() {
+    let ticks = Arc::new(AtomicI64::new(NOW));
+    let clock_ticks = ticks.clone();
+    let mut context = HashMapContext::new();
+    context.set_clock(Clock::new(move || {
+        clock_ticks.fetch_add(1, Ordering::SeqCst)
+    }));
+
+    assert_eq!(
+        eval_with_context("(now(), now())", &context),
+        Ok(Value::from((NOW, NOW + 1)))
+    );
+    ticks.store(0, Ordering::SeqCst);
+    assert_eq!(eval_with_context("now()", &context), Ok(Value::from(0)));
+}
+
+#[test]
+fn test_system_clock() {
+    let system_now = || {
+        let duration = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        duration.as_secs() as IntType
+    };
+    let before = system_now();
+    let now = eval_int("now()").unwrap();
+    let after = system_now();
+    assert!(before <= now && now <= after);
+    assert_eq!(format!("{:?}", Clock::system()), "Clock::system()");
+    assert_eq!(format!("{:?}", Clock::fixed(NOW)), "Clock::new(..)");
+}
+
+#[test]
+fn test_durations() {
+    assert_eq!(eval("days(1)"), Ok(Value::from(86_400)));
+    assert_eq!(eval("hours(1)"), Ok(Value::from(3600)));
+    assert_eq!(eval("days(-2)"), Ok(Value::from(-172_800)));
+    assert_eq!(eval("hours(1.5)"), Ok(Value::from(5400.0)));
+    assert_eq!(eval("days(1) == hours(24)"), Ok(Value::from(true)));
+    assert_eq!(
+        eval("days(\"1\")"),
+        Err(EvalexprError::expected_number(Value::from("1")))
+    );
+    assert_eq!(
+        eval(&format!("days({})", IntType::MAX)),
+        Err(EvalexprError::MultiplicationError {
+            multiplicand: Value::from(IntType::MAX),
+            multiplier: Value::from(86_400),
+        })
+    );
+}
+
+#[test]
+fn test_date_builtins() {
+    // 2024-02-29T17:30:05Z, a Thursday
+    let context = context_map! {
+        "created_at" => 1_709_227_805,
+        "epoch" => 0,
+        "before_epoch" => -1,
+    }
+    .unwrap();
+
+    assert_eq!(
+        eval_with_context(
+            "(date::year(created_at), date::month(created_at), date::day(created_at))",
+            &context
+        ),
+        Ok(Value::from(vec![
+            Value::Int(2024),
+            Value::Int(2),
+            Value::Int(29)
+        ]))
+    );
+    assert_eq!(
+        eval_with_context("date::weekday(created_at)", &context),
+        Ok(Value::Int(4))
+    );
+    assert_eq!(
+        eval_with_context("date::hour(created_at)", &context),
+        Ok(Value::Int(17))
+    );
+    assert_eq!(
+        eval_with_context(
+            "date::weekday(created_at) <= 5 && date::hour(created_at) < 18",
+            &context
+        ),
+        Ok(Value::Boolean(true))
+    );
+    assert_eq!(
+        eval_with_context(
+            "date::format(created_at, \"%Y-%m-%d %H:%M:%S (%u) 100%%\")",
+            &context
+        ),
+        Ok(Value::from("2024-02-29 17:30:05 (4) 100%"))
+    );
+    assert_eq!(
+        eval_with_context("date::format(epoch, \"%Y-%m-%d\")", &context),
+        Ok(Value::from("1970-01-01"))
+    );
+    assert_eq!(
+        eval_with_context(
+            "date::format(before_epoch, \"%Y-%m-%d %H:%M:%S %u\")",
+            &context
+        ),
+        Ok(Value::from("1969-12-31 23:59:59 3"))
+    );
+
+    assert_eq!(
+        eval_with_context("date::format(created_at, \"%Y-%Q\")", &context),
+        Err(EvalexprError::InvalidTimeFormat {
+            format: "%Y-%Q".to_string()
+        })
+    );
+    assert_eq!(
+        eval_with_context("date::format(created_at, \"%\")", &context),
+        Err(EvalexprError::InvalidTimeFormat {
+            format: "%".to_string()
+        })
+    );
+    assert_eq!(
+        eval("date::year(\"2024\")"),
+        Err(EvalexprError::expected_int(Value::from("2024")))
+    );
+    assert_eq!(
+        eval(&format!("date::day({})", IntType::MAX)),
+        Err(EvalexprError::TimestampOutOfRange {
+            timestamp: IntType::MAX
+        })
+    );
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(
+        eval("format_time(0, \"%Y-%m-%dT%H:%M:%SZ\")"),
+        Ok(Value::from("1970-01-01T00:00:00Z"))
+    );
+    assert_eq!(
+        eval("format_time(-1, \"%A, %e %B %Y %T\")"),
+        Ok(Value::from("Wednesday, 31 December 1969 23:59:59"))
+    );
+    assert_eq!(
+        eval("format_time(951782400, \"%F %j %% %a\")"),
+        Ok(Value::from("2000-02-29 060 % Tue"))
+    );
+    assert_eq!(eval("format_time(0, \"\")"), Ok(Value::from("")));
+
+    assert_eq!(
+        eval("format_time(0, \"%Q\")"),
+        Err(EvalexprError::InvalidTimeFormat {
+            format: "%Q".to_string()
+        })
+    );
+    assert_eq!(
+        eval("format_time(0, \"%Y%\")"),
+        Err(EvalexprError::InvalidTimeFormat {
+            format: "%Y%".to_string()
+        })
+    );
+    assert_eq!(
+        eval(&format!("format_time({}, \"%Y\")", IntType::MAX)),
+        Err(EvalexprError::TimestampOutOfRange {
+            timestamp: IntType::MAX
+        })
+    );
+    assert_eq!(
+        eval("format_time(0)"),
+        Err(EvalexprError::expected_tuple(Value::from(0)))
+    );
+    assert_eq!(
+        eval("format_time(0.5, \"%Y\")"),
+        Err(EvalexprError::expected_int(Value::from(0.5)))
+    );
+}
+
+#[test]
+fn test_time_functions_are_builtin_functions() {
+    let mut context = fixed_context();
+    context.set_builtin_functions_disabled(true).unwrap();
+    assert_eq!(
+        eval_with_context("now()", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound("now".to_string()))
+    );
+
+    // Functions of the context take precedence
+    let mut context = fixed_context();
+    context
+        .set_function("now".to_string(), Function::new(|_| Ok(Value::from(7))))
+        .unwrap();
+    assert_eq!(eval_with_context("now()", &context), Ok(Value::from(7)));
+    assert_eq!(
+        eval("now(1)"),
+        Err(EvalexprError::expected_empty(Value::from(1)))
+    );
+}
+
+#[test]
+fn test_date_time_conversions() {
+    let date_time = Utc.with_ymd_and_hms(2023, 11, 14, 22, 13, 20).unwrap();
+    assert_eq!(Value::from(date_time), Value::from(NOW));
+    assert_eq!(DateTime::<Utc>::try_from(Value::from(NOW)), Ok(date_time));
+    assert_eq!(
+        DateTime::<Utc>::try_from(Value::from("now")),
+        Err(EvalexprError::expected_int(Value::from("now")))
+    );
+    assert_eq!(
+        DateTime::<Utc>::try_from(Value::from(IntType::MAX)),
+        Err(EvalexprError::TimestampOutOfRange {
+            timestamp: IntType::MAX
+        })
+    );
+
+    let mut context = fixed_context();
+    context
+        .set_value("deadline".to_string(), Value::from(date_time))
+        .unwrap();
+    assert_eq!(
+        eval_with_context("deadline - now() < days(1)", &context),
+        Ok(Value::from(true))
+    );
+}
@@ -0,0 +1,356 @@
+#![cfg(not(tarpaulin_include))]
+#![cfg(feature = "units_support")]
+
+use evalexpr::*;
+
+fn quantity(value: FloatType, unit: &str) -> Value {
+    UnitRegistry::standard().quantity(value, unit).unwrap()
+}
+
+fn unit_name(value: &Value) -> &str {
+    match value {
+        Value::Quantity { unit, .. } => unit.name(),
+        value => panic!("expected a quantity, but got {:?}", value),
+    }
+}
+
+fn amount(value: &Value) -> FloatType {
+    match value {
+        Value::Quantity { value, .. } => *value,
+        value => panic!("expected a quantity, but got {:?}", value),
+    }
+}
+
+#[test]
+fn test_unit_functions() {
+    let length = eval("with_unit(3, \"m\")").unwrap();
+    assert_eq!(length, quantity(3.0, "m"));
+    assert!(length.is_quantity());
+    assert_eq!(length.to_string(), "3 m");
+    assert_eq!(eval("unit_of(with_unit(3, \"m\"))"), Ok(Value::from("m")));
+    assert_eq!(eval("unit_of(3)"), Ok(Value::from("")));
+    assert_eq!(
+        eval("typeof(with_unit(3, \"m\"))"),
+        Ok(Value::from("quantity"))
+    );
+
+    let miles = eval("to_unit(with_unit(1, \"mi\"), \"ft\")").unwrap();
+    assert_eq!(unit_name(&miles), "ft");
+    assert!((amount(&miles) - 5280.0).abs() < 1e-9);
+
+    assert_eq!(
+        eval("with_unit(3, \"parsec\")"),
+        Err(EvalexprError::UnknownUnit("parsec".to_string()))
+    );
+    assert_eq!(
+        eval("to_unit(with_unit(3, \"m\"), \"parsec\")"),
+        Err(EvalexprError::UnknownUnit("parsec".to_string()))
+    );
+    assert_eq!(
+        eval("to_unit(with_unit(3, \"m\"), \"s\")"),
+        Err(EvalexprError::UnitMismatch {
+            left: "m".to_string(),
+            right: "s".to_string(),
+            operator: Operator::FunctionIdentifier {
+                identifier: "to_unit".into()
+            },
+        })
+    );
+    assert_eq!(
+        eval("to_unit(3, \"s\")"),
+        Err(EvalexprError::UnitMismatch {
+            left: String::new(),
+            right: "s".to_string(),
+            operator: Operator::FunctionIdentifier {
+                identifier: "to_unit".into()
+            },
+        })
+    );
+    assert_eq!(
+        eval("with_unit(\"3\", \"m\")"),
+        Err(EvalexprError::expected_number(Value::from("3")))
+    );
+
+    let mut context = HashMapContext::new();
+    context.set_builtin_functions_disabled(true).unwrap();
+    assert_eq!(
+        eval_with_context("with_unit(3, \"m\")", &context),
+        Err(EvalexprError::FunctionIdentifierNotFound(
+            "with_unit".to_string()
+        ))
+    );
+}
+
+#[test]
+fn test_quantity_addition() {
+    assert_eq!(
+        eval("with_unit(1, \"m\") + with_unit(2, \"m\")"),
+        Ok(quantity(3.0, "m"))
+    );
+    assert_eq!(
+        eval("with_unit(1, \"km\") + with_unit(500, \"m\")"),
+        Ok(quantity(1.5, "km"))
+    );
+    assert_eq!(
+        eval("with_unit(500, \"m\") - with_unit(1, \"km\")"),
+        Ok(quantity(-500.0, "m"))
+    );
+    assert_eq!(eval("-with_unit(2, \"s\")"), Ok(quantity(-2.0, "s")));
+    assert_eq!(
+        eval("a = with_unit(1, \"m\"); a += with_unit(50, \"cm\"); a"),
+        Ok(quantity(1.5, "m"))
+    );
+
+    assert_eq!(
+        eval("with_unit(1, \"m\") + with_unit(2, \"s\")"),
+        Err(EvalexprError::UnitMismatch {
+            left: "m".to_string(),
+            right: "s".to_string(),
+            operator: Operator::Add,
+        })
+    );
+    assert_eq!(
+        eval("1 - with_unit(2, \"kg\")"),
+        Err(EvalexprError::UnitMismatch {
+            left: String::new(),
+            right: "kg".to_string(),
+            operator: Operator::Sub,
+        })
+    );
+    assert_eq!(
+        eval("with_unit(2, \"m\") ^ 2"),
+        Err(EvalexprError::expected_number(quantity(2.0, "m")))
+    );
+}
+
+#[test]
+fn test_quantity_multiplication() {
+    assert_eq!(eval("2 * with_unit(3, \"m\")"), Ok(quantity(6.0, "m")));
+    assert_eq!(eval("with_unit(3, \"m\") * 2"), Ok(quantity(6.0, "m")));
+    assert_eq!(eval("with_unit(3, \"m\") / 2"), Ok(quantity(1.5, "m")));
+    assert_eq!(eval("2 / with_unit(4, \"s\")"), Ok(quantity(0.5, "Hz")));
+
+    assert_eq!(
+        eval("with_unit(2, \"m\") * with_unit(3, \"m\")"),
+        Ok(quantity(6.0, "m^2"))
+    );
+    assert_eq!(
+        eval("with_unit(100, \"m\") / with_unit(10, \"s\")"),
+        Ok(quantity(10.0, "m/s"))
+    );
+    assert_eq!(
+        eval("with_unit(100, \"km\") / with_unit(2, \"h\")"),
+        Ok(quantity(50.0, "km/h"))
+    );
+    assert_eq!(
+        eval(
+            "with_unit(2, \"kg\") * with_unit(3, \"m\") / with_unit(1, \"s\") / with_unit(1, \
+             \"s\")"
+        ),
+        Ok(quantity(6.0, "N"))
+    );
+
+    // Units that are not registered are derived from the units of the operands
+    let area = eval("with_unit(2, \"m\") * with_unit(3, \"ft\")").unwrap();
+    assert_eq!(area.to_string(), "6 m*ft");
+    let area = eval_with_context(
+        "to_unit(area, \"m^2\")",
+        &context_map! { "area" => area }.unwrap(),
+    )
+    .unwrap();
+    assert!((amount(&area) - 6.0 * 0.3048).abs() < 1e-12);
+    assert_eq!(
+        eval("unit_of(with_unit(1, \"kg\") / (with_unit(1, \"m\") * with_unit(1, \"s\")))"),
+        Ok(Value::from("kg/(m*s)"))
+    );
+
+    // Quantities of the same dimension cancel out
+    assert_eq!(
+        eval("with_unit(3, \"km\") / with_unit(500, \"m\")"),
+        Ok(Value::Float(6.0))
+    );
+    assert_eq!(
+        eval("with_unit(1, \"Hz\") * with_unit(1, \"min\")"),
+        Ok(Value::Float(60.0))
+    );
+}
+
+#[test]
+fn test_quantity_comparison() {
+    assert_eq!(
+        eval("with_unit(1, \"ft\") < with_unit(1, \"m\")"),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval("with_unit(1, \"km\") >= with_unit(1001, \"m\")"),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        eval("with_unit(1, \"km\") == with_unit(1000, \"m\")"),
+        Ok(Value::from(true))
+    );
+    assert_eq!(
+        eval("with_unit(1, \"km\") != with_unit(1000, \"m\")"),
+        Ok(Value::from(false))
+    );
+    assert_eq!(
+        eval("with_unit(1, \"km\") <=> with_unit(999, \"m\")"),
+        Ok(Value::from(1))
+    );
+    assert_eq!(
+        eval("with_unit(1, \"ft\") <=> with_unit(1, \"m\")"),
+        Ok(Value::from(-1))
+    );
+    assert_eq!(
+        eval("with_unit(1, \"m\") < with_unit(1, \"s\")"),
+        Err(EvalexprError::UnitMismatch {
+            left: "m".to_string(),
+            right: "s".to_string(),
+            operator: Operator::Lt,
+        })
+    );
+    assert_eq!(
+        eval("with_unit(1, \"m\") == 1"),
+        Err(EvalexprError::UnitMismatch {
+            left: "m".to_string(),
+            right: String::new(),
+            operator: Operator::Eq,
+        })
+    );
+
+    // The float equality of the context applies to the converted amounts
+    let mut context = HashMapContext::new();
+    let expression = "with_unit(0.1, \"km\") + with_unit(200, \"m\") == with_unit(300, \"m\")";
+    assert_eq!(
+        eval_with_context(expression, &context),
+        Ok(Value::from(false))
+    );
+    context.set_float_equality(FloatEquality::Absolute(1e-9));
+    assert_eq!(
+        eval_with_context(expression, &context),
+        Ok(Value::from(true))
+    );
+}
+
+#[test]
+fn test_unit_registry() {
+    let mut registry = UnitRegistry::new();
+    let furlong = registry
+        .add_unit("furlong", Dimension::LENGTH, 201.168)
+        .unwrap();
+    assert_eq!(furlong.name(), "furlong");
+    assert_eq!(furlong.dimension(), Dimension::LENGTH);
+    assert_eq!(furlong.factor(), 201.168);
+    registry
+        .add_unit("fortnight", Dimension::TIME, 1_209_600.0)
+        .unwrap();
+    registry.add_unit("m", Dimension::LENGTH, 1.0).unwrap();
+    assert_eq!(
+        registry.add_unit("m", Dimension::LENGTH, 1.0),
+        Err(EvalexprError::DuplicateBinding("m".to_string()))
+    );
+    assert!(registry.get("km").is_none());
+
+    let mut context = HashMapContext::new();
+    context.set_unit_registry(registry);
+    assert_eq!(
+        eval_with_context("with_unit(1, \"km\")", &context),
+        Err(EvalexprError::UnknownUnit("km".to_string()))
+    );
+    assert_eq!(
+        eval_with_context("to_unit(with_unit(2, \"furlong\"), \"m\")", &context),
+        Ok(Value::Quantity {
+            value: 402.336,
+            unit: context.unit_registry().get("m").unwrap().clone()
+        })
+    );
+    assert_eq!(
+        eval_with_context(
+            "unit_of(with_unit(1, \"furlong\") / with_unit(1, \"fortnight\"))",
+            &context
+        ),
+        Ok(Value::from("furlong/fortnight"))
+    );
+
+    // Wrapping contexts use the units of the wrapped context
+    let defaults = HashMapContext::new();
+    let layers: &[&dyn Context] = &[&context, &defaults];
+    assert_eq!(
+        eval_with_context("unit_of(with_unit(1, \"furlong\"))", &layers),
+        Ok(Value::from("furlong"))
+    );
+    assert_eq!(
+        eval_with_context(
+            "unit_of(with_unit(1, \"furlong\"))",
+            &CachingContext::new(&context)
+        ),
+        Ok(Value::from("furlong"))
+    );
+    assert_eq!(
+        eval_with_context(
+            "unit_of(with_unit(1, \"km\"))",
+            &EmptyContextWithBuiltinFunctions
+        ),
+        Ok(Value::from("km"))
+    );
+}
+
+#[test]
+fn test_unit_error_codes() {
+    let error = eval("with_unit(1, \"m\") + with_unit(1, \"s\")").unwrap_err();
+    assert_eq!(error.code(), "unit_mismatch");
+    assert!(error.is_type_error());
+    assert_eq!(
+        error.to_string(),
+        "Operator Add cannot be applied to the units \"m\" and \"s\", which measure different \
+         dimensions."
+    );
+    let error = eval("with_unit(1, \"parsec\")").unwrap_err();
+    assert_eq!(error.code(), "unknown_unit");
+    assert!(error.is_runtime_error());
+}
+
+#[test]
+fn test_ordered_quantities() {
+    let mut values = [
+        OrderedValue(quantity(2.0, "m")),
+        OrderedValue(Value::Custom(std::sync::Arc::new(Marker))),
+        OrderedValue(quantity(1.0, "s")),
+        OrderedValue(quantity(1.0, "m")),
+        OrderedValue(Value::from(3)),
+    ];
+    values.sort();
+    let values: Vec<_> = values.iter().map(|value| value.0.to_string()).collect();
+    assert_eq!(values, ["3", "1 m", "2 m", "1 s", "marker"]);
+}
+
+#[derive(Debug)]
+struct Marker;
+
+impl CustomValue for Marker {
+    fn type_name(&self) -> &str {
+        "marker"
+    }
+
+    fn display(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "marker")
+    }
+
+    fn eq(&self, other: &dyn CustomValue) -> bool {
+        other.as_any().is::<Self>()
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[test]
+fn test_quantities_cannot_be_encoded() {
+    let length = eval("with_unit(3, \"m\")").unwrap();
+    let tree = NodeBuilder::value(length.clone()).build();
+    assert_eq!(
+        tree.to_bytes(),
+        Err(EvalexprError::UnencodableValue(length))
+    );
+}